@@ -0,0 +1,102 @@
+use bytes::{Bytes, BytesMut};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+
+use webrtc_data::{
+    marshal::{Marshal, MarshalSize, Unmarshal},
+    message::{DataChannelOpen, Message},
+    pubsub::Packet,
+    rpc::{Frame, FrameKind},
+    ChannelType,
+};
+
+fn open_message() -> Message {
+    Message::DataChannelOpen(DataChannelOpen {
+        channel_type: ChannelType::PartialReliableRexmitUnordered,
+        priority: 256,
+        reliability_parameter: 3,
+        label: b"game-state".to_vec(),
+        protocol: b"proto/v1".to_vec(),
+    })
+}
+
+fn bench_message(c: &mut Criterion) {
+    let mut group = c.benchmark_group("message");
+
+    let open = open_message();
+    let open_bytes = open.marshal().unwrap();
+    let ack = Message::DataChannelAck;
+    let ack_bytes = ack.marshal().unwrap();
+
+    group.throughput(Throughput::Bytes(open_bytes.len() as u64));
+    group.bench_function("marshal_open", |b| {
+        let mut buf = BytesMut::with_capacity(open.marshal_size());
+        b.iter(|| {
+            buf.clear();
+            black_box(&open).marshal_to(&mut buf).unwrap()
+        })
+    });
+    group.bench_function("unmarshal_open", |b| {
+        b.iter(|| {
+            let mut bytes = black_box(open_bytes.clone());
+            Message::unmarshal_from(&mut bytes).unwrap()
+        })
+    });
+
+    group.throughput(Throughput::Bytes(ack_bytes.len() as u64));
+    group.bench_function("marshal_ack", |b| {
+        let mut buf = BytesMut::with_capacity(ack.marshal_size());
+        b.iter(|| {
+            buf.clear();
+            black_box(&ack).marshal_to(&mut buf).unwrap()
+        })
+    });
+    group.bench_function("unmarshal_ack", |b| {
+        b.iter(|| {
+            let mut bytes = black_box(&ack_bytes[..]);
+            Message::unmarshal_from(&mut bytes).unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_framing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("framing");
+
+    let payload = Bytes::from(vec![0x5a; 1024]);
+    let frame = Frame::new(FrameKind::Request, 42, payload.clone());
+    let frame_bytes = frame.marshal().unwrap();
+    let packet = Packet::Publish {
+        topic: "telemetry/cpu".to_owned(),
+        payload,
+    };
+    let packet_bytes = packet.marshal().unwrap();
+
+    group.throughput(Throughput::Bytes(frame_bytes.len() as u64));
+    group.bench_function("marshal_rpc_frame", |b| {
+        b.iter(|| black_box(&frame).marshal().unwrap())
+    });
+    group.bench_function("unmarshal_rpc_frame", |b| {
+        b.iter(|| {
+            let mut bytes = black_box(frame_bytes.clone());
+            Frame::unmarshal_from(&mut bytes).unwrap()
+        })
+    });
+
+    group.throughput(Throughput::Bytes(packet_bytes.len() as u64));
+    group.bench_function("marshal_pubsub_packet", |b| {
+        b.iter(|| black_box(&packet).marshal().unwrap())
+    });
+    group.bench_function("unmarshal_pubsub_packet", |b| {
+        b.iter(|| {
+            let mut bytes = black_box(packet_bytes.clone());
+            Packet::unmarshal_from(&mut bytes).unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_message, bench_framing);
+criterion_main!(benches);
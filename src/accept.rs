@@ -0,0 +1,123 @@
+//! Filtering of the channels opened by the remote.
+//!
+//! `DataChannel::accept_with_options` checks the label and protocol of an
+//! incoming DATA_CHANNEL_OPEN against the patterns of its `AcceptOptions`,
+//! resetting the stream of channels matching none of them, just as it does
+//! for malformed OPEN messages.
+
+use crate::error::RejectReason;
+
+/// AcceptOptions restricts which channels are accepted, by label and protocol.
+///
+/// Patterns match a whole label or protocol, with `*` matching any run of
+/// characters. Without any patterns of a kind, all labels or protocols are
+/// allowed.
+#[derive(Eq, PartialEq, Clone, Debug, Default)]
+pub struct AcceptOptions {
+    allowed_labels: Vec<String>,
+    allowed_protocols: Vec<String>,
+}
+
+impl AcceptOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// AllowLabel allows channels whose label matches `pattern`.
+    pub fn allow_label(mut self, pattern: impl Into<String>) -> Self {
+        self.allowed_labels.push(pattern.into());
+        self
+    }
+
+    /// AllowProtocol allows channels whose protocol matches `pattern`.
+    pub fn allow_protocol(mut self, pattern: impl Into<String>) -> Self {
+        self.allowed_protocols.push(pattern.into());
+        self
+    }
+
+    /// Check returns why a channel with the given label and protocol
+    /// is to be rejected, if it is.
+    pub fn check(&self, label: &str, protocol: &str) -> Result<(), RejectReason> {
+        if !is_allowed(&self.allowed_labels, label) {
+            return Err(RejectReason::LabelNotAllowed {
+                label: label.to_owned(),
+            });
+        }
+        if !is_allowed(&self.allowed_protocols, protocol) {
+            return Err(RejectReason::ProtocolNotAllowed {
+                protocol: protocol.to_owned(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+fn is_allowed(patterns: &[String], value: &str) -> bool {
+    patterns.is_empty() || patterns.iter().any(|pattern| matches(pattern, value))
+}
+
+// Matches `value` against a pattern in which `*` stands for any run of characters.
+fn matches(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match value.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some(split) => split,
+        // No wildcard at all.
+        None => return rest.is_empty(),
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(matches("chat", "chat"));
+        assert!(!matches("chat", "chats"));
+        assert!(matches("chat-*", "chat-general"));
+        assert!(matches("*", ""));
+        assert!(matches("*.v1", "telemetry.v1"));
+        assert!(matches("a*b*c", "a-b-b-c"));
+        assert!(!matches("a*b*c", "a-c"));
+        assert!(!matches("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn checks_label_and_protocol() {
+        let options = AcceptOptions::new()
+            .allow_label("chat-*")
+            .allow_label("telemetry");
+
+        assert_eq!(options.check("chat-general", "anything"), Ok(()));
+        assert_eq!(
+            options.check("video", ""),
+            Err(RejectReason::LabelNotAllowed {
+                label: "video".to_owned()
+            })
+        );
+
+        let options = options.allow_protocol("json");
+        assert_eq!(
+            options.check("telemetry", "xml"),
+            Err(RejectReason::ProtocolNotAllowed {
+                protocol: "xml".to_owned()
+            })
+        );
+    }
+}
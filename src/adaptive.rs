@@ -0,0 +1,260 @@
+//! Experimental adaptive reliability for managed channels.
+//!
+//! An `AdaptiveReliability` engine watches the level reported by a
+//! manager's `QualityMonitor` and, whenever it drops to or below the
+//! threshold of a user-declared `AdaptivePolicy`, reopens the channels the
+//! policy selects with its degraded reliability settings. Once the level
+//! recovers, the channels are reopened with their original settings.
+//!
+//! Reopened channels are assigned a new stream, so each transition
+//! reports the stream identifiers involved.
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+};
+
+use crate::{
+    channel_type::ChannelType, data_channel::Config, error::DataChannelError, label::Label,
+    manager::DataChannelManager, quality::QualityLevel,
+};
+
+/// The reliability settings of a channel.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub struct ReliabilityMode {
+    pub channel_type: ChannelType,
+    pub reliability_parameter: u32,
+}
+
+impl ReliabilityMode {
+    /// Of returns the reliability settings of the given config.
+    pub fn of(config: &Config) -> Self {
+        Self {
+            channel_type: config.channel_type,
+            reliability_parameter: config.reliability_parameter,
+        }
+    }
+
+    fn apply(&self, config: &mut Config) {
+        config.channel_type = self.channel_type;
+        config.reliability_parameter = self.reliability_parameter;
+    }
+}
+
+/// Which channels to switch to which settings, once the quality level
+/// drops to or below `degrade_at`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct AdaptivePolicy {
+    /// The label of the channels the policy applies to.
+    pub label: String,
+    pub degrade_at: QualityLevel,
+    pub degraded: ReliabilityMode,
+}
+
+/// Published for every channel reopened by an `AdaptiveReliability` engine.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct ReliabilityTransition {
+    pub label: Label,
+    /// The stream of the channel which was closed.
+    pub from_stream_identifier: u16,
+    /// The stream of the channel which replaced it.
+    pub to_stream_identifier: u16,
+    pub from: ReliabilityMode,
+    pub to: ReliabilityMode,
+    /// The quality level which caused the transition.
+    pub level: QualityLevel,
+}
+
+/// AdaptiveReliability applies `AdaptivePolicy`s to the channels of a manager.
+///
+/// The first policy matching a channel's label applies to it.
+#[derive(Debug, Default)]
+pub struct AdaptiveReliability {
+    policies: Vec<AdaptivePolicy>,
+    // The original settings of the channels currently degraded, by stream identifier.
+    degraded: BTreeMap<u16, ReliabilityMode>,
+    subscribers: Mutex<Vec<Sender<ReliabilityTransition>>>,
+}
+
+impl AdaptiveReliability {
+    pub fn new(policies: Vec<AdaptivePolicy>) -> Self {
+        Self {
+            policies,
+            ..Default::default()
+        }
+    }
+
+    /// Subscribe returns a receiver for all transitions from here on.
+    pub fn subscribe(&self) -> Receiver<ReliabilityTransition> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    /// IsDegraded returns whether the channel on the given stream
+    /// was reopened with degraded settings.
+    pub fn is_degraded(&self, stream_identifier: u16) -> bool {
+        self.degraded.contains_key(&stream_identifier)
+    }
+
+    /// Evaluate reopens the manager's channels whose policy calls for different
+    /// settings at the level of the manager's `QualityMonitor`, returning the
+    /// transitions made. Does nothing if the manager has no monitor.
+    ///
+    /// Stops at the first channel which fails to reopen.
+    pub fn evaluate(
+        &mut self,
+        manager: &mut DataChannelManager,
+    ) -> Result<Vec<ReliabilityTransition>, DataChannelError> {
+        let level = match manager.quality_monitor() {
+            Some(quality_monitor) => quality_monitor.level(),
+            None => return Ok(vec![]),
+        };
+
+        let mut transitions = vec![];
+        for channel in manager.channels() {
+            let policy = match self
+                .policies
+                .iter()
+                .find(|p| channel.label == p.label.as_str())
+            {
+                Some(policy) => policy,
+                None => continue,
+            };
+            let should_degrade = level <= policy.degrade_at;
+            if should_degrade == self.is_degraded(channel.stream_identifier) {
+                continue;
+            }
+
+            let mut config = match manager.get(channel.stream_identifier) {
+                Some(data_channel) => data_channel.config.clone(),
+                None => continue,
+            };
+            let from = ReliabilityMode::of(&config);
+            let to = if should_degrade {
+                policy.degraded
+            } else {
+                self.degraded[&channel.stream_identifier]
+            };
+            to.apply(&mut config);
+
+            let to_stream_identifier = manager
+                .reopen(channel.stream_identifier, config)?
+                .stream_identifier();
+            self.degraded.remove(&channel.stream_identifier);
+            if should_degrade {
+                self.degraded.insert(to_stream_identifier, from);
+            }
+
+            log::debug!(
+                "Reopened channel {:?} on stream {} as {:?}, at {:?} quality",
+                channel.label,
+                to_stream_identifier,
+                to,
+                level
+            );
+            let transition = ReliabilityTransition {
+                label: channel.label,
+                from_stream_identifier: channel.stream_identifier,
+                to_stream_identifier,
+                from,
+                to,
+                level,
+            };
+            self.publish(&transition);
+            transitions.push(transition);
+        }
+
+        Ok(transitions)
+    }
+
+    fn publish(&self, transition: &ReliabilityTransition) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(transition.clone()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        quality::{QualityMonitor, QualitySignals},
+        testing,
+    };
+
+    use super::*;
+
+    #[test]
+    fn degrades_and_restores_matching_channels() {
+        let (association, _remote) = testing::simulated();
+        let mut manager =
+            DataChannelManager::new(association).with_quality_monitor(QualityMonitor::new());
+
+        let config = |label: &str| Config {
+            label: label.to_owned(),
+            ..Default::default()
+        };
+        let video = manager.open(config("video")).unwrap().stream_identifier();
+        let chat = manager.open(config("chat")).unwrap().stream_identifier();
+
+        let degraded = ReliabilityMode {
+            channel_type: ChannelType::PartialReliableRexmitUnordered,
+            reliability_parameter: 0,
+        };
+        let mut engine = AdaptiveReliability::new(vec![AdaptivePolicy {
+            label: "video".to_owned(),
+            degrade_at: QualityLevel::Fair,
+            degraded,
+        }]);
+        let events = engine.subscribe();
+
+        manager.sample_quality().unwrap();
+        assert!(engine.evaluate(&mut manager).unwrap().is_empty());
+
+        testing::set_quality_signals(
+            manager.association(),
+            QualitySignals {
+                rtt: Some(Duration::from_millis(500)),
+                retransmits: 10,
+                ..Default::default()
+            },
+        );
+        manager.sample_quality().unwrap();
+        let transitions = engine.evaluate(&mut manager).unwrap();
+        assert_eq!(transitions.len(), 1);
+        let transition = &transitions[0];
+        assert_eq!(transition.from_stream_identifier, video);
+        assert_eq!(transition.to, degraded);
+        assert_eq!(events.try_recv().as_ref(), Ok(transition));
+
+        let video = transition.to_stream_identifier;
+        assert!(engine.is_degraded(video));
+        let data_channel = manager.get(video).unwrap();
+        assert_eq!(ReliabilityMode::of(&data_channel.config), degraded);
+        assert!(manager.get(chat).is_some());
+
+        // Still degraded, nothing to do.
+        assert!(engine.evaluate(&mut manager).unwrap().is_empty());
+
+        testing::set_quality_signals(
+            manager.association(),
+            QualitySignals {
+                rtt: Some(Duration::from_millis(20)),
+                retransmits: 10,
+                ..Default::default()
+            },
+        );
+        manager.sample_quality().unwrap();
+        let transitions = engine.evaluate(&mut manager).unwrap();
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].from_stream_identifier, video);
+        assert_eq!(transitions[0].to.channel_type, ChannelType::Reliable);
+        assert!(!engine.is_degraded(transitions[0].to_stream_identifier));
+    }
+}
@@ -0,0 +1,132 @@
+//! Blocking variants of a channel's non-blocking operations.
+//!
+//! For applications without an executor, such as command line tools or game
+//! loops: with the `blocking` feature enabled, each drives the corresponding
+//! `poll_*` method to completion, parking the calling thread until the stream
+//! wakes it, rather than sleeping on the config's clock. The crate's other
+//! blocking calls wait the same way, parking on the config's clock
+//! instead when they have a timeout.
+
+use std::{
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+    time::Instant,
+};
+
+use crate::clock::SharedClock;
+
+#[cfg(feature = "blocking")]
+use bytes::BytesMut;
+
+#[cfg(feature = "blocking")]
+use crate::{data_channel::DataChannel, error::DataChannelError, exact_size_buf::ExactSizeBuf};
+
+#[cfg(feature = "blocking")]
+impl DataChannel {
+    /// BlockingRead parks the calling thread until a message has been read
+    /// into `buf`, returning its length and whether it is text.
+    pub fn blocking_read(&mut self, buf: &mut BytesMut) -> Result<(usize, bool), DataChannelError> {
+        block_on(|cx| self.poll_read_data_channel(cx, buf))
+    }
+
+    /// BlockingWrite parks the calling thread until the stream took `bytes`
+    /// as a text or binary message.
+    pub fn blocking_write<B>(
+        &mut self,
+        bytes: &mut B,
+        is_string: bool,
+    ) -> Result<usize, DataChannelError>
+    where
+        B: bytes::Buf + ExactSizeBuf,
+    {
+        block_on(|cx| self.poll_write_data_channel(cx, bytes, is_string))
+    }
+
+    /// BlockingClose closes the channel and parks the calling thread until the
+    /// remote has reset its stream as well, discarding messages arriving until then.
+    pub fn blocking_close(&mut self) -> Result<(), DataChannelError> {
+        self.close()?;
+        block_on(|cx| self.poll_closed(cx))
+    }
+}
+
+// Unparks the thread blocked in `block_on`.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn thread_waker() -> Waker {
+    Waker::from(Arc::new(ThreadWaker(thread::current())))
+}
+
+/// Polls `poll` on the calling thread until it is ready,
+/// parking the thread until it is woken in between.
+pub(crate) fn block_on<T>(mut poll: impl FnMut(&mut Context<'_>) -> Poll<T>) -> T {
+    let waker = thread_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        if let Poll::Ready(value) = poll(&mut cx) {
+            return value;
+        }
+        thread::park();
+    }
+}
+
+/// Like `block_on`, but parks on `clock` and gives up with `None`
+/// once `deadline` has passed.
+pub(crate) fn poll_until<T>(
+    clock: &SharedClock,
+    deadline: Instant,
+    mut poll: impl FnMut(&mut Context<'_>) -> Poll<T>,
+) -> Option<T> {
+    let waker = thread_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        if let Poll::Ready(value) = poll(&mut cx) {
+            return Some(value);
+        }
+
+        let remaining = deadline.saturating_duration_since(clock.now());
+        if remaining.is_zero() {
+            return None;
+        }
+        clock.park_timeout(remaining);
+    }
+}
+
+#[cfg(all(test, feature = "blocking"))]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{data_channel::Config, data_channel::ReadyState, testing};
+
+    use super::*;
+
+    #[test]
+    fn blocks_until_done() {
+        let (mut dialer, mut acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+
+        let remote = thread::spawn(move || {
+            let mut buf = BytesMut::with_capacity(16);
+            let read = acceptor.blocking_read(&mut buf);
+            acceptor.blocking_close().unwrap();
+            (read, buf)
+        });
+
+        let written = dialer.blocking_write(&mut Bytes::from_static(b"ping"), true);
+        assert_eq!(written, Ok(4));
+        dialer.blocking_close().unwrap();
+        assert_eq!(dialer.ready_state(), ReadyState::Closed);
+
+        let (read, buf) = remote.join().unwrap();
+        assert_eq!(read, Ok((4, true)));
+        assert_eq!(&buf[..], b"ping");
+    }
+}
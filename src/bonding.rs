@@ -0,0 +1,282 @@
+//! Experimental bonding of channels across multiple associations.
+//!
+//! A `BondedChannel` sends one logical channel's messages over several
+//! member channels, each typically on a different association to the same
+//! peer, either striping them across all members for throughput or
+//! sticking to one member and failing over to the next once it fails.
+//! Messages are sequenced as in the `ordering` layer, and the receiving
+//! end puts them back into order within a bounded window, skipping over
+//! messages lost along with a failed member.
+
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+
+use crate::{
+    blocking,
+    data_channel::{DataChannel, RECEIVE_MTU},
+    error::DataChannelError,
+    marshal::{Marshal, Unmarshal},
+    ordering::{Delivery, ReorderBuffer, SequencedFrame},
+};
+
+/// How a `BondedChannel` spreads its messages across its members.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum BondingMode {
+    /// Sends each message over the next healthy member, in turn.
+    Stripe,
+    /// Sends all messages over the first healthy member.
+    Failover,
+}
+
+struct Member {
+    data_channel: DataChannel,
+    failed: bool,
+}
+
+/// BondedChannel sends and receives sequenced messages over a set of member channels.
+///
+/// A member is considered failed, and no longer used, after its first
+/// failed read or write.
+pub struct BondedChannel {
+    members: Vec<Member>,
+    mode: BondingMode,
+    next_sequence: u64,
+    // The member to send the next striped message over.
+    next_member: usize,
+    reorder_buffer: ReorderBuffer,
+}
+
+impl BondedChannel {
+    /// Creates a bond over the given members, holding back at most
+    /// `window` sequence numbers' worth of messages behind a missing one.
+    pub fn new(members: Vec<DataChannel>, mode: BondingMode, window: usize) -> Self {
+        Self {
+            members: members
+                .into_iter()
+                .map(|data_channel| Member {
+                    data_channel,
+                    failed: false,
+                })
+                .collect(),
+            mode,
+            next_sequence: 0,
+            next_member: 0,
+            reorder_buffer: ReorderBuffer::new(window),
+        }
+    }
+
+    pub fn mode(&self) -> BondingMode {
+        self.mode
+    }
+
+    /// HealthyMembers returns the number of members which haven't failed.
+    pub fn healthy_members(&self) -> usize {
+        self.members.iter().filter(|member| !member.failed).count()
+    }
+
+    /// Write sends a message over the member picked by the bonding mode,
+    /// moving on to the next healthy member if it fails. Returns the
+    /// message's sequence number.
+    pub fn write(&mut self, payload: Bytes, is_string: bool) -> Result<u64, DataChannelError> {
+        let sequence = self.next_sequence;
+        let frame = SequencedFrame {
+            sequence,
+            is_string,
+            payload,
+        };
+        let bytes = frame.marshal()?;
+
+        let first = match self.mode {
+            BondingMode::Stripe => self.next_member,
+            BondingMode::Failover => 0,
+        };
+        let len = self.members.len();
+        for offset in 0..len {
+            let index = (first + offset) % len;
+            let member = &mut self.members[index];
+            if member.failed {
+                continue;
+            }
+
+            let mut bytes = bytes.clone();
+            match member.data_channel.write_data_channel(&mut bytes, false) {
+                Ok(_) => {
+                    self.next_sequence += 1;
+                    self.next_member = (index + 1) % len;
+                    return Ok(sequence);
+                }
+                Err(error) => {
+                    log::warn!(
+                        "Bond member on stream {} failed: {:?}",
+                        member.data_channel.stream_identifier(),
+                        error
+                    );
+                    member.failed = true;
+                }
+            }
+        }
+
+        Err(DataChannelError::BondFailed)
+    }
+
+    /// Read blocks until the next message or gap is due for delivery.
+    ///
+    /// Once all members have failed, the messages held back are delivered,
+    /// after which reads fail with `DataChannelError::BondFailed`.
+    pub fn read(&mut self) -> Result<Delivery, DataChannelError> {
+        blocking::block_on(|cx| self.poll_read(cx))
+    }
+
+    /// PollRead is the non-blocking variant of Read
+    pub fn poll_read(&mut self, cx: &mut Context<'_>) -> Poll<Result<Delivery, DataChannelError>> {
+        loop {
+            if let Some(delivery) = self.reorder_buffer.pop() {
+                return Poll::Ready(Ok(delivery));
+            }
+
+            if self.healthy_members() == 0 {
+                self.reorder_buffer.flush();
+                return match self.reorder_buffer.pop() {
+                    Some(delivery) => Poll::Ready(Ok(delivery)),
+                    None => Poll::Ready(Err(DataChannelError::BondFailed)),
+                };
+            }
+
+            let mut is_pending = true;
+            for member in self.members.iter_mut().filter(|member| !member.failed) {
+                match Self::poll_read_frame(&mut member.data_channel, cx) {
+                    Poll::Ready(Ok(frame)) => {
+                        self.reorder_buffer
+                            .push(frame.sequence, frame.is_string, frame.payload);
+                        is_pending = false;
+                    }
+                    Poll::Ready(Err(DataChannelError::SequencedFrame(error))) => {
+                        log::warn!("Dropping malformed frame: {:?}", error);
+                        is_pending = false;
+                    }
+                    Poll::Ready(Err(error)) => {
+                        log::warn!(
+                            "Bond member on stream {} failed: {:?}",
+                            member.data_channel.stream_identifier(),
+                            error
+                        );
+                        member.failed = true;
+                        is_pending = false;
+                    }
+                    Poll::Pending => {}
+                }
+            }
+            if is_pending {
+                return Poll::Pending;
+            }
+        }
+    }
+
+    /// Flush makes all messages held back behind missing ones due for delivery.
+    pub fn flush(&mut self) {
+        self.reorder_buffer.flush()
+    }
+
+    pub fn reorder_buffer(&self) -> &ReorderBuffer {
+        &self.reorder_buffer
+    }
+
+    pub fn into_inner(self) -> Vec<DataChannel> {
+        self.members
+            .into_iter()
+            .map(|member| member.data_channel)
+            .collect()
+    }
+
+    fn poll_read_frame(
+        data_channel: &mut DataChannel,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<SequencedFrame, DataChannelError>> {
+        let buffer_provider = data_channel.config.buffer_provider.clone();
+        let mut buf = buffer_provider.acquire(RECEIVE_MTU);
+        let result = match data_channel.poll_read_data_channel(cx, &mut buf) {
+            Poll::Ready(result) => result.map(|(n, _)| buf.split_to(n).freeze()),
+            Poll::Pending => {
+                buffer_provider.release(buf);
+                return Poll::Pending;
+            }
+        };
+        buffer_provider.release(buf);
+
+        let mut bytes = result?;
+        Poll::Ready(SequencedFrame::unmarshal_from(&mut bytes).map_err(From::from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{data_channel::Config, testing};
+
+    use super::*;
+
+    fn members(n: usize) -> (Vec<DataChannel>, Vec<DataChannel>) {
+        (0..n)
+            .map(|_| testing::channel_pair(1, Config::default()).unwrap())
+            .unzip()
+    }
+
+    fn read_message(bond: &mut BondedChannel) -> (u64, Bytes) {
+        match bond.read().unwrap() {
+            Delivery::Message {
+                sequence, payload, ..
+            } => (sequence, payload),
+            delivery => panic!("expected a message, got {:?}", delivery),
+        }
+    }
+
+    #[test]
+    fn stripes_across_members() {
+        let (local, remote) = members(2);
+        let mut sender = BondedChannel::new(local, BondingMode::Stripe, 8);
+        let mut receiver = BondedChannel::new(remote, BondingMode::Stripe, 8);
+
+        for payload in ["a", "b", "c", "d"] {
+            sender.write(Bytes::from(payload), true).unwrap();
+        }
+
+        for (expected_sequence, expected) in ["a", "b", "c", "d"].iter().enumerate() {
+            let (sequence, payload) = read_message(&mut receiver);
+            assert_eq!(sequence, expected_sequence as u64);
+            assert_eq!(&payload[..], expected.as_bytes());
+        }
+        // Every other message went over each member.
+        let members = receiver.into_inner();
+        assert_eq!(members[0].messages_received(), 2);
+        assert_eq!(members[1].messages_received(), 2);
+    }
+
+    #[test]
+    fn fails_over_to_next_member() {
+        let (mut local, remote) = members(2);
+        local[0].close().unwrap();
+        let mut sender = BondedChannel::new(local, BondingMode::Failover, 8);
+        let mut receiver = BondedChannel::new(remote, BondingMode::Failover, 8);
+
+        assert_eq!(sender.write(Bytes::from_static(b"hello"), false), Ok(0));
+        assert_eq!(sender.healthy_members(), 1);
+
+        // The closed member fails on the receiving end as well.
+        let (sequence, payload) = read_message(&mut receiver);
+        assert_eq!(sequence, 0);
+        assert_eq!(&payload[..], b"hello");
+    }
+
+    #[test]
+    fn fails_once_all_members_failed() {
+        let (mut local, _remote) = members(1);
+        local[0].close().unwrap();
+        let mut sender = BondedChannel::new(local, BondingMode::Stripe, 8);
+
+        assert_eq!(
+            sender.write(Bytes::from_static(b"hello"), false),
+            Err(DataChannelError::BondFailed)
+        );
+        assert_eq!(sender.read(), Err(DataChannelError::BondFailed));
+    }
+}
@@ -0,0 +1,152 @@
+use std::sync::{Arc, Mutex};
+
+use bytes::BytesMut;
+
+/// A source of buffers for assembling inbound messages.
+pub trait BufferProvider: Send + Sync {
+    /// Returns an empty buffer with room for at least `capacity` bytes.
+    fn acquire(&self, capacity: usize) -> BytesMut;
+
+    /// Hands back a buffer which is no longer needed.
+    ///
+    /// This method has a default implementation which simply drops the buffer.
+    fn release(&self, _buf: BytesMut) {}
+}
+
+/// A `BufferProvider` allocating every buffer from the global allocator.
+#[derive(Eq, PartialEq, Default, Copy, Clone, Debug)]
+pub struct GlobalBufferProvider;
+
+impl BufferProvider for GlobalBufferProvider {
+    fn acquire(&self, capacity: usize) -> BytesMut {
+        BytesMut::with_capacity(capacity)
+    }
+}
+
+/// A `BufferProvider` recycling released buffers, keeping at most `max_pooled` of them.
+#[derive(Debug)]
+pub struct BufferPool {
+    buffers: Mutex<Vec<BytesMut>>,
+    max_pooled: usize,
+}
+
+impl BufferPool {
+    pub fn new(max_pooled: usize) -> Self {
+        Self {
+            buffers: Mutex::new(Vec::with_capacity(max_pooled)),
+            max_pooled,
+        }
+    }
+
+    /// Pooled returns the number of buffers currently available for reuse.
+    pub fn pooled(&self) -> usize {
+        self.buffers.lock().unwrap().len()
+    }
+}
+
+impl BufferProvider for BufferPool {
+    fn acquire(&self, capacity: usize) -> BytesMut {
+        let mut buf = self.buffers.lock().unwrap().pop().unwrap_or_default();
+        buf.reserve(capacity);
+        buf
+    }
+
+    fn release(&self, mut buf: BytesMut) {
+        buf.clear();
+
+        let mut buffers = self.buffers.lock().unwrap();
+        if buffers.len() < self.max_pooled {
+            buffers.push(buf);
+        }
+    }
+}
+
+/// A cloneable handle to a `BufferProvider`, suitable for storing in a `Config`.
+///
+/// Defaults to the `GlobalBufferProvider`. Two handles compare equal if they
+/// point to the same provider.
+#[derive(Clone)]
+pub struct SharedBufferProvider(Arc<dyn BufferProvider>);
+
+impl SharedBufferProvider {
+    pub fn new<P>(provider: P) -> Self
+    where
+        P: BufferProvider + 'static,
+    {
+        Self(Arc::new(provider))
+    }
+
+    pub fn acquire(&self, capacity: usize) -> BytesMut {
+        self.0.acquire(capacity)
+    }
+
+    pub fn release(&self, buf: BytesMut) {
+        self.0.release(buf)
+    }
+}
+
+impl Default for SharedBufferProvider {
+    fn default() -> Self {
+        Self::new(GlobalBufferProvider)
+    }
+}
+
+impl From<Arc<dyn BufferProvider>> for SharedBufferProvider {
+    fn from(provider: Arc<dyn BufferProvider>) -> Self {
+        Self(provider)
+    }
+}
+
+impl PartialEq for SharedBufferProvider {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedBufferProvider {}
+
+impl std::fmt::Debug for SharedBufferProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SharedBufferProvider").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BufMut;
+
+    use super::*;
+
+    #[test]
+    fn global_buffer_provider() {
+        let buf = GlobalBufferProvider.acquire(1024);
+
+        assert!(buf.is_empty());
+        assert!(buf.capacity() >= 1024);
+    }
+
+    #[test]
+    fn buffer_pool_reuses_buffers() {
+        let pool = BufferPool::new(1);
+
+        let mut buf = pool.acquire(64);
+        buf.put_slice(b"hello");
+        pool.release(buf);
+        assert_eq!(pool.pooled(), 1);
+
+        let buf = pool.acquire(64);
+        assert!(buf.is_empty());
+        assert!(buf.capacity() >= 64);
+        assert_eq!(pool.pooled(), 0);
+    }
+
+    #[test]
+    fn buffer_pool_is_bounded() {
+        let pool = BufferPool::new(1);
+
+        pool.release(BytesMut::with_capacity(8));
+        pool.release(BytesMut::with_capacity(8));
+
+        assert_eq!(pool.pooled(), 1);
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+};
+
+#[derive(Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+/// A token for cooperatively stopping the operations of data channels.
+///
+/// Once cancelled, operations on channels configured with the token fail
+/// with `DataChannelError::Cancelled`, and pending non-blocking operations
+/// are woken up. Blocking operations already waiting on the stream are only
+/// stopped once they return.
+///
+/// Clones share the same state. Two tokens compare equal if they are clones
+/// of each other.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel cancels the token and all of its clones, waking pending operations.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Release);
+
+        let wakers = std::mem::take(&mut *self.inner.wakers.lock().unwrap());
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Acquire)
+    }
+
+    /// PollCancelled resolves once the token has been cancelled,
+    /// registering the current task to be woken up otherwise.
+    pub fn poll_cancelled(&self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.is_cancelled() {
+            return Poll::Ready(());
+        }
+
+        {
+            let mut wakers = self.inner.wakers.lock().unwrap();
+            if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                wakers.push(cx.waker().clone());
+            }
+        }
+
+        // The token may have been cancelled while registering.
+        if self.is_cancelled() {
+            return Poll::Ready(());
+        }
+
+        Poll::Pending
+    }
+
+    /// Cancelled returns a future resolving once the token has been cancelled.
+    pub fn cancelled(&self) -> Cancelled {
+        Cancelled {
+            token: self.clone(),
+        }
+    }
+}
+
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for CancellationToken {}
+
+impl std::fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// The future returned by `CancellationToken::cancelled`.
+#[derive(Debug)]
+pub struct Cancelled {
+    token: CancellationToken,
+}
+
+impl Future for Cancelled {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.token.poll_cancelled(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::atomic::AtomicUsize, task::Wake};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn cancel_is_shared_by_clones() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+
+        assert!(!clone.is_cancelled());
+        assert_eq!(token, clone);
+        assert_ne!(token, CancellationToken::new());
+
+        token.cancel();
+
+        assert!(clone.is_cancelled());
+    }
+
+    #[test]
+    fn cancel_wakes_pending_tasks() {
+        let token = CancellationToken::new();
+        let counter = Arc::new(CountingWaker::default());
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut cancelled = token.cancelled();
+        assert_eq!(Pin::new(&mut cancelled).poll(&mut cx), Poll::Pending);
+        assert_eq!(token.poll_cancelled(&mut cx), Poll::Pending);
+
+        token.cancel();
+
+        assert_eq!(counter.0.load(Ordering::Relaxed), 1);
+        assert_eq!(Pin::new(&mut cancelled).poll(&mut cx), Poll::Ready(()));
+    }
+}
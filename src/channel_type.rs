@@ -1,3 +1,5 @@
+use core::fmt;
+
 use bytes::{Buf, BufMut};
 
 use crate::{
@@ -14,10 +16,14 @@ const CHANNEL_TYPE_PARTIAL_RELIABLE_TIMED_UNORDERED: u8 = 0x82;
 
 const CHANNEL_TYPE_LEN: usize = 1;
 
-#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum ChannelType {
     // `Reliable` determines the Data Channel provides a
     // reliable in-order bi-directional communication.
+    #[default]
     Reliable,
     // `ReliableUnordered` determines the Data Channel
     // provides a reliable unordered bi-directional communication.
@@ -41,11 +47,41 @@ pub enum ChannelType {
     // after a specified life-time given in milli- seconds in the Reliability Parameter.
     // This life-time starts when providing the user message to the protocol stack.
     PartialReliableTimedUnordered,
+    // A channel type this crate doesn't know, such as one defined by a future
+    // extension. Only produced by unmarshaling with an `UnmarshalConfig` which
+    // preserves unknown channel types, so that it can be marshaled again as is.
+    Unknown(u8),
+}
+
+impl ChannelType {
+    /// IsOrdered returns whether messages are delivered in the order they were sent.
+    pub fn is_ordered(&self) -> bool {
+        match self {
+            Self::Reliable => true,
+            Self::ReliableUnordered => false,
+            Self::PartialReliableRexmit => true,
+            Self::PartialReliableRexmitUnordered => false,
+            Self::PartialReliableTimed => true,
+            Self::PartialReliableTimedUnordered => false,
+            // The high bit of all known channel types tells them apart.
+            Self::Unknown(byte) => byte & CHANNEL_TYPE_RELIABLE_UNORDERED == 0,
+        }
+    }
 }
 
-impl Default for ChannelType {
-    fn default() -> Self {
-        Self::Reliable
+impl fmt::Display for ChannelType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Reliable => f.write_str("reliable"),
+            Self::ReliableUnordered => f.write_str("reliable_unordered"),
+            Self::PartialReliableRexmit => f.write_str("partial_reliable_rexmit"),
+            Self::PartialReliableRexmitUnordered => {
+                f.write_str("partial_reliable_rexmit_unordered")
+            }
+            Self::PartialReliableTimed => f.write_str("partial_reliable_timed"),
+            Self::PartialReliableTimedUnordered => f.write_str("partial_reliable_timed_unordered"),
+            Self::Unknown(byte) => write!(f, "unknown({:#04x})", byte),
+        }
     }
 }
 
@@ -111,6 +147,7 @@ impl Marshal for ChannelType {
             Self::PartialReliableRexmitUnordered => CHANNEL_TYPE_PARTIAL_RELIABLE_REXMIT_UNORDERED,
             Self::PartialReliableTimed => CHANNEL_TYPE_PARTIAL_RELIABLE_TIMED,
             Self::PartialReliableTimedUnordered => CHANNEL_TYPE_PARTIAL_RELIABLE_TIMED_UNORDERED,
+            Self::Unknown(byte) => *byte,
         };
 
         buf.put_u8(byte);
@@ -175,4 +212,12 @@ mod tests {
         let bytes = buf.freeze();
         assert_eq!(&bytes[..], &[0x00]);
     }
+
+    #[test]
+    fn is_ordered() {
+        assert!(ChannelType::Reliable.is_ordered());
+        assert!(ChannelType::PartialReliableTimed.is_ordered());
+        assert!(!ChannelType::ReliableUnordered.is_ordered());
+        assert!(!ChannelType::PartialReliableRexmitUnordered.is_ordered());
+    }
 }
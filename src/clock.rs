@@ -0,0 +1,279 @@
+//! Telling time for a channel's own operations.
+//!
+//! Channels don't spawn tasks: their operations either run on the calling
+//! thread or are `poll_*` methods driven by whichever executor polls them,
+//! with the `tokio` and `futures` features only adding adapters on top;
+//! work handed off to the background runs on a `runtime::Runtime` instead.
+//! What remains is telling time, which all timeouts, deadlines and pacing
+//! do through the `Clock` of a channel's config; embedders on any executor
+//! can supply their own. Blocking calls park on it, while polled ones wait
+//! on the futures returned by `Clock::delay`.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A future resolving once a `Clock`'s delay has elapsed.
+pub type BoxDelay = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A source of time for all timeouts, deadlines and pacing within the crate.
+///
+/// Replacing the `SystemClock` with a `ManualClock` lets tests fast-forward
+/// through timeouts deterministically instead of waiting in real time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Blocks the current thread for `duration`.
+    fn sleep(&self, duration: Duration);
+
+    /// Blocks the current thread for up to `duration`, or until it is unparked.
+    ///
+    /// Blocking calls with a timeout park on the clock while waiting to be
+    /// woken, so clocks telling real time should park the thread rather
+    /// than sleep through all of `duration`, which is the default.
+    fn park_timeout(&self, duration: Duration) {
+        self.sleep(duration)
+    }
+
+    /// Returns a future resolving once `duration` has elapsed.
+    fn delay(&self, duration: Duration) -> BoxDelay;
+}
+
+/// A `Clock` backed by the operating system.
+#[derive(Eq, PartialEq, Default, Copy, Clone, Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration)
+    }
+
+    fn park_timeout(&self, duration: Duration) {
+        thread::park_timeout(duration)
+    }
+
+    fn delay(&self, duration: Duration) -> BoxDelay {
+        Box::pin(futures_timer::Delay::new(duration))
+    }
+}
+
+#[derive(Debug)]
+struct ManualState {
+    now: Instant,
+    // Tasks waiting on a delay, along with the instant each delay elapses at.
+    timers: Vec<(Instant, Waker)>,
+}
+
+/// A `Clock` which only moves forward when told to.
+///
+/// Sleeping or parking on a manual clock advances it by the given duration right away.
+/// Clones share the same time.
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    state: Arc<Mutex<ManualState>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ManualState {
+                now: Instant::now(),
+                timers: Vec::new(),
+            })),
+        }
+    }
+
+    /// Advance moves the clock forward, waking the tasks whose delays have elapsed.
+    pub fn advance(&self, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.now += duration;
+
+        let now = state.now;
+        let (elapsed, pending) = std::mem::take(&mut state.timers)
+            .into_iter()
+            .partition(|(deadline, _)| *deadline <= now);
+        state.timers = pending;
+        drop(state);
+
+        for (_, waker) in elapsed {
+            waker.wake();
+        }
+    }
+
+    fn poll_until(&self, cx: &mut Context<'_>, deadline: Instant) -> Poll<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.now >= deadline {
+            return Poll::Ready(());
+        }
+
+        // A delay polled again by the same task keeps its one entry.
+        let registered = state
+            .timers
+            .iter_mut()
+            .find(|(timer, waker)| *timer == deadline && waker.will_wake(cx.waker()));
+        match registered {
+            Some((_, waker)) => waker.clone_from(cx.waker()),
+            None => state.timers.push((deadline, cx.waker().clone())),
+        }
+        Poll::Pending
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.state.lock().unwrap().now
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration)
+    }
+
+    fn delay(&self, duration: Duration) -> BoxDelay {
+        Box::pin(ManualDelay {
+            clock: self.clone(),
+            deadline: self.now() + duration,
+        })
+    }
+}
+
+struct ManualDelay {
+    clock: ManualClock,
+    deadline: Instant,
+}
+
+impl Future for ManualDelay {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.clock.poll_until(cx, self.deadline)
+    }
+}
+
+/// A cloneable handle to a `Clock`, suitable for storing in a `Config`.
+///
+/// Defaults to the `SystemClock`. Two handles compare equal if they
+/// point to the same clock.
+#[derive(Clone)]
+pub struct SharedClock(Arc<dyn Clock>);
+
+impl SharedClock {
+    pub fn new<C>(clock: C) -> Self
+    where
+        C: Clock + 'static,
+    {
+        Self(Arc::new(clock))
+    }
+
+    pub fn now(&self) -> Instant {
+        self.0.now()
+    }
+
+    pub fn sleep(&self, duration: Duration) {
+        self.0.sleep(duration)
+    }
+
+    pub fn park_timeout(&self, duration: Duration) {
+        self.0.park_timeout(duration)
+    }
+
+    pub fn delay(&self, duration: Duration) -> BoxDelay {
+        self.0.delay(duration)
+    }
+}
+
+impl Default for SharedClock {
+    fn default() -> Self {
+        Self::new(SystemClock)
+    }
+}
+
+impl From<Arc<dyn Clock>> for SharedClock {
+    fn from(clock: Arc<dyn Clock>) -> Self {
+        Self(clock)
+    }
+}
+
+impl PartialEq for SharedClock {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedClock {}
+
+impl std::fmt::Debug for SharedClock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SharedClock").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::Wake;
+
+    use super::*;
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    #[test]
+    fn manual_clock_advances() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+
+        clock.advance(Duration::from_secs(5));
+        clock.sleep(Duration::from_secs(1));
+
+        assert_eq!(clock.now() - start, Duration::from_secs(6));
+        assert_eq!(clock.clone().now(), clock.now());
+    }
+
+    #[test]
+    fn manual_delay_resolves_on_advance() {
+        let clock = ManualClock::new();
+        let mut delay = clock.delay(Duration::from_secs(30));
+        let mut cx = Context::from_waker(Waker::noop());
+
+        assert_eq!(delay.as_mut().poll(&mut cx), Poll::Pending);
+
+        clock.advance(Duration::from_secs(29));
+        assert_eq!(delay.as_mut().poll(&mut cx), Poll::Pending);
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(delay.as_mut().poll(&mut cx), Poll::Ready(()));
+    }
+
+    #[test]
+    fn manual_delay_registers_once_per_task() {
+        let clock = ManualClock::new();
+        let mut delay = clock.delay(Duration::from_secs(1));
+        let mut other = clock.delay(Duration::from_secs(2));
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+
+        for _ in 0..3 {
+            assert_eq!(delay.as_mut().poll(&mut cx), Poll::Pending);
+            assert_eq!(other.as_mut().poll(&mut cx), Poll::Pending);
+        }
+
+        assert_eq!(clock.state.lock().unwrap().timers.len(), 2);
+    }
+}
@@ -1,54 +1,141 @@
 use std::{
-    io::{Read, Write},
-    sync::atomic::{AtomicUsize, Ordering},
+    convert::TryFrom,
+    io::{IoSlice, Read, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{ready, Context, Poll, Waker},
+    time::{Duration, Instant, SystemTime},
 };
 
 use bytes::{Buf, Bytes, BytesMut};
 use derive_builder::Builder;
 
 use crate::{
-    error::DataChannelError,
+    accept::AcceptOptions,
+    blocking,
+    buffer_provider::SharedBufferProvider,
+    cancellation::CancellationToken,
+    clock::SharedClock,
+    dcep::{DcepHandler, DcepRegistry},
+    error::{ConfigError, DataChannelError, RejectReason},
+    event_log::{EventKind, EventRecord, SharedEventEmitter},
     exact_size_buf::ExactSizeBuf,
+    extensions::Extensions,
+    head_of_line::{HeadOfLineStats, HeadOfLineTracker},
+    io_slices::IoSlices,
+    label::{Label, LabelInterner},
     marshal::{Marshal, Unmarshal},
-    message::{DataChannelOpen, Message},
+    message::{DataChannelOpen, Message, RawMessage},
+    observer::{InFlight, Observed, Observer, Operation},
+    recording::{Direction, SharedRecorder},
     sctp::{self, Association, PayloadType, StreamError},
+    send_failure::{SendFailure, SendFailureAction, SharedSendFailureHandler},
+    send_slot::SendSlot,
+    state::DataChannelState,
+    stats::{ChannelStats, DataChannelStats, StatsStream},
+    supervision::{self, SupervisedTask, SupervisionPolicy, TaskFailure},
+    wire::{MESSAGE_TYPE_ACK, MESSAGE_TYPE_OPEN},
     ChannelType,
 };
 
-const RECEIVE_MTU: usize = 8192;
+#[cfg(feature = "metrics")]
+use crate::metrics;
+#[cfg(feature = "otel")]
+use crate::otel;
+#[cfg(feature = "tracing")]
+use crate::trace;
+
+pub(crate) const RECEIVE_MTU: usize = 8192;
+
+// Sent in place of an empty message, which SCTP can't carry, along with
+// an empty PPID telling the receiver to ignore it (RFC 8831, section 6.6).
+const EMPTY_MESSAGE_PADDING: [u8; 1] = [0];
 
 /// Reader is an extended io.Reader
 /// that also returns if the message is text.
+#[allow(dead_code)]
 trait ChannelReader: Read {
     fn read_data_channel(&mut self); // ([]byte) (int, bool, error)
 }
 
 /// Writer is an extended io.Writer
 /// that also allows indicating if a message is text.
+#[allow(dead_code)]
 trait ChannelWriter: Write {
     fn write_data_channel(&mut self); // []byte, bool) (int, error)
 }
 
 /// ReadWriteCloser is an extended io.ReadWriteCloser
 /// that also implements our Reader and Writer.
+#[allow(dead_code)]
 trait ChannelReadWriteCloser: ChannelReader + ChannelWriter {}
 
 /// DataChannel represents a data channel
 pub struct DataChannel {
-    pub messages_sent: AtomicUsize,
-    pub messages_received: AtomicUsize,
-    pub bytes_sent: AtomicUsize,
-    pub bytes_received: AtomicUsize,
+    pub messages_sent: Arc<AtomicUsize>,
+    pub messages_received: Arc<AtomicUsize>,
+    pub bytes_sent: Arc<AtomicUsize>,
+    pub bytes_received: Arc<AtomicUsize>,
     pub stream: sctp::Stream,
     pub config: Config,
+    // The config's label and protocol, as handed out in events and snapshots.
+    label: Label,
+    protocol: Label,
+    created_at: Instant,
+    // The wall-clock time at `created_at`, which event timestamps are told from.
+    created_at_wall: SystemTime,
+    last_activity: Instant,
+    // Whether the current idle period has already been notified of.
+    idle_notified: bool,
+    extensions: Extensions,
+    // Whether the remote opened the channel by sending a DATA_CHANNEL_OPEN.
+    remote_initiated: bool,
+    ready_state: ReadyState,
+    observed: Arc<Observed>,
+    head_of_line: HeadOfLineTracker,
+    // The unread part of a message, and its payload type, left over by `read_into`.
+    pending_read: Option<(Bytes, PayloadType)>,
+    // An error which ended a batch read, left for the next read to return.
+    pending_error: Option<DataChannelError>,
+    // Polled reads and writes which are pending, counted as in flight.
+    read_in_flight: Option<InFlight>,
+    write_in_flight: Option<InFlight>,
+    // Set once a supervised task failed the channel.
+    failure: Option<TaskFailure>,
+    callbacks: Callbacks,
+    // Whether a DATA_CHANNEL_OPEN was sent but not acknowledged yet,
+    // during which messages are sent ordered whatever the channel type.
+    awaiting_ack: bool,
+    // The reliability settings last committed to the stream,
+    // along with whether messages are sent unordered.
+    committed_reliability: Option<(bool, ChannelType, u32)>,
+}
+
+// The handlers registered with `DataChannel::on_open` and its siblings.
+#[derive(Default)]
+struct Callbacks {
+    on_open: Option<Arc<dyn Fn() + Send + Sync>>,
+    on_message: Option<Arc<dyn Fn(DataChannelMessage) + Send + Sync>>,
+    on_error: Option<Arc<dyn Fn(DataChannelError) + Send + Sync>>,
+    on_close: Option<Arc<dyn Fn() + Send + Sync>>,
+    on_state_change: Option<Arc<dyn Fn(ReadyState) + Send + Sync>>,
 }
 
 impl DataChannel {
-    pub fn new(stream: sctp::Stream, config: Config) -> Self {
-        let messages_sent = AtomicUsize::new(0);
-        let messages_received = AtomicUsize::new(0);
-        let bytes_sent = AtomicUsize::new(0);
-        let bytes_received = AtomicUsize::new(0);
+    pub fn new(mut stream: sctp::Stream, config: Config) -> Self {
+        if let Some(max_receive_buffer) = config.max_receive_buffer {
+            stream.set_receive_buffer_limit(max_receive_buffer, config.receive_buffer_policy);
+        }
+
+        let messages_sent = Arc::new(AtomicUsize::new(0));
+        let messages_received = Arc::new(AtomicUsize::new(0));
+        let bytes_sent = Arc::new(AtomicUsize::new(0));
+        let bytes_received = Arc::new(AtomicUsize::new(0));
+        let created_at = config.clock.now();
+        let label = Label::from(config.label.as_str());
+        let protocol = Label::from(config.protocol.as_str());
 
         Self {
             messages_sent,
@@ -57,6 +144,198 @@ impl DataChannel {
             bytes_received,
             stream,
             config,
+            label,
+            protocol,
+            created_at,
+            created_at_wall: SystemTime::now(),
+            last_activity: created_at,
+            idle_notified: false,
+            extensions: Extensions::new(),
+            remote_initiated: false,
+            ready_state: ReadyState::Connecting,
+            observed: Arc::new(Observed::new(ReadyState::Connecting)),
+            head_of_line: HeadOfLineTracker::default(),
+            pending_read: None,
+            pending_error: None,
+            read_in_flight: None,
+            write_in_flight: None,
+            failure: None,
+            callbacks: Callbacks::default(),
+            awaiting_ack: false,
+            committed_reliability: None,
+        }
+    }
+}
+
+/// DataChannelMessage is a whole message received on a data channel.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct DataChannelMessage {
+    pub is_string: bool,
+    pub data: Bytes,
+    /// The payload protocol identifier the message was sent with,
+    /// telling empty messages apart from others.
+    pub ppid: PayloadType,
+    /// The stream the message was received on.
+    pub stream_identifier: u16,
+}
+
+/// Payload is a message read with `DataChannel::read_payload`, told apart
+/// by its payload protocol identifier.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum Payload {
+    /// Sent as WebRTC String (PPID 51), or WebRTC String Empty.
+    Text(String),
+    /// Sent as WebRTC Binary (PPID 53), or WebRTC Binary Empty.
+    Binary(Bytes),
+}
+
+impl Payload {
+    pub fn is_string(&self) -> bool {
+        matches!(self, Self::Text(_))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Text(text) => text.as_bytes(),
+            Self::Binary(bytes) => bytes,
+        }
+    }
+}
+
+/// ReadyState describes the lifecycle stage of a data channel.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum ReadyState {
+    // The DCEP handshake has not completed yet.
+    Connecting,
+    // The channel can be used to send and receive messages.
+    Open,
+    // The channel is being closed.
+    Closing,
+    // The channel has been closed, or the remote reset its stream.
+    Closed,
+}
+
+/// TruncationPolicy determines how `DataChannel::read_into` handles
+/// messages which don't fit into the provided buffer.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum TruncationPolicy {
+    /// Fails with `DataChannelError::BufferTooSmall`, keeping the message
+    /// so it can be read with a larger buffer.
+    Error,
+    /// Fills the buffer and discards the rest of the message.
+    Truncate,
+    /// Fills the buffer and keeps the rest of the message for the next read.
+    BufferRemainder,
+}
+
+/// The SCTP stream sequence numbers (SSNs) of a channel's stream.
+///
+/// Messages sent unordered don't consume a sequence number,
+/// so these only advance for ordered channels.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub struct SequenceNumbers {
+    /// The SSN the next outbound message will be sent with.
+    pub next_outbound: u16,
+    /// The SSN of the last inbound message delivered,
+    /// or `None` if none has been delivered yet.
+    pub last_delivered: Option<u16>,
+}
+
+/// ReceiveBufferPolicy determines what happens to messages arriving once
+/// the channel's `max_receive_buffer` is reached.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub enum ReceiveBufferPolicy {
+    /// Holds the messages back on the sending end, counting towards its
+    /// buffered amount, until reading makes room for them.
+    #[default]
+    Block,
+    /// Drops the messages, counting them in `ChannelStats::messages_dropped`.
+    DropNewest,
+    /// Drops the messages and closes the channel, failing the next read
+    /// with `StreamError::ReceiveBufferExceeded`.
+    CloseChannel,
+}
+
+/// IdleAction determines what happens to a channel which has been idle
+/// for longer than its `idle_timeout`.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub enum IdleAction {
+    /// Closes the channel.
+    #[default]
+    Close,
+    /// Emits an `EventKind::Idle` event, once per idle period.
+    Notify,
+}
+
+/// The outcome of a successful `DataChannel::read_into`.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub struct ReadInto {
+    /// The number of bytes copied into the buffer.
+    pub n: usize,
+    /// The length of the message, or of its remainder being read.
+    pub message_len: usize,
+    /// The number of bytes of the message kept for the next read.
+    pub remaining: usize,
+    pub is_string: bool,
+}
+
+/// Copies `message` into `buf` according to `policy`,
+/// returning the part of the message to keep for the next read, if any.
+fn copy_message(
+    message: Bytes,
+    is_string: bool,
+    buf: &mut [u8],
+    policy: TruncationPolicy,
+) -> (Result<ReadInto, DataChannelError>, Option<Bytes>) {
+    let message_len = message.len();
+    let n = message_len.min(buf.len());
+
+    let pending = match (message_len > buf.len(), policy) {
+        (false, _) | (true, TruncationPolicy::Truncate) => None,
+        (true, TruncationPolicy::Error) => {
+            let error = DataChannelError::BufferTooSmall {
+                message_len,
+                buffer_len: buf.len(),
+            };
+            return (Err(error), Some(message));
+        }
+        (true, TruncationPolicy::BufferRemainder) => Some(message.slice(n..)),
+    };
+
+    buf[..n].copy_from_slice(&message[..n]);
+
+    let read_into = ReadInto {
+        n,
+        message_len,
+        remaining: pending.as_ref().map_or(0, Bytes::len),
+        is_string,
+    };
+    (Ok(read_into), pending)
+}
+
+/// PayloadFormat determines whether messages written with `DataChannel::write`
+/// are sent as binary or as text.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PayloadFormat {
+    #[default]
+    Binary,
+    Text,
+}
+
+impl PayloadFormat {
+    pub(crate) fn is_string(&self) -> bool {
+        match self {
+            Self::Binary => false,
+            Self::Text => true,
+        }
+    }
+
+    fn payload_type(&self) -> PayloadType {
+        match self {
+            Self::Binary => PayloadType::WebRtcBinary,
+            Self::Text => PayloadType::WebRtcString,
         }
     }
 }
@@ -76,6 +355,216 @@ pub struct Config {
     pub label: String,
     #[builder(default)]
     pub protocol: String,
+    /// The format of messages written with `DataChannel::write`.
+    #[builder(default)]
+    pub default_payload_format: PayloadFormat,
+    /// Supplies the buffers inbound messages are received into,
+    /// as well as those of slots reserved with `DataChannel::send_reserve`.
+    #[builder(default)]
+    pub buffer_provider: SharedBufferProvider,
+    /// Receives structured records of lifecycle events, if set.
+    #[builder(default, setter(strip_option))]
+    pub event_emitter: Option<SharedEventEmitter>,
+    /// Captures every inbound and outbound message, if set.
+    #[builder(default, setter(strip_option))]
+    pub recorder: Option<SharedRecorder>,
+    /// What to do once handling DCEP or recording a message failed.
+    #[builder(default)]
+    pub supervision: SupervisionPolicy,
+    /// Decides what to do about messages which transiently failed to be
+    /// sent, if set. Such messages fail the write otherwise.
+    #[builder(default, setter(strip_option))]
+    pub send_failure_handler: Option<SharedSendFailureHandler>,
+    /// The source of time for the channel's timestamps and timeouts.
+    #[builder(default)]
+    pub clock: SharedClock,
+    /// Stops the channel's operations once cancelled, if set.
+    #[builder(default, setter(strip_option))]
+    pub cancellation_token: Option<CancellationToken>,
+    /// The size of the largest message to send, if set. Defaults to the limit
+    /// advertised by the remote for the association.
+    #[builder(default, setter(strip_option))]
+    pub max_message_size: Option<usize>,
+    /// How long `DataChannel::dial` waits for the whole open sequence, up to
+    /// and including the remote's ACK, if set. Without it, `dial` returns
+    /// as soon as the DATA_CHANNEL_OPEN has been sent.
+    #[builder(default, setter(strip_option))]
+    pub open_timeout: Option<Duration>,
+    /// How many times `DataChannel::dial` re-sends the DATA_CHANNEL_OPEN if
+    /// no ACK arrives within `open_timeout`, before giving up.
+    #[builder(default)]
+    pub open_retries: u32,
+    /// How long the channel may go without traffic in either direction
+    /// before `idle_action` is taken, if set.
+    #[builder(default, setter(strip_option))]
+    pub idle_timeout: Option<Duration>,
+    #[builder(default)]
+    pub idle_action: IdleAction,
+    /// Handles DCEP messages of experimental types, if set.
+    #[builder(default, setter(strip_option))]
+    pub dcep_registry: Option<DcepRegistry>,
+    /// The most bytes of received messages queued on the channel's stream
+    /// waiting to be read, if limited.
+    #[builder(default, setter(strip_option))]
+    pub max_receive_buffer: Option<usize>,
+    /// What happens to messages arriving once `max_receive_buffer` is reached.
+    #[builder(default)]
+    pub receive_buffer_policy: ReceiveBufferPolicy,
+}
+
+/// RuntimeConfig holds the settings which may be changed while a channel is open.
+///
+/// Settings left at `None` keep their current value.
+#[derive(Eq, PartialEq, Default, Clone, Debug)]
+pub struct RuntimeConfig {
+    /// See `DataChannel::set_buffered_amount_low_threshold`.
+    pub buffered_amount_low_threshold: Option<u64>,
+    /// See `Config::idle_timeout`.
+    pub idle_timeout: Option<Duration>,
+}
+
+/// DataChannelBuilder assembles a `Config` through fluent setters,
+/// validating the reliability options against each other on `build`.
+///
+/// Channels are reliable and ordered unless told otherwise.
+#[derive(Clone, Debug)]
+pub struct DataChannelBuilder {
+    config: Config,
+    ordered: bool,
+    max_retransmits: Option<u32>,
+    max_packet_lifetime: Option<Duration>,
+    negotiated: Option<u16>,
+}
+
+impl Default for DataChannelBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataChannelBuilder {
+    pub fn new() -> Self {
+        Self::with_config(Config::default())
+    }
+
+    /// WithConfig starts from the given config, for the settings the builder
+    /// has no setter for. Its reliability settings are replaced on `build`.
+    pub fn with_config(config: Config) -> Self {
+        Self {
+            config,
+            ordered: true,
+            max_retransmits: None,
+            max_packet_lifetime: None,
+            negotiated: None,
+        }
+    }
+
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.config.label = label.into();
+        self
+    }
+
+    pub fn protocol(mut self, protocol: impl Into<String>) -> Self {
+        self.config.protocol = protocol.into();
+        self
+    }
+
+    pub fn ordered(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+
+    /// MaxRetransmits limits how often a message is retransmitted.
+    /// Mutually exclusive with `max_packet_lifetime`.
+    pub fn max_retransmits(mut self, max_retransmits: u32) -> Self {
+        self.max_retransmits = Some(max_retransmits);
+        self
+    }
+
+    /// MaxPacketLifetime limits how long a message is (re)transmitted for,
+    /// at millisecond precision. Mutually exclusive with `max_retransmits`.
+    pub fn max_packet_lifetime(mut self, max_packet_lifetime: Duration) -> Self {
+        self.max_packet_lifetime = Some(max_packet_lifetime);
+        self
+    }
+
+    /// Negotiated marks the channel as negotiated out-of-band, on the given stream.
+    pub fn negotiated(mut self, stream_identifier: u16) -> Self {
+        self.negotiated = Some(stream_identifier);
+        self
+    }
+
+    pub fn priority(mut self, priority: u16) -> Self {
+        self.config.priority = priority;
+        self
+    }
+
+    /// NegotiatedStreamIdentifier returns the stream given to `negotiated`, if any.
+    pub fn negotiated_stream_identifier(&self) -> Option<u16> {
+        self.negotiated
+    }
+
+    /// Build returns the assembled config.
+    pub fn build(&self) -> Result<Config, ConfigError> {
+        let (channel_type, reliability_parameter) =
+            match (self.max_retransmits, self.max_packet_lifetime) {
+                (Some(_), Some(_)) => return Err(ConfigError::ConflictingReliability),
+                (Some(max_retransmits), None) => (
+                    if self.ordered {
+                        ChannelType::PartialReliableRexmit
+                    } else {
+                        ChannelType::PartialReliableRexmitUnordered
+                    },
+                    max_retransmits,
+                ),
+                (None, Some(lifetime)) => (
+                    if self.ordered {
+                        ChannelType::PartialReliableTimed
+                    } else {
+                        ChannelType::PartialReliableTimedUnordered
+                    },
+                    u32::try_from(lifetime.as_millis())
+                        .map_err(|_| ConfigError::PacketLifetimeOutOfRange { lifetime })?,
+                ),
+                (None, None) => (
+                    if self.ordered {
+                        ChannelType::Reliable
+                    } else {
+                        ChannelType::ReliableUnordered
+                    },
+                    0,
+                ),
+            };
+
+        Ok(Config {
+            channel_type,
+            reliability_parameter,
+            negotiated: self.negotiated.is_some(),
+            ..self.config.clone()
+        })
+    }
+
+    /// Dial builds the config and opens a channel with it on the given stream,
+    /// which must be the agreed one for negotiated channels. Negotiated channels
+    /// are opened with `DataChannel::open_negotiated`.
+    pub fn dial(
+        &self,
+        association: &Association,
+        identifier: u16,
+    ) -> Result<DataChannel, DataChannelError> {
+        let config = self.build()?;
+        match self.negotiated {
+            Some(negotiated) if negotiated != identifier => {
+                Err(ConfigError::NegotiatedStreamIdentifierMismatch {
+                    negotiated,
+                    requested: identifier,
+                }
+                .into())
+            }
+            Some(_) => DataChannel::open_negotiated(association, identifier, config),
+            None => DataChannel::dial(association, identifier, config),
+        }
+    }
 }
 
 impl DataChannel {
@@ -85,273 +574,3298 @@ impl DataChannel {
         identifier: u16,
         config: Config,
     ) -> Result<Self, DataChannelError> {
-        let stream = association.open_stream(identifier, PayloadType::WebRtcBinary)?;
+        let started_at = config.clock.now();
+        let open_timeout = config.open_timeout;
+
+        let stream =
+            association.open_stream(identifier, config.default_payload_format.payload_type())?;
+        let mut data_channel = Self::client(stream, config)?;
+
+        if let Some(open_timeout) = open_timeout {
+            data_channel.wait_open(started_at, open_timeout)?;
+        }
+
+        Ok(data_channel)
+    }
+
+    /// Blocks until the DCEP handshake has completed, re-sending the
+    /// DATA_CHANNEL_OPEN up to `open_retries` times whenever `timeout` passes
+    /// without an ACK. Once out of retries, resets the stream and fails with
+    /// `DataChannelError::HandshakeTimedOut`.
+    fn wait_open(
+        &mut self,
+        started_at: Instant,
+        timeout: Duration,
+    ) -> Result<(), DataChannelError> {
+        let clock = self.config.clock.clone();
+        let mut deadline = started_at + timeout;
+        let mut retries = self.config.open_retries;
+
+        loop {
+            if let Some(result) = blocking::poll_until(&clock, deadline, |cx| self.poll_open(cx)) {
+                return result;
+            }
+
+            let now = clock.now();
+            if retries > 0 {
+                retries -= 1;
+                log::debug!(
+                    "Re-sending DATA_CHANNEL_OPEN on stream {}, {} retries left",
+                    self.stream_identifier(),
+                    retries
+                );
+                self.send_open()?;
+                deadline = now + timeout;
+                continue;
+            }
+
+            let elapsed = now.saturating_duration_since(started_at);
+            log::debug!(
+                "Handshake on stream {} timed out after {:?}",
+                self.stream_identifier(),
+                elapsed
+            );
 
-        Self::client(stream, config)
+            if let Err(error) = self.close() {
+                log::warn!("Failed to reset stream: {:?}", error);
+            }
+            return Err(DataChannelError::HandshakeTimedOut { elapsed });
+        }
     }
 
     /// Accept is used to accept incoming data channels over SCTP
+    ///
+    /// Malformed DATA_CHANNEL_OPEN messages get the stream reset, failing
+    /// with `DataChannelError::OpenRejected`.
     pub fn accept(association: &Association, config: Config) -> Result<Self, DataChannelError> {
+        Self::accept_with_options(association, config, &AcceptOptions::default())
+    }
+
+    /// AcceptWithOptions is like `accept`, but also rejects channels whose
+    /// label or protocol aren't allowed by `options`.
+    pub fn accept_with_options(
+        association: &Association,
+        config: Config,
+        options: &AcceptOptions,
+    ) -> Result<Self, DataChannelError> {
         let mut stream = association.accept_stream()?;
 
-        stream.set_default_payload_type(PayloadType::WebRtcBinary);
+        stream.set_default_payload_type(config.default_payload_format.payload_type());
+
+        let mut data_channel = DataChannel::new(stream, config);
+        data_channel.receive_open(options)?;
+
+        Ok(data_channel)
+    }
+
+    /// AcceptTimeout is like `accept`, but gives up with `DataChannelError::Timeout`
+    /// if the remote hasn't opened a stream within `timeout`, as measured by
+    /// the config's clock. The handshake on an accepted stream isn't limited.
+    pub fn accept_timeout(
+        association: &Association,
+        config: Config,
+        timeout: Duration,
+    ) -> Result<Self, DataChannelError> {
+        let clock = config.clock.clone();
+        let deadline = clock.now() + timeout;
+
+        let accepted = blocking::poll_until(&clock, deadline, |cx| {
+            if let Some(token) = &config.cancellation_token {
+                if token.poll_cancelled(cx).is_ready() {
+                    return Poll::Ready(Err(DataChannelError::Cancelled));
+                }
+            }
+
+            association
+                .poll_accept_stream(cx)
+                .map_err(DataChannelError::from)
+        });
+        let mut stream = accepted.ok_or(DataChannelError::Timeout)??;
+
+        stream.set_default_payload_type(config.default_payload_format.payload_type());
 
         Self::server(stream, config)
     }
 
+    /// OpenNegotiated opens a channel negotiated out-of-band on the agreed
+    /// stream, without any DCEP handshake. Both ends open the same stream.
+    pub fn open_negotiated(
+        association: &Association,
+        identifier: u16,
+        config: Config,
+    ) -> Result<Self, DataChannelError> {
+        let stream =
+            association.open_stream(identifier, config.default_payload_format.payload_type())?;
+
+        Self::accept_negotiated(stream, config)
+    }
+
+    /// AcceptNegotiated binds a channel negotiated out-of-band to the given
+    /// stream, neither sending nor waiting for DCEP messages. The channel is
+    /// open right away.
+    pub fn accept_negotiated(
+        mut stream: sctp::Stream,
+        mut config: Config,
+    ) -> Result<Self, DataChannelError> {
+        config.negotiated = true;
+        stream.set_default_payload_type(config.default_payload_format.payload_type());
+
+        let mut data_channel = DataChannel::new(stream, config);
+        data_channel.set_ready_state(ReadyState::Open);
+
+        Ok(data_channel)
+    }
+
     /// Client opens a data channel over an SCTP stream
-    pub fn client(mut stream: sctp::Stream, config: Config) -> Result<Self, DataChannelError> {
-        if !config.negotiated {
-            let open = Message::DataChannelOpen(DataChannelOpen {
-                channel_type: config.channel_type,
-                priority: config.priority,
-                reliability_parameter: config.reliability_parameter,
-                label: config.label.bytes().collect(),
-                protocol: config.protocol.bytes().collect(),
-            });
-            let mut open_bytes = open.marshal()?;
-            stream.write_sctp(&mut open_bytes, PayloadType::WebRtcDcep)?;
-        }
-        Ok(DataChannel::new(stream, config))
+    pub fn client(stream: sctp::Stream, config: Config) -> Result<Self, DataChannelError> {
+        let mut data_channel = DataChannel::new(stream, config);
+
+        data_channel.send_open()?;
+
+        Ok(data_channel)
     }
 
     // Server accepts a data channel over an SCTP stream
-    pub fn server(mut stream: sctp::Stream, mut config: Config) -> Result<Self, DataChannelError> {
-        let mut buf = BytesMut::with_capacity(RECEIVE_MTU);
+    pub fn server(stream: sctp::Stream, config: Config) -> Result<Self, DataChannelError> {
+        let mut data_channel = DataChannel::new(stream, config);
 
-        let (n, ppi) = stream.read_sctp(&mut buf)?;
+        data_channel.receive_open(&AcceptOptions::default())?;
 
-        if ppi != sctp::PayloadType::WebRtcDcep {
-            return Err(DataChannelError::InvalidPayloadProtocolIdentifier {
-                invalid_identifier: ppi,
+        Ok(data_channel)
+    }
+
+    /// Rebind moves the channel onto a replacement association to the same
+    /// peer, re-opening its stream and re-sending the DATA_CHANNEL_OPEN,
+    /// while keeping its configuration, counters and extensions.
+    ///
+    /// Channels opened by the remote can only be rebound once the remote
+    /// re-opens them; use `rebind_all` for those.
+    pub fn rebind(&mut self, association: &Association) -> Result<(), DataChannelError> {
+        if self.remote_initiated {
+            return Err(DataChannelError::RemoteInitiatedChannel {
+                stream_identifier: self.stream_identifier(),
             });
         }
 
-        let mut buf = Bytes::copy_from_slice(buf.get(..n).unwrap());
-        let open = Message::unmarshal_from(&mut buf)?;
+        let identifier = self.stream_identifier();
+        self.stream = association.open_stream(
+            identifier,
+            self.config.default_payload_format.payload_type(),
+        )?;
 
-        if let Message::DataChannelOpen(open) = open {
-            config.channel_type = open.channel_type;
-            config.priority = open.priority;
-            config.reliability_parameter = open.reliability_parameter;
-            config.label = String::from_utf8(open.label)?;
-            config.protocol = String::from_utf8(open.protocol)?;
-        } else {
-            return Err(DataChannelError::InvalidMessageType {
-                invalid_type: open.message_type(),
-            });
-        };
+        self.send_open()
+    }
 
-        let mut data_channel = DataChannel::new(stream, config);
+    /// RebindAll moves all channels of a failed association onto its replacement.
+    ///
+    /// Locally opened and negotiated channels are rebound right away. Channels
+    /// opened by the remote are then matched, by stream identifier, against the
+    /// streams accepted on the new association as the remote re-opens them.
+    pub fn rebind_all(
+        association: &Association,
+        data_channels: &mut [DataChannel],
+    ) -> Result<(), DataChannelError> {
+        let mut pending = 0;
+        for data_channel in data_channels.iter_mut() {
+            if data_channel.remote_initiated {
+                pending += 1;
+            } else {
+                data_channel.rebind(association)?;
+            }
+        }
 
-        data_channel.write_data_channel_ack()?;
+        while pending > 0 {
+            let mut stream = association.accept_stream()?;
+            let stream_identifier = stream.stream_identifier();
 
-        data_channel.commit_reliability_params()?;
+            let data_channel = data_channels
+                .iter_mut()
+                .find(|data_channel| {
+                    data_channel.remote_initiated
+                        && data_channel.stream_identifier() == stream_identifier
+                })
+                .ok_or(DataChannelError::UnknownStreamIdentifier { stream_identifier })?;
 
-        Ok(data_channel)
-    }
+            stream.set_default_payload_type(
+                data_channel.config.default_payload_format.payload_type(),
+            );
+            data_channel.stream = stream;
+            data_channel.receive_open(&AcceptOptions::default())?;
 
-    /// Read reads a packet of len(p) bytes as binary data
-    pub fn read(&mut self, buf: &mut BytesMut) -> Result<usize, DataChannelError> {
-        self.read_data_channel(buf).map(|(n, _)| n)
+            pending -= 1;
+        }
+
+        Ok(())
     }
 
-    /// ReadDataChannel reads a packet of len(p) bytes
-    pub fn read_data_channel(
-        &mut self,
-        buf: &mut BytesMut,
-    ) -> Result<(usize, bool), DataChannelError> {
-        loop {
-            let (n, ppi) = match self.stream.read_sctp(buf) {
-                Ok((n, ppi)) => (n, ppi),
-                Err(error @ StreamError::Eof) => {
-                    // When the peer sees that an incoming stream was
-                    // reset, it also resets its corresponding outgoing stream.
-                    self.stream.close()?;
+    pub(crate) fn send_open(&mut self) -> Result<(), DataChannelError> {
+        if self.config.negotiated {
+            self.set_ready_state(ReadyState::Open);
+            return Ok(());
+        }
 
-                    return Err(error.into());
-                }
-            };
+        self.set_ready_state(ReadyState::Connecting);
+        // RFC 8832, section 6: messages sent before the ACK arrives must be
+        // sent ordered, so that none of them overtakes the DATA_CHANNEL_OPEN.
+        self.awaiting_ack = true;
+        self.committed_reliability = None;
 
-            let bytes_len = match (n, &ppi) {
-                (n, &PayloadType::WebRtcDcep) => {
-                    let mut buf = Bytes::copy_from_slice(buf.get(..n).unwrap());
-                    match self.handle_dcep(&mut buf) {
-                        Ok(()) => {}
-                        Err(error) => {
-                            log::error!("Failed to handle DCEP: {:?}", error);
-                        }
-                    }
-                    continue;
-                }
-                (_, ppi) if ppi.is_empty() => 0,
-                (n, _) => n,
-            };
+        #[cfg(feature = "otel")]
+        let span = otel::start_span("data_channel.open", self.stream_identifier());
 
-            self.messages_received.fetch_add(1, Ordering::Relaxed);
-            self.bytes_received.fetch_add(bytes_len, Ordering::Relaxed);
+        let result = self.write_data_channel_open();
 
-            let is_string = ppi.is_string();
+        #[cfg(feature = "otel")]
+        otel::end_span(span, &self.config.label, &result);
 
-            return Ok((bytes_len, is_string));
+        match &result {
+            Ok(_) => self.emit_event(EventKind::OpenSent, None),
+            Err(error) => self.emit_event(EventKind::Failed, Some(error)),
         }
-    }
 
-    /// MessagesSent returns the number of messages sent
-    pub fn messages_sent(&self) -> usize {
-        self.messages_sent.load(Ordering::Relaxed)
+        result.map(|_| ())
     }
 
-    /// MessagesReceived returns the number of messages received
-    pub fn messages_received(&self) -> usize {
-        self.messages_received.load(Ordering::Relaxed)
-    }
+    // Starts accepting a channel on a stream opened by the remote, which
+    // is connecting until `poll_receive_open` has read its DATA_CHANNEL_OPEN.
+    pub(crate) fn accepting(mut stream: sctp::Stream, config: Config) -> Self {
+        stream.set_default_payload_type(config.default_payload_format.payload_type());
 
-    /// BytesSent returns the number of bytes sent
-    pub fn bytes_sent(&self) -> usize {
-        self.bytes_sent.load(Ordering::Relaxed)
+        let mut data_channel = DataChannel::new(stream, config);
+        data_channel.remote_initiated = true;
+        data_channel.set_ready_state(ReadyState::Connecting);
+
+        data_channel
     }
 
-    /// BytesReceived returns the number of bytes received
-    pub fn bytes_received(&self) -> usize {
-        self.bytes_received.load(Ordering::Relaxed)
+    fn receive_open(&mut self, options: &AcceptOptions) -> Result<(), DataChannelError> {
+        self.remote_initiated = true;
+        self.set_ready_state(ReadyState::Connecting);
+
+        let mut buf = self.config.buffer_provider.acquire(RECEIVE_MTU);
+        let read = self.stream.read_sctp(&mut buf);
+
+        self.handle_open(buf, read, options)
     }
 
-    /// StreamIdentifier returns the Stream identifier associated to the stream.
-    pub fn stream_identifier(&self) -> u16 {
-        self.stream.stream_identifier()
+    /// The non-blocking variant of `receive_open`, for channels started with `accepting`.
+    pub(crate) fn poll_receive_open(
+        &mut self,
+        cx: &mut Context<'_>,
+        options: &AcceptOptions,
+    ) -> Poll<Result<(), DataChannelError>> {
+        let mut buf = self.config.buffer_provider.acquire(RECEIVE_MTU);
+        let read = match self.stream.poll_read_sctp(cx, &mut buf) {
+            Poll::Ready(read) => read,
+            Poll::Pending => {
+                self.config.buffer_provider.release(buf);
+                return Poll::Pending;
+            }
+        };
+
+        Poll::Ready(self.handle_open(buf, read, options))
     }
 
-    pub fn handle_dcep<B>(&mut self, bytes: &mut B) -> Result<(), DataChannelError>
-    where
-        B: Buf,
-    {
-        let message = Message::unmarshal_from(bytes)?;
+    // Answers the DATA_CHANNEL_OPEN read into `buf` with an ACK,
+    // resetting the stream if it got rejected.
+    fn handle_open(
+        &mut self,
+        buf: BytesMut,
+        read: Result<(usize, PayloadType), StreamError>,
+        options: &AcceptOptions,
+    ) -> Result<(), DataChannelError> {
+        #[cfg(feature = "otel")]
+        let span = otel::start_span("data_channel.handshake", self.stream_identifier());
 
-        match message {
-            Message::DataChannelAck => {
-                log::debug!("Received DATA_CHANNEL_ACK");
+        let result = self.handshake(buf, read, options);
 
-                self.commit_reliability_params()
+        #[cfg(feature = "otel")]
+        otel::end_span(span, &self.config.label, &result);
+
+        if let Err(error) = &result {
+            if let DataChannelError::OpenRejected { reason, .. } = error {
+                log::debug!(
+                    "Rejecting channel on stream {}: {:?}",
+                    self.stream_identifier(),
+                    reason
+                );
+                if let Err(error) = self.stream.close() {
+                    log::warn!("Failed to reset stream: {:?}", error);
+                }
+                self.set_ready_state(ReadyState::Closed);
             }
-            message => Err(DataChannelError::InvalidMessageType {
-                invalid_type: message.message_type(),
-            }),
+            self.emit_event(EventKind::Failed, Some(error));
         }
-    }
 
-    /// Write writes len(p) bytes from p as binary data
-    pub fn write<B>(&mut self, bytes: &mut B) -> Result<usize, DataChannelError>
-    where
-        B: Buf + ExactSizeBuf,
-    {
-        self.write_data_channel(bytes, false)
+        result
     }
 
-    /// WriteDataChannel writes len(p) bytes from p
-    pub fn write_data_channel<B>(
+    fn handshake(
         &mut self,
-        bytes: &mut B,
-        is_string: bool,
-    ) -> Result<usize, DataChannelError>
-    where
-        B: Buf + ExactSizeBuf,
-    {
-        let bytes_len = bytes.len();
+        buf: BytesMut,
+        read: Result<(usize, PayloadType), StreamError>,
+        options: &AcceptOptions,
+    ) -> Result<(), DataChannelError> {
+        let (n, ppi) = match read {
+            Ok(read) => read,
+            Err(error) => {
+                self.config.buffer_provider.release(buf);
+                return Err(error.into());
+            }
+        };
+        let raw = buf[..n].to_vec();
+        self.config.buffer_provider.release(buf);
 
-        // https://tools.ietf.org/html/draft-ietf-rtcweb-data-channel-12#section-6.6
-        // SCTP does not support the sending of empty user messages.  Therefore,
-        // if an empty message has to be sent, the appropriate PPID (WebRTC
-        // String Empty or WebRTC Binary Empty) is used and the SCTP user
-        // message of one zero byte is sent.  When receiving an SCTP user
-        // message with one of these PPIDs, the receiver MUST ignore the SCTP
-        // user message and process it as an empty message.
-        let ppi = match (is_string, bytes_len) {
-            (false, 0) => sctp::PayloadType::WebRtcBinaryEmpty,
-            (false, _) => sctp::PayloadType::WebRtcBinary,
-            (true, 0) => sctp::PayloadType::WebRtcStringEmpty,
-            (true, _) => sctp::PayloadType::WebRtcString,
+        let open = match Self::parse_open(&raw, ppi) {
+            Ok(open) => open,
+            Err(error) => {
+                let reason = RejectReason::Malformed {
+                    cause: error.to_string().trim_end().to_owned(),
+                };
+                return Err(DataChannelError::OpenRejected { reason, raw });
+            }
         };
+        let label = String::from_utf8(open.label).map_err(|error| {
+            let reason = RejectReason::Malformed {
+                cause: format!("Label is not UTF-8: {}", error),
+            };
+            DataChannelError::OpenRejected {
+                reason,
+                raw: raw.clone(),
+            }
+        })?;
+        let protocol = String::from_utf8(open.protocol).map_err(|error| {
+            let reason = RejectReason::Malformed {
+                cause: format!("Protocol is not UTF-8: {}", error),
+            };
+            DataChannelError::OpenRejected {
+                reason,
+                raw: raw.clone(),
+            }
+        })?;
+        if let Err(reason) = options.check(&label, &protocol) {
+            return Err(DataChannelError::OpenRejected { reason, raw });
+        }
+
+        self.config.channel_type = open.channel_type;
+        self.config.priority = open.priority;
+        self.config.reliability_parameter = open.reliability_parameter;
+        self.config.label = label;
+        self.config.protocol = protocol;
+        self.label = Label::from(self.config.label.as_str());
+        self.protocol = Label::from(self.config.protocol.as_str());
+
+        self.emit_event(EventKind::OpenReceived, None);
+
+        self.write_data_channel_ack()?;
+
+        self.emit_event(EventKind::AckSent, None);
+
+        self.commit_reliability_params()?;
+
+        self.set_ready_state(ReadyState::Open);
+
+        Ok(())
+    }
+
+    fn parse_open(raw: &[u8], ppi: PayloadType) -> Result<DataChannelOpen, DataChannelError> {
+        if ppi != sctp::PayloadType::WebRtcDcep {
+            return Err(DataChannelError::InvalidPayloadProtocolIdentifier {
+                invalid_identifier: ppi,
+            });
+        }
+
+        match Message::unmarshal_from(&mut &raw[..]).map_err(unmarshal_failed)? {
+            Message::DataChannelOpen(open) => Ok(open),
+            message => Err(DataChannelError::InvalidMessageType {
+                invalid_type: message.message_type(),
+            }),
+        }
+    }
+
+    /// Restore re-creates a data channel from state exported by `export_state`,
+    /// taking over its stream from an association that has been handed off
+    /// by another process. No DCEP messages are exchanged.
+    ///
+    /// The channel parameters of `config` are overwritten by those of `state`.
+    pub fn restore(
+        association: &Association,
+        state: &DataChannelState,
+        mut config: Config,
+    ) -> Result<Self, DataChannelError> {
+        let stream = association.restore_stream(state.stream_identifier)?;
+
+        state.apply_to(&mut config);
+
+        let mut data_channel = DataChannel::new(stream, config);
+        data_channel.remote_initiated = state.remote_initiated;
+        data_channel
+            .messages_sent
+            .store(state.messages_sent as usize, Ordering::Relaxed);
+        data_channel
+            .messages_received
+            .store(state.messages_received as usize, Ordering::Relaxed);
+        data_channel
+            .bytes_sent
+            .store(state.bytes_sent as usize, Ordering::Relaxed);
+        data_channel
+            .bytes_received
+            .store(state.bytes_received as usize, Ordering::Relaxed);
+
+        data_channel.commit_reliability_params()?;
+
+        data_channel.set_ready_state(ReadyState::Open);
+
+        Ok(data_channel)
+    }
+
+    /// ExportState returns the state needed to `restore` this channel in another process.
+    pub fn export_state(&self) -> DataChannelState {
+        DataChannelState {
+            stream_identifier: self.stream_identifier(),
+            channel_type: self.config.channel_type,
+            negotiated: self.config.negotiated,
+            remote_initiated: self.remote_initiated,
+            priority: self.config.priority,
+            reliability_parameter: self.config.reliability_parameter,
+            label: self.config.label.clone(),
+            protocol: self.config.protocol.clone(),
+            default_payload_format: self.config.default_payload_format,
+            messages_sent: self.messages_sent() as u64,
+            messages_received: self.messages_received() as u64,
+            bytes_sent: self.bytes_sent() as u64,
+            bytes_received: self.bytes_received() as u64,
+        }
+    }
+
+    /// Read reads a packet of len(p) bytes as binary data
+    ///
+    /// `buf` grows as needed to hold the message; use `read_into`
+    /// to read into a buffer of fixed size instead.
+    pub fn read(&mut self, buf: &mut BytesMut) -> Result<usize, DataChannelError> {
+        self.read_data_channel(buf).map(|(n, _)| n)
+    }
+
+    /// ReadInto reads a message into `buf`, handling messages larger
+    /// than `buf` according to `policy`.
+    pub fn read_into(
+        &mut self,
+        buf: &mut [u8],
+        policy: TruncationPolicy,
+    ) -> Result<ReadInto, DataChannelError> {
+        let (message, ppi) = match self.pending_read.take() {
+            Some(pending_read) => pending_read,
+            None => {
+                let buffer_provider = self.config.buffer_provider.clone();
+                let mut message = buffer_provider.acquire(RECEIVE_MTU.max(buf.len()));
+                let result = self
+                    .read_message_into(&mut message)
+                    .map(|(n, ppi)| (message.split_to(n).freeze(), ppi));
+                buffer_provider.release(message);
+                result?
+            }
+        };
+
+        let (result, pending) = copy_message(message, ppi.is_string(), buf, policy);
+        self.pending_read = pending.map(|pending| (pending, ppi));
+
+        result
+    }
+
+    /// Moves a message left over by `read_into` into `buf`, if any.
+    fn take_pending_read(&mut self, buf: &mut BytesMut) -> Option<(usize, PayloadType)> {
+        let (message, ppi) = self.pending_read.take()?;
+
+        buf.clear();
+        buf.extend_from_slice(&message[..]);
+
+        Some((message.len(), ppi))
+    }
+
+    /// ReadDataChannel reads a packet of len(p) bytes
+    pub fn read_data_channel(
+        &mut self,
+        buf: &mut BytesMut,
+    ) -> Result<(usize, bool), DataChannelError> {
+        self.read_message_into(buf)
+            .map(|(n, ppi)| (n, ppi.is_string()))
+    }
+
+    // Like `read_data_channel`, but returns the payload type the message was sent with.
+    fn read_message_into(
+        &mut self,
+        buf: &mut BytesMut,
+    ) -> Result<(usize, PayloadType), DataChannelError> {
+        if let Some(read) = self.take_pending_read(buf) {
+            return Ok(read);
+        }
+
+        let _in_flight = self.start_in_flight(Operation::Read);
+        loop {
+            self.check_cancelled()?;
+
+            let (n, ppi) = match self.stream.read_sctp(buf) {
+                Ok((n, ppi)) => (n, ppi),
+                Err(error) => return Err(self.handle_read_error(error)),
+            };
+
+            if let Some(n) = self.process_read(buf, n, ppi) {
+                return Ok((n, ppi));
+            }
+        }
+    }
+
+    /// ReadTimeout is like `read_data_channel`, but gives up with
+    /// `DataChannelError::Timeout` if no message arrived within `timeout`,
+    /// as measured by the config's clock.
+    pub fn read_timeout(
+        &mut self,
+        buf: &mut BytesMut,
+        timeout: Duration,
+    ) -> Result<(usize, bool), DataChannelError> {
+        let clock = self.config.clock.clone();
+        let deadline = clock.now() + timeout;
+
+        match blocking::poll_until(&clock, deadline, |cx| self.poll_read_data_channel(cx, buf)) {
+            Some(result) => result,
+            None => {
+                self.read_in_flight = None;
+                Err(DataChannelError::Timeout)
+            }
+        }
+    }
+
+    /// PollReadDataChannel is the non-blocking variant of ReadDataChannel
+    pub fn poll_read_data_channel(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut BytesMut,
+    ) -> Poll<Result<(usize, bool), DataChannelError>> {
+        self.poll_read_message_into(cx, buf)
+            .map_ok(|(n, ppi)| (n, ppi.is_string()))
+    }
+
+    // Like `poll_read_data_channel`, but returns the payload type the message was sent with.
+    pub(crate) fn poll_read_message_into(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut BytesMut,
+    ) -> Poll<Result<(usize, PayloadType), DataChannelError>> {
+        if let Some(read) = self.take_pending_read(buf) {
+            return Poll::Ready(Ok(read));
+        }
+
+        let in_flight = self.start_in_flight(Operation::Read);
+        let poll = self.poll_read_stream(cx, buf);
+        self.keep_in_flight(in_flight, &poll);
+        poll
+    }
+
+    fn poll_read_stream(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut BytesMut,
+    ) -> Poll<Result<(usize, PayloadType), DataChannelError>> {
+        loop {
+            ready!(self.poll_check_cancelled(cx))?;
+
+            let (n, ppi) = match ready!(self.stream.poll_read_sctp(cx, buf)) {
+                Ok((n, ppi)) => (n, ppi),
+                Err(error) => return Poll::Ready(Err(self.handle_read_error(error))),
+            };
+
+            if let Some(n) = self.process_read(buf, n, ppi) {
+                return Poll::Ready(Ok((n, ppi)));
+            }
+        }
+    }
+
+    /// ReadBytes reads a message, handing back its payload as received from
+    /// SCTP instead of copying it into a buffer, and whether it is text.
+    pub fn read_bytes(&mut self) -> Result<(Bytes, bool), DataChannelError> {
+        self.read_message()
+            .map(|(message, ppi)| (message, ppi.is_string()))
+    }
+
+    // Like `read_bytes`, but returns the payload type the message was sent with.
+    fn read_message(&mut self) -> Result<(Bytes, PayloadType), DataChannelError> {
+        if let Some(read) = self.pending_read.take() {
+            return Ok(read);
+        }
+
+        let _in_flight = self.start_in_flight(Operation::Read);
+        loop {
+            self.check_cancelled()?;
+
+            let (message, ppi) = match self.stream.read_sctp_bytes() {
+                Ok((message, ppi)) => (message, ppi),
+                Err(error) => return Err(self.handle_read_error(error)),
+            };
+
+            if let Some(n) = self.process_read(&message, message.len(), ppi) {
+                return Ok((message.slice(..n), ppi));
+            }
+        }
+    }
+
+    /// PollReadBytes is the non-blocking variant of ReadBytes
+    pub fn poll_read_bytes(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(Bytes, bool), DataChannelError>> {
+        self.poll_read_message(cx)
+            .map_ok(|(message, ppi)| (message, ppi.is_string()))
+    }
+
+    // Like `poll_read_bytes`, but returns the payload type the message was sent with.
+    fn poll_read_message(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(Bytes, PayloadType), DataChannelError>> {
+        if let Some(read) = self.pending_read.take() {
+            return Poll::Ready(Ok(read));
+        }
+
+        let in_flight = self.start_in_flight(Operation::Read);
+        let poll = self.poll_read_stream_bytes(cx);
+        self.keep_in_flight(in_flight, &poll);
+        poll
+    }
+
+    fn poll_read_stream_bytes(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(Bytes, PayloadType), DataChannelError>> {
+        loop {
+            ready!(self.poll_check_cancelled(cx))?;
+
+            let (message, ppi) = match ready!(self.stream.poll_read_sctp_bytes(cx)) {
+                Ok((message, ppi)) => (message, ppi),
+                Err(error) => return Poll::Ready(Err(self.handle_read_error(error))),
+            };
+
+            if let Some(n) = self.process_read(&message, message.len(), ppi) {
+                return Poll::Ready(Ok((message.slice(..n), ppi)));
+            }
+        }
+    }
+
+    /// Recv reads the next message, along with its metadata.
+    pub fn recv(&mut self) -> Result<DataChannelMessage, DataChannelError> {
+        if let Some(error) = self.pending_error.take() {
+            return Err(error);
+        }
+
+        let (data, ppi) = self.read_message()?;
+
+        Ok(self.message(data, ppi))
+    }
+
+    /// PollRecv is the non-blocking variant of Recv
+    pub fn poll_recv(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<DataChannelMessage, DataChannelError>> {
+        if let Some(error) = self.pending_error.take() {
+            return Poll::Ready(Err(error));
+        }
+
+        let (data, ppi) = ready!(self.poll_read_message(cx))?;
+
+        Poll::Ready(Ok(self.message(data, ppi)))
+    }
+
+    /// ReadBatch waits for the next message like `recv`, and then also reads
+    /// the messages queued on the stream after it, without waiting for more,
+    /// appending up to `max` messages to `messages`. Returns the number of
+    /// messages appended.
+    ///
+    /// An error which occurs once at least one message has been read ends
+    /// the batch, and is returned by the next call to `recv`, `poll_recv`
+    /// or `read_batch` instead.
+    pub fn read_batch(
+        &mut self,
+        messages: &mut Vec<DataChannelMessage>,
+        max: usize,
+    ) -> Result<usize, DataChannelError> {
+        if max == 0 {
+            return Ok(0);
+        }
+
+        messages.push(self.recv()?);
+
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut n = 1;
+        while n < max {
+            match self.poll_recv(&mut cx) {
+                Poll::Ready(Ok(message)) => messages.push(message),
+                Poll::Ready(Err(error)) => {
+                    self.pending_error = Some(error);
+                    break;
+                }
+                Poll::Pending => {
+                    // The batch doesn't wait for the next message.
+                    self.read_in_flight = None;
+                    break;
+                }
+            }
+            n += 1;
+        }
+
+        Ok(n)
+    }
+
+    pub(crate) fn message(&self, data: Bytes, ppid: PayloadType) -> DataChannelMessage {
+        DataChannelMessage {
+            is_string: ppid.is_string(),
+            ppid,
+            data,
+            stream_identifier: self.stream_identifier(),
+        }
+    }
+
+    /// ReadPayload reads a message as text or binary data, according to its
+    /// payload protocol identifier. Text which isn't valid UTF-8 fails the
+    /// read with `DataChannelError::String`.
+    pub fn read_payload(&mut self) -> Result<Payload, DataChannelError> {
+        let (message, is_string) = self.read_bytes()?;
+        if !is_string {
+            return Ok(Payload::Binary(message));
+        }
+
+        Ok(Payload::Text(String::from_utf8(message.to_vec())?))
+    }
+
+    /// PollOpen resolves once the DCEP handshake has completed, reading
+    /// the remote's DATA_CHANNEL_ACK if it hasn't been read yet.
+    ///
+    /// A message arriving ahead of the ACK implies the remote has opened the
+    /// channel; it is kept and returned by the next read.
+    pub fn poll_open(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), DataChannelError>> {
+        loop {
+            match self.ready_state {
+                ReadyState::Connecting => {}
+                ReadyState::Open => return Poll::Ready(Ok(())),
+                ReadyState::Closing | ReadyState::Closed => {
+                    return Poll::Ready(Err(StreamError::Eof.into()))
+                }
+            }
+
+            ready!(self.poll_check_cancelled(cx))?;
+
+            let buffer_provider = self.config.buffer_provider.clone();
+            let mut buf = buffer_provider.acquire(RECEIVE_MTU);
+            let (n, ppi) = match self.stream.poll_read_sctp(cx, &mut buf) {
+                Poll::Ready(Ok((n, ppi))) => (n, ppi),
+                Poll::Ready(Err(error)) => {
+                    buffer_provider.release(buf);
+                    return Poll::Ready(Err(self.handle_read_error(error)));
+                }
+                Poll::Pending => {
+                    buffer_provider.release(buf);
+                    return Poll::Pending;
+                }
+            };
+
+            if let Some(n) = self.process_read(&buf, n, ppi) {
+                self.awaiting_ack = false;
+                if let Err(error) = self.commit_reliability_params() {
+                    // Nothing of the half-finished handshake is left behind.
+                    self.awaiting_ack = true;
+                    buffer_provider.release(buf);
+                    return Poll::Ready(Err(error));
+                }
+                self.pending_read = Some((buf.split_to(n).freeze(), ppi));
+                self.set_ready_state(ReadyState::Open);
+            }
+            buffer_provider.release(buf);
+        }
+    }
+
+    fn handle_read_error(&mut self, error: StreamError) -> DataChannelError {
+        match error {
+            StreamError::Eof => {
+                let previous = self.ready_state;
+                if previous == ReadyState::Closed {
+                    return error.into();
+                }
+                self.set_ready_state(ReadyState::Closed);
+
+                // When the peer sees that an incoming stream was
+                // reset, it also resets its corresponding outgoing stream,
+                // unless it did so already by closing the channel itself.
+                if previous != ReadyState::Closing {
+                    if let Err(close_error) = self.stream.close() {
+                        return close_error.into();
+                    }
+                }
+                self.emit_event(EventKind::Closed, None);
+
+                error.into()
+            }
+            StreamError::SendBufferFull => {
+                self.observed.record_error();
+                error.into()
+            }
+            StreamError::ReceiveBufferExceeded => {
+                log::warn!(
+                    "Closing channel on stream {}: receive buffer limit exceeded",
+                    self.stream_identifier()
+                );
+                let error = DataChannelError::from(error);
+                self.emit_event(EventKind::Failed, Some(&error));
+                if let Err(close_error) = self.close() {
+                    return close_error;
+                }
+
+                error
+            }
+        }
+    }
+
+    /// Processes a message read from the stream, returning its length, or
+    /// `None` if it was a DCEP message which got handled internally.
+    fn process_read(&mut self, buf: &[u8], n: usize, ppi: PayloadType) -> Option<usize> {
+        self.mark_active();
+
+        let bytes_len = match (n, &ppi) {
+            (n, &PayloadType::WebRtcDcep) => {
+                let result = supervision::run(SupervisedTask::DcepHandling, || {
+                    self.handle_dcep(&mut &buf[..n])
+                });
+                if let Err(failure) = result {
+                    self.handle_task_failure(failure);
+                }
+                return None;
+            }
+            (_, ppi) if ppi.is_empty() => 0,
+            (n, _) => n,
+        };
+
+        #[cfg(feature = "tracing")]
+        if self.messages_received.load(Ordering::Relaxed) == 0 {
+            trace::first_read(self.stream_identifier(), &self.config.label, bytes_len);
+        }
+
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received.fetch_add(bytes_len, Ordering::Relaxed);
+
+        self.update_head_of_line();
+
+        #[cfg(feature = "otel")]
+        otel::record_received(&self.config.label, bytes_len);
+        #[cfg(feature = "metrics")]
+        metrics::record_received(&self.config.label, bytes_len);
+
+        let is_string = ppi.is_string();
+
+        if let Some(recorder) = self.config.recorder.clone() {
+            let stream_identifier = self.stream_identifier();
+            let result = supervision::run(SupervisedTask::Recording, || {
+                recorder.record(
+                    stream_identifier,
+                    Direction::Inbound,
+                    is_string,
+                    &buf[..bytes_len],
+                );
+                Ok(())
+            });
+            if let Err(failure) = result {
+                self.handle_task_failure(failure);
+            }
+        }
+
+        Some(bytes_len)
+    }
+
+    /// MessagesSent returns the number of messages sent
+    pub fn messages_sent(&self) -> usize {
+        self.messages_sent.load(Ordering::Relaxed)
+    }
+
+    /// MessagesReceived returns the number of messages received
+    pub fn messages_received(&self) -> usize {
+        self.messages_received.load(Ordering::Relaxed)
+    }
+
+    /// BytesSent returns the number of bytes sent
+    pub fn bytes_sent(&self) -> usize {
+        self.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    /// BytesReceived returns the number of bytes received
+    pub fn bytes_received(&self) -> usize {
+        self.bytes_received.load(Ordering::Relaxed)
+    }
+
+    /// StreamIdentifier returns the Stream identifier associated to the stream.
+    pub fn stream_identifier(&self) -> u16 {
+        self.stream.stream_identifier()
+    }
+
+    /// ReadyState returns the lifecycle stage the channel is in.
+    pub fn ready_state(&self) -> ReadyState {
+        self.ready_state
+    }
+
+    fn set_ready_state(&mut self, ready_state: ReadyState) {
+        let previous = std::mem::replace(&mut self.ready_state, ready_state);
+        self.observed.set_ready_state(ready_state);
+        if ready_state == previous {
+            return;
+        }
+
+        #[cfg(feature = "metrics")]
+        metrics::record_transition(
+            &self.config.label,
+            previous,
+            ready_state,
+            previous == ReadyState::Connecting && !self.config.negotiated,
+            self.config
+                .clock
+                .now()
+                .saturating_duration_since(self.created_at),
+        );
+
+        if let Some(on_state_change) = &self.callbacks.on_state_change {
+            on_state_change(ready_state);
+        }
+        let callback = match ready_state {
+            ReadyState::Open => &self.callbacks.on_open,
+            ReadyState::Closed => &self.callbacks.on_close,
+            _ => &None,
+        };
+        if let Some(callback) = callback {
+            callback();
+        }
+    }
+
+    /// OnOpen sets the callback handler which would be called once the DCEP
+    /// handshake has completed, replacing any previous one.
+    pub fn on_open<F>(&mut self, f: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.callbacks.on_open = Some(Arc::new(f));
+    }
+
+    /// OnMessage sets the callback handler which would be called by `read_loop`
+    /// with every message received, replacing any previous one.
+    pub fn on_message<F>(&mut self, f: F)
+    where
+        F: Fn(DataChannelMessage) + Send + Sync + 'static,
+    {
+        self.callbacks.on_message = Some(Arc::new(f));
+    }
+
+    /// OnError sets the callback handler which would be called by `read_loop`
+    /// with the error ending it, replacing any previous one. The remote
+    /// closing the channel isn't an error.
+    pub fn on_error<F>(&mut self, f: F)
+    where
+        F: Fn(DataChannelError) + Send + Sync + 'static,
+    {
+        self.callbacks.on_error = Some(Arc::new(f));
+    }
+
+    /// ReadLoop reads messages until the channel is closed, handing each one
+    /// to the `on_message` callback, as an alternative to calling `read`.
+    ///
+    /// Returns once the remote has reset its stream, or with the first
+    /// error, after handing it to the `on_error` callback.
+    pub fn read_loop(&mut self) -> Result<(), DataChannelError> {
+        loop {
+            let result = self.read_message();
+            if let Some(result) = self.dispatch(result) {
+                return result;
+            }
+        }
+    }
+
+    /// PollReadLoop is the non-blocking variant of ReadLoop
+    pub fn poll_read_loop(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), DataChannelError>> {
+        loop {
+            let result = ready!(self.poll_read_message(cx));
+            if let Some(result) = self.dispatch(result) {
+                return Poll::Ready(result);
+            }
+        }
+    }
+
+    // Hands a read's outcome to the callbacks, returning the loop's result once it ends.
+    fn dispatch(
+        &self,
+        result: Result<(Bytes, PayloadType), DataChannelError>,
+    ) -> Option<Result<(), DataChannelError>> {
+        match result {
+            Ok((data, ppi)) => {
+                if let Some(on_message) = &self.callbacks.on_message {
+                    on_message(self.message(data, ppi));
+                }
+                None
+            }
+            Err(DataChannelError::Stream(StreamError::Eof)) => Some(Ok(())),
+            Err(error) => {
+                if let Some(on_error) = &self.callbacks.on_error {
+                    on_error(error.clone());
+                }
+                Some(Err(error))
+            }
+        }
+    }
+
+    /// OnStateChange sets the callback handler which would be called with the
+    /// new ready state on every transition, replacing any previous one.
+    ///
+    /// Transitions happen within the channel's operations: on completing the
+    /// DCEP handshake, on `close`, and on reading the remote's stream reset.
+    pub fn on_state_change<F>(&mut self, f: F)
+    where
+        F: Fn(ReadyState) + Send + Sync + 'static,
+    {
+        self.callbacks.on_state_change = Some(Arc::new(f));
+    }
+
+    /// OnClose sets the callback handler which would be called once the channel
+    /// is closed, whichever end closed it, replacing any previous one.
+    pub fn on_close<F>(&mut self, f: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.callbacks.on_close = Some(Arc::new(f));
+    }
+
+    fn mark_active(&mut self) {
+        self.last_activity = self.config.clock.now();
+        self.idle_notified = false;
+    }
+
+    /// IdleFor returns how long the channel has gone without traffic in either direction.
+    pub fn idle_for(&self) -> Duration {
+        self.config
+            .clock
+            .now()
+            .saturating_duration_since(self.last_activity)
+    }
+
+    /// IsIdle returns whether the channel is open and has been
+    /// idle for at least its `idle_timeout`.
+    pub fn is_idle(&self) -> bool {
+        match self.config.idle_timeout {
+            Some(idle_timeout) => {
+                self.ready_state == ReadyState::Open && self.idle_for() >= idle_timeout
+            }
+            None => false,
+        }
+    }
+
+    /// CheckIdle takes the channel's `idle_action` if it is idle, returning
+    /// the action taken, if any. Notifications are only sent once per idle period.
+    ///
+    /// This needs to be called periodically, such as from the task
+    /// spawned with `DataChannelHandle::spawn_idle_timer`.
+    pub fn check_idle(&mut self) -> Result<Option<IdleAction>, DataChannelError> {
+        if !self.is_idle() {
+            return Ok(None);
+        }
+
+        match self.config.idle_action {
+            IdleAction::Close => {
+                log::debug!(
+                    "Closing channel on stream {}, idle for {:?}",
+                    self.stream_identifier(),
+                    self.idle_for()
+                );
+                self.close()?;
+            }
+            IdleAction::Notify => {
+                if self.idle_notified {
+                    return Ok(None);
+                }
+                self.idle_notified = true;
+                self.emit_event(EventKind::Idle, None);
+            }
+        }
+
+        Ok(Some(self.config.idle_action))
+    }
+
+    /// Label returns the channel's label. Unlike `config.label`,
+    /// it is cheap to clone, and shared by channels interning it.
+    pub fn label(&self) -> &Label {
+        &self.label
+    }
+
+    /// Protocol returns the channel's subprotocol, like `label`.
+    pub fn protocol(&self) -> &Label {
+        &self.protocol
+    }
+
+    /// InternLabels replaces the channel's label and protocol
+    /// with the ones shared through `interner`.
+    pub fn intern_labels(&mut self, interner: &LabelInterner) {
+        self.label = interner.intern(&self.label);
+        self.protocol = interner.intern(&self.protocol);
+    }
+
+    /// Observer returns a read-only handle to the channel, which can be used to
+    /// query its state and counters, and to subscribe to its lifecycle events.
+    pub fn observer(&self) -> Observer {
+        Observer {
+            stream_identifier: self.stream_identifier(),
+            label: self.label.clone(),
+            messages_sent: self.messages_sent.clone(),
+            messages_received: self.messages_received.clone(),
+            bytes_sent: self.bytes_sent.clone(),
+            bytes_received: self.bytes_received.clone(),
+            observed: self.observed.clone(),
+        }
+    }
+
+    /// Stats returns a snapshot of the channel's lifetime counters. Use an
+    /// `Observer` to sample them from elsewhere.
+    pub fn stats(&self) -> DataChannelStats {
+        self.observer().stats()
+    }
+
+    /// MessagesDropped returns the number of received messages dropped for
+    /// exceeding the channel's `max_receive_buffer`.
+    pub fn messages_dropped(&self) -> u64 {
+        self.stream.dropped_messages()
+    }
+
+    /// SequenceNumbers returns the stream's current SCTP stream sequence numbers,
+    /// for correlating application logs with packet captures.
+    pub fn sequence_numbers(&self) -> SequenceNumbers {
+        SequenceNumbers {
+            next_outbound: self.stream.next_outbound_ssn(),
+            last_delivered: self.stream.last_delivered_ssn(),
+        }
+    }
+
+    /// StatsStream yields a snapshot of the channel's counters once per `interval`,
+    /// timed by the channel's clock.
+    pub fn stats_stream(&self, interval: Duration) -> StatsStream<'_, ChannelStats> {
+        StatsStream::new(
+            move || ChannelStats::of(self),
+            self.config.clock.clone(),
+            interval,
+        )
+    }
+
+    /// HeadOfLineStats samples the stream for head-of-line blocking and returns
+    /// the statistics gathered so far. Unordered channels never stall.
+    ///
+    /// The stream is also sampled whenever a message is read, so stalls are
+    /// noticed even if this method is called infrequently.
+    pub fn head_of_line_stats(&mut self) -> HeadOfLineStats {
+        self.update_head_of_line();
+
+        self.head_of_line.stats(self.config.clock.now())
+    }
+
+    fn update_head_of_line(&mut self) {
+        if !self.config.channel_type.is_ordered() {
+            return;
+        }
+
+        let is_blocked = self.stream.held_back_amount() > 0;
+        self.head_of_line
+            .update(is_blocked, self.config.clock.now());
+    }
+
+    /// Extensions returns the application-defined state attached to this channel.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// ExtensionsMut returns a mutable reference to the application-defined state
+    /// attached to this channel.
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
+    /// HandleDcep processes a DCEP message received on the stream. Messages of
+    /// types other than OPEN and ACK are dispatched to the `dcep_registry`.
+    pub fn handle_dcep<B>(&mut self, bytes: &mut B) -> Result<(), DataChannelError>
+    where
+        B: Buf,
+    {
+        if let Some(handler) = self.dcep_handler(bytes.chunk().first().copied()) {
+            let message = RawMessage::unmarshal_from(bytes).map_err(unmarshal_failed)?;
+            let stream_identifier = self.stream_identifier();
+            if let Some(response) = handler.handle(stream_identifier, &message) {
+                let mut response_bytes = response.marshal()?;
+                self.stream
+                    .write_sctp(&mut response_bytes, PayloadType::WebRtcDcep)?;
+            }
+
+            return Ok(());
+        }
+
+        let message = Message::unmarshal_from(bytes).map_err(unmarshal_failed)?;
+
+        match message {
+            Message::DataChannelAck => {
+                log::debug!("Received DATA_CHANNEL_ACK");
+
+                self.emit_event(EventKind::AckReceived, None);
+
+                self.awaiting_ack = false;
+                self.commit_reliability_params()?;
+
+                self.set_ready_state(ReadyState::Open);
+
+                Ok(())
+            }
+            // The remote re-sent its OPEN, as the ACK must have been lost.
+            Message::DataChannelOpen(_) if self.remote_initiated => {
+                log::debug!("Received duplicate DATA_CHANNEL_OPEN");
+
+                self.write_data_channel_ack()?;
+
+                self.emit_event(EventKind::AckSent, None);
+
+                Ok(())
+            }
+            message => Err(DataChannelError::InvalidMessageType {
+                invalid_type: message.message_type(),
+            }),
+        }
+    }
+
+    fn dcep_handler(&self, message_type: Option<u8>) -> Option<Arc<dyn DcepHandler>> {
+        let registry = self.config.dcep_registry.as_ref()?;
+        match message_type? {
+            MESSAGE_TYPE_ACK | MESSAGE_TYPE_OPEN => None,
+            message_type => registry.handler(message_type),
+        }
+    }
+
+    /// Write writes len(p) bytes from p, as binary or text data
+    /// according to the channel's default payload format
+    pub fn write<B>(&mut self, bytes: &mut B) -> Result<usize, DataChannelError>
+    where
+        B: Buf + ExactSizeBuf,
+    {
+        let is_string = self.config.default_payload_format.is_string();
+        self.write_data_channel(bytes, is_string)
+    }
+
+    /// WriteVectored writes the concatenation of `bufs` as a single message,
+    /// in the default payload format, without concatenating them first.
+    pub fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, DataChannelError> {
+        self.write(&mut IoSlices::new(bufs))
+    }
+
+    /// WriteText writes `text` as a WebRTC String message, whatever the
+    /// default payload format.
+    pub fn write_text(&mut self, text: &str) -> Result<usize, DataChannelError> {
+        self.write_data_channel(&mut text.as_bytes(), true)
+    }
+
+    /// WriteDataChannel writes len(p) bytes from p
+    pub fn write_data_channel<B>(
+        &mut self,
+        bytes: &mut B,
+        is_string: bool,
+    ) -> Result<usize, DataChannelError>
+    where
+        B: Buf + ExactSizeBuf,
+    {
+        self.check_cancelled()?;
+        self.check_writable()?;
+
+        let bytes_len = bytes.len();
+        self.check_message_size(bytes_len)?;
+        self.apply_reliability_params()?;
+        let ppi = Self::payload_type(is_string, bytes_len);
+        let recorded = self.snapshot_for_recorder(bytes);
+
+        let _in_flight = self.start_in_flight(Operation::Write);
+        let mut attempt = 0;
+        let n = loop {
+            attempt += 1;
+            let result = if ppi.is_empty() {
+                self.stream
+                    .write_sctp(&mut &EMPTY_MESSAGE_PADDING[..], ppi)
+                    .map(|_| 0)
+            } else {
+                self.stream.write_sctp(bytes, ppi)
+            };
+            match result {
+                Ok(n) => break n,
+                Err(error) => {
+                    if !self.handle_send_failure(error, attempt, bytes_len, is_string)? {
+                        return Ok(0);
+                    }
+                }
+            }
+        };
+
+        self.record_sent(bytes_len);
+
+        self.record_outbound(recorded, is_string);
+
+        Ok(n)
+    }
+
+    /// WriteTimeout is like `write_data_channel`, but gives up with
+    /// `DataChannelError::Timeout` if the stream didn't take the message
+    /// within `timeout`, as measured by the config's clock.
+    pub fn write_timeout<B>(
+        &mut self,
+        bytes: &mut B,
+        is_string: bool,
+        timeout: Duration,
+    ) -> Result<usize, DataChannelError>
+    where
+        B: Buf + ExactSizeBuf,
+    {
+        let clock = self.config.clock.clone();
+        let deadline = clock.now() + timeout;
+
+        let written = blocking::poll_until(&clock, deadline, |cx| {
+            self.poll_write_data_channel(cx, bytes, is_string)
+        });
+        match written {
+            Some(result) => result,
+            None => {
+                self.write_in_flight = None;
+                Err(DataChannelError::Timeout)
+            }
+        }
+    }
+
+    /// WriteWhenBelow waits until fewer than `threshold` bytes are queued to be
+    /// sent on the channel, as measured by `buffered_amount`, and then writes
+    /// `bytes`, so that bulk senders don't pile up data in the send buffer.
+    ///
+    /// Waiting parks the calling thread until the buffered amount drops, and is
+    /// cut short by cancelling the channel. Fails right away once it is closing.
+    pub fn write_when_below<B>(
+        &mut self,
+        threshold: u64,
+        bytes: &mut B,
+        is_string: bool,
+    ) -> Result<usize, DataChannelError>
+    where
+        B: Buf + ExactSizeBuf,
+    {
+        blocking::block_on(|cx| -> Poll<Result<(), DataChannelError>> {
+            ready!(self.poll_check_cancelled(cx))?;
+            self.check_writable()?;
+            self.poll_buffered_amount_below(cx, threshold).map(Ok)
+        })?;
+
+        self.write_data_channel(bytes, is_string)
+    }
+
+    /// WriteBatch writes each of `messages` as a message in the default payload
+    /// format, handing all of them to the stream at once instead of one by one.
+    /// Returns the number of bytes written.
+    ///
+    /// Either all messages are written or none of them; failures aren't
+    /// handed to the channel's `SendFailureHandler`.
+    pub fn write_batch(&mut self, messages: &[Bytes]) -> Result<usize, DataChannelError> {
+        self.check_cancelled()?;
+        self.check_writable()?;
+
+        for message in messages {
+            self.check_message_size(message.len())?;
+        }
+        self.apply_reliability_params()?;
+
+        let is_string = self.config.default_payload_format.is_string();
+        let batch = messages
+            .iter()
+            .map(|message| {
+                let ppi = Self::payload_type(is_string, message.len());
+                if ppi.is_empty() {
+                    (Bytes::from_static(&EMPTY_MESSAGE_PADDING), ppi)
+                } else {
+                    (message.clone(), ppi)
+                }
+            })
+            .collect();
+
+        let in_flight = self.start_in_flight(Operation::Write);
+        let result = self.stream.write_sctp_batch(batch);
+        drop(in_flight);
+        if let Err(error) = result {
+            self.observed.record_error();
+            return Err(error.into());
+        }
+
+        let mut n = 0;
+        for message in messages {
+            let recorded = self.snapshot_for_recorder(message);
+            self.record_sent(message.len());
+            self.record_outbound(recorded, is_string);
+            n += message.len();
+        }
+
+        Ok(n)
+    }
+
+    /// SendReserve reserves a slot of at least `len` bytes to encode a message into,
+    /// which gets sent once the slot is committed.
+    pub fn send_reserve(&mut self, len: usize) -> SendSlot<'_> {
+        let buf = self.config.buffer_provider.acquire(len);
+        SendSlot::new(self, buf)
+    }
+
+    /// PollWriteDataChannel is the non-blocking variant of WriteDataChannel
+    pub fn poll_write_data_channel<B>(
+        &mut self,
+        cx: &mut Context<'_>,
+        bytes: &mut B,
+        is_string: bool,
+    ) -> Poll<Result<usize, DataChannelError>>
+    where
+        B: Buf + ExactSizeBuf,
+    {
+        let in_flight = self.start_in_flight(Operation::Write);
+        let poll = self.poll_write_stream(cx, bytes, is_string);
+        self.keep_in_flight(in_flight, &poll);
+        poll
+    }
+
+    fn poll_write_stream<B>(
+        &mut self,
+        cx: &mut Context<'_>,
+        bytes: &mut B,
+        is_string: bool,
+    ) -> Poll<Result<usize, DataChannelError>>
+    where
+        B: Buf + ExactSizeBuf,
+    {
+        ready!(self.poll_check_cancelled(cx))?;
+        self.check_writable()?;
+
+        let bytes_len = bytes.len();
+        self.check_message_size(bytes_len)?;
+        self.apply_reliability_params()?;
+        let ppi = Self::payload_type(is_string, bytes_len);
+        let recorded = self.snapshot_for_recorder(bytes);
+
+        let mut attempt = 0;
+        let n = loop {
+            attempt += 1;
+            let result = if ppi.is_empty() {
+                ready!(self
+                    .stream
+                    .poll_write_sctp(cx, &mut &EMPTY_MESSAGE_PADDING[..], ppi))
+                .map(|_| 0)
+            } else {
+                ready!(self.stream.poll_write_sctp(cx, bytes, ppi))
+            };
+            match result {
+                Ok(n) => break n,
+                Err(error) => {
+                    if !self.handle_send_failure(error, attempt, bytes_len, is_string)? {
+                        return Poll::Ready(Ok(0));
+                    }
+                }
+            }
+        };
+
+        self.record_sent(bytes_len);
+        self.record_outbound(recorded, is_string);
+
+        Poll::Ready(Ok(n))
+    }
+
+    // Counts a read or write as in flight until the returned guard is dropped,
+    // taking over the count of a polled one which is pending.
+    fn start_in_flight(&mut self, operation: Operation) -> InFlight {
+        let pending = match operation {
+            Operation::Read => self.read_in_flight.take(),
+            Operation::Write => self.write_in_flight.take(),
+        };
+        pending.unwrap_or_else(|| InFlight::new(self.observed.clone(), operation, &self.label))
+    }
+
+    // Keeps counting a polled read or write as in flight while it is pending.
+    fn keep_in_flight<T>(&mut self, in_flight: InFlight, poll: &Poll<T>) {
+        if poll.is_ready() {
+            return;
+        }
+        match in_flight.operation() {
+            Operation::Read => self.read_in_flight = Some(in_flight),
+            Operation::Write => self.write_in_flight = Some(in_flight),
+        }
+    }
+
+    /// Consults the channel's `SendFailureHandler` about a failed write,
+    /// returning whether to retry it, or `false` if the message is to be dropped.
+    ///
+    /// Errors which aren't transient, or which happen without a handler
+    /// being set, are returned as they are.
+    fn handle_send_failure(
+        &mut self,
+        error: StreamError,
+        attempt: u32,
+        bytes_len: usize,
+        is_string: bool,
+    ) -> Result<bool, DataChannelError> {
+        self.observed.record_error();
+
+        let handler = match &self.config.send_failure_handler {
+            Some(handler) if error.is_transient() => handler.clone(),
+            _ => return Err(error.into()),
+        };
+
+        let action = handler.on_send_failure(&SendFailure {
+            stream_identifier: self.stream_identifier(),
+            attempt,
+            error: &error,
+            len: bytes_len,
+            is_string,
+        });
+        match action {
+            SendFailureAction::Retry => Ok(true),
+            SendFailureAction::Drop => {
+                log::debug!(
+                    "Dropping message of {} bytes on stream {} after {} attempts: {:?}",
+                    bytes_len,
+                    self.stream_identifier(),
+                    attempt,
+                    error
+                );
+                Ok(false)
+            }
+            SendFailureAction::Close => {
+                self.close()?;
+                Err(error.into())
+            }
+        }
+    }
+
+    /// MaxMessageSize returns the size of the largest message the channel sends,
+    /// or 0 if messages of any size are sent.
+    pub fn max_message_size(&self) -> usize {
+        self.config
+            .max_message_size
+            .unwrap_or_else(|| self.stream.max_message_size())
+    }
+
+    fn check_message_size(&self, size: usize) -> Result<(), DataChannelError> {
+        match self.max_message_size() {
+            max if max > 0 && size > max => Err(DataChannelError::MessageTooLarge { size, max }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails writes once the channel is closing, without reaching the stream.
+    fn check_writable(&self) -> Result<(), DataChannelError> {
+        match self.ready_state {
+            ReadyState::Closing | ReadyState::Closed => Err(StreamError::Eof.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_failed(&self) -> Result<(), DataChannelError> {
+        match &self.failure {
+            Some(failure) => Err(failure.clone().into()),
+            None => Ok(()),
+        }
+    }
+
+    fn check_cancelled(&self) -> Result<(), DataChannelError> {
+        self.check_failed()?;
+
+        match &self.config.cancellation_token {
+            Some(token) if token.is_cancelled() => Err(DataChannelError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Resolves with an error once the channel's cancellation token has been
+    /// cancelled, and is ready right away otherwise, making sure the current
+    /// task gets woken up on cancellation.
+    fn poll_check_cancelled(&self, cx: &mut Context<'_>) -> Poll<Result<(), DataChannelError>> {
+        self.check_failed()?;
+
+        match &self.config.cancellation_token {
+            Some(token) if token.poll_cancelled(cx).is_ready() => {
+                Poll::Ready(Err(DataChannelError::Cancelled))
+            }
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn payload_type(is_string: bool, bytes_len: usize) -> PayloadType {
+        // https://tools.ietf.org/html/draft-ietf-rtcweb-data-channel-12#section-6.6
+        // SCTP does not support the sending of empty user messages.  Therefore,
+        // if an empty message has to be sent, the appropriate PPID (WebRTC
+        // String Empty or WebRTC Binary Empty) is used and the SCTP user
+        // message of one zero byte is sent.  When receiving an SCTP user
+        // message with one of these PPIDs, the receiver MUST ignore the SCTP
+        // user message and process it as an empty message.
+        match (is_string, bytes_len) {
+            (false, 0) => sctp::PayloadType::WebRtcBinaryEmpty,
+            (false, _) => sctp::PayloadType::WebRtcBinary,
+            (true, 0) => sctp::PayloadType::WebRtcStringEmpty,
+            (true, _) => sctp::PayloadType::WebRtcString,
+        }
+    }
+
+    fn record_sent(&mut self, bytes_len: usize) {
+        self.mark_active();
+
+        #[cfg(feature = "tracing")]
+        if self.messages_sent.load(Ordering::Relaxed) == 0 {
+            trace::first_write(self.stream_identifier(), &self.config.label, bytes_len);
+        }
 
         self.messages_sent.fetch_add(1, Ordering::Relaxed);
         self.bytes_sent.fetch_add(bytes_len, Ordering::Relaxed);
 
-        self.stream.write_sctp(bytes, ppi).map_err(From::from)
+        #[cfg(feature = "otel")]
+        otel::record_sent(&self.config.label, bytes_len);
+        #[cfg(feature = "metrics")]
+        metrics::record_sent(&self.config.label, bytes_len);
+    }
+
+    /// Copies the contents of `bytes` without consuming them, if a recorder is set.
+    ///
+    /// Buffers of several chunks are gathered with `Buf::chunks_vectored`,
+    /// given room for more chunks until it hands out all of them, as every
+    /// `ExactSizeBuf` does.
+    fn snapshot_for_recorder<B>(&self, bytes: &B) -> Option<Bytes>
+    where
+        B: Buf,
+    {
+        self.config.recorder.as_ref()?;
+
+        let chunk = bytes.chunk();
+        if chunk.len() == bytes.remaining() {
+            return Some(Bytes::copy_from_slice(chunk));
+        }
+
+        let mut chunks = vec![IoSlice::new(&[]); 16];
+        let count = loop {
+            let count = bytes.chunks_vectored(&mut chunks);
+            let len: usize = chunks[..count].iter().map(|chunk| chunk.len()).sum();
+            if len == bytes.remaining() || count < chunks.len() {
+                break count;
+            }
+            chunks.resize(chunks.len() * 2, IoSlice::new(&[]));
+        };
+
+        let mut snapshot = BytesMut::with_capacity(bytes.remaining());
+        for chunk in &chunks[..count] {
+            snapshot.extend_from_slice(chunk);
+        }
+        Some(snapshot.freeze())
+    }
+
+    fn record_outbound(&mut self, recorded: Option<Bytes>, is_string: bool) {
+        if let (Some(recorder), Some(payload)) = (self.config.recorder.clone(), recorded) {
+            let stream_identifier = self.stream_identifier();
+            let result = supervision::run(SupervisedTask::Recording, || {
+                recorder.record(
+                    stream_identifier,
+                    Direction::Outbound,
+                    is_string,
+                    &payload[..],
+                );
+                Ok(())
+            });
+            if let Err(failure) = result {
+                self.handle_task_failure(failure);
+            }
+        }
+    }
+
+    fn handle_task_failure(&mut self, failure: TaskFailure) {
+        let stream_identifier = self.stream_identifier();
+        match self.config.supervision {
+            SupervisionPolicy::Restart => {
+                log::error!(
+                    "{} failed on stream {}, carrying on: {}",
+                    failure.task.as_str(),
+                    stream_identifier,
+                    failure.cause
+                );
+                self.observed.record_error();
+            }
+            SupervisionPolicy::Fail => {
+                log::error!(
+                    "{} failed on stream {}, failing the channel: {}",
+                    failure.task.as_str(),
+                    stream_identifier,
+                    failure.cause
+                );
+
+                if let Err(error) = self.stream.close() {
+                    log::warn!("Failed to close stream {}: {:?}", stream_identifier, error);
+                }
+                self.set_ready_state(ReadyState::Closed);
+                self.emit_event(EventKind::Failed, Some(&failure.clone().into()));
+                self.failure = Some(failure);
+            }
+        }
+    }
+
+    /// Failure returns the cause of the channel having failed, if it did.
+    /// All reads and writes fail with it from then on.
+    pub fn failure(&self) -> Option<&TaskFailure> {
+        self.failure.as_ref()
+    }
+
+    fn write_data_channel_open(&mut self) -> Result<usize, DataChannelError> {
+        let open = Message::DataChannelOpen(DataChannelOpen {
+            channel_type: self.config.channel_type,
+            priority: self.config.priority,
+            reliability_parameter: self.config.reliability_parameter,
+            label: self.config.label.as_bytes().to_vec(),
+            protocol: self.config.protocol.as_bytes().to_vec(),
+        });
+        let mut open_bytes = open.marshal()?;
+
+        self.stream
+            .write_sctp(&mut open_bytes, PayloadType::WebRtcDcep)
+            .map_err(From::from)
+    }
+
+    pub fn write_data_channel_ack(&mut self) -> Result<usize, DataChannelError> {
+        let ack = Message::DataChannelAck;
+        let mut ack_bytes = ack.marshal()?;
+
+        self.stream
+            .write_sctp(&mut ack_bytes, PayloadType::WebRtcDcep)
+            .map_err(From::from)
+    }
+
+    /// Close closes the DataChannel and the underlying SCTP stream.
+    pub fn close(&mut self) -> Result<(), DataChannelError> {
+        // https://tools.ietf.org/html/draft-ietf-rtcweb-data-channel-13#section-6.7
+        // Closing of a data channel MUST be signaled by resetting the
+        // corresponding outgoing streams [RFC6525].  This means that if one
+        // side decides to close the data channel, it resets the corresponding
+        // outgoing stream.  When the peer sees that an incoming stream was
+        // reset, it also resets its corresponding outgoing stream.  Once this
+        // is completed, the data channel is closed.  Resetting a stream sets
+        // the Stream Sequence Numbers (SSNs) of the stream back to 'zero' with
+        // a corresponding notification to the application layer that the reset
+        // has been performed.  Streams are available for reuse after a reset
+        // has been performed.
+        //
+        // Close only resets the outgoing stream, leaving the channel
+        // `ReadyState::Closing` until the remote's reset of its outgoing
+        // stream has been read; see `wait_closed`.
+        if matches!(self.ready_state, ReadyState::Closing | ReadyState::Closed) {
+            return Ok(());
+        }
+
+        #[cfg(feature = "otel")]
+        let span = otel::start_span("data_channel.close", self.stream_identifier());
+
+        let result = self.stream.close().map_err(From::from);
+
+        #[cfg(feature = "otel")]
+        otel::end_span(span, &self.config.label, &result);
+
+        match &result {
+            Ok(()) => {
+                self.set_ready_state(ReadyState::Closing);
+                self.emit_event(EventKind::CloseSent, None);
+            }
+            Err(error) => {
+                self.set_ready_state(ReadyState::Closed);
+                self.emit_event(EventKind::Failed, Some(error));
+            }
+        }
+
+        result
+    }
+
+    /// WaitClosed blocks until the remote has reset its outgoing stream,
+    /// in response to `close` or by closing the channel itself, discarding
+    /// messages which arrive until then.
+    pub fn wait_closed(&mut self) -> Result<(), DataChannelError> {
+        while self.ready_state != ReadyState::Closed {
+            match self.read_bytes() {
+                Ok(_) => {}
+                Err(DataChannelError::Stream(StreamError::Eof)) => break,
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// PollClosed is the non-blocking variant of WaitClosed
+    pub fn poll_closed(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), DataChannelError>> {
+        while self.ready_state != ReadyState::Closed {
+            match ready!(self.poll_read_bytes(cx)) {
+                Ok(_) => {}
+                Err(DataChannelError::Stream(StreamError::Eof)) => break,
+                Err(error) => return Poll::Ready(Err(error)),
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    /// ApplyRuntimeConfig updates the channel's settings
+    /// with those set in `runtime_config`.
+    pub fn apply_runtime_config(&mut self, runtime_config: &RuntimeConfig) {
+        if let Some(threshold) = runtime_config.buffered_amount_low_threshold {
+            self.set_buffered_amount_low_threshold(threshold);
+        }
+        if let Some(idle_timeout) = runtime_config.idle_timeout {
+            self.config.idle_timeout = Some(idle_timeout);
+        }
+    }
+
+    /// BufferedAmount returns the number of bytes of data currently queued to be
+    /// sent over this stream, as reported by the SCTP stream. This includes
+    /// DCEP messages, such as a DATA_CHANNEL_OPEN yet to be sent.
+    pub fn buffered_amount(&self) -> u64 {
+        self.stream.buffered_amount()
+    }
+
+    /// PollBufferedAmountBelow resolves once fewer than `threshold` bytes are
+    /// queued to be sent on the channel, as measured by `buffered_amount`.
+    pub fn poll_buffered_amount_below(&mut self, cx: &mut Context<'_>, threshold: u64) -> Poll<()> {
+        self.stream.poll_buffered_amount_below(cx, threshold)
+    }
+
+    /// BytesInFlight returns the number of bytes of data handed to SCTP on this
+    /// stream, and sent, which haven't been cumulatively acknowledged yet.
+    ///
+    /// Together with `buffered_amount`, this accounts for all data
+    /// written to the channel which the remote hasn't received yet.
+    pub fn bytes_in_flight(&self) -> u64 {
+        self.stream.bytes_in_flight()
+    }
+
+    /// Rtt returns the smoothed round-trip time of the channel's association,
+    /// once SCTP has measured it.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.stream.rtt()
+    }
+
+    /// CurrentSendRate returns the rate the association's congestion window
+    /// allows sending at, in bytes per second, once SCTP has measured the
+    /// round-trip time.
+    pub fn current_send_rate(&self) -> Option<u64> {
+        let rtt = self.rtt().filter(|rtt| !rtt.is_zero())?;
+        let rate = self.stream.congestion_window() as u128 * 1_000_000 / rtt.as_micros().max(1);
+
+        Some(rate.min(u64::MAX as u128) as u64)
+    }
+
+    /// MaxBurst returns the number of bytes which can be written right away
+    /// without being queued, that is the association's congestion window less
+    /// the channel's data in flight or queued to be sent.
+    pub fn max_burst(&self) -> u64 {
+        self.stream
+            .congestion_window()
+            .saturating_sub(self.bytes_in_flight() + self.buffered_amount())
+    }
+
+    /// RttUpdates returns a receiver for every change of the association's
+    /// smoothed round-trip time from here on, for plotting latency over time.
+    /// The receiver stays valid after the channel has been dropped.
+    pub fn rtt_updates(&self) -> std::sync::mpsc::Receiver<Duration> {
+        self.stream.rtt_updates()
+    }
+
+    /// BufferedAmountLowThreshold returns the number of bytes of buffered outgoing
+    /// data that is considered "low." Defaults to 0.
+    pub fn buffered_amount_low_threshold(&self) -> u64 {
+        self.stream.buffered_amount_low_threshold()
+    }
+
+    /// SetBufferedAmountLowThreshold is used to update the threshold.
+    /// See BufferedAmountLowThreshold().
+    pub fn set_buffered_amount_low_threshold(&mut self, threshold: u64) {
+        self.stream.set_buffered_amount_low_threshold(threshold)
+    }
+
+    /// OnBufferedAmountLow sets the callback handler which would be called when the
+    /// number of bytes of outgoing data buffered drops from above the threshold
+    /// to or below it, replacing any previous one.
+    ///
+    /// The callback is called by the SCTP stack as buffered data is sent, so
+    /// it must not block.
+    pub fn on_buffered_amount_low<F>(&mut self, f: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.stream.on_buffered_amount_low(Arc::new(f))
+    }
+
+    fn emit_event(&self, kind: EventKind, error: Option<&DataChannelError>) {
+        if kind == EventKind::Failed {
+            self.observed.record_error();
+        }
+
+        #[cfg(feature = "tracing")]
+        trace::lifecycle(kind, self.stream_identifier(), &self.config.label, error);
+
+        if self.config.event_emitter.is_none() && !self.observed.has_subscribers() {
+            return;
+        }
+
+        // Timestamps advance with the config's clock, so that they agree
+        // with `elapsed`, and with each other under a `ManualClock`.
+        let elapsed = self
+            .config
+            .clock
+            .now()
+            .saturating_duration_since(self.created_at);
+        let record = EventRecord {
+            kind,
+            stream_identifier: self.stream_identifier(),
+            label: self.label.clone(),
+            timestamp: self.created_at_wall + elapsed,
+            elapsed,
+            error: error.map(|error| error.to_string().trim_end().to_owned()),
+        };
+
+        if let Some(event_emitter) = &self.config.event_emitter {
+            event_emitter.emit(&record);
+        }
+        self.observed.publish(&record);
+    }
+
+    /// SetReliabilityParams changes the channel's type and reliability
+    /// parameter, committing them to the stream right away.
+    ///
+    /// Until the DATA_CHANNEL_ACK arrives, messages are sent ordered even on
+    /// unordered channels, switching to unordered once it does.
+    pub fn set_reliability_params(
+        &mut self,
+        channel_type: ChannelType,
+        reliability_parameter: u32,
+    ) -> Result<(), DataChannelError> {
+        self.config.channel_type = channel_type;
+        self.config.reliability_parameter = reliability_parameter;
+
+        self.apply_reliability_params()
+    }
+
+    /// Commits the config's reliability settings to the stream if they
+    /// changed since they were last committed. Called before every send,
+    /// so that changes made to the config directly are applied too.
+    fn apply_reliability_params(&mut self) -> Result<(), DataChannelError> {
+        let reliability = (
+            self.sends_unordered(),
+            self.config.channel_type,
+            self.config.reliability_parameter,
+        );
+        if self.committed_reliability == Some(reliability) {
+            return Ok(());
+        }
+
+        self.commit_reliability_params()
+    }
+
+    /// SendsUnordered returns whether messages are currently sent unordered,
+    /// which they aren't before the DATA_CHANNEL_ACK arrives.
+    pub fn sends_unordered(&self) -> bool {
+        !self.config.channel_type.is_ordered() && !self.awaiting_ack
+    }
+
+    pub fn commit_reliability_params(&mut self) -> Result<(), DataChannelError> {
+        let unordered = self.sends_unordered();
+        let reliability_type = match self.config.channel_type {
+            ChannelType::Reliable | ChannelType::ReliableUnordered | ChannelType::Unknown(_) => {
+                sctp::ReliabilityType::Reliable
+            }
+            ChannelType::PartialReliableRexmit | ChannelType::PartialReliableRexmitUnordered => {
+                sctp::ReliabilityType::Rexmit
+            }
+            ChannelType::PartialReliableTimed | ChannelType::PartialReliableTimedUnordered => {
+                sctp::ReliabilityType::Timed
+            }
+        };
+
+        self.stream.set_reliability_params(
+            unordered,
+            reliability_type,
+            self.config.reliability_parameter,
+        )?;
+        self.committed_reliability = Some((
+            unordered,
+            self.config.channel_type,
+            self.config.reliability_parameter,
+        ));
+
+        Ok(())
+    }
+}
+
+// Counts a DCEP message which failed to unmarshal, with the `metrics` feature enabled.
+fn unmarshal_failed<E>(error: E) -> E {
+    #[cfg(feature = "metrics")]
+    metrics::record_unmarshal_error();
+
+    error
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for DataChannel {
+    fn drop(&mut self) {
+        // Channels dropped while open no longer count as open,
+        // without running the callbacks of the transition.
+        if self.ready_state == ReadyState::Open {
+            metrics::record_transition(
+                &self.config.label,
+                ReadyState::Open,
+                ReadyState::Closed,
+                false,
+                Duration::ZERO,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BufMut;
+
+    use crate::{
+        clock::{Clock, ManualClock},
+        quality::QualitySignals,
+        recording::Recorder,
+        send_failure::SendFailureHandler,
+        testing,
+    };
+
+    #[test]
+    fn it_works() {
+        assert_eq!(2 + 2, 4);
+    }
+
+    #[test]
+    fn check_idle() {
+        let clock = ManualClock::new();
+        let config = Config {
+            idle_timeout: Some(Duration::from_secs(30)),
+            clock: SharedClock::new(clock.clone()),
+            ..Default::default()
+        };
+        let (mut dialer, mut acceptor) = testing::channel_pair(1, config).unwrap();
+
+        clock.advance(Duration::from_secs(20));
+        acceptor
+            .write_data_channel(&mut Bytes::from_static(b"ping"), false)
+            .unwrap();
+        clock.advance(Duration::from_secs(20));
+
+        assert_eq!(acceptor.check_idle(), Ok(None));
+        assert_eq!(acceptor.idle_for(), Duration::from_secs(20));
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(acceptor.check_idle(), Ok(Some(IdleAction::Close)));
+        assert_eq!(acceptor.ready_state(), ReadyState::Closing);
+
+        // The dialer hasn't read the ACK yet, so it isn't open.
+        assert_eq!(dialer.check_idle(), Ok(None));
+    }
+
+    fn send_failure_pair(
+        handler: impl SendFailureHandler + 'static,
+    ) -> (Association, DataChannel, DataChannel) {
+        let (local, remote) = testing::simulated();
+        let config = Config {
+            send_failure_handler: Some(SharedSendFailureHandler::new(handler)),
+            ..Default::default()
+        };
+        let dialer = DataChannel::dial(&local, 1, config.clone()).unwrap();
+        let acceptor = DataChannel::accept(&remote, config).unwrap();
+
+        (local, dialer, acceptor)
+    }
+
+    #[test]
+    fn send_failure_retry() {
+        let (local, mut dialer, mut acceptor) = send_failure_pair(|failure: &SendFailure<'_>| {
+            assert_eq!(failure.error, &StreamError::SendBufferFull);
+            assert_eq!(failure.len, 5);
+            SendFailureAction::Retry
+        });
+
+        testing::fail_writes(&local, 1, 2);
+        let n = dialer
+            .write_data_channel(&mut Bytes::from_static(b"hello"), true)
+            .unwrap();
+        assert_eq!(n, 5);
+
+        let mut buf = BytesMut::with_capacity(1024);
+        let (n, _) = acceptor.read_data_channel(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"hello");
+        assert_eq!(dialer.messages_sent(), 1);
+    }
+
+    #[test]
+    fn send_failure_drop() {
+        let (local, mut dialer, _acceptor) = send_failure_pair(|failure: &SendFailure<'_>| {
+            if failure.attempt < 2 {
+                SendFailureAction::Retry
+            } else {
+                SendFailureAction::Drop
+            }
+        });
+
+        testing::fail_writes(&local, 1, 2);
+        let n = dialer
+            .write_data_channel(&mut Bytes::from_static(b"hello"), true)
+            .unwrap();
+        assert_eq!(n, 0);
+        assert_eq!(dialer.messages_sent(), 0);
+        assert_eq!(dialer.ready_state(), ReadyState::Connecting);
+    }
+
+    #[test]
+    fn send_failure_close() {
+        let (local, mut dialer, _acceptor) =
+            send_failure_pair(|_: &SendFailure<'_>| SendFailureAction::Close);
+
+        testing::fail_writes(&local, 1, 1);
+        let result = dialer.write_data_channel(&mut Bytes::from_static(b"hello"), true);
+        assert_eq!(
+            result,
+            Err(DataChannelError::Stream(StreamError::SendBufferFull))
+        );
+        assert_eq!(dialer.ready_state(), ReadyState::Closing);
+    }
+
+    #[test]
+    fn send_failure_without_handler() {
+        let (local, remote) = testing::simulated();
+        let mut dialer = DataChannel::dial(&local, 1, Config::default()).unwrap();
+        let _acceptor = DataChannel::accept(&remote, Config::default()).unwrap();
+
+        testing::fail_writes(&local, 1, 1);
+        let result = dialer.write_data_channel(&mut Bytes::from_static(b"hello"), true);
+        assert_eq!(
+            result,
+            Err(DataChannelError::Stream(StreamError::SendBufferFull))
+        );
+    }
+
+    #[test]
+    fn send_reserve() {
+        let (mut dialer, mut acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+
+        let mut slot = dialer.send_reserve(8);
+        slot.put_u32(0xdead_beef);
+        slot.put_slice(b"ok");
+        assert_eq!(slot.commit_data_channel(true), Ok(6));
+
+        // Dropped slots are never sent.
+        let mut slot = dialer.send_reserve(8);
+        slot.put_slice(b"discarded");
+        drop(slot);
+
+        let mut slot = dialer.send_reserve(8);
+        slot.put_slice(b"next");
+        assert_eq!(slot.commit(), Ok(4));
+
+        let mut buf = BytesMut::with_capacity(1024);
+        let (n, is_string) = acceptor.read_data_channel(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"\xde\xad\xbe\xefok");
+        assert!(is_string);
+        let (n, is_string) = acceptor.read_data_channel(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"next");
+        assert!(!is_string);
+        assert_eq!(dialer.messages_sent(), 2);
+    }
+
+    fn write_dcep(data_channel: &mut DataChannel, message_type: u8) {
+        let message = RawMessage {
+            message_type,
+            payload: Bytes::new(),
+        };
+        data_channel
+            .stream
+            .write_sctp(&mut message.marshal().unwrap(), PayloadType::WebRtcDcep)
+            .unwrap();
+    }
+
+    fn supervised_pair(supervision: SupervisionPolicy) -> (DataChannel, DataChannel) {
+        let dcep_registry = DcepRegistry::new();
+        dcep_registry
+            .register(0x42, |_, _: &RawMessage| -> Option<RawMessage> {
+                panic!("handler bug")
+            })
+            .unwrap();
+        let config = Config {
+            supervision,
+            dcep_registry: Some(dcep_registry),
+            ..Default::default()
+        };
+
+        testing::channel_pair(1, config).unwrap()
+    }
+
+    #[test]
+    fn supervision_restart_carries_on() {
+        let (mut dialer, mut acceptor) = supervised_pair(SupervisionPolicy::Restart);
+
+        write_dcep(&mut dialer, 0x42);
+        dialer
+            .write_data_channel(&mut Bytes::from_static(b"after"), false)
+            .unwrap();
+
+        let mut buf = BytesMut::with_capacity(1024);
+        let (n, _) = acceptor.read_data_channel(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"after");
+        assert_eq!(acceptor.failure(), None);
+    }
+
+    #[test]
+    fn supervision_fail_fails_channel() {
+        let (mut dialer, mut acceptor) = supervised_pair(SupervisionPolicy::Fail);
+        let events = acceptor.observer().subscribe();
+
+        write_dcep(&mut dialer, 0x42);
+
+        let expected = DataChannelError::TaskFailed {
+            task: SupervisedTask::DcepHandling,
+            cause: "panicked: handler bug".to_owned(),
+        };
+        let mut buf = BytesMut::with_capacity(1024);
+        assert_eq!(acceptor.read_data_channel(&mut buf), Err(expected.clone()));
+        assert_eq!(acceptor.ready_state(), ReadyState::Closed);
+        assert_eq!(
+            acceptor.write_data_channel(&mut Bytes::from_static(b"hello"), false),
+            Err(expected)
+        );
+
+        let failed = events
+            .try_iter()
+            .find(|event| event.kind == EventKind::Failed)
+            .unwrap();
+        assert_eq!(
+            failed.error.as_deref(),
+            Some("Channel failed in dcep_handling: panicked: handler bug")
+        );
+    }
+
+    #[test]
+    fn dial_waits_for_ack_within_open_timeout() {
+        let (local, remote) = testing::simulated();
+        let acceptor = std::thread::spawn(move || {
+            let _acceptor = DataChannel::accept(&remote, Config::default()).unwrap();
+            remote
+        });
+
+        let config = Config {
+            open_timeout: Some(Duration::from_secs(5)),
+            ..Default::default()
+        };
+        let dialer = DataChannel::dial(&local, 1, config).unwrap();
+        assert_eq!(dialer.ready_state(), ReadyState::Open);
+
+        acceptor.join().unwrap();
+    }
+
+    #[test]
+    fn dial_fails_after_open_timeout() {
+        let clock = ManualClock::new();
+        let (local, _remote) = testing::simulated();
+
+        let config = Config {
+            open_timeout: Some(Duration::from_secs(1)),
+            clock: SharedClock::new(clock.clone()),
+            ..Default::default()
+        };
+        let result = DataChannel::dial(&local, 1, config);
+
+        assert_eq!(
+            result.err(),
+            Some(DataChannelError::HandshakeTimedOut {
+                elapsed: Duration::from_secs(1)
+            })
+        );
+    }
+
+    #[test]
+    fn dial_retries_open_before_failing() {
+        let clock = ManualClock::new();
+        let (local, _remote) = testing::simulated();
+
+        let config = Config {
+            open_timeout: Some(Duration::from_secs(1)),
+            open_retries: 2,
+            clock: SharedClock::new(clock.clone()),
+            ..Default::default()
+        };
+        let result = DataChannel::dial(&local, 1, config);
+
+        assert_eq!(
+            result.err(),
+            Some(DataChannelError::HandshakeTimedOut {
+                elapsed: Duration::from_secs(3)
+            })
+        );
     }
 
-    pub fn write_data_channel_ack(&mut self) -> Result<usize, DataChannelError> {
-        let ack = Message::DataChannelAck;
-        let mut ack_bytes = ack.marshal()?;
+    #[test]
+    fn acknowledges_duplicate_open() {
+        let (mut local, mut remote) = testing::channel_pair(1, Config::default()).unwrap();
 
-        self.stream
-            .write_sctp(&mut ack_bytes, PayloadType::WebRtcDcep)
-            .map_err(From::from)
+        local.write_data_channel_open().unwrap();
+        local.write_text("hello").unwrap();
+        assert_eq!(remote.read_payload(), Ok(Payload::Text("hello".to_owned())));
+
+        remote.write_text("bye").unwrap();
+        assert_eq!(local.read_payload(), Ok(Payload::Text("bye".to_owned())));
+        assert_eq!(local.ready_state(), ReadyState::Open);
     }
 
-    /// Close closes the DataChannel and the underlying SCTP stream.
-    pub fn close(&mut self) -> Result<(), DataChannelError> {
-        // https://tools.ietf.org/html/draft-ietf-rtcweb-data-channel-13#section-6.7
-        // Closing of a data channel MUST be signaled by resetting the
-        // corresponding outgoing streams [RFC6525].  This means that if one
-        // side decides to close the data channel, it resets the corresponding
-        // outgoing stream.  When the peer sees that an incoming stream was
-        // reset, it also resets its corresponding outgoing stream.  Once this
-        // is completed, the data channel is closed.  Resetting a stream sets
-        // the Stream Sequence Numbers (SSNs) of the stream back to 'zero' with
-        // a corresponding notification to the application layer that the reset
-        // has been performed.  Streams are available for reuse after a reset
-        // has been performed.
-        self.stream.close().map_err(From::from)
+    #[test]
+    fn accept_rejects_malformed_open() {
+        let (local, remote) = testing::simulated();
+        let mut stream = local.open_stream(1, PayloadType::WebRtcBinary).unwrap();
+        stream
+            .write_sctp(&mut &[0x03, 0x00][..], PayloadType::WebRtcDcep)
+            .unwrap();
+
+        match DataChannel::accept(&remote, Config::default()) {
+            Err(DataChannelError::OpenRejected {
+                reason: RejectReason::Malformed { .. },
+                raw,
+            }) => assert_eq!(raw, vec![0x03, 0x00]),
+            result => panic!("expected a rejection, got {:?}", result.err()),
+        }
+        // The stream was reset.
+        let mut buf = BytesMut::zeroed(16);
+        assert_eq!(stream.read_sctp(&mut buf), Err(StreamError::Eof));
     }
 
-    /// BufferedAmount returns the number of bytes of data currently queued to be
-    /// sent over this stream.
-    pub fn buffered_amount(&self) -> u64 {
-        self.stream.buffered_amount()
+    #[test]
+    fn accept_with_options_rejects_disallowed_labels() {
+        let (local, remote) = testing::simulated();
+        let config = Config {
+            label: "video".to_owned(),
+            ..Default::default()
+        };
+        let mut dialer = DataChannel::dial(&local, 1, config).unwrap();
+        let options = AcceptOptions::new().allow_label("chat-*");
+
+        match DataChannel::accept_with_options(&remote, Config::default(), &options) {
+            Err(DataChannelError::OpenRejected { reason, .. }) => assert_eq!(
+                reason,
+                RejectReason::LabelNotAllowed {
+                    label: "video".to_owned()
+                }
+            ),
+            result => panic!("expected a rejection, got {:?}", result.err()),
+        }
+        assert_eq!(dialer.read_payload(), Err(StreamError::Eof.into()));
+        assert_eq!(dialer.ready_state(), ReadyState::Closed);
     }
 
-    /// BufferedAmountLowThreshold returns the number of bytes of buffered outgoing
-    /// data that is considered "low." Defaults to 0.
-    pub fn buffered_amount_low_threshold(&self) -> u64 {
-        self.stream.buffered_amount_low_threshold()
+    #[test]
+    fn builder_maps_reliability_options() {
+        let config = DataChannelBuilder::new()
+            .label("video")
+            .protocol("rtp")
+            .ordered(false)
+            .max_packet_lifetime(Duration::from_millis(150))
+            .priority(512)
+            .build()
+            .unwrap();
+
+        assert_eq!(config.label, "video");
+        assert_eq!(config.protocol, "rtp");
+        assert_eq!(
+            config.channel_type,
+            ChannelType::PartialReliableTimedUnordered
+        );
+        assert_eq!(config.reliability_parameter, 150);
+        assert_eq!(config.priority, 512);
+        assert!(!config.negotiated);
+
+        let config = DataChannelBuilder::new()
+            .max_retransmits(3)
+            .negotiated(5)
+            .build()
+            .unwrap();
+        assert_eq!(config.channel_type, ChannelType::PartialReliableRexmit);
+        assert_eq!(config.reliability_parameter, 3);
+        assert!(config.negotiated);
     }
 
-    /// SetBufferedAmountLowThreshold is used to update the threshold.
-    /// See BufferedAmountLowThreshold().
-    pub fn set_buffered_amount_low_threshold(&mut self, threshold: u64) {
-        self.stream.set_buffered_amount_low_threshold(threshold)
+    #[test]
+    fn builder_rejects_invalid_reliability_options() {
+        let builder = DataChannelBuilder::new()
+            .max_retransmits(3)
+            .max_packet_lifetime(Duration::from_millis(150));
+        assert_eq!(builder.build(), Err(ConfigError::ConflictingReliability));
+
+        let lifetime = Duration::from_secs(u64::MAX);
+        let builder = DataChannelBuilder::new().max_packet_lifetime(lifetime);
+        assert_eq!(
+            builder.build(),
+            Err(ConfigError::PacketLifetimeOutOfRange { lifetime })
+        );
     }
 
-    /// OnBufferedAmountLow sets the callback handler which would be called when the
-    /// number of bytes of outgoing data buffered is lower than the threshold.
-    pub fn on_buffered_amount_low<F>(&mut self, f: F) {
-        self.stream.on_buffered_amount_low(f)
+    #[test]
+    fn builder_dials_negotiated_stream() {
+        let (association, _remote) = testing::simulated();
+        let builder = DataChannelBuilder::new().negotiated(5);
+
+        assert_eq!(
+            builder.dial(&association, 3).err(),
+            Some(DataChannelError::Config(
+                ConfigError::NegotiatedStreamIdentifierMismatch {
+                    negotiated: 5,
+                    requested: 3
+                }
+            ))
+        );
+
+        let data_channel = builder.dial(&association, 5).unwrap();
+        assert_eq!(data_channel.ready_state(), ReadyState::Open);
     }
 
-    pub fn commit_reliability_params(&mut self) -> Result<(), DataChannelError> {
-        let (unordered, reliability_type) = match self.config.channel_type {
-            ChannelType::Reliable => (false, sctp::ReliabilityType::Reliable),
-            ChannelType::ReliableUnordered => (true, sctp::ReliabilityType::Reliable),
-            ChannelType::PartialReliableRexmit => (false, sctp::ReliabilityType::Rexmit),
-            ChannelType::PartialReliableRexmitUnordered => (true, sctp::ReliabilityType::Rexmit),
-            ChannelType::PartialReliableTimed => (false, sctp::ReliabilityType::Timed),
-            ChannelType::PartialReliableTimedUnordered => (true, sctp::ReliabilityType::Timed),
+    #[test]
+    fn negotiated_channels_skip_dcep() {
+        let (local, remote) = testing::simulated();
+        let config = Config {
+            label: "chat".to_owned(),
+            ..Default::default()
         };
 
-        self.stream
-            .set_reliability_params(
-                unordered,
-                reliability_type,
-                self.config.reliability_parameter,
-            )
-            .map_err(From::from)
+        let mut dialer = DataChannel::open_negotiated(&local, 4, config.clone()).unwrap();
+        let mut acceptor = DataChannel::open_negotiated(&remote, 4, config).unwrap();
+        assert_eq!(dialer.ready_state(), ReadyState::Open);
+        assert_eq!(acceptor.ready_state(), ReadyState::Open);
+        assert!(dialer.config.negotiated);
+
+        dialer
+            .write_data_channel(&mut Bytes::from_static(b"hello"), true)
+            .unwrap();
+        let mut buf = BytesMut::with_capacity(1024);
+        let (n, is_string) = acceptor.read_data_channel(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"hello");
+        assert!(is_string);
+        // Nothing but the message went over the stream.
+        assert_eq!(dialer.bytes_sent(), 5);
+        assert_eq!(acceptor.messages_received(), 1);
     }
-}
 
-#[cfg(test)]
-mod tests {
     #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
+    fn buffered_amount_low_fires_once_drained() {
+        let (local, remote) = testing::simulated();
+        let mut dialer = DataChannel::dial(&local, 1, Config::default()).unwrap();
+        let _acceptor = DataChannel::accept(&remote, Config::default()).unwrap();
+
+        let fired = Arc::new(AtomicUsize::new(0));
+        let counter = fired.clone();
+        dialer.set_buffered_amount_low_threshold(4);
+        dialer.on_buffered_amount_low(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        testing::hold_writes(&local, 1);
+        dialer
+            .write_data_channel(&mut Bytes::from_static(b"abc"), false)
+            .unwrap();
+        assert_eq!(dialer.buffered_amount(), 3);
+        // Never rose above the threshold.
+        testing::release_writes(&local, 1);
+        assert_eq!(fired.load(Ordering::SeqCst), 0);
+
+        testing::hold_writes(&local, 1);
+        dialer
+            .write_data_channel(&mut Bytes::from_static(b"hello"), false)
+            .unwrap();
+        assert_eq!(dialer.buffered_amount(), 5);
+        testing::release_writes(&local, 1);
+        assert_eq!(dialer.buffered_amount(), 0);
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn recv_carries_metadata() {
+        let (mut dialer, mut acceptor) = testing::channel_pair(3, Config::default()).unwrap();
+
+        dialer.write_text("hi").unwrap();
+        dialer.write_data_channel(&mut Bytes::new(), false).unwrap();
+
+        assert_eq!(
+            acceptor.recv(),
+            Ok(DataChannelMessage {
+                is_string: true,
+                data: Bytes::from_static(b"hi"),
+                ppid: PayloadType::WebRtcString,
+                stream_identifier: 3,
+            })
+        );
+        let message = acceptor.recv().unwrap();
+        assert!(message.data.is_empty());
+        assert_eq!(message.ppid, PayloadType::WebRtcBinaryEmpty);
+    }
+
+    #[test]
+    fn recv_keeps_ppid_sent_with() {
+        let (mut dialer, mut acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+
+        // Not an empty PPID, even though nothing was sent with it.
+        dialer
+            .stream
+            .write_sctp(&mut Bytes::new(), PayloadType::WebRtcString)
+            .unwrap();
+
+        let message = acceptor.recv().unwrap();
+        assert!(message.data.is_empty() && message.is_string);
+        assert_eq!(message.ppid, PayloadType::WebRtcString);
+    }
+
+    #[test]
+    fn read_batch_drains_queued_messages() {
+        let (mut dialer, mut acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        for data in [&b"a"[..], b"b", b"c"] {
+            dialer.write(&mut Bytes::from_static(data)).unwrap();
+        }
+
+        let mut messages = vec![];
+        assert_eq!(acceptor.read_batch(&mut messages, 2), Ok(2));
+        assert_eq!(acceptor.read_batch(&mut messages, 10), Ok(1));
+        let data: Vec<&[u8]> = messages.iter().map(|message| &message.data[..]).collect();
+        assert_eq!(data, vec![&b"a"[..], b"b", b"c"]);
+
+        dialer.close().unwrap();
+        assert_eq!(
+            acceptor.read_batch(&mut messages, 10),
+            Err(DataChannelError::Stream(StreamError::Eof))
+        );
+    }
+
+    #[test]
+    fn read_batch_keeps_errors_for_the_next_read() {
+        let (mut dialer, mut acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        for data in [&b"a"[..], b"b"] {
+            dialer.write(&mut Bytes::from_static(data)).unwrap();
+        }
+        dialer.close().unwrap();
+
+        let mut messages = vec![];
+        assert_eq!(acceptor.read_batch(&mut messages, 10), Ok(2));
+        assert_eq!(acceptor.ready_state(), ReadyState::Closed);
+        assert_eq!(
+            acceptor.recv(),
+            Err(DataChannelError::Stream(StreamError::Eof))
+        );
+    }
+
+    #[test]
+    fn write_batch_sends_all_messages() {
+        let (local, remote) = testing::simulated();
+        let mut dialer = DataChannel::dial(&local, 1, Config::default()).unwrap();
+        let mut acceptor = DataChannel::accept(&remote, Config::default()).unwrap();
+        let messages = [
+            Bytes::from_static(b"a"),
+            Bytes::new(),
+            Bytes::from_static(b"bc"),
+        ];
+
+        assert_eq!(dialer.write_batch(&messages), Ok(3));
+        assert_eq!(dialer.messages_sent(), 3);
+        for message in &messages {
+            assert_eq!(&acceptor.recv().unwrap().data, message);
+        }
+
+        testing::fail_writes(&local, 1, 1);
+        assert!(dialer.write_batch(&messages).is_err());
+        assert_eq!(dialer.messages_sent(), 3);
+    }
+
+    #[test]
+    fn buffered_amount_low_fires_once_unblocked() {
+        let config = Config {
+            max_receive_buffer: Some(4),
+            receive_buffer_policy: ReceiveBufferPolicy::Block,
+            ..Default::default()
+        };
+        let (local, remote) = testing::simulated();
+        let mut dialer = DataChannel::dial(&local, 1, Config::default()).unwrap();
+        let mut acceptor = DataChannel::accept(&remote, config).unwrap();
+
+        let fired = Arc::new(AtomicUsize::new(0));
+        let counter = fired.clone();
+        dialer.set_buffered_amount_low_threshold(1);
+        dialer.on_buffered_amount_low(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        dialer.write(&mut Bytes::from_static(b"abc")).unwrap();
+        dialer.write(&mut Bytes::from_static(b"de")).unwrap();
+        assert_eq!(dialer.buffered_amount(), 2);
+        assert_eq!(fired.load(Ordering::SeqCst), 0);
+
+        assert_eq!(acceptor.read_bytes().unwrap().0, &b"abc"[..]);
+        assert_eq!(dialer.buffered_amount(), 0);
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+        assert_eq!(acceptor.read_bytes().unwrap().0, &b"de"[..]);
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn limits_receive_buffer() {
+        let config = |receive_buffer_policy| Config {
+            max_receive_buffer: Some(4),
+            receive_buffer_policy,
+            ..Default::default()
+        };
+        let write = |data_channel: &mut DataChannel, data: &'static [u8]| {
+            data_channel.write(&mut Bytes::from_static(data)).unwrap();
+        };
+
+        let (local, remote) = testing::simulated();
+        let mut dialer = DataChannel::dial(&local, 1, Config::default()).unwrap();
+        let mut acceptor =
+            DataChannel::accept(&remote, config(ReceiveBufferPolicy::DropNewest)).unwrap();
+        write(&mut dialer, b"abc");
+        write(&mut dialer, b"de");
+        write(&mut dialer, b"f");
+        assert_eq!(acceptor.read_bytes().unwrap().0, &b"abc"[..]);
+        assert_eq!(acceptor.read_bytes().unwrap().0, &b"f"[..]);
+        assert_eq!(acceptor.messages_dropped(), 1);
+
+        let (local, remote) = testing::simulated();
+        let mut dialer = DataChannel::dial(&local, 1, Config::default()).unwrap();
+        let mut acceptor =
+            DataChannel::accept(&remote, config(ReceiveBufferPolicy::Block)).unwrap();
+        write(&mut dialer, b"abc");
+        write(&mut dialer, b"de");
+        assert_eq!(dialer.buffered_amount(), 2);
+        assert_eq!(acceptor.read_bytes().unwrap().0, &b"abc"[..]);
+        assert_eq!(dialer.buffered_amount(), 0);
+        assert_eq!(acceptor.read_bytes().unwrap().0, &b"de"[..]);
+
+        let (local, remote) = testing::simulated();
+        let mut dialer = DataChannel::dial(&local, 1, Config::default()).unwrap();
+        let mut acceptor =
+            DataChannel::accept(&remote, config(ReceiveBufferPolicy::CloseChannel)).unwrap();
+        write(&mut dialer, b"abc");
+        write(&mut dialer, b"de");
+        assert_eq!(
+            acceptor.read_bytes(),
+            Err(DataChannelError::Stream(StreamError::ReceiveBufferExceeded))
+        );
+        assert_eq!(acceptor.ready_state(), ReadyState::Closing);
+    }
+
+    #[test]
+    fn event_timestamps_follow_clock() {
+        let clock = ManualClock::new();
+        let config = Config {
+            clock: SharedClock::new(clock.clone()),
+            ..Default::default()
+        };
+        let (mut dialer, mut acceptor) = testing::channel_pair(1, config).unwrap();
+        let events = dialer.observer().subscribe();
+
+        clock.advance(Duration::from_secs(5));
+        dialer.close().unwrap();
+        clock.advance(Duration::from_secs(3));
+        assert!(acceptor.read_bytes().is_err());
+        dialer.wait_closed().unwrap();
+
+        let records: Vec<_> = events.try_iter().collect();
+        let (first, last) = (&records[0], &records[records.len() - 1]);
+        assert_eq!(first.elapsed, Duration::from_secs(5));
+        assert_eq!(last.elapsed, Duration::from_secs(8));
+        assert_eq!(
+            last.timestamp.duration_since(first.timestamp).unwrap(),
+            Duration::from_secs(3)
+        );
+    }
+
+    #[test]
+    fn read_timeout_gives_up() {
+        let clock = ManualClock::new();
+        let config = Config {
+            clock: SharedClock::new(clock.clone()),
+            ..Default::default()
+        };
+        let (mut dialer, mut acceptor) = testing::channel_pair(1, config).unwrap();
+        let mut buf = BytesMut::with_capacity(16);
+
+        let start = clock.now();
+        assert_eq!(
+            acceptor.read_timeout(&mut buf, Duration::from_secs(1)),
+            Err(DataChannelError::Timeout)
+        );
+        assert!(clock.now() >= start + Duration::from_secs(1));
+
+        dialer
+            .write_timeout(&mut Bytes::from_static(b"hi"), true, Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(
+            acceptor.read_timeout(&mut buf, Duration::from_secs(1)),
+            Ok((2, true))
+        );
+        assert_eq!(&buf[..2], b"hi");
+    }
+
+    #[test]
+    fn read_timeout_wakes_up_on_message() {
+        let (mut dialer, mut acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            dialer
+                .write_data_channel(&mut Bytes::from_static(b"hi"), true)
+                .unwrap();
+            dialer
+        });
+
+        let start = Instant::now();
+        let mut buf = BytesMut::with_capacity(16);
+        assert_eq!(
+            acceptor.read_timeout(&mut buf, Duration::from_secs(60)),
+            Ok((2, true))
+        );
+        assert!(start.elapsed() < Duration::from_secs(30));
+
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn accept_timeout_wakes_up_once_opened() {
+        let (local, remote) = testing::simulated();
+        let dialer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            DataChannel::dial(&local, 1, Config::default()).unwrap();
+            local
+        });
+
+        let start = Instant::now();
+        let acceptor =
+            DataChannel::accept_timeout(&remote, Config::default(), Duration::from_secs(60))
+                .unwrap();
+        assert_eq!(acceptor.stream_identifier(), 1);
+        assert!(start.elapsed() < Duration::from_secs(30));
+
+        dialer.join().unwrap();
+    }
+
+    #[test]
+    fn write_when_below_waits_for_drain() {
+        let (local, remote) = testing::simulated();
+        let mut dialer = DataChannel::dial(&local, 1, Config::default()).unwrap();
+        let mut acceptor = DataChannel::accept(&remote, Config::default()).unwrap();
+
+        testing::hold_writes(&local, 1);
+        dialer
+            .write_data_channel(&mut Bytes::from_static(b"hello"), false)
+            .unwrap();
+        let releaser = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            testing::release_writes(&local, 1);
+        });
+
+        dialer
+            .write_when_below(4, &mut Bytes::from_static(b"world"), false)
+            .unwrap();
+        releaser.join().unwrap();
+
+        assert_eq!(acceptor.read_bytes().unwrap().0, &b"hello"[..]);
+        assert_eq!(acceptor.read_bytes().unwrap().0, &b"world"[..]);
+    }
+
+    #[test]
+    fn reports_send_rate_from_congestion_window() {
+        let (local, remote) = testing::simulated();
+        let mut dialer = DataChannel::dial(&local, 1, Config::default()).unwrap();
+        DataChannel::accept(&remote, Config::default()).unwrap();
+        testing::set_congestion_window(&local, 10_000);
+
+        assert_eq!(dialer.current_send_rate(), None);
+        testing::set_quality_signals(
+            &local,
+            QualitySignals {
+                rtt: Some(Duration::from_millis(100)),
+                ..Default::default()
+            },
+        );
+        assert_eq!(dialer.current_send_rate(), Some(100_000));
+
+        assert_eq!(dialer.max_burst(), 10_000);
+        testing::hold_writes(&local, 1);
+        dialer
+            .write_data_channel(&mut Bytes::from(vec![0; 4000]), false)
+            .unwrap();
+        assert_eq!(dialer.max_burst(), 6000);
+    }
+
+    #[test]
+    fn buffered_amount_counts_dcep_messages() {
+        let (local, _remote) = testing::simulated();
+        testing::hold_writes(&local, 1);
+
+        let config = Config {
+            label: "chat".to_owned(),
+            ..Default::default()
+        };
+        let mut data_channel = DataChannel::dial(&local, 1, config).unwrap();
+        // The 12 byte DATA_CHANNEL_OPEN header, and the label.
+        assert_eq!(data_channel.buffered_amount(), 16);
+
+        data_channel
+            .write_data_channel(&mut Bytes::from_static(b"hello"), false)
+            .unwrap();
+        assert_eq!(data_channel.buffered_amount(), 21);
+
+        testing::release_writes(&local, 1);
+        assert_eq!(data_channel.buffered_amount(), 0);
+    }
+
+    #[test]
+    fn read_bytes_hands_back_payload() {
+        let (mut dialer, mut acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+
+        let payload = Bytes::from_static(b"hello");
+        dialer
+            .write_data_channel(&mut payload.clone(), true)
+            .unwrap();
+        acceptor
+            .write_data_channel(&mut Bytes::from_static(b"world"), false)
+            .unwrap();
+
+        let (message, is_string) = acceptor.read_bytes().unwrap();
+        assert_eq!(message, payload);
+        assert!(is_string);
+        assert_eq!(acceptor.messages_received(), 1);
+
+        // The ACK is handled on the way.
+        let (message, is_string) = dialer.read_bytes().unwrap();
+        assert_eq!(&message[..], b"world");
+        assert!(!is_string);
+        assert_eq!(dialer.ready_state(), ReadyState::Open);
+    }
+
+    #[test]
+    fn write_vectored_sends_single_message() {
+        let (mut dialer, mut acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+
+        let header = [0x00, 0x05];
+        let n = dialer
+            .write_vectored(&[IoSlice::new(&header), IoSlice::new(b"hello")])
+            .unwrap();
+        assert_eq!(n, 7);
+
+        let (message, is_string) = acceptor.read_bytes().unwrap();
+        assert_eq!(&message[..], b"\x00\x05hello");
+        assert!(!is_string);
+        assert_eq!(acceptor.messages_received(), 1);
+    }
+
+    #[test]
+    fn records_messages_of_many_chunks() {
+        #[derive(Default)]
+        struct Payloads(std::sync::Mutex<Vec<Vec<u8>>>);
+
+        impl Recorder for Payloads {
+            fn record(&self, _: u16, _: Direction, _: bool, payload: &[u8]) {
+                self.0.lock().unwrap().push(payload.to_vec());
+            }
+        }
+
+        let payloads = Arc::new(Payloads::default());
+        let config = Config {
+            recorder: Some(SharedRecorder::from(payloads.clone() as Arc<dyn Recorder>)),
+            ..Default::default()
+        };
+        let (mut dialer, _acceptor) = testing::channel_pair(1, config).unwrap();
+
+        let data: Vec<[u8; 1]> = (0..20).map(|byte| [byte]).collect();
+        let slices: Vec<IoSlice<'_>> = data.iter().map(|byte| IoSlice::new(byte)).collect();
+        dialer.write_vectored(&slices).unwrap();
+
+        let expected: Vec<u8> = (0..20).collect();
+        assert_eq!(*payloads.0.lock().unwrap(), vec![expected]);
+    }
+
+    #[test]
+    fn read_payload_tells_text_and_binary_apart() {
+        let (mut dialer, mut acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+
+        dialer.write_text("héllo").unwrap();
+        dialer
+            .write_data_channel(&mut Bytes::from_static(b"hello"), false)
+            .unwrap();
+        dialer
+            .write_data_channel(&mut Bytes::from_static(&[0xff, 0xfe]), true)
+            .unwrap();
+
+        let payload = acceptor.read_payload().unwrap();
+        assert_eq!(payload, Payload::Text("héllo".to_owned()));
+        assert!(payload.is_string());
+        assert_eq!(
+            acceptor.read_payload(),
+            Ok(Payload::Binary(Bytes::from_static(b"hello")))
+        );
+        assert!(matches!(
+            acceptor.read_payload(),
+            Err(DataChannelError::String(_))
+        ));
+    }
+
+    #[test]
+    fn empty_messages_round_trip() {
+        let (local, remote) = testing::simulated();
+        let mut dialer = DataChannel::dial(&local, 1, Config::default()).unwrap();
+        let mut acceptor = DataChannel::accept(&remote, Config::default()).unwrap();
+
+        testing::hold_writes(&local, 1);
+        assert_eq!(dialer.write_text(""), Ok(0));
+        assert_eq!(dialer.write_data_channel(&mut Bytes::new(), false), Ok(0));
+        // Each went out as a single padding byte.
+        assert_eq!(dialer.buffered_amount(), 2);
+        testing::release_writes(&local, 1);
+
+        assert_eq!(acceptor.read_payload(), Ok(Payload::Text(String::new())));
+        assert_eq!(acceptor.read_payload(), Ok(Payload::Binary(Bytes::new())));
+        assert_eq!(acceptor.messages_received(), 2);
+        assert_eq!(acceptor.bytes_received(), 0);
+    }
+
+    #[test]
+    fn close_waits_for_remote_reset() {
+        let (mut dialer, mut acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        let closed = Arc::new(AtomicUsize::new(0));
+        let counter = closed.clone();
+        dialer.on_close(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        acceptor
+            .write_data_channel(&mut Bytes::from_static(b"late"), false)
+            .unwrap();
+        dialer.close().unwrap();
+        assert_eq!(dialer.ready_state(), ReadyState::Closing);
+        assert_eq!(
+            dialer.write_data_channel(&mut Bytes::from_static(b"hello"), false),
+            Err(DataChannelError::Stream(StreamError::Eof))
+        );
+        // Closing again is a no-op.
+        assert_eq!(dialer.close(), Ok(()));
+
+        // The acceptor resets its own stream in response.
+        assert_eq!(
+            acceptor.read_bytes(),
+            Err(DataChannelError::Stream(StreamError::Eof))
+        );
+        assert_eq!(acceptor.ready_state(), ReadyState::Closed);
+        assert_eq!(closed.load(Ordering::SeqCst), 0);
+
+        dialer.wait_closed().unwrap();
+        assert_eq!(dialer.ready_state(), ReadyState::Closed);
+        assert_eq!(closed.load(Ordering::SeqCst), 1);
+        assert_eq!(dialer.wait_closed(), Ok(()));
+        assert_eq!(closed.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn poll_open_fails_cleanly_if_reliability_params_fail() {
+        let (local, remote) = testing::simulated();
+        let config = Config {
+            channel_type: ChannelType::ReliableUnordered,
+            ..Default::default()
+        };
+        let mut dialer = DataChannel::dial(&local, 1, config).unwrap();
+        // The remote skips the ACK, so the message implies it.
+        let mut stream = remote.accept_stream().unwrap();
+        stream
+            .write_sctp(&mut Bytes::from_static(b"early"), PayloadType::WebRtcBinary)
+            .unwrap();
+        testing::fail_reliability_params(&local, 1);
+
+        let poll = dialer.poll_open(&mut Context::from_waker(Waker::noop()));
+        assert_eq!(
+            poll,
+            Poll::Ready(Err(DataChannelError::Stream(StreamError::Eof)))
+        );
+        assert_eq!(dialer.ready_state(), ReadyState::Connecting);
+        assert!(dialer.pending_read.is_none());
+        assert!(!dialer.sends_unordered());
+    }
+
+    #[test]
+    fn on_state_change_follows_transitions() {
+        let (local, remote) = testing::simulated();
+        let mut dialer = DataChannel::dial(&local, 1, Config::default()).unwrap();
+        let mut acceptor = DataChannel::accept(&remote, Config::default()).unwrap();
+
+        let states = Arc::new(std::sync::Mutex::new(vec![]));
+        let recorded = states.clone();
+        dialer.on_state_change(move |ready_state| recorded.lock().unwrap().push(ready_state));
+
+        let poll = dialer.poll_open(&mut Context::from_waker(Waker::noop()));
+        assert_eq!(poll, Poll::Ready(Ok(())));
+        dialer.close().unwrap();
+        assert!(acceptor.read_bytes().is_err());
+        dialer.wait_closed().unwrap();
+
+        assert_eq!(
+            *states.lock().unwrap(),
+            vec![ReadyState::Open, ReadyState::Closing, ReadyState::Closed]
+        );
+    }
+
+    #[test]
+    fn read_loop_invokes_callbacks() {
+        let (local, remote) = testing::simulated();
+        let mut dialer = DataChannel::dial(&local, 1, Config::default()).unwrap();
+        let mut acceptor = DataChannel::accept(&remote, Config::default()).unwrap();
+
+        let calls = Arc::new(std::sync::Mutex::new(vec![]));
+        let on_open = calls.clone();
+        dialer.on_open(move || on_open.lock().unwrap().push("open".to_owned()));
+        let on_message = calls.clone();
+        dialer.on_message(move |message| {
+            let data = String::from_utf8(message.data.to_vec()).unwrap();
+            on_message.lock().unwrap().push(data);
+        });
+        let on_close = calls.clone();
+        dialer.on_close(move || on_close.lock().unwrap().push("close".to_owned()));
+        let on_error = calls.clone();
+        dialer.on_error(move |_| on_error.lock().unwrap().push("error".to_owned()));
+
+        acceptor.write_text("hello").unwrap();
+        acceptor.write_text("world").unwrap();
+        acceptor.close().unwrap();
+
+        assert_eq!(dialer.read_loop(), Ok(()));
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["open", "hello", "world", "close"]
+        );
+    }
+
+    #[test]
+    fn read_loop_reports_errors() {
+        let token = CancellationToken::new();
+        let config = Config {
+            cancellation_token: Some(token.clone()),
+            ..Default::default()
+        };
+        let (_dialer, mut acceptor) = testing::channel_pair(1, config).unwrap();
+
+        let errors = Arc::new(std::sync::Mutex::new(vec![]));
+        let on_error = errors.clone();
+        acceptor.on_error(move |error| on_error.lock().unwrap().push(error));
+
+        token.cancel();
+        assert_eq!(acceptor.read_loop(), Err(DataChannelError::Cancelled));
+        assert_eq!(*errors.lock().unwrap(), vec![DataChannelError::Cancelled]);
+    }
+
+    #[test]
+    fn stats_count_traffic_and_errors() {
+        let (local, remote) = testing::simulated();
+        let mut dialer = DataChannel::dial(&local, 1, Config::default()).unwrap();
+        let mut acceptor = DataChannel::accept(&remote, Config::default()).unwrap();
+        let observer = dialer.observer();
+        assert_eq!(dialer.stats().opened_at, None);
+
+        dialer.write_text("hello").unwrap();
+        testing::fail_writes(&local, 1, 1);
+        assert!(dialer.write_text("world").is_err());
+        acceptor.write_text("hi").unwrap();
+        dialer.read_bytes().unwrap();
+
+        let stats = dialer.stats();
+        assert_eq!(stats.messages_sent, 1);
+        assert_eq!(stats.bytes_sent, 5);
+        assert_eq!(stats.messages_received, 1);
+        assert_eq!(stats.bytes_received, 2);
+        assert_eq!(stats.errors, 1);
+        assert!(stats.opened_at.is_some());
+        assert_eq!(observer.stats(), stats);
+    }
+
+    #[test]
+    fn rtt_follows_association() {
+        let (local, _remote) = testing::simulated();
+        let dialer = DataChannel::dial(&local, 1, Config::default()).unwrap();
+        assert_eq!(dialer.rtt(), None);
+        let updates = dialer.rtt_updates();
+
+        for rtt in [20, 20, 35] {
+            let signals = QualitySignals {
+                rtt: Some(Duration::from_millis(rtt)),
+                ..Default::default()
+            };
+            testing::set_quality_signals(&local, signals);
+        }
+
+        assert_eq!(dialer.rtt(), Some(Duration::from_millis(35)));
+        let updates: Vec<_> = updates.try_iter().collect();
+        assert_eq!(
+            updates,
+            vec![Duration::from_millis(20), Duration::from_millis(35)]
+        );
+    }
+
+    #[test]
+    fn applies_reliability_params_on_send() {
+        let config = Config {
+            channel_type: ChannelType::PartialReliableRexmit,
+            reliability_parameter: 3,
+            ..Default::default()
+        };
+        let (mut local, mut remote) = testing::channel_pair(1, config).unwrap();
+        assert_eq!(
+            remote.stream.reliability_params(),
+            (false, sctp::ReliabilityType::Rexmit, 3)
+        );
+
+        local
+            .set_reliability_params(ChannelType::PartialReliableTimed, 250)
+            .unwrap();
+        assert_eq!(
+            local.stream.reliability_params(),
+            (false, sctp::ReliabilityType::Timed, 250)
+        );
+
+        remote
+            .set_reliability_params(ChannelType::PartialReliableRexmitUnordered, 0)
+            .unwrap();
+        assert_eq!(
+            remote.stream.reliability_params(),
+            (true, sctp::ReliabilityType::Rexmit, 0)
+        );
+
+        // Changes made to the config directly apply to the next send.
+        remote.config.reliability_parameter = 5;
+        remote.write_text("hello").unwrap();
+        assert_eq!(
+            remote.stream.reliability_params(),
+            (true, sctp::ReliabilityType::Rexmit, 5)
+        );
+    }
+
+    #[test]
+    fn sends_ordered_until_acknowledged() {
+        let config = Config {
+            channel_type: ChannelType::ReliableUnordered,
+            ..Default::default()
+        };
+        let (mut local, mut remote) = testing::channel_pair(1, config).unwrap();
+        assert!(remote.sends_unordered());
+        assert!(!local.sends_unordered());
+
+        local.write_text("early").unwrap();
+        assert_eq!(
+            local.stream.reliability_params(),
+            (false, sctp::ReliabilityType::Reliable, 0)
+        );
+
+        remote.write_text("hello").unwrap();
+        local.read_payload().unwrap();
+        assert!(local.sends_unordered());
+        assert_eq!(
+            local.stream.reliability_params(),
+            (true, sctp::ReliabilityType::Reliable, 0)
+        );
+    }
+
+    #[test]
+    fn rejects_messages_over_max_message_size() {
+        let (local, _remote) = testing::simulated();
+        testing::set_max_message_size(&local, 8);
+        let mut data_channel = DataChannel::dial(&local, 1, Config::default()).unwrap();
+        assert_eq!(data_channel.max_message_size(), 8);
+
+        assert_eq!(data_channel.write_text("12345678"), Ok(8));
+        assert_eq!(
+            data_channel.write_text("123456789"),
+            Err(DataChannelError::MessageTooLarge { size: 9, max: 8 })
+        );
+        assert_eq!(data_channel.messages_sent(), 1);
+
+        data_channel.config.max_message_size = Some(4);
+        assert_eq!(
+            data_channel.write_text("12345"),
+            Err(DataChannelError::MessageTooLarge { size: 5, max: 4 })
+        );
+
+        // Zero means no limit at all.
+        data_channel.config.max_message_size = Some(0);
+        assert_eq!(data_channel.write_text("123456789"), Ok(9));
+    }
+
+    #[test]
+    fn default_payload_format() {
+        let config = ConfigBuilder::default().build().unwrap();
+        assert_eq!(config.default_payload_format, PayloadFormat::Binary);
+
+        let config = ConfigBuilder::default()
+            .default_payload_format(PayloadFormat::Text)
+            .build()
+            .unwrap();
+        assert_eq!(config.default_payload_format, PayloadFormat::Text);
+        assert!(config.default_payload_format.is_string());
+        assert_eq!(
+            config.default_payload_format.payload_type(),
+            PayloadType::WebRtcString
+        );
+    }
+
+    #[test]
+    fn copy_message_fits() {
+        let mut buf = [0; 8];
+
+        let (result, pending) = copy_message(
+            Bytes::from_static(b"hello"),
+            true,
+            &mut buf,
+            TruncationPolicy::Error,
+        );
+
+        let read_into = result.unwrap();
+        assert_eq!(read_into.n, 5);
+        assert_eq!(read_into.message_len, 5);
+        assert_eq!(read_into.remaining, 0);
+        assert!(read_into.is_string);
+        assert_eq!(&buf[..5], b"hello");
+        assert_eq!(pending, None);
+    }
+
+    #[test]
+    fn copy_message_truncation_policies() {
+        let message = Bytes::from_static(b"hello world");
+        let mut buf = [0; 5];
+
+        let (result, pending) =
+            copy_message(message.clone(), false, &mut buf, TruncationPolicy::Error);
+        assert_eq!(
+            result,
+            Err(DataChannelError::BufferTooSmall {
+                message_len: 11,
+                buffer_len: 5
+            })
+        );
+        assert_eq!(pending, Some(message.clone()));
+
+        let (result, pending) =
+            copy_message(message.clone(), false, &mut buf, TruncationPolicy::Truncate);
+        assert_eq!(result.unwrap().n, 5);
+        assert_eq!(&buf, b"hello");
+        assert_eq!(pending, None);
+
+        let (result, pending) =
+            copy_message(message, false, &mut buf, TruncationPolicy::BufferRemainder);
+        let read_into = result.unwrap();
+        assert_eq!(read_into.n, 5);
+        assert_eq!(read_into.message_len, 11);
+        assert_eq!(read_into.remaining, 6);
+        assert_eq!(pending, Some(Bytes::from_static(b" world")));
     }
 }
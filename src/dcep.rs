@@ -0,0 +1,325 @@
+//! Extension points of the Data Channel Establishment Protocol (DCEP),
+//! and an `Inspector` to observe it from outside of a channel.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    error::{DataChannelError, MessageError},
+    marshal::Unmarshal,
+    message::{DataChannelOpenRef, MessageRef, RawMessage},
+    sctp::PayloadType,
+    wire::{MESSAGE_TYPE_ACK, MESSAGE_TYPE_OPEN},
+    ChannelType,
+};
+
+/// A handler for DCEP messages of an experimental type.
+pub trait DcepHandler: Send + Sync {
+    /// Handles a message received on the given stream,
+    /// returning a message to send back in response, if any.
+    fn handle(&self, stream_identifier: u16, message: &RawMessage) -> Option<RawMessage>;
+}
+
+impl<F> DcepHandler for F
+where
+    F: Fn(u16, &RawMessage) -> Option<RawMessage> + Send + Sync,
+{
+    fn handle(&self, stream_identifier: u16, message: &RawMessage) -> Option<RawMessage> {
+        self(stream_identifier, message)
+    }
+}
+
+/// A set of `DcepHandler`s, keyed by the message type they handle.
+///
+/// Clones share the same handlers, so handlers registered after a channel
+/// has been configured with the registry apply to that channel as well.
+/// Two registries compare equal if they share the same handlers.
+#[derive(Clone, Default)]
+pub struct DcepRegistry {
+    handlers: Arc<RwLock<BTreeMap<u8, Arc<dyn DcepHandler>>>>,
+}
+
+impl DcepRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register sets the handler for messages of the given type, replacing
+    /// any previously registered one. The types defined by RFC 8832 are reserved.
+    pub fn register<H>(&self, message_type: u8, handler: H) -> Result<(), DataChannelError>
+    where
+        H: DcepHandler + 'static,
+    {
+        if message_type == MESSAGE_TYPE_ACK || message_type == MESSAGE_TYPE_OPEN {
+            return Err(DataChannelError::ReservedMessageType { message_type });
+        }
+
+        let mut handlers = self.handlers.write().unwrap();
+        handlers.insert(message_type, Arc::new(handler));
+
+        Ok(())
+    }
+
+    /// Unregister removes the handler for messages of the given type,
+    /// returning whether there was one.
+    pub fn unregister(&self, message_type: u8) -> bool {
+        let mut handlers = self.handlers.write().unwrap();
+        handlers.remove(&message_type).is_some()
+    }
+
+    /// Handler returns the handler for messages of the given type, if any.
+    pub fn handler(&self, message_type: u8) -> Option<Arc<dyn DcepHandler>> {
+        let handlers = self.handlers.read().unwrap();
+        handlers.get(&message_type).cloned()
+    }
+}
+
+impl PartialEq for DcepRegistry {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.handlers, &other.handlers)
+    }
+}
+
+impl Eq for DcepRegistry {}
+
+impl std::fmt::Debug for DcepRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let handlers = self.handlers.read().unwrap();
+        f.debug_tuple("DcepRegistry")
+            .field(&handlers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// What an `Inspector` made of a single stream payload.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum Inspection<'a> {
+    Open(DataChannelOpenRef<'a>),
+    Ack,
+    /// A DCEP message of a type other than OPEN and ACK, such as an experimental one.
+    OtherDcep {
+        message_type: u8,
+    },
+    /// A user message. The padding byte of empty messages is stripped.
+    UserData {
+        is_string: bool,
+        data: &'a [u8],
+    },
+    /// A payload whose PPID isn't one of those defined for WebRTC.
+    Unknown {
+        ppid: u32,
+    },
+}
+
+/// A channel as observed by an `Inspector`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct InspectedChannel {
+    pub channel_type: ChannelType,
+    pub priority: u16,
+    pub reliability_parameter: u32,
+    /// The label, with invalid UTF-8 replaced.
+    pub label: String,
+    /// The protocol, with invalid UTF-8 replaced.
+    pub protocol: String,
+    /// Whether the DATA_CHANNEL_ACK has been seen.
+    pub acknowledged: bool,
+}
+
+/// Inspector classifies the payloads of SCTP streams, such as those relayed
+/// by a proxy, and keeps track of the channels opened on them, without
+/// taking part in the handshake.
+#[derive(Debug, Default)]
+pub struct Inspector {
+    channels: BTreeMap<u16, InspectedChannel>,
+}
+
+impl Inspector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Classify tells what a payload with the given PPID is, borrowing the
+    /// fields of DCEP messages from it. Fails if a DCEP message is malformed.
+    pub fn classify(ppid: u32, payload: &[u8]) -> Result<Inspection<'_>, MessageError> {
+        let payload_type = match PayloadType::unmarshal_from(&mut &ppid.to_be_bytes()[..]) {
+            Ok(payload_type) => payload_type,
+            Err(_) => return Ok(Inspection::Unknown { ppid }),
+        };
+
+        let inspection = match payload_type {
+            PayloadType::WebRtcDcep => match payload.first() {
+                Some(&message_type)
+                    if message_type != MESSAGE_TYPE_ACK && message_type != MESSAGE_TYPE_OPEN =>
+                {
+                    Inspection::OtherDcep { message_type }
+                }
+                _ => match MessageRef::parse(payload)? {
+                    MessageRef::DataChannelAck => Inspection::Ack,
+                    MessageRef::DataChannelOpen(open) => Inspection::Open(open),
+                },
+            },
+            PayloadType::WebRtcString | PayloadType::WebRtcBinary => Inspection::UserData {
+                is_string: payload_type == PayloadType::WebRtcString,
+                data: payload,
+            },
+            PayloadType::WebRtcStringEmpty | PayloadType::WebRtcBinaryEmpty => {
+                Inspection::UserData {
+                    is_string: payload_type == PayloadType::WebRtcStringEmpty,
+                    data: &[],
+                }
+            }
+        };
+
+        Ok(inspection)
+    }
+
+    /// Inspect classifies a payload seen on the given stream, recording
+    /// the channel it opens or acknowledges.
+    pub fn inspect<'a>(
+        &mut self,
+        stream_identifier: u16,
+        ppid: u32,
+        payload: &'a [u8],
+    ) -> Result<Inspection<'a>, MessageError> {
+        let inspection = Self::classify(ppid, payload)?;
+
+        match inspection {
+            Inspection::Open(open) => {
+                let channel = InspectedChannel {
+                    channel_type: open.channel_type,
+                    priority: open.priority,
+                    reliability_parameter: open.reliability_parameter,
+                    label: String::from_utf8_lossy(open.label).into_owned(),
+                    protocol: String::from_utf8_lossy(open.protocol).into_owned(),
+                    acknowledged: false,
+                };
+                self.channels.insert(stream_identifier, channel);
+            }
+            Inspection::Ack => {
+                if let Some(channel) = self.channels.get_mut(&stream_identifier) {
+                    channel.acknowledged = true;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(inspection)
+    }
+
+    /// Channel returns the channel last opened on the given stream, if any.
+    pub fn channel(&self, stream_identifier: u16) -> Option<&InspectedChannel> {
+        self.channels.get(&stream_identifier)
+    }
+
+    /// Forget stops tracking the channel on the given stream,
+    /// such as once the stream has been reset.
+    pub fn forget(&mut self, stream_identifier: u16) -> Option<InspectedChannel> {
+        self.channels.remove(&stream_identifier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+
+    #[test]
+    fn register_and_dispatch() {
+        let registry = DcepRegistry::new();
+        registry
+            .register(0x42, |_, message: &RawMessage| {
+                Some(RawMessage {
+                    message_type: 0x43,
+                    payload: message.payload.clone(),
+                })
+            })
+            .unwrap();
+
+        let message = RawMessage {
+            message_type: 0x42,
+            payload: Bytes::from_static(b"ping"),
+        };
+        let response = registry.handler(0x42).unwrap().handle(1, &message);
+
+        assert_eq!(
+            response,
+            Some(RawMessage {
+                message_type: 0x43,
+                payload: Bytes::from_static(b"ping"),
+            })
+        );
+        assert!(registry.handler(0x43).is_none());
+        assert!(registry.unregister(0x42));
+        assert!(registry.handler(0x42).is_none());
+    }
+
+    #[test]
+    fn register_reserved_type() {
+        let registry = DcepRegistry::new();
+
+        let result = registry.register(MESSAGE_TYPE_OPEN, |_, _: &RawMessage| None);
+
+        assert_eq!(
+            result,
+            Err(DataChannelError::ReservedMessageType {
+                message_type: MESSAGE_TYPE_OPEN
+            })
+        );
+    }
+
+    #[test]
+    fn inspector_tracks_handshake() {
+        let open = [
+            0x03, // message type
+            0x80, // channel type
+            0x00, 0x00, // priority
+            0x00, 0x00, 0x00, 0x00, // reliability parameter
+            0x00, 0x04, // label length
+            0x00, 0x00, // protocol length
+            0x63, 0x68, 0x61, 0x74, // label
+        ];
+        let mut inspector = Inspector::new();
+
+        match inspector.inspect(1, 50, &open) {
+            Ok(Inspection::Open(open)) => assert_eq!(open.label, b"chat"),
+            inspection => panic!("expected an OPEN, got {:?}", inspection),
+        }
+        let channel = inspector.channel(1).unwrap();
+        assert_eq!(channel.channel_type, ChannelType::ReliableUnordered);
+        assert_eq!(channel.label, "chat");
+        assert!(!channel.acknowledged);
+
+        assert_eq!(inspector.inspect(1, 50, &[0x02]), Ok(Inspection::Ack));
+        assert!(inspector.channel(1).unwrap().acknowledged);
+
+        assert_eq!(
+            inspector.inspect(1, 51, b"hi"),
+            Ok(Inspection::UserData {
+                is_string: true,
+                data: b"hi"
+            })
+        );
+        assert_eq!(
+            Inspector::classify(57, &[0]),
+            Ok(Inspection::UserData {
+                is_string: false,
+                data: &[]
+            })
+        );
+        assert_eq!(
+            Inspector::classify(50, &[0x42, 0x01]),
+            Ok(Inspection::OtherDcep { message_type: 0x42 })
+        );
+        assert_eq!(
+            Inspector::classify(46, b""),
+            Ok(Inspection::Unknown { ppid: 46 })
+        );
+        assert!(Inspector::classify(50, &[0x03, 0x00]).is_err());
+
+        assert!(inspector.forget(1).is_some());
+        assert!(inspector.channel(1).is_none());
+    }
+}
@@ -0,0 +1,13 @@
+//! Opt-in duplicate suppression.
+//!
+//! Applications retrying messages over unreliable channels may get the
+//! same message delivered more than once. A `DedupChannel` tags each
+//! message with an ID, either supplied by the application, so retries of
+//! a message share it, or generated, and drops messages whose ID is
+//! among the most recent ones received.
+
+mod channel;
+mod window;
+
+pub use channel::DedupChannel;
+pub use window::DedupWindow;
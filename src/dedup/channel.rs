@@ -0,0 +1,136 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+
+use crate::{
+    data_channel::{DataChannel, RECEIVE_MTU},
+    dedup::DedupWindow,
+    error::DataChannelError,
+    marshal::{Marshal, Unmarshal},
+    ordering::SequencedFrame,
+};
+
+/// DedupChannel sends messages tagged with an ID over a data channel,
+/// and drops received messages whose ID it has seen recently.
+///
+/// Messages are framed as `SequencedFrame`s, carrying their ID
+/// in place of a sequence number.
+pub struct DedupChannel {
+    data_channel: DataChannel,
+    next_id: u64,
+    window: DedupWindow,
+}
+
+impl DedupChannel {
+    /// Creates a channel remembering the IDs of the last `window` messages received.
+    ///
+    /// Generated IDs start from the current time, so they don't collide
+    /// with those of an earlier channel to the same remote.
+    pub fn new(data_channel: DataChannel, window: usize) -> Self {
+        let next_id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+
+        Self {
+            data_channel,
+            next_id,
+            window: DedupWindow::new(window),
+        }
+    }
+
+    /// Write sends a message with a generated ID, returning the ID.
+    pub fn write(&mut self, payload: Bytes, is_string: bool) -> Result<u64, DataChannelError> {
+        let id = self.next_id;
+        self.write_with_id(id, payload, is_string)?;
+        self.next_id = self.next_id.wrapping_add(1);
+
+        Ok(id)
+    }
+
+    /// WriteWithId sends a message with the given ID. Retries
+    /// of a message should be sent with the ID of the original.
+    pub fn write_with_id(
+        &mut self,
+        id: u64,
+        payload: Bytes,
+        is_string: bool,
+    ) -> Result<(), DataChannelError> {
+        let frame = SequencedFrame {
+            sequence: id,
+            is_string,
+            payload,
+        };
+
+        let mut bytes = frame.marshal()?;
+        self.data_channel.write_data_channel(&mut bytes, false)?;
+
+        Ok(())
+    }
+
+    /// Read blocks until the next message which isn't a duplicate
+    /// arrives, returning its ID, payload and whether it is text.
+    pub fn read(&mut self) -> Result<(u64, Bytes, bool), DataChannelError> {
+        loop {
+            let frame = self.read_frame()?;
+            if self.window.insert(frame.sequence) {
+                return Ok((frame.sequence, frame.payload, frame.is_string));
+            }
+
+            log::debug!("Dropping duplicate message {}", frame.sequence);
+        }
+    }
+
+    pub fn window(&self) -> &DedupWindow {
+        &self.window
+    }
+
+    pub fn into_inner(self) -> DataChannel {
+        self.data_channel
+    }
+
+    fn read_frame(&mut self) -> Result<SequencedFrame, DataChannelError> {
+        let buffer_provider = self.data_channel.config.buffer_provider.clone();
+        let mut buf = buffer_provider.acquire(RECEIVE_MTU);
+        let result = self
+            .data_channel
+            .read(&mut buf)
+            .map(|n| buf.split_to(n).freeze());
+        buffer_provider.release(buf);
+
+        let mut bytes = result?;
+        SequencedFrame::unmarshal_from(&mut bytes).map_err(From::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{data_channel::Config, testing};
+
+    use super::*;
+
+    #[test]
+    fn drops_retried_messages() {
+        let (dialer, acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        let mut dialer = DedupChannel::new(dialer, 16);
+        let mut acceptor = DedupChannel::new(acceptor, 16);
+
+        let id = dialer.write(Bytes::from_static(b"once"), true).unwrap();
+        dialer
+            .write_with_id(id, Bytes::from_static(b"once"), true)
+            .unwrap();
+        dialer
+            .write_with_id(7, Bytes::from_static(b"next"), false)
+            .unwrap();
+
+        assert_eq!(
+            acceptor.read().unwrap(),
+            (id, Bytes::from_static(b"once"), true)
+        );
+        assert_eq!(
+            acceptor.read().unwrap(),
+            (7, Bytes::from_static(b"next"), false)
+        );
+        assert_eq!(acceptor.window().duplicates(), 1);
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::{HashSet, VecDeque};
+
+/// DedupWindow remembers the IDs of the last `capacity` messages received.
+#[derive(Debug)]
+pub struct DedupWindow {
+    capacity: usize,
+    seen: HashSet<u64>,
+    // The IDs in `seen`, oldest first.
+    order: VecDeque<u64>,
+    duplicates: u64,
+}
+
+impl DedupWindow {
+    /// Creates a window remembering `capacity` IDs.
+    /// A `capacity` of zero is treated like a capacity of one.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+
+        Self {
+            capacity,
+            seen: HashSet::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            duplicates: 0,
+        }
+    }
+
+    /// Insert records an ID, returning whether it wasn't already in the window.
+    pub fn insert(&mut self, id: u64) -> bool {
+        if !self.seen.insert(id) {
+            self.duplicates += 1;
+            return false;
+        }
+
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.order.push_back(id);
+
+        true
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        self.seen.contains(&id)
+    }
+
+    /// Duplicates returns the number of duplicate IDs inserted so far.
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suppresses_duplicates_within_capacity() {
+        let mut window = DedupWindow::new(2);
+
+        assert!(window.insert(1));
+        assert!(window.insert(2));
+        assert!(!window.insert(1));
+        assert_eq!(window.duplicates(), 1);
+
+        // Evicts the oldest ID.
+        assert!(window.insert(3));
+        assert!(!window.contains(1));
+        assert!(window.insert(1));
+        assert_eq!(window.len(), 2);
+    }
+}
@@ -1,13 +1,17 @@
-use std::string::FromUtf8Error;
+use core::fmt;
 
+#[cfg(feature = "std")]
 use thiserror::Error;
 
-use crate::{
-    message::MessageType,
-    sctp::{AssociationError, PayloadType, StreamError},
-};
+// Errors of the channel machinery, as opposed to those of the wire format.
+#[cfg(feature = "std")]
+mod channel;
 
-#[derive(Error, Eq, PartialEq, Clone, Debug)]
+#[cfg(feature = "std")]
+pub use channel::*;
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
 pub enum ChannelTypeError {
     // Marshal buffer was too short
     UnexpectedEndOfBuffer { expected: usize, actual: usize },
@@ -16,8 +20,8 @@ pub enum ChannelTypeError {
     InvalidChannelType { invalid_type: u8 },
 }
 
-impl std::fmt::Display for ChannelTypeError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Display for ChannelTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::UnexpectedEndOfBuffer { expected, actual } => {
                 writeln!(
@@ -33,26 +37,54 @@ impl std::fmt::Display for ChannelTypeError {
     }
 }
 
-#[derive(Error, Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
 pub enum DataChannelAckError {}
 
-#[derive(Error, Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
 pub enum DataChannelOpenError {
     // Marshal buffer was too short
-    UnexpectedEndOfBuffer { expected: usize, actual: usize },
+    UnexpectedEndOfBuffer {
+        expected: usize,
+        actual: usize,
+    },
 
     // Declared length and actual length don't match
-    ExpectedAndActualLengthMismatch { expected: usize, actual: usize },
+    ExpectedAndActualLengthMismatch {
+        expected: usize,
+        actual: usize,
+    },
 
     // DataChannel messages with a Payload Protocol Identifier we don't know how to handle
     InvalidPayloadProtocolIdentifier,
 
+    // Label or protocol doesn't fit into its 16 bit length field
+    FieldTooLong {
+        field: &'static str,
+        len: usize,
+        max: usize,
+    },
+
+    // Label or protocol is not valid UTF-8
+    NotUtf8 {
+        field: &'static str,
+    },
+
+    // Bytes were left over after the message, in strict mode
+    TrailingBytes {
+        len: usize,
+    },
+
+    // Label is empty, in strict mode
+    EmptyLabel,
+
     // Remote requested a channel type that we don't support
-    ChannelType(#[from] ChannelTypeError),
+    ChannelType(#[cfg_attr(feature = "std", source)] ChannelTypeError),
 }
 
-impl std::fmt::Display for DataChannelOpenError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Display for DataChannelOpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::UnexpectedEndOfBuffer { expected, actual } => {
                 writeln!(
@@ -72,43 +104,29 @@ impl std::fmt::Display for DataChannelOpenError {
                     expected, actual
                 )
             }
+            Self::FieldTooLong { field, len, max } => writeln!(
+                f,
+                "Field is too long: (field: {}, len: {:?}, max: {:?})",
+                field, len, max
+            ),
+            Self::NotUtf8 { field } => writeln!(f, "Field is not valid UTF-8: {}", field),
+            Self::TrailingBytes { len } => {
+                writeln!(f, "Message is followed by {} trailing bytes", len)
+            }
+            Self::EmptyLabel => writeln!(f, "Label is empty"),
             Self::ChannelType(error) => error.fmt(f),
         }
     }
 }
 
-#[derive(Error, Eq, PartialEq, Clone, Debug)]
-pub enum DataChannelError {
-    InvalidMessageType { invalid_type: MessageType },
-    InvalidPayloadProtocolIdentifier { invalid_identifier: PayloadType },
-    Message(#[from] MessageError),
-    Stream(#[from] StreamError),
-    String(#[from] FromUtf8Error),
-    Association(#[from] AssociationError),
-}
-
-impl std::fmt::Display for DataChannelError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            DataChannelError::InvalidMessageType { invalid_type } => {
-                writeln!(f, "Invalid message type: {:?}", invalid_type)
-            }
-            DataChannelError::InvalidPayloadProtocolIdentifier { invalid_identifier } => {
-                writeln!(
-                    f,
-                    "Invalid payload protocol identifier: {:?}",
-                    invalid_identifier
-                )
-            }
-            DataChannelError::Message(error) => error.fmt(f),
-            DataChannelError::Stream(error) => error.fmt(f),
-            DataChannelError::String(error) => error.fmt(f),
-            DataChannelError::Association(error) => error.fmt(f),
-        }
+impl From<ChannelTypeError> for DataChannelOpenError {
+    fn from(error: ChannelTypeError) -> Self {
+        Self::ChannelType(error)
     }
 }
 
-#[derive(Error, Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
 pub enum MessageTypeError {
     // Marshal buffer was too short
     UnexpectedEndOfBuffer { expected: usize, actual: usize },
@@ -117,8 +135,8 @@ pub enum MessageTypeError {
     InvalidMessageType { invalid_type: u8 },
 }
 
-impl std::fmt::Display for MessageTypeError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Display for MessageTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::UnexpectedEndOfBuffer { expected, actual } => {
                 writeln!(
@@ -134,7 +152,8 @@ impl std::fmt::Display for MessageTypeError {
     }
 }
 
-#[derive(Error, Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
 pub enum MessageError {
     // Marshal buffer was too short
     UnexpectedEndOfBuffer { expected: usize, actual: usize },
@@ -145,15 +164,18 @@ pub enum MessageError {
     // DataChannel messages with a Payload Protocol Identifier we don't know how to handle
     InvalidPayloadProtocolIdentifier,
 
+    // Bytes were left over after the message, in strict mode
+    TrailingBytes { len: usize },
+
     // DataChannel message has a type we don't support
-    MessageType(#[from] MessageTypeError),
+    MessageType(#[cfg_attr(feature = "std", source)] MessageTypeError),
 
     // Invalid DATA_CHANNEL_OPEN message body
-    DataChannelOpen(#[from] DataChannelOpenError),
+    DataChannelOpen(#[cfg_attr(feature = "std", source)] DataChannelOpenError),
 }
 
-impl std::fmt::Display for MessageError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Display for MessageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::UnexpectedEndOfBuffer { expected, actual } => {
                 writeln!(
@@ -173,8 +195,23 @@ impl std::fmt::Display for MessageError {
                     expected, actual
                 )
             }
+            Self::TrailingBytes { len } => {
+                writeln!(f, "Message is followed by {} trailing bytes", len)
+            }
             Self::MessageType(error) => error.fmt(f),
             Self::DataChannelOpen(error) => error.fmt(f),
         }
     }
 }
+
+impl From<MessageTypeError> for MessageError {
+    fn from(error: MessageTypeError) -> Self {
+        Self::MessageType(error)
+    }
+}
+
+impl From<DataChannelOpenError> for MessageError {
+    fn from(error: DataChannelOpenError) -> Self {
+        Self::DataChannelOpen(error)
+    }
+}
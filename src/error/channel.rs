@@ -0,0 +1,570 @@
+use std::{string::FromUtf8Error, time::Duration};
+
+use thiserror::Error;
+
+use super::{ChannelTypeError, MessageError};
+use crate::{
+    message::MessageType,
+    sctp::{AssociationError, PayloadType, StreamError},
+    supervision::SupervisedTask,
+};
+
+#[derive(Error, Eq, PartialEq, Clone, Debug)]
+pub enum PayloadTypeError {
+    // Marshal buffer was too short
+    UnexpectedEndOfBuffer { expected: usize, actual: usize },
+
+    // Payload protocol identifier isn't one used by data channels
+    InvalidPayloadType { invalid_type: u32 },
+}
+
+impl std::fmt::Display for PayloadTypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEndOfBuffer { expected, actual } => {
+                writeln!(
+                    f,
+                    "Marshal buffer was too short: (expected: {:?}, actual: {:?})",
+                    expected, actual
+                )
+            }
+            Self::InvalidPayloadType { invalid_type } => {
+                writeln!(f, "Invalid payload type: {:?}", invalid_type)
+            }
+        }
+    }
+}
+
+#[derive(Error, Eq, PartialEq, Clone, Debug)]
+pub enum DataChannelError {
+    InvalidMessageType {
+        invalid_type: MessageType,
+    },
+    InvalidPayloadProtocolIdentifier {
+        invalid_identifier: PayloadType,
+    },
+    UnknownStreamIdentifier {
+        stream_identifier: u16,
+    },
+    RemoteInitiatedChannel {
+        stream_identifier: u16,
+    },
+    ReservedMessageType {
+        message_type: u8,
+    },
+    StreamIdentifiersExhausted,
+    Draining,
+    Cancelled,
+    Timeout,
+    HandshakeTimedOut {
+        elapsed: Duration,
+    },
+    OpenRejected {
+        reason: RejectReason,
+        raw: Vec<u8>,
+    },
+    BondFailed,
+    TaskFailed {
+        task: SupervisedTask,
+        cause: String,
+    },
+    BufferTooSmall {
+        message_len: usize,
+        buffer_len: usize,
+    },
+    MessageTooLarge {
+        size: usize,
+        max: usize,
+    },
+    Message(#[from] MessageError),
+    Frame(#[from] FrameError),
+    Packet(#[from] PacketError),
+    Fragment(#[from] FragmentError),
+    SequencedFrame(#[from] SequencedFrameError),
+    Fec(#[from] FecError),
+    Padding(#[from] PaddingError),
+    Config(#[from] ConfigError),
+    Stream(#[from] StreamError),
+    String(#[from] FromUtf8Error),
+    Association(#[from] AssociationError),
+}
+
+impl std::fmt::Display for DataChannelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataChannelError::InvalidMessageType { invalid_type } => {
+                writeln!(f, "Invalid message type: {:?}", invalid_type)
+            }
+            DataChannelError::InvalidPayloadProtocolIdentifier { invalid_identifier } => {
+                writeln!(
+                    f,
+                    "Invalid payload protocol identifier: {:?}",
+                    invalid_identifier
+                )
+            }
+            DataChannelError::UnknownStreamIdentifier { stream_identifier } => {
+                writeln!(f, "Unknown stream identifier: {:?}", stream_identifier)
+            }
+            DataChannelError::RemoteInitiatedChannel { stream_identifier } => {
+                writeln!(
+                    f,
+                    "Channel on stream {:?} was opened by the remote",
+                    stream_identifier
+                )
+            }
+            DataChannelError::ReservedMessageType { message_type } => {
+                writeln!(f, "Message type is reserved: {:?}", message_type)
+            }
+            DataChannelError::StreamIdentifiersExhausted => {
+                writeln!(f, "No stream identifier is left to open a channel on")
+            }
+            DataChannelError::Draining => {
+                writeln!(f, "No new channels are accepted while draining")
+            }
+            DataChannelError::Cancelled => writeln!(f, "Operation was cancelled"),
+            DataChannelError::Timeout => writeln!(f, "Operation timed out"),
+            DataChannelError::HandshakeTimedOut { elapsed } => {
+                writeln!(f, "Handshake timed out after {:?}", elapsed)
+            }
+            DataChannelError::OpenRejected { reason, raw } => writeln!(
+                f,
+                "Rejected DATA_CHANNEL_OPEN of {} bytes: {}",
+                raw.len(),
+                reason.to_string().trim_end()
+            ),
+            DataChannelError::BondFailed => writeln!(f, "All members of the bond have failed"),
+            DataChannelError::TaskFailed { task, cause } => {
+                writeln!(f, "Channel failed in {}: {}", task.as_str(), cause)
+            }
+            DataChannelError::BufferTooSmall {
+                message_len,
+                buffer_len,
+            } => writeln!(
+                f,
+                "Buffer is too small for message: (message: {:?}, buffer: {:?})",
+                message_len, buffer_len
+            ),
+            DataChannelError::MessageTooLarge { size, max } => writeln!(
+                f,
+                "Message is too large: (size: {:?}, max: {:?})",
+                size, max
+            ),
+            DataChannelError::Message(error) => error.fmt(f),
+            DataChannelError::Frame(error) => error.fmt(f),
+            DataChannelError::Packet(error) => error.fmt(f),
+            DataChannelError::Fragment(error) => error.fmt(f),
+            DataChannelError::SequencedFrame(error) => error.fmt(f),
+            DataChannelError::Fec(error) => error.fmt(f),
+            DataChannelError::Padding(error) => error.fmt(f),
+            DataChannelError::Config(error) => error.fmt(f),
+            DataChannelError::Stream(error) => error.fmt(f),
+            DataChannelError::String(error) => error.fmt(f),
+            DataChannelError::Association(error) => error.fmt(f),
+        }
+    }
+}
+
+#[derive(Error, Eq, PartialEq, Clone, Debug)]
+pub enum ConfigError {
+    // Both a retransmit limit and a packet lifetime were given
+    ConflictingReliability,
+
+    // Packet lifetime doesn't fit the reliability parameter in milliseconds
+    PacketLifetimeOutOfRange { lifetime: Duration },
+
+    // Negotiated channel dialed on a stream other than the agreed one
+    NegotiatedStreamIdentifierMismatch { negotiated: u16, requested: u16 },
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ConflictingReliability => writeln!(
+                f,
+                "Max retransmits and max packet lifetime are mutually exclusive"
+            ),
+            Self::PacketLifetimeOutOfRange { lifetime } => {
+                writeln!(f, "Max packet lifetime is out of range: {:?}", lifetime)
+            }
+            Self::NegotiatedStreamIdentifierMismatch {
+                negotiated,
+                requested,
+            } => writeln!(
+                f,
+                "Channel was negotiated on another stream: (negotiated: {:?}, requested: {:?})",
+                negotiated, requested
+            ),
+        }
+    }
+}
+
+#[derive(Error, Eq, PartialEq, Clone, Debug)]
+pub enum RejectReason {
+    // The message couldn't be parsed as a DATA_CHANNEL_OPEN
+    Malformed { cause: String },
+
+    // The label matches none of the allowed patterns
+    LabelNotAllowed { label: String },
+
+    // The protocol matches none of the allowed patterns
+    ProtocolNotAllowed { protocol: String },
+}
+
+impl std::fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Malformed { cause } => writeln!(f, "Malformed message: {}", cause),
+            Self::LabelNotAllowed { label } => {
+                writeln!(f, "Label is not allowed: {:?}", label)
+            }
+            Self::ProtocolNotAllowed { protocol } => {
+                writeln!(f, "Protocol is not allowed: {:?}", protocol)
+            }
+        }
+    }
+}
+
+#[derive(Error, Eq, PartialEq, Clone, Debug)]
+pub enum FrameError {
+    // Marshal buffer was too short
+    UnexpectedEndOfBuffer { expected: usize, actual: usize },
+
+    // Frame has a kind we don't support
+    InvalidFrameKind { invalid_kind: u8 },
+}
+
+impl std::fmt::Display for FrameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEndOfBuffer { expected, actual } => {
+                writeln!(
+                    f,
+                    "Marshal buffer was too short: (expected: {:?}, actual: {:?})",
+                    expected, actual
+                )
+            }
+            Self::InvalidFrameKind { invalid_kind } => {
+                writeln!(f, "Invalid frame kind: {:?}", invalid_kind)
+            }
+        }
+    }
+}
+
+#[derive(Error, Eq, PartialEq, Clone, Debug)]
+pub enum RpcError {
+    // The remote handler failed to process the request
+    Remote { message: String },
+
+    // No response arrived before the call timed out
+    Timeout,
+
+    DataChannel(#[from] DataChannelError),
+}
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Remote { message } => writeln!(f, "Remote handler failed: {}", message),
+            Self::Timeout => writeln!(f, "Call timed out"),
+            Self::DataChannel(error) => error.fmt(f),
+        }
+    }
+}
+
+#[derive(Error, Eq, PartialEq, Clone, Debug)]
+pub enum PacketError {
+    // Marshal buffer was too short
+    UnexpectedEndOfBuffer { expected: usize, actual: usize },
+
+    // Declared length and actual length don't match
+    ExpectedAndActualLengthMismatch { expected: usize, actual: usize },
+
+    // Packet has a type we don't support
+    InvalidPacketType { invalid_type: u8 },
+
+    // Topic doesn't fit into the 16 bit length field
+    TopicTooLong { len: usize },
+
+    // Topic is not valid UTF-8
+    InvalidTopic(#[from] FromUtf8Error),
+}
+
+impl std::fmt::Display for PacketError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEndOfBuffer { expected, actual } => {
+                writeln!(
+                    f,
+                    "Marshal buffer was too short: (expected: {:?}, actual: {:?})",
+                    expected, actual
+                )
+            }
+            Self::ExpectedAndActualLengthMismatch { expected, actual } => {
+                writeln!(
+                    f,
+                    "Expected and actual length do not match: (expected: {:?}, actual: {:?})",
+                    expected, actual
+                )
+            }
+            Self::InvalidPacketType { invalid_type } => {
+                writeln!(f, "Invalid packet type: {:?}", invalid_type)
+            }
+            Self::TopicTooLong { len } => {
+                writeln!(f, "Topic is too long: {:?} bytes", len)
+            }
+            Self::InvalidTopic(error) => error.fmt(f),
+        }
+    }
+}
+
+#[derive(Error, Eq, PartialEq, Clone, Debug)]
+pub enum RecordedMessageError {
+    // Marshal buffer was too short
+    UnexpectedEndOfBuffer { expected: usize, actual: usize },
+
+    // Recorded message has a direction we don't support
+    InvalidDirection { invalid_direction: u8 },
+
+    // Payload doesn't fit into the 32 bit length field
+    PayloadTooLong { len: usize },
+}
+
+impl std::fmt::Display for RecordedMessageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEndOfBuffer { expected, actual } => {
+                writeln!(
+                    f,
+                    "Marshal buffer was too short: (expected: {:?}, actual: {:?})",
+                    expected, actual
+                )
+            }
+            Self::InvalidDirection { invalid_direction } => {
+                writeln!(f, "Invalid direction: {:?}", invalid_direction)
+            }
+            Self::PayloadTooLong { len } => {
+                writeln!(f, "Payload is too long: {:?} bytes", len)
+            }
+        }
+    }
+}
+
+#[derive(Error, Eq, PartialEq, Clone, Debug)]
+pub enum ReplayError {
+    // Reading the recording failed
+    Io { kind: std::io::ErrorKind },
+
+    // The recording is malformed
+    RecordedMessage(#[from] RecordedMessageError),
+
+    DataChannel(#[from] DataChannelError),
+}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io { kind: error.kind() }
+    }
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { kind } => writeln!(f, "Failed to read recording: {:?}", kind),
+            Self::RecordedMessage(error) => error.fmt(f),
+            Self::DataChannel(error) => error.fmt(f),
+        }
+    }
+}
+
+#[derive(Error, Eq, PartialEq, Clone, Debug)]
+pub enum StateError {
+    // Marshal buffer was too short
+    UnexpectedEndOfBuffer { expected: usize, actual: usize },
+
+    // Declared length and actual length don't match
+    ExpectedAndActualLengthMismatch { expected: usize, actual: usize },
+
+    // State was exported by a version we don't support
+    UnsupportedVersion { version: u8 },
+
+    // Label or protocol doesn't fit into the 16 bit length field
+    StringTooLong { len: usize },
+
+    ChannelType(#[from] ChannelTypeError),
+
+    // Label or protocol is not valid UTF-8
+    String(#[from] FromUtf8Error),
+}
+
+impl std::fmt::Display for StateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEndOfBuffer { expected, actual } => {
+                writeln!(
+                    f,
+                    "Marshal buffer was too short: (expected: {:?}, actual: {:?})",
+                    expected, actual
+                )
+            }
+            Self::ExpectedAndActualLengthMismatch { expected, actual } => {
+                writeln!(
+                    f,
+                    "Expected and actual length do not match: (expected: {:?}, actual: {:?})",
+                    expected, actual
+                )
+            }
+            Self::UnsupportedVersion { version } => {
+                writeln!(f, "Unsupported state version: {:?}", version)
+            }
+            Self::StringTooLong { len } => {
+                writeln!(f, "String is too long: {:?} bytes", len)
+            }
+            Self::ChannelType(error) => error.fmt(f),
+            Self::String(error) => error.fmt(f),
+        }
+    }
+}
+
+#[derive(Error, Eq, PartialEq, Clone, Debug)]
+pub enum FragmentError {
+    // Marshal buffer was too short
+    UnexpectedEndOfBuffer { expected: usize, actual: usize },
+
+    // Frame has a type we don't support
+    InvalidFrameType { invalid_type: u8 },
+
+    // Fragment index is not below the fragment count
+    InvalidFragmentIndex { index: u16, count: u16 },
+
+    // Fragments of the same message disagree on the fragment count
+    InconsistentFragmentCount { message_id: u32 },
+
+    // Message exceeds the length which can be fragmented or reassembled
+    MessageTooLarge { len: usize, max: usize },
+
+    // Too many messages are partially received at once
+    TooManyPartialMessages { max: usize },
+
+    // Remote doesn't support fragmentation
+    Unsupported,
+}
+
+impl std::fmt::Display for FragmentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEndOfBuffer { expected, actual } => {
+                writeln!(
+                    f,
+                    "Marshal buffer was too short: (expected: {:?}, actual: {:?})",
+                    expected, actual
+                )
+            }
+            Self::InvalidFrameType { invalid_type } => {
+                writeln!(f, "Invalid frame type: {:?}", invalid_type)
+            }
+            Self::InvalidFragmentIndex { index, count } => {
+                writeln!(
+                    f,
+                    "Invalid fragment index: (index: {:?}, count: {:?})",
+                    index, count
+                )
+            }
+            Self::InconsistentFragmentCount { message_id } => {
+                writeln!(
+                    f,
+                    "Inconsistent fragment count for message {:?}",
+                    message_id
+                )
+            }
+            Self::MessageTooLarge { len, max } => {
+                writeln!(f, "Message is too large: (len: {:?}, max: {:?})", len, max)
+            }
+            Self::TooManyPartialMessages { max } => {
+                writeln!(f, "Too many partially received messages: (max: {:?})", max)
+            }
+            Self::Unsupported => writeln!(f, "Remote doesn't support fragmentation"),
+        }
+    }
+}
+
+#[derive(Error, Eq, PartialEq, Clone, Debug)]
+pub enum SequencedFrameError {
+    // Marshal buffer was too short
+    UnexpectedEndOfBuffer { expected: usize, actual: usize },
+}
+
+impl std::fmt::Display for SequencedFrameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEndOfBuffer { expected, actual } => {
+                writeln!(
+                    f,
+                    "Marshal buffer was too short: (expected: {:?}, actual: {:?})",
+                    expected, actual
+                )
+            }
+        }
+    }
+}
+
+#[derive(Error, Eq, PartialEq, Clone, Debug)]
+pub enum FecError {
+    // Marshal buffer was too short
+    UnexpectedEndOfBuffer { expected: usize, actual: usize },
+
+    // Frame has a type we don't support
+    InvalidFrameType { invalid_type: u8 },
+
+    // Message index is not below the group size
+    InvalidIndex { index: u8, group_size: u8 },
+}
+
+impl std::fmt::Display for FecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEndOfBuffer { expected, actual } => {
+                writeln!(
+                    f,
+                    "Marshal buffer was too short: (expected: {:?}, actual: {:?})",
+                    expected, actual
+                )
+            }
+            Self::InvalidFrameType { invalid_type } => {
+                writeln!(f, "Invalid frame type: {:?}", invalid_type)
+            }
+            Self::InvalidIndex { index, group_size } => {
+                writeln!(
+                    f,
+                    "Invalid message index: (index: {:?}, group size: {:?})",
+                    index, group_size
+                )
+            }
+        }
+    }
+}
+
+#[derive(Error, Eq, PartialEq, Clone, Debug)]
+pub enum PaddingError {
+    // Marshal buffer was too short
+    UnexpectedEndOfBuffer { expected: usize, actual: usize },
+
+    // Frame has a type we don't support
+    InvalidFrameType { invalid_type: u8 },
+}
+
+impl std::fmt::Display for PaddingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEndOfBuffer { expected, actual } => {
+                writeln!(
+                    f,
+                    "Marshal buffer was too short: (expected: {:?}, actual: {:?})",
+                    expected, actual
+                )
+            }
+            Self::InvalidFrameType { invalid_type } => {
+                writeln!(f, "Invalid frame type: {:?}", invalid_type)
+            }
+        }
+    }
+}
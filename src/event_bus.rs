@@ -0,0 +1,112 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{error::DataChannelError, label::Label};
+
+/// Why a managed channel stopped being managed.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum CloseReason {
+    // The channel was closed through the manager.
+    Closed,
+    // The channel was closed while draining the manager.
+    Drained,
+    // The channel was closed after being idle for longer than its idle timeout.
+    Idle,
+    // Closing the channel failed; its stream may not have been reset.
+    Failed(DataChannelError),
+}
+
+/// An event concerning the channels of a `DataChannelManager`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum AssociationEvent {
+    // A channel started being managed.
+    ChannelOpened {
+        stream_identifier: u16,
+        label: Label,
+    },
+    // A managed channel was closed.
+    ChannelClosed {
+        stream_identifier: u16,
+        reason: CloseReason,
+    },
+    // A managed channel has been idle for longer than its idle timeout.
+    // Only sent for channels configured with `IdleAction::Notify`.
+    ChannelIdle {
+        stream_identifier: u16,
+        idle_for: Duration,
+    },
+    // Opening or accepting channels failed. Lists the affected streams,
+    // which is empty if the failure happened before a stream was known.
+    HandshakeFailed {
+        stream_identifiers: Vec<u16>,
+        error: DataChannelError,
+    },
+    // The manager started draining.
+    Draining,
+}
+
+/// EventBus fans out `AssociationEvent`s to any number of subscribers.
+///
+/// Subscribers which have been dropped are forgotten on the next event.
+#[derive(Debug, Default)]
+pub struct EventBus {
+    subscribers: Mutex<Vec<Sender<AssociationEvent>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe returns a receiver for all events published from here on.
+    pub fn subscribe(&self) -> Receiver<AssociationEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    pub fn publish(&self, event: AssociationEvent) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+
+    /// SubscriberCount returns the number of subscribers,
+    /// including dropped ones which haven't been forgotten yet.
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn publish_to_subscribers() {
+        let bus = EventBus::new();
+        let first = bus.subscribe();
+        let second = bus.subscribe();
+
+        bus.publish(AssociationEvent::Draining);
+
+        assert_eq!(first.try_recv(), Ok(AssociationEvent::Draining));
+        assert_eq!(second.try_recv(), Ok(AssociationEvent::Draining));
+    }
+
+    #[test]
+    fn forget_dropped_subscribers() {
+        let bus = EventBus::new();
+        drop(bus.subscribe());
+        let subscriber = bus.subscribe();
+
+        bus.publish(AssociationEvent::Draining);
+
+        assert_eq!(bus.subscriber_count(), 1);
+        assert_eq!(subscriber.try_recv(), Ok(AssociationEvent::Draining));
+    }
+}
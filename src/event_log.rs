@@ -0,0 +1,243 @@
+use std::{
+    fmt::Write as _,
+    io::Write,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::label::Label;
+
+/// The lifecycle step an `EventRecord` describes.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum EventKind {
+    // A DATA_CHANNEL_OPEN message was sent to the remote.
+    OpenSent,
+    // A DATA_CHANNEL_OPEN message was received from the remote.
+    OpenReceived,
+    // A DATA_CHANNEL_ACK message was sent to the remote.
+    AckSent,
+    // A DATA_CHANNEL_ACK message was received from the remote.
+    AckReceived,
+    // The outgoing stream was reset to close the channel.
+    CloseSent,
+    // The channel was closed, both streams having been reset.
+    Closed,
+    // The channel has gone without traffic for longer than its idle timeout.
+    Idle,
+    // A lifecycle step failed; the record carries the error.
+    Failed,
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::OpenSent => "open_sent",
+            Self::OpenReceived => "open_received",
+            Self::AckSent => "ack_sent",
+            Self::AckReceived => "ack_received",
+            Self::CloseSent => "close_sent",
+            Self::Closed => "closed",
+            Self::Idle => "idle",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// A machine-parseable record of a single data channel lifecycle event.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct EventRecord {
+    pub kind: EventKind,
+    pub stream_identifier: u16,
+    pub label: Label,
+    /// Wall-clock time at which the event happened, as told by
+    /// the channel's clock since the channel started opening.
+    pub timestamp: SystemTime,
+    /// Time elapsed since the channel started opening.
+    pub elapsed: Duration,
+    pub error: Option<String>,
+}
+
+impl EventRecord {
+    /// Formats the record as a single-line JSON object.
+    pub fn to_json(&self) -> String {
+        let timestamp_ms = self
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+
+        let mut json = String::new();
+        json.push_str("{\"event\":");
+        push_json_string(&mut json, self.kind.as_str());
+        let _ = write!(json, ",\"stream_identifier\":{}", self.stream_identifier);
+        json.push_str(",\"label\":");
+        push_json_string(&mut json, &self.label);
+        let _ = write!(
+            json,
+            ",\"timestamp_ms\":{},\"elapsed_us\":{}",
+            timestamp_ms,
+            self.elapsed.as_micros()
+        );
+        json.push_str(",\"error\":");
+        match &self.error {
+            Some(error) => push_json_string(&mut json, error),
+            None => json.push_str("null"),
+        }
+        json.push('}');
+        json
+    }
+}
+
+fn push_json_string(json: &mut String, value: &str) {
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+/// A receiver of data channel lifecycle events.
+pub trait EventEmitter: Send + Sync {
+    fn emit(&self, record: &EventRecord);
+}
+
+/// An `EventEmitter` writing one JSON record per line to the wrapped writer.
+#[derive(Debug)]
+pub struct JsonEventEmitter<W> {
+    writer: Mutex<W>,
+}
+
+impl<W> JsonEventEmitter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<W> EventEmitter for JsonEventEmitter<W>
+where
+    W: Write + Send,
+{
+    fn emit(&self, record: &EventRecord) {
+        let mut writer = self
+            .writer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Err(error) = writeln!(writer, "{}", record.to_json()) {
+            log::warn!("Failed to write lifecycle event: {:?}", error);
+        }
+    }
+}
+
+/// A cloneable handle to an `EventEmitter`, suitable for storing in a `Config`.
+///
+/// Two handles compare equal if they point to the same emitter.
+#[derive(Clone)]
+pub struct SharedEventEmitter(Arc<dyn EventEmitter>);
+
+impl SharedEventEmitter {
+    pub fn new<E>(emitter: E) -> Self
+    where
+        E: EventEmitter + 'static,
+    {
+        Self(Arc::new(emitter))
+    }
+
+    pub fn emit(&self, record: &EventRecord) {
+        self.0.emit(record)
+    }
+}
+
+impl From<Arc<dyn EventEmitter>> for SharedEventEmitter {
+    fn from(emitter: Arc<dyn EventEmitter>) -> Self {
+        Self(emitter)
+    }
+}
+
+impl PartialEq for SharedEventEmitter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedEventEmitter {}
+
+impl std::fmt::Debug for SharedEventEmitter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SharedEventEmitter").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(error: Option<String>) -> EventRecord {
+        EventRecord {
+            kind: EventKind::OpenReceived,
+            stream_identifier: 7,
+            label: Label::from("chat \"room\""),
+            timestamp: UNIX_EPOCH + Duration::from_millis(1_600_000_000_123),
+            elapsed: Duration::from_micros(1500),
+            error,
+        }
+    }
+
+    #[test]
+    fn to_json() {
+        let actual = record(None).to_json();
+        let expected = concat!(
+            "{\"event\":\"open_received\",\"stream_identifier\":7,",
+            "\"label\":\"chat \\\"room\\\"\",\"timestamp_ms\":1600000000123,",
+            "\"elapsed_us\":1500,\"error\":null}"
+        );
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn to_json_with_error() {
+        let actual = record(Some("line\nbreak\u{1}".to_owned())).to_json();
+
+        assert!(actual.ends_with(",\"error\":\"line\\nbreak\\u0001\"}"));
+    }
+
+    #[test]
+    fn json_event_emitter_writes_lines() {
+        let emitter = JsonEventEmitter::new(Vec::new());
+        emitter.emit(&record(None));
+        emitter.emit(&record(None));
+
+        let output = String::from_utf8(emitter.into_inner()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], record(None).to_json());
+    }
+
+    #[test]
+    fn shared_event_emitter_eq() {
+        let a = SharedEventEmitter::new(JsonEventEmitter::new(Vec::new()));
+        let b = SharedEventEmitter::new(JsonEventEmitter::new(Vec::new()));
+
+        assert_eq!(a, a.clone());
+        assert_ne!(a, b);
+    }
+}
@@ -58,6 +58,18 @@ impl ExactSizeBuf for [u8] {
     }
 }
 
+impl ExactSizeBuf for &[u8] {
+    #[inline]
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        <[u8]>::is_empty(self)
+    }
+}
+
 impl<T, U> ExactSizeBuf for Chain<T, U>
 where
     T: ExactSizeBuf,
@@ -0,0 +1,140 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+/// A type map of application-defined per-channel state.
+///
+/// Each type can be stored at most once; inserting a value of a type
+/// that is already present replaces the previous value.
+#[derive(Default)]
+pub struct Extensions {
+    map: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl Extensions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a value, returning the previously stored value of the same type, if any.
+    pub fn insert<T>(&mut self, value: T) -> Option<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.map
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|boxed| boxed.downcast().ok().map(|boxed| *boxed))
+    }
+
+    pub fn get<T>(&self) -> Option<&T>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|boxed| boxed.downcast_ref())
+    }
+
+    pub fn get_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.map
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|boxed| boxed.downcast_mut())
+    }
+
+    pub fn remove<T>(&mut self) -> Option<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.map
+            .remove(&TypeId::of::<T>())
+            .and_then(|boxed| boxed.downcast().ok().map(|boxed| *boxed))
+    }
+
+    pub fn contains<T>(&self) -> bool
+    where
+        T: Send + Sync + 'static,
+    {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl std::fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.map.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Eq, PartialEq, Debug)]
+    struct SessionId(u64);
+
+    #[test]
+    fn insert_and_get() {
+        let mut extensions = Extensions::new();
+
+        assert_eq!(extensions.insert(SessionId(1)), None);
+        assert_eq!(extensions.insert(5u32), None);
+
+        assert_eq!(extensions.get::<SessionId>(), Some(&SessionId(1)));
+        assert_eq!(extensions.get::<u32>(), Some(&5));
+        assert_eq!(extensions.get::<u64>(), None);
+        assert_eq!(extensions.len(), 2);
+    }
+
+    #[test]
+    fn insert_replaces() {
+        let mut extensions = Extensions::new();
+        extensions.insert(SessionId(1));
+
+        let previous = extensions.insert(SessionId(2));
+
+        assert_eq!(previous, Some(SessionId(1)));
+        assert_eq!(extensions.get::<SessionId>(), Some(&SessionId(2)));
+    }
+
+    #[test]
+    fn get_mut() {
+        let mut extensions = Extensions::new();
+        extensions.insert(SessionId(1));
+
+        extensions.get_mut::<SessionId>().unwrap().0 = 42;
+
+        assert_eq!(extensions.get::<SessionId>(), Some(&SessionId(42)));
+    }
+
+    #[test]
+    fn remove_and_clear() {
+        let mut extensions = Extensions::new();
+        extensions.insert(SessionId(1));
+        extensions.insert(5u32);
+
+        assert_eq!(extensions.remove::<SessionId>(), Some(SessionId(1)));
+        assert!(!extensions.contains::<SessionId>());
+        assert!(extensions.contains::<u32>());
+
+        extensions.clear();
+
+        assert!(extensions.is_empty());
+    }
+}
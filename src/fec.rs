@@ -0,0 +1,17 @@
+//! Experimental forward error correction for unreliable data channels.
+//!
+//! An `FecChannel` sends messages in groups of N, followed by a parity
+//! frame holding the XOR of the group's (zero-padded) payloads, lengths
+//! and flags. The receiving end recovers any single message lost from a
+//! group without waiting for a retransmission, at the cost of one extra
+//! message, as large as the group's largest, per group.
+
+mod channel;
+mod decoder;
+mod encoder;
+mod frame;
+
+pub use channel::FecChannel;
+pub use decoder::FecDecoder;
+pub use encoder::FecEncoder;
+pub use frame::FecFrame;
@@ -0,0 +1,80 @@
+use std::collections::VecDeque;
+
+use bytes::Bytes;
+
+use crate::{
+    data_channel::{DataChannel, RECEIVE_MTU},
+    error::DataChannelError,
+    fec::{FecDecoder, FecEncoder, FecFrame},
+    marshal::{Marshal, Unmarshal},
+};
+
+/// FecChannel protects the messages sent over an unreliable data channel
+/// with parity frames, recovering isolated losses on the receiving end.
+///
+/// Messages are delivered in arrival order; a recovered message is
+/// delivered as soon as the rest of its group has arrived.
+pub struct FecChannel {
+    data_channel: DataChannel,
+    encoder: FecEncoder,
+    decoder: FecDecoder,
+    available: VecDeque<(Bytes, bool)>,
+}
+
+impl FecChannel {
+    /// Creates a channel sending a parity frame after every `group_size` messages.
+    pub fn new(data_channel: DataChannel, group_size: u8) -> Self {
+        Self {
+            data_channel,
+            encoder: FecEncoder::new(group_size),
+            decoder: FecDecoder::new(),
+            available: VecDeque::new(),
+        }
+    }
+
+    /// Write sends a message, returning its length.
+    pub fn write(&mut self, payload: Bytes, is_string: bool) -> Result<usize, DataChannelError> {
+        let len = payload.len();
+
+        for frame in self.encoder.encode(payload, is_string) {
+            let mut bytes = frame.marshal()?;
+            self.data_channel.write_data_channel(&mut bytes, false)?;
+        }
+
+        Ok(len)
+    }
+
+    /// Read blocks until a message has been received or
+    /// recovered, returning it along with whether it is text.
+    pub fn read(&mut self) -> Result<(Bytes, bool), DataChannelError> {
+        loop {
+            if let Some(message) = self.available.pop_front() {
+                return Ok(message);
+            }
+
+            let frame = self.read_frame()?;
+            self.available.extend(self.decoder.push(frame));
+        }
+    }
+
+    pub fn decoder(&self) -> &FecDecoder {
+        &self.decoder
+    }
+
+    pub fn into_inner(self) -> DataChannel {
+        self.data_channel
+    }
+
+    fn read_frame(&mut self) -> Result<FecFrame, DataChannelError> {
+        let buffer_provider = self.data_channel.config.buffer_provider.clone();
+        let mut buf = buffer_provider.acquire(RECEIVE_MTU);
+        let result = self
+            .data_channel
+            .read(&mut buf)
+            .map(|n| buf.split_to(n).freeze());
+        buffer_provider.release(buf);
+
+        let mut bytes = result?;
+        FecFrame::unmarshal_from(&mut bytes).map_err(From::from)
+    }
+}
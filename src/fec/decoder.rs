@@ -0,0 +1,232 @@
+use std::collections::BTreeMap;
+
+use bytes::Bytes;
+
+use crate::fec::{frame::FLAG_IS_STRING, FecFrame};
+
+/// The default number of groups a `FecDecoder` keeps around for recovery.
+pub const DEFAULT_MAX_GROUPS: usize = 16;
+
+struct Parity {
+    flags_xor: u8,
+    len_xor: u32,
+    payload_xor: Bytes,
+}
+
+struct Group {
+    messages: Vec<Option<(Bytes, bool)>>,
+    received: usize,
+    parity: Option<Parity>,
+    complete: bool,
+}
+
+/// FecDecoder passes messages through as they arrive and recovers
+/// a group's single missing message once its parity has arrived.
+pub struct FecDecoder {
+    groups: BTreeMap<u32, Group>,
+    max_groups: usize,
+    recovered: u64,
+}
+
+impl FecDecoder {
+    pub fn new() -> Self {
+        Self::with_max_groups(DEFAULT_MAX_GROUPS)
+    }
+
+    /// Creates a decoder keeping the messages of at most `max_groups`
+    /// incomplete groups, evicting the oldest group beyond that.
+    pub fn with_max_groups(max_groups: usize) -> Self {
+        Self {
+            groups: BTreeMap::new(),
+            max_groups: max_groups.max(1),
+            recovered: 0,
+        }
+    }
+
+    /// Recovered returns the number of messages recovered so far.
+    pub fn recovered(&self) -> u64 {
+        self.recovered
+    }
+
+    /// Push adds a frame, returning the messages which became available:
+    /// the message carried by a data frame, plus a recovered one, if any.
+    pub fn push(&mut self, frame: FecFrame) -> Vec<(Bytes, bool)> {
+        let (group_id, group_size) = match &frame {
+            FecFrame::Data {
+                group, group_size, ..
+            }
+            | FecFrame::Parity {
+                group, group_size, ..
+            } => (*group, *group_size as usize),
+        };
+
+        let group = self.groups.entry(group_id).or_insert_with(|| Group {
+            messages: vec![None; group_size],
+            received: 0,
+            parity: None,
+            complete: false,
+        });
+        if group.messages.len() != group_size {
+            log::warn!(
+                "Dropping frame with inconsistent size of group {}",
+                group_id
+            );
+            return Vec::new();
+        }
+
+        let mut available = Vec::new();
+        match frame {
+            FecFrame::Data {
+                index,
+                is_string,
+                payload,
+                ..
+            } => {
+                let message = &mut group.messages[index as usize];
+                if message.is_some() || group.complete {
+                    return available;
+                }
+                *message = Some((payload.clone(), is_string));
+                group.received += 1;
+
+                available.push((payload, is_string));
+            }
+            FecFrame::Parity {
+                flags_xor,
+                len_xor,
+                payload_xor,
+                ..
+            } => {
+                group.parity = Some(Parity {
+                    flags_xor,
+                    len_xor,
+                    payload_xor,
+                });
+            }
+        }
+
+        if let Some(recovered) = Self::recover(group) {
+            self.recovered += 1;
+            available.push(recovered);
+        }
+
+        while self.groups.len() > self.max_groups {
+            self.groups.pop_first();
+        }
+
+        available
+    }
+
+    fn recover(group: &mut Group) -> Option<(Bytes, bool)> {
+        if group.complete {
+            return None;
+        }
+        if group.received == group.messages.len() {
+            group.complete = true;
+            return None;
+        }
+        if group.received + 1 != group.messages.len() {
+            return None;
+        }
+        let parity = group.parity.as_ref()?;
+
+        let mut flags = parity.flags_xor;
+        let mut len = parity.len_xor;
+        let mut payload = parity.payload_xor.to_vec();
+        for (message, is_string) in group.messages.iter().flatten() {
+            if *is_string {
+                flags ^= FLAG_IS_STRING;
+            }
+            len ^= message.len() as u32;
+            for (byte, other) in payload.iter_mut().zip(&message[..]) {
+                *byte ^= other;
+            }
+        }
+        payload.truncate(len as usize);
+
+        group.complete = true;
+        group.messages.clear();
+
+        Some((Bytes::from(payload), flags & FLAG_IS_STRING != 0))
+    }
+}
+
+impl Default for FecDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fec::FecEncoder;
+
+    use super::*;
+
+    fn encode(messages: &[(&'static [u8], bool)]) -> Vec<FecFrame> {
+        let mut encoder = FecEncoder::new(messages.len() as u8);
+        messages
+            .iter()
+            .flat_map(|(payload, is_string)| {
+                encoder.encode(Bytes::from_static(payload), *is_string)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn recovers_single_loss() {
+        let mut frames = encode(&[(b"one", false), (b"three", true), (b"xy", false)]);
+        assert_eq!(frames.len(), 4);
+        frames.remove(1);
+        let mut decoder = FecDecoder::new();
+
+        let available: Vec<_> = frames
+            .into_iter()
+            .flat_map(|frame| decoder.push(frame))
+            .collect();
+
+        assert_eq!(
+            available,
+            vec![
+                (Bytes::from_static(b"one"), false),
+                (Bytes::from_static(b"xy"), false),
+                (Bytes::from_static(b"three"), true),
+            ]
+        );
+        assert_eq!(decoder.recovered(), 1);
+    }
+
+    #[test]
+    fn recovers_when_parity_arrives_first() {
+        let mut frames = encode(&[(b"one", false), (b"two", false)]);
+        let parity = frames.pop().unwrap();
+        let mut decoder = FecDecoder::new();
+
+        assert_eq!(decoder.push(parity), vec![]);
+        let available = decoder.push(frames.remove(1));
+
+        assert_eq!(
+            available,
+            vec![
+                (Bytes::from_static(b"two"), false),
+                (Bytes::from_static(b"one"), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn cannot_recover_double_loss() {
+        let frames = encode(&[(b"one", false), (b"two", false), (b"six", false)]);
+        let mut decoder = FecDecoder::new();
+
+        let available: Vec<_> = frames
+            .into_iter()
+            .skip(1)
+            .step_by(2)
+            .flat_map(|frame| decoder.push(frame))
+            .collect();
+
+        assert_eq!(available, vec![(Bytes::from_static(b"two"), false)]);
+        assert_eq!(decoder.recovered(), 0);
+    }
+}
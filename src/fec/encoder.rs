@@ -0,0 +1,73 @@
+use bytes::Bytes;
+
+use crate::fec::{frame::FLAG_IS_STRING, FecFrame};
+
+/// FecEncoder assigns messages to groups, emitting
+/// a parity frame after the last message of each group.
+#[derive(Debug)]
+pub struct FecEncoder {
+    group_size: u8,
+    group: u32,
+    index: u8,
+    flags_xor: u8,
+    len_xor: u32,
+    payload_xor: Vec<u8>,
+}
+
+impl FecEncoder {
+    /// Creates an encoder for groups of `group_size` messages;
+    /// a size of zero is treated like a size of one.
+    pub fn new(group_size: u8) -> Self {
+        Self {
+            group_size: group_size.max(1),
+            group: 0,
+            index: 0,
+            flags_xor: 0,
+            len_xor: 0,
+            payload_xor: Vec::new(),
+        }
+    }
+
+    pub fn group_size(&self) -> u8 {
+        self.group_size
+    }
+
+    /// Encode returns the frames to send for a message: its data frame,
+    /// followed by the group's parity frame if it completes the group.
+    pub fn encode(&mut self, payload: Bytes, is_string: bool) -> Vec<FecFrame> {
+        if self.payload_xor.len() < payload.len() {
+            self.payload_xor.resize(payload.len(), 0);
+        }
+        for (parity, byte) in self.payload_xor.iter_mut().zip(&payload[..]) {
+            *parity ^= byte;
+        }
+        self.len_xor ^= payload.len() as u32;
+        if is_string {
+            self.flags_xor ^= FLAG_IS_STRING;
+        }
+
+        let mut frames = vec![FecFrame::Data {
+            group: self.group,
+            index: self.index,
+            group_size: self.group_size,
+            is_string,
+            payload,
+        }];
+
+        self.index += 1;
+        if self.index == self.group_size {
+            frames.push(FecFrame::Parity {
+                group: self.group,
+                group_size: self.group_size,
+                flags_xor: std::mem::take(&mut self.flags_xor),
+                len_xor: std::mem::take(&mut self.len_xor),
+                payload_xor: Bytes::from(std::mem::take(&mut self.payload_xor)),
+            });
+
+            self.group = self.group.wrapping_add(1);
+            self.index = 0;
+        }
+
+        frames
+    }
+}
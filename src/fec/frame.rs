@@ -0,0 +1,253 @@
+use bytes::{Buf, BufMut, Bytes};
+
+use crate::{
+    error::FecError,
+    marshal::{Marshal, MarshalSize, Unmarshal},
+};
+
+// The first byte in an `FecFrame` that specifies its type:
+const FRAME_TYPE_DATA: u8 = 0x01;
+const FRAME_TYPE_PARITY: u8 = 0x02;
+
+pub(crate) const FLAG_IS_STRING: u8 = 0b0000_0001;
+
+const DATA_HEADER_LEN: usize = 8;
+const PARITY_HEADER_LEN: usize = 11;
+
+/// A frame exchanged by `FecChannel`s,
+/// with each data channel message carrying exactly one frame.
+///
+/// # Memory layout
+///
+/// A message of a group:
+///
+/// ```plain
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |  Frame Type   |     Flags     |         Group (high)          |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |          Group (low)          |     Index     |  Group Size   |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                            Payload                            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+///
+/// The parity of a group, sent after its last message:
+///
+/// ```plain
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |  Frame Type   |   Flags XOR   |         Group (high)          |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |          Group (low)          |  Group Size   |               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+               +
+/// |                          Length XOR                           |
+/// +               +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |               |                                               |
+/// +-+-+-+-+-+-+-+-+                                               +
+/// |                          Payload XOR                          |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+///
+/// Bit 0 of the flags is set for text messages.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum FecFrame {
+    Data {
+        group: u32,
+        index: u8,
+        group_size: u8,
+        is_string: bool,
+        payload: Bytes,
+    },
+    Parity {
+        group: u32,
+        group_size: u8,
+        flags_xor: u8,
+        len_xor: u32,
+        payload_xor: Bytes,
+    },
+}
+
+impl MarshalSize for FecFrame {
+    fn marshal_size(&self) -> usize {
+        match self {
+            Self::Data { payload, .. } => DATA_HEADER_LEN + payload.len(),
+            Self::Parity { payload_xor, .. } => PARITY_HEADER_LEN + payload_xor.len(),
+        }
+    }
+}
+
+impl Unmarshal for FecFrame {
+    type Error = FecError;
+
+    fn unmarshal_from<B>(buf: &mut B) -> Result<Self, Self::Error>
+    where
+        B: Buf,
+    {
+        if !buf.has_remaining() {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: 1,
+                actual: 0,
+            });
+        }
+
+        let frame_type = buf.chunk()[0];
+        let required_len = match frame_type {
+            FRAME_TYPE_DATA => DATA_HEADER_LEN,
+            FRAME_TYPE_PARITY => PARITY_HEADER_LEN,
+            invalid_type => return Err(Self::Error::InvalidFrameType { invalid_type }),
+        };
+        if buf.remaining() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining(),
+            });
+        }
+
+        buf.advance(1);
+        let flags = buf.get_u8();
+        let group = buf.get_u32();
+
+        let frame = if frame_type == FRAME_TYPE_DATA {
+            let index = buf.get_u8();
+            let group_size = buf.get_u8();
+            if index >= group_size {
+                return Err(Self::Error::InvalidIndex { index, group_size });
+            }
+
+            Self::Data {
+                group,
+                index,
+                group_size,
+                is_string: flags & FLAG_IS_STRING != 0,
+                payload: buf.copy_to_bytes(buf.remaining()),
+            }
+        } else {
+            Self::Parity {
+                group,
+                group_size: buf.get_u8(),
+                flags_xor: flags,
+                len_xor: buf.get_u32(),
+                payload_xor: buf.copy_to_bytes(buf.remaining()),
+            }
+        };
+
+        Ok(frame)
+    }
+}
+
+impl Marshal for FecFrame {
+    type Error = FecError;
+
+    fn marshal_to<B>(&self, buf: &mut B) -> Result<usize, Self::Error>
+    where
+        B: BufMut,
+    {
+        let required_len = self.marshal_size();
+        if buf.remaining_mut() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining_mut(),
+            });
+        }
+
+        match self {
+            Self::Data {
+                group,
+                index,
+                group_size,
+                is_string,
+                payload,
+            } => {
+                buf.put_u8(FRAME_TYPE_DATA);
+                buf.put_u8(if *is_string { FLAG_IS_STRING } else { 0 });
+                buf.put_u32(*group);
+                buf.put_u8(*index);
+                buf.put_u8(*group_size);
+                buf.put_slice(&payload[..]);
+            }
+            Self::Parity {
+                group,
+                group_size,
+                flags_xor,
+                len_xor,
+                payload_xor,
+            } => {
+                buf.put_u8(FRAME_TYPE_PARITY);
+                buf.put_u8(*flags_xor);
+                buf.put_u32(*group);
+                buf.put_u8(*group_size);
+                buf.put_u32(*len_xor);
+                buf.put_slice(&payload_xor[..]);
+            }
+        }
+
+        Ok(required_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static MARSHALED_DATA: [u8; 10] = [
+        0x01, // frame type
+        0x01, // flags
+        0x00, 0x00, 0x00, 0x05, // group
+        0x02, // index
+        0x04, // group size
+        0x68, 0x69, // payload
+    ];
+
+    #[test]
+    fn unmarshal_data() {
+        let mut bytes = Bytes::from_static(&MARSHALED_DATA);
+
+        let frame = FecFrame::unmarshal_from(&mut bytes).unwrap();
+
+        assert_eq!(
+            frame,
+            FecFrame::Data {
+                group: 5,
+                index: 2,
+                group_size: 4,
+                is_string: true,
+                payload: Bytes::from_static(b"hi"),
+            }
+        );
+        assert_eq!(&frame.marshal().unwrap()[..], &MARSHALED_DATA);
+    }
+
+    #[test]
+    fn unmarshal_invalid_index() {
+        let mut bytes = Bytes::from_static(&[0x01, 0x00, 0x00, 0x00, 0x00, 0x05, 0x04, 0x04]);
+
+        let result = FecFrame::unmarshal_from(&mut bytes);
+
+        assert_eq!(
+            result,
+            Err(FecError::InvalidIndex {
+                index: 4,
+                group_size: 4
+            })
+        );
+    }
+
+    #[test]
+    fn parity_roundtrip() {
+        let frame = FecFrame::Parity {
+            group: 5,
+            group_size: 4,
+            flags_xor: 0x01,
+            len_xor: 0x0102,
+            payload_xor: Bytes::from_static(&[0xff, 0x00]),
+        };
+
+        let mut bytes = frame.marshal().unwrap();
+
+        assert_eq!(bytes.len(), 13);
+        assert_eq!(FecFrame::unmarshal_from(&mut bytes).unwrap(), frame);
+    }
+}
@@ -0,0 +1,22 @@
+//! Application-level fragmentation of messages larger than the SCTP message limit.
+//!
+//! A `FragmentingChannel` wraps a data channel whose peer runs a
+//! `FragmentingChannel` too. Both ends first exchange a capabilities frame
+//! announcing fragmentation support along with the largest message each of
+//! them accepts. Messages exceeding the smaller limit are then split into
+//! numbered fragments, which the receiving end reassembles, regardless of
+//! the order they arrive in.
+//!
+//! Alternatively, when both ends know the other wraps its channel too,
+//! `FragmentingChannel::new` skips the exchange and fragments messages
+//! to fit the association's own message size limit.
+
+mod channel;
+mod fragmenter;
+mod frame;
+mod reassembler;
+
+pub use channel::FragmentingChannel;
+pub use fragmenter::Fragmenter;
+pub use frame::{FragmentFrame, CAPABILITY_FRAGMENTATION};
+pub use reassembler::{Reassembler, DEFAULT_MAX_MESSAGE_LEN, DEFAULT_MAX_PARTIAL_MESSAGES};
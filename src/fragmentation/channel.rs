@@ -0,0 +1,161 @@
+use bytes::Bytes;
+
+use crate::{
+    data_channel::{DataChannel, RECEIVE_MTU},
+    error::{DataChannelError, FragmentError},
+    fragmentation::{FragmentFrame, Fragmenter, Reassembler, CAPABILITY_FRAGMENTATION},
+    marshal::{Marshal, Unmarshal},
+};
+
+fn write_frame(
+    data_channel: &mut DataChannel,
+    frame: &FragmentFrame,
+) -> Result<usize, DataChannelError> {
+    let mut bytes = frame.marshal()?;
+    data_channel.write_data_channel(&mut bytes, false)
+}
+
+fn read_frame(data_channel: &mut DataChannel) -> Result<FragmentFrame, DataChannelError> {
+    let buffer_provider = data_channel.config.buffer_provider.clone();
+    let mut buf = buffer_provider.acquire(RECEIVE_MTU);
+    let result = data_channel
+        .read(&mut buf)
+        .map(|n| buf.split_to(n).freeze());
+    buffer_provider.release(buf);
+
+    let mut bytes = result?;
+    FragmentFrame::unmarshal_from(&mut bytes).map_err(From::from)
+}
+
+/// FragmentingChannel sends and receives messages of any size over a data
+/// channel, fragmenting those exceeding the negotiated message size limit.
+pub struct FragmentingChannel {
+    data_channel: DataChannel,
+    fragmenter: Fragmenter,
+    reassembler: Reassembler,
+    max_frame_size: usize,
+}
+
+impl FragmentingChannel {
+    /// New wraps a data channel whose remote end is wrapped too, without
+    /// exchanging capabilities. Messages are fragmented so that no frame
+    /// exceeds the channel's `max_message_size`.
+    pub fn new(data_channel: DataChannel) -> Self {
+        let max_frame_size = match data_channel.max_message_size() {
+            0 => usize::MAX,
+            max_message_size => max_message_size,
+        };
+
+        Self {
+            data_channel,
+            fragmenter: Fragmenter::new(),
+            reassembler: Reassembler::new(),
+            max_frame_size,
+        }
+    }
+
+    /// Negotiate exchanges capabilities with the remote, which must be
+    /// negotiating too. `max_message_size` is the size of the largest
+    /// data channel message this end accepts; messages are fragmented so
+    /// that no frame exceeds the smaller of both ends' limits.
+    ///
+    /// Fails with `FragmentError::Unsupported` if the remote doesn't
+    /// announce support for fragmentation.
+    pub fn negotiate(
+        mut data_channel: DataChannel,
+        max_message_size: usize,
+    ) -> Result<Self, DataChannelError> {
+        let capabilities = FragmentFrame::Capabilities {
+            capabilities: CAPABILITY_FRAGMENTATION,
+            max_message_size: max_message_size.min(u32::MAX as usize) as u32,
+        };
+        write_frame(&mut data_channel, &capabilities)?;
+
+        let remote_max_message_size = match read_frame(&mut data_channel) {
+            Ok(FragmentFrame::Capabilities {
+                capabilities,
+                max_message_size,
+            }) if capabilities & CAPABILITY_FRAGMENTATION != 0 => max_message_size as usize,
+            Ok(_) | Err(DataChannelError::Fragment(_)) => {
+                return Err(FragmentError::Unsupported.into())
+            }
+            Err(error) => return Err(error),
+        };
+
+        Ok(Self {
+            data_channel,
+            fragmenter: Fragmenter::new(),
+            reassembler: Reassembler::new(),
+            max_frame_size: max_message_size.min(remote_max_message_size),
+        })
+    }
+
+    /// WithReassembler replaces the reassembler, e.g. to adjust its limits.
+    pub fn with_reassembler(mut self, reassembler: Reassembler) -> Self {
+        self.reassembler = reassembler;
+        self
+    }
+
+    /// MaxFrameSize returns the negotiated size limit of a single data channel message.
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+
+    /// Write sends a message, returning its length.
+    pub fn write(&mut self, payload: Bytes, is_string: bool) -> Result<usize, DataChannelError> {
+        let len = payload.len();
+
+        let frames = self
+            .fragmenter
+            .fragment(payload, is_string, self.max_frame_size)?;
+        for frame in &frames {
+            write_frame(&mut self.data_channel, frame)?;
+        }
+
+        Ok(len)
+    }
+
+    /// Read blocks until a complete message has been received,
+    /// returning it along with whether it is text.
+    pub fn read(&mut self) -> Result<(Bytes, bool), DataChannelError> {
+        loop {
+            let frame = read_frame(&mut self.data_channel)?;
+            if let Some(message) = self.reassembler.push(frame)? {
+                return Ok(message);
+            }
+        }
+    }
+
+    pub fn data_channel(&self) -> &DataChannel {
+        &self.data_channel
+    }
+
+    pub fn into_inner(self) -> DataChannel {
+        self.data_channel
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{data_channel::Config, testing};
+
+    use super::*;
+
+    #[test]
+    fn fragments_to_fit_the_association_limit() {
+        let (local, remote) = testing::simulated();
+        testing::set_max_message_size(&local, 16);
+        let dialer = DataChannel::dial(&local, 1, Config::default()).unwrap();
+        let acceptor = DataChannel::accept(&remote, Config::default()).unwrap();
+        let mut sender = FragmentingChannel::new(dialer);
+        let mut receiver = FragmentingChannel::new(acceptor);
+        assert_eq!(sender.max_frame_size(), 16);
+
+        let payload = Bytes::from(vec![0xab; 100]);
+        assert_eq!(sender.write(payload.clone(), false), Ok(100));
+        assert_eq!(sender.data_channel().messages_sent(), 17);
+
+        assert_eq!(receiver.read(), Ok((payload, false)));
+        assert_eq!(receiver.data_channel().messages_received(), 17);
+    }
+}
@@ -0,0 +1,119 @@
+use bytes::Bytes;
+
+use crate::{
+    error::FragmentError,
+    fragmentation::{
+        frame::{COMPLETE_HEADER_LEN, FRAGMENT_HEADER_LEN},
+        FragmentFrame,
+    },
+};
+
+/// Fragmenter splits messages into frames of bounded size,
+/// numbering fragmented messages consecutively.
+#[derive(Default, Debug)]
+pub struct Fragmenter {
+    next_message_id: u32,
+}
+
+impl Fragmenter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fragment returns the frames carrying `payload`, none of them larger
+    /// than `max_frame_size` bytes. Messages which fit are sent as a whole.
+    pub fn fragment(
+        &mut self,
+        payload: Bytes,
+        is_string: bool,
+        max_frame_size: usize,
+    ) -> Result<Vec<FragmentFrame>, FragmentError> {
+        if COMPLETE_HEADER_LEN + payload.len() <= max_frame_size {
+            return Ok(vec![FragmentFrame::Complete { is_string, payload }]);
+        }
+
+        if max_frame_size <= FRAGMENT_HEADER_LEN {
+            return Err(FragmentError::MessageTooLarge {
+                len: payload.len(),
+                max: 0,
+            });
+        }
+        let chunk_len = max_frame_size - FRAGMENT_HEADER_LEN;
+        let max_len = chunk_len * u16::MAX as usize;
+        if payload.len() > max_len {
+            return Err(FragmentError::MessageTooLarge {
+                len: payload.len(),
+                max: max_len,
+            });
+        }
+
+        let count = payload.len().div_ceil(chunk_len) as u16;
+        let message_id = self.next_message_id;
+        self.next_message_id = self.next_message_id.wrapping_add(1);
+
+        let frames = (0..count)
+            .map(|index| {
+                let start = index as usize * chunk_len;
+                let end = (start + chunk_len).min(payload.len());
+                FragmentFrame::Fragment {
+                    is_string,
+                    message_id,
+                    index,
+                    count,
+                    payload: payload.slice(start..end),
+                }
+            })
+            .collect();
+
+        Ok(frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::marshal::MarshalSize;
+
+    use super::*;
+
+    #[test]
+    fn small_messages_are_sent_whole() {
+        let mut fragmenter = Fragmenter::new();
+
+        let frames = fragmenter
+            .fragment(Bytes::from_static(b"hello"), true, 64)
+            .unwrap();
+
+        assert_eq!(
+            frames,
+            vec![FragmentFrame::Complete {
+                is_string: true,
+                payload: Bytes::from_static(b"hello"),
+            }]
+        );
+    }
+
+    #[test]
+    fn large_messages_are_fragmented() {
+        let mut fragmenter = Fragmenter::new();
+        let payload = Bytes::from(vec![0xab; 25]);
+
+        let frames = fragmenter.fragment(payload.clone(), false, 20).unwrap();
+        let next = fragmenter.fragment(payload, false, 20).unwrap();
+
+        assert_eq!(frames.len(), 3);
+        assert!(frames.iter().all(|frame| frame.marshal_size() <= 20));
+        match (&frames[2], &next[0]) {
+            (
+                FragmentFrame::Fragment {
+                    message_id: 0,
+                    index: 2,
+                    count: 3,
+                    payload,
+                    ..
+                },
+                FragmentFrame::Fragment { message_id: 1, .. },
+            ) => assert_eq!(payload.len(), 5),
+            frames => panic!("unexpected frames: {:?}", frames),
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use bytes::{Buf, BufMut, Bytes};
+
+use crate::{
+    error::FragmentError,
+    marshal::{Marshal, MarshalSize, Unmarshal},
+};
+
+// The first byte in a `FragmentFrame` that specifies its type:
+const FRAME_TYPE_CAPABILITIES: u8 = 0x01;
+const FRAME_TYPE_COMPLETE: u8 = 0x02;
+const FRAME_TYPE_FRAGMENT: u8 = 0x03;
+
+const FLAG_IS_STRING: u8 = 0b0000_0001;
+
+/// The capability flag announcing support for fragmentation.
+pub const CAPABILITY_FRAGMENTATION: u32 = 0x0000_0001;
+
+const CAPABILITIES_LEN: usize = 9;
+pub(crate) const COMPLETE_HEADER_LEN: usize = 2;
+pub(crate) const FRAGMENT_HEADER_LEN: usize = 10;
+
+/// A frame exchanged by `FragmentingChannel`s,
+/// with each data channel message carrying exactly one frame.
+///
+/// # Memory layout
+///
+/// Capabilities, sent once by either end when the channel is set up:
+///
+/// ```plain
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |  Frame Type   |                  Capabilities                 |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |               |               Max Message Size                |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |               |
+/// +-+-+-+-+-+-+-+-+
+/// ```
+///
+/// A message small enough to be sent as a whole:
+///
+/// ```plain
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |  Frame Type   |     Flags     |                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+                               +
+/// |                            Payload                            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+///
+/// A fragment of a larger message:
+///
+/// ```plain
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |  Frame Type   |     Flags     |      Message ID (high)        |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |      Message ID (low)         |         Fragment Index        |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |        Fragment Count         |                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+                               +
+/// |                            Payload                            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+///
+/// Bit 0 of the flags is set if the (reassembled) message is text.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum FragmentFrame {
+    Capabilities {
+        capabilities: u32,
+        max_message_size: u32,
+    },
+    Complete {
+        is_string: bool,
+        payload: Bytes,
+    },
+    Fragment {
+        is_string: bool,
+        message_id: u32,
+        index: u16,
+        count: u16,
+        payload: Bytes,
+    },
+}
+
+impl MarshalSize for FragmentFrame {
+    fn marshal_size(&self) -> usize {
+        match self {
+            Self::Capabilities { .. } => CAPABILITIES_LEN,
+            Self::Complete { payload, .. } => COMPLETE_HEADER_LEN + payload.len(),
+            Self::Fragment { payload, .. } => FRAGMENT_HEADER_LEN + payload.len(),
+        }
+    }
+}
+
+impl Unmarshal for FragmentFrame {
+    type Error = FragmentError;
+
+    fn unmarshal_from<B>(buf: &mut B) -> Result<Self, Self::Error>
+    where
+        B: Buf,
+    {
+        if !buf.has_remaining() {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: 1,
+                actual: 0,
+            });
+        }
+
+        let frame_type = buf.chunk()[0];
+        let required_len = match frame_type {
+            FRAME_TYPE_CAPABILITIES => CAPABILITIES_LEN,
+            FRAME_TYPE_COMPLETE => COMPLETE_HEADER_LEN,
+            FRAME_TYPE_FRAGMENT => FRAGMENT_HEADER_LEN,
+            invalid_type => return Err(Self::Error::InvalidFrameType { invalid_type }),
+        };
+        if buf.remaining() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining(),
+            });
+        }
+
+        buf.advance(1);
+
+        let frame = match frame_type {
+            FRAME_TYPE_CAPABILITIES => Self::Capabilities {
+                capabilities: buf.get_u32(),
+                max_message_size: buf.get_u32(),
+            },
+            FRAME_TYPE_COMPLETE => Self::Complete {
+                is_string: buf.get_u8() & FLAG_IS_STRING != 0,
+                payload: buf.copy_to_bytes(buf.remaining()),
+            },
+            _ => {
+                let is_string = buf.get_u8() & FLAG_IS_STRING != 0;
+                let message_id = buf.get_u32();
+                let index = buf.get_u16();
+                let count = buf.get_u16();
+                if index >= count {
+                    return Err(Self::Error::InvalidFragmentIndex { index, count });
+                }
+
+                Self::Fragment {
+                    is_string,
+                    message_id,
+                    index,
+                    count,
+                    payload: buf.copy_to_bytes(buf.remaining()),
+                }
+            }
+        };
+
+        Ok(frame)
+    }
+}
+
+impl Marshal for FragmentFrame {
+    type Error = FragmentError;
+
+    fn marshal_to<B>(&self, buf: &mut B) -> Result<usize, Self::Error>
+    where
+        B: BufMut,
+    {
+        let required_len = self.marshal_size();
+        if buf.remaining_mut() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining_mut(),
+            });
+        }
+
+        let flags = |is_string: bool| if is_string { FLAG_IS_STRING } else { 0 };
+
+        match self {
+            Self::Capabilities {
+                capabilities,
+                max_message_size,
+            } => {
+                buf.put_u8(FRAME_TYPE_CAPABILITIES);
+                buf.put_u32(*capabilities);
+                buf.put_u32(*max_message_size);
+            }
+            Self::Complete { is_string, payload } => {
+                buf.put_u8(FRAME_TYPE_COMPLETE);
+                buf.put_u8(flags(*is_string));
+                buf.put_slice(&payload[..]);
+            }
+            Self::Fragment {
+                is_string,
+                message_id,
+                index,
+                count,
+                payload,
+            } => {
+                buf.put_u8(FRAME_TYPE_FRAGMENT);
+                buf.put_u8(flags(*is_string));
+                buf.put_u32(*message_id);
+                buf.put_u16(*index);
+                buf.put_u16(*count);
+                buf.put_slice(&payload[..]);
+            }
+        }
+
+        Ok(required_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static MARSHALED_FRAGMENT: [u8; 12] = [
+        0x03, // frame type
+        0x01, // flags
+        0x00, 0x00, 0x00, 0x07, // message id
+        0x00, 0x01, // fragment index
+        0x00, 0x03, // fragment count
+        0x68, 0x69, // payload
+    ];
+
+    #[test]
+    fn unmarshal_fragment() {
+        let mut bytes = Bytes::from_static(&MARSHALED_FRAGMENT);
+
+        let frame = FragmentFrame::unmarshal_from(&mut bytes).unwrap();
+
+        assert_eq!(
+            frame,
+            FragmentFrame::Fragment {
+                is_string: true,
+                message_id: 7,
+                index: 1,
+                count: 3,
+                payload: Bytes::from_static(b"hi"),
+            }
+        );
+    }
+
+    #[test]
+    fn unmarshal_invalid_fragment_index() {
+        let mut bytes =
+            Bytes::from_static(&[0x03, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x03, 0x00, 0x03]);
+
+        let result = FragmentFrame::unmarshal_from(&mut bytes);
+
+        assert_eq!(
+            result,
+            Err(FragmentError::InvalidFragmentIndex { index: 3, count: 3 })
+        );
+    }
+
+    #[test]
+    fn unmarshal_invalid_frame_type() {
+        let mut bytes = Bytes::from_static(&[0x7f]);
+
+        let result = FragmentFrame::unmarshal_from(&mut bytes);
+
+        assert_eq!(
+            result,
+            Err(FragmentError::InvalidFrameType { invalid_type: 0x7f })
+        );
+    }
+
+    #[test]
+    fn marshal_unmarshal_roundtrip() {
+        let frames = [
+            FragmentFrame::Capabilities {
+                capabilities: CAPABILITY_FRAGMENTATION,
+                max_message_size: 65536,
+            },
+            FragmentFrame::Complete {
+                is_string: false,
+                payload: Bytes::from_static(b"whole"),
+            },
+        ];
+
+        for frame in frames {
+            let mut bytes = frame.marshal().unwrap();
+            assert_eq!(bytes.len(), frame.marshal_size());
+            assert_eq!(FragmentFrame::unmarshal_from(&mut bytes).unwrap(), frame);
+        }
+
+        let fragment = FragmentFrame::unmarshal_from(&mut &MARSHALED_FRAGMENT[..]).unwrap();
+        assert_eq!(&fragment.marshal().unwrap()[..], &MARSHALED_FRAGMENT);
+    }
+}
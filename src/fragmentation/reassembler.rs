@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use bytes::{Bytes, BytesMut};
+
+use crate::{error::FragmentError, fragmentation::FragmentFrame};
+
+/// The default upper bound of the length of reassembled messages.
+pub const DEFAULT_MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+/// The default number of messages which may be partially received at once.
+pub const DEFAULT_MAX_PARTIAL_MESSAGES: usize = 16;
+
+struct PartialMessage {
+    is_string: bool,
+    fragments: Vec<Option<Bytes>>,
+    received: usize,
+    len: usize,
+}
+
+/// Reassembler puts fragmented messages back together,
+/// accepting their fragments in any order.
+pub struct Reassembler {
+    partial_messages: HashMap<u32, PartialMessage>,
+    max_message_len: usize,
+    max_partial_messages: usize,
+}
+
+impl Reassembler {
+    pub fn new() -> Self {
+        Self::with_limits(DEFAULT_MAX_MESSAGE_LEN, DEFAULT_MAX_PARTIAL_MESSAGES)
+    }
+
+    pub fn with_limits(max_message_len: usize, max_partial_messages: usize) -> Self {
+        Self {
+            partial_messages: HashMap::new(),
+            max_message_len,
+            max_partial_messages,
+        }
+    }
+
+    /// PartialMessages returns the number of messages still missing fragments.
+    pub fn partial_messages(&self) -> usize {
+        self.partial_messages.len()
+    }
+
+    /// Push adds a frame, returning the message and whether it is text once
+    /// all of its fragments have arrived. Capabilities frames are ignored.
+    pub fn push(&mut self, frame: FragmentFrame) -> Result<Option<(Bytes, bool)>, FragmentError> {
+        let (is_string, message_id, index, count, payload) = match frame {
+            FragmentFrame::Capabilities { .. } => return Ok(None),
+            FragmentFrame::Complete { is_string, payload } => {
+                return Ok(Some((payload, is_string)));
+            }
+            FragmentFrame::Fragment {
+                is_string,
+                message_id,
+                index,
+                count,
+                payload,
+            } => (is_string, message_id, index, count, payload),
+        };
+
+        let partial_messages = self.partial_messages.len();
+        let partial_message = match self.partial_messages.get_mut(&message_id) {
+            Some(partial_message) => partial_message,
+            None if partial_messages >= self.max_partial_messages => {
+                return Err(FragmentError::TooManyPartialMessages {
+                    max: self.max_partial_messages,
+                });
+            }
+            None => self
+                .partial_messages
+                .entry(message_id)
+                .or_insert_with(|| PartialMessage {
+                    is_string,
+                    fragments: vec![None; count as usize],
+                    received: 0,
+                    len: 0,
+                }),
+        };
+
+        if partial_message.fragments.len() != count as usize {
+            self.partial_messages.remove(&message_id);
+            return Err(FragmentError::InconsistentFragmentCount { message_id });
+        }
+
+        let fragment = &mut partial_message.fragments[index as usize];
+        if fragment.is_some() {
+            log::debug!("Dropping duplicate fragment {} of {}", index, message_id);
+            return Ok(None);
+        }
+
+        partial_message.len += payload.len();
+        if partial_message.len > self.max_message_len {
+            let len = partial_message.len;
+            self.partial_messages.remove(&message_id);
+            return Err(FragmentError::MessageTooLarge {
+                len,
+                max: self.max_message_len,
+            });
+        }
+
+        *fragment = Some(payload);
+        partial_message.received += 1;
+        if partial_message.received < partial_message.fragments.len() {
+            return Ok(None);
+        }
+
+        let partial_message = self
+            .partial_messages
+            .remove(&message_id)
+            .expect("partial message is present");
+        let mut message = BytesMut::with_capacity(partial_message.len);
+        for fragment in partial_message.fragments.into_iter().flatten() {
+            message.extend_from_slice(&fragment[..]);
+        }
+
+        Ok(Some((message.freeze(), partial_message.is_string)))
+    }
+}
+
+impl Default for Reassembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fragmentation::Fragmenter;
+
+    use super::*;
+
+    #[test]
+    fn reassembles_out_of_order_fragments() {
+        let payload = Bytes::from((0..100).collect::<Vec<u8>>());
+        let mut frames = Fragmenter::new()
+            .fragment(payload.clone(), true, 30)
+            .unwrap();
+        frames.reverse();
+        let mut reassembler = Reassembler::new();
+
+        let last = frames.pop().unwrap();
+        for frame in frames {
+            assert_eq!(reassembler.push(frame).unwrap(), None);
+        }
+        assert_eq!(reassembler.partial_messages(), 1);
+
+        assert_eq!(reassembler.push(last).unwrap(), Some((payload, true)));
+        assert_eq!(reassembler.partial_messages(), 0);
+    }
+
+    #[test]
+    fn rejects_oversized_messages() {
+        let frames = Fragmenter::new()
+            .fragment(Bytes::from(vec![0; 60]), false, 30)
+            .unwrap();
+        let mut reassembler = Reassembler::with_limits(40, 1);
+
+        let results: Vec<_> = frames
+            .into_iter()
+            .map(|frame| reassembler.push(frame))
+            .collect();
+
+        assert_eq!(
+            results,
+            vec![
+                Ok(None),
+                Ok(None),
+                Err(FragmentError::MessageTooLarge { len: 60, max: 40 }),
+            ]
+        );
+        assert_eq!(reassembler.partial_messages(), 0);
+    }
+
+    #[test]
+    fn rejects_too_many_partial_messages() {
+        let mut fragmenter = Fragmenter::new();
+        let mut reassembler = Reassembler::with_limits(DEFAULT_MAX_MESSAGE_LEN, 1);
+
+        for expected in [
+            Ok(None),
+            Err(FragmentError::TooManyPartialMessages { max: 1 }),
+        ] {
+            let mut frames = fragmenter
+                .fragment(Bytes::from(vec![0; 60]), false, 30)
+                .unwrap();
+            assert_eq!(reassembler.push(frames.remove(0)), expected);
+        }
+    }
+}
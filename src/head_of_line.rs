@@ -0,0 +1,81 @@
+use std::time::{Duration, Instant};
+
+/// Head-of-line blocking statistics of an ordered data channel.
+///
+/// A channel is stalled while its stream holds back data which has already
+/// arrived, waiting for an earlier message lost in transit to be retransmitted.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub struct HeadOfLineStats {
+    /// The number of stalls observed so far.
+    pub stalls: u64,
+    /// The total time spent stalled, including the ongoing stall, if any.
+    pub stalled_for: Duration,
+    /// Whether the channel is stalled right now.
+    pub is_stalled: bool,
+}
+
+#[derive(Default, Debug)]
+pub(crate) struct HeadOfLineTracker {
+    stalled_since: Option<Instant>,
+    stalls: u64,
+    stalled_for: Duration,
+}
+
+impl HeadOfLineTracker {
+    /// Updates the tracker with whether the stream is holding back data at `now`.
+    pub(crate) fn update(&mut self, is_blocked: bool, now: Instant) {
+        match (self.stalled_since, is_blocked) {
+            (None, true) => {
+                self.stalled_since = Some(now);
+                self.stalls += 1;
+            }
+            (Some(stalled_since), false) => {
+                self.stalled_since = None;
+                self.stalled_for += now.saturating_duration_since(stalled_since);
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn stats(&self, now: Instant) -> HeadOfLineStats {
+        let ongoing = self
+            .stalled_since
+            .map(|stalled_since| now.saturating_duration_since(stalled_since))
+            .unwrap_or_default();
+
+        HeadOfLineStats {
+            stalls: self.stalls,
+            stalled_for: self.stalled_for + ongoing,
+            is_stalled: self.stalled_since.is_some(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_stalls() {
+        let start = Instant::now();
+        let mut tracker = HeadOfLineTracker::default();
+
+        tracker.update(false, start);
+        assert_eq!(tracker.stats(start), HeadOfLineStats::default());
+
+        tracker.update(true, start);
+        tracker.update(true, start + Duration::from_millis(10));
+        let stats = tracker.stats(start + Duration::from_millis(15));
+        assert_eq!(stats.stalls, 1);
+        assert_eq!(stats.stalled_for, Duration::from_millis(15));
+        assert!(stats.is_stalled);
+
+        tracker.update(false, start + Duration::from_millis(20));
+        tracker.update(true, start + Duration::from_millis(50));
+        tracker.update(false, start + Duration::from_millis(55));
+        let stats = tracker.stats(start + Duration::from_millis(100));
+        assert_eq!(stats.stalls, 2);
+        assert_eq!(stats.stalled_for, Duration::from_millis(25));
+        assert!(!stats.is_stalled);
+    }
+}
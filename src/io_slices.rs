@@ -0,0 +1,120 @@
+//! A `Buf` over a sequence of borrowed slices.
+
+use std::io::IoSlice;
+
+use bytes::Buf;
+
+use crate::exact_size_buf::ExactSizeBuf;
+
+/// IoSlices reads through a sequence of `IoSlice`s, in order, without copying them.
+#[derive(Clone, Debug)]
+pub struct IoSlices<'a> {
+    slices: &'a [IoSlice<'a>],
+    // The number of bytes of `slices[0]` read already.
+    offset: usize,
+    remaining: usize,
+}
+
+impl<'a> IoSlices<'a> {
+    pub fn new(slices: &'a [IoSlice<'a>]) -> Self {
+        let mut io_slices = Self {
+            slices,
+            offset: 0,
+            remaining: slices.iter().map(|slice| slice.len()).sum(),
+        };
+        io_slices.skip_exhausted();
+        io_slices
+    }
+
+    // Moves past the slices read completely, and empty ones.
+    fn skip_exhausted(&mut self) {
+        while let Some(slice) = self.slices.first() {
+            if self.offset < slice.len() {
+                break;
+            }
+            self.slices = &self.slices[1..];
+            self.offset = 0;
+        }
+    }
+}
+
+impl Buf for IoSlices<'_> {
+    fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    fn chunk(&self) -> &[u8] {
+        match self.slices.first() {
+            Some(slice) => &slice[self.offset..],
+            None => &[],
+        }
+    }
+
+    fn advance(&mut self, mut cnt: usize) {
+        assert!(
+            cnt <= self.remaining,
+            "cannot advance past `remaining`: {:?} <= {:?}",
+            cnt,
+            self.remaining
+        );
+        self.remaining -= cnt;
+
+        while cnt > 0 {
+            let available = self.slices[0].len() - self.offset;
+            let n = available.min(cnt);
+            self.offset += n;
+            cnt -= n;
+            self.skip_exhausted();
+        }
+    }
+
+    fn chunks_vectored<'b>(&'b self, dst: &mut [IoSlice<'b>]) -> usize {
+        let mut count = 0;
+        for (index, slice) in self.slices.iter().enumerate() {
+            if count == dst.len() {
+                break;
+            }
+            let offset = if index == 0 { self.offset } else { 0 };
+            if slice.len() > offset {
+                dst[count] = IoSlice::new(&slice[offset..]);
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
+impl ExactSizeBuf for IoSlices<'_> {
+    fn len(&self) -> usize {
+        self.remaining
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_across_slices() {
+        let slices = [
+            IoSlice::new(b"head"),
+            IoSlice::new(b""),
+            IoSlice::new(b"er body"),
+        ];
+        let mut buf = IoSlices::new(&slices);
+
+        assert_eq!(buf.len(), 11);
+        assert_eq!(buf.chunk(), b"head");
+
+        buf.advance(6);
+        assert_eq!(buf.chunk(), b" body");
+        assert_eq!(buf.len(), 5);
+
+        let mut chunks = [IoSlice::new(&[]); 4];
+        assert_eq!(buf.chunks_vectored(&mut chunks), 1);
+        assert_eq!(&chunks[0][..], b" body");
+
+        assert_eq!(&buf.copy_to_bytes(5)[..], b" body");
+        assert!(!buf.has_remaining());
+    }
+}
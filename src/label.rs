@@ -0,0 +1,171 @@
+//! Cheap, shareable channel labels.
+//!
+//! Channels sharing a label or protocol can share a single allocation
+//! of it by interning it with a `LabelInterner`, which the
+//! `DataChannelManager` does for all channels it manages. The resulting
+//! `Label`s are cheap to clone, so events and snapshots carry them
+//! instead of copies of the string.
+
+use std::{
+    borrow::Borrow,
+    collections::HashSet,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::{Arc, Mutex},
+};
+
+/// An immutable, reference-counted string, such as a channel label or protocol.
+#[derive(Clone, PartialOrd, Ord)]
+pub struct Label(Arc<str>);
+
+impl Label {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// PtrEq returns whether both labels share the same allocation,
+    /// as labels interned with the same `LabelInterner` do.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Default for Label {
+    fn default() -> Self {
+        Self::from("")
+    }
+}
+
+impl From<&str> for Label {
+    fn from(label: &str) -> Self {
+        Self(Arc::from(label))
+    }
+}
+
+impl From<String> for Label {
+    fn from(label: String) -> Self {
+        Self(Arc::from(label))
+    }
+}
+
+impl Deref for Label {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Label {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Label {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Label {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other) || self.0 == other.0
+    }
+}
+
+impl Eq for Label {}
+
+impl PartialEq<str> for Label {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Label {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl Hash for Label {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl std::fmt::Debug for Label {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::fmt::Display for Label {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// LabelInterner hands out a single shared `Label` per distinct string.
+///
+/// Labels stay interned for as long as the interner lives.
+#[derive(Debug, Default)]
+pub struct LabelInterner {
+    labels: Mutex<HashSet<Label>>,
+}
+
+impl LabelInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Intern returns the shared label equal to `label`, interning it if necessary.
+    pub fn intern(&self, label: &str) -> Label {
+        let mut labels = self.labels.lock().unwrap();
+        if let Some(interned) = labels.get(label) {
+            return interned.clone();
+        }
+
+        let interned = Label::from(label);
+        labels.insert(interned.clone());
+        interned
+    }
+
+    /// Len returns the number of distinct labels interned.
+    pub fn len(&self) -> usize {
+        self.labels.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intern_shares_allocations() {
+        let interner = LabelInterner::new();
+
+        let chat = interner.intern("chat");
+        let again = interner.intern("chat");
+        let telemetry = interner.intern("telemetry");
+
+        assert!(chat.ptr_eq(&again));
+        assert_ne!(chat, telemetry);
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn compares_by_value() {
+        let label = Label::from("chat");
+
+        assert_eq!(label, Label::from("chat".to_owned()));
+        assert!(!label.ptr_eq(&Label::from("chat")));
+        assert_eq!(label, "chat");
+        assert_eq!(label.to_string(), "chat");
+        assert_eq!(format!("{:?}", label), "\"chat\"");
+    }
+}
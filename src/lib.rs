@@ -1,12 +1,105 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+// The wire format, which is all that builds without std.
 mod channel_type;
-pub mod data_channel;
 pub mod error;
-pub mod exact_size_buf;
 pub mod marshal;
 pub mod message;
+pub mod wire;
+
+#[cfg(feature = "std")]
+pub mod accept;
+#[cfg(feature = "std")]
+pub mod blocking;
+#[cfg(feature = "std")]
+pub mod buffer_provider;
+#[cfg(feature = "std")]
+pub mod cancellation;
+#[cfg(feature = "std")]
+pub mod clock;
+#[cfg(feature = "std")]
+pub mod data_channel;
+#[cfg(feature = "std")]
+pub mod dcep;
+#[cfg(feature = "std")]
+pub mod dedup;
+#[cfg(feature = "std")]
+pub mod event_bus;
+#[cfg(feature = "std")]
+pub mod event_log;
+#[cfg(feature = "std")]
+pub mod exact_size_buf;
+#[cfg(feature = "std")]
+pub mod extensions;
+#[cfg(feature = "std")]
+pub mod head_of_line;
+#[cfg(feature = "std")]
+pub mod io_slices;
+#[cfg(feature = "std")]
+pub mod label;
+#[cfg(feature = "std")]
+pub mod manager;
+#[cfg(feature = "std")]
+pub mod observer;
+#[cfg(feature = "std")]
+pub mod ordering;
+#[cfg(feature = "std")]
+pub mod padding;
+#[cfg(feature = "std")]
+pub mod priority;
+#[cfg(feature = "std")]
+pub mod pubsub;
+#[cfg(feature = "std")]
+pub mod quality;
+#[cfg(feature = "std")]
+pub mod recording;
+#[cfg(feature = "std")]
+pub mod rpc;
+#[cfg(feature = "std")]
+pub mod runtime;
+#[cfg(feature = "std")]
+pub mod send_failure;
+#[cfg(feature = "std")]
+pub mod send_slot;
+#[cfg(feature = "std")]
+pub mod split;
+#[cfg(feature = "std")]
+pub mod state;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod stream_id;
+#[cfg(feature = "std")]
+pub mod supervision;
+#[cfg(feature = "std")]
+pub mod testing;
+
+#[cfg(feature = "adaptive")]
+pub mod adaptive;
+#[cfg(feature = "bonding")]
+pub mod bonding;
+#[cfg(feature = "fec")]
+pub mod fec;
+#[cfg(feature = "fragmentation")]
+pub mod fragmentation;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "otel")]
+mod otel;
+#[cfg(feature = "tokio")]
+pub mod poll_data_channel;
+#[cfg(feature = "futures")]
+pub mod stream;
+#[cfg(feature = "serde")]
+pub mod topology;
+#[cfg(feature = "tracing")]
+mod trace;
 
 // This basically a stub for the still incomplete 'webrtc-sctp' crate:
 // https://crates.io/crates/webrtc-sctp
+#[cfg(feature = "std")]
 mod sctp;
 
 pub use channel_type::ChannelType;
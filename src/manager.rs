@@ -0,0 +1,1505 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    pin::Pin,
+    sync::{mpsc::Receiver, Arc},
+    task::{ready, Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    accept::AcceptOptions,
+    blocking,
+    cancellation::CancellationToken,
+    clock::{BoxDelay, SharedClock},
+    data_channel::{Config, DataChannel, IdleAction, ReadyState, RuntimeConfig, SequenceNumbers},
+    dcep::DcepRegistry,
+    error::DataChannelError,
+    event_bus::{AssociationEvent, CloseReason, EventBus},
+    label::{Label, LabelInterner},
+    quality::QualityMonitor,
+    sctp::Association,
+    stats::{AssociationStats, ChannelStats, StatsStream},
+    stream_id::{Role, SequentialAllocator, StreamIdAllocator},
+};
+
+// How many outbound streams `open` requests at a time, once it ran out of them.
+const ADD_STREAMS_STEP: u16 = 16;
+
+type OnDataChannelFn = Arc<dyn Fn(&mut DataChannel) + Send + Sync>;
+
+/// A snapshot of a single channel managed by a `DataChannelManager`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct ChannelInfo {
+    pub stream_identifier: u16,
+    pub label: Label,
+    pub protocol: Label,
+    pub ready_state: ReadyState,
+    /// The number of bytes queued to be sent, at the time of the snapshot.
+    pub buffered_amount: u64,
+    /// The number of bytes sent but not acknowledged yet, at the time of the snapshot.
+    pub bytes_in_flight: u64,
+    /// The stream's sequence numbers, at the time of the snapshot.
+    pub sequence_numbers: SequenceNumbers,
+}
+
+/// DataChannelManager owns an SCTP association together with
+/// the data channels opened over it, keyed by stream identifier.
+pub struct DataChannelManager {
+    association: Association,
+    data_channels: BTreeMap<u16, DataChannel>,
+    // Closed channels waiting for the remote to reset its stream,
+    // whose stream identifiers can't be reused until then.
+    closing: BTreeMap<u16, DataChannel>,
+    draining: bool,
+    cancellation_token: CancellationToken,
+    dcep_registry: DcepRegistry,
+    runtime_config: RuntimeConfig,
+    stream_id_allocator: Box<dyn StreamIdAllocator>,
+    event_bus: EventBus,
+    quality_monitor: Option<QualityMonitor>,
+    labels: LabelInterner,
+    clock: SharedClock,
+    on_data_channel: Option<OnDataChannelFn>,
+}
+
+impl DataChannelManager {
+    pub fn new(association: Association) -> Self {
+        let mut manager = Self {
+            association,
+            data_channels: BTreeMap::new(),
+            closing: BTreeMap::new(),
+            draining: false,
+            cancellation_token: CancellationToken::new(),
+            dcep_registry: DcepRegistry::new(),
+            runtime_config: RuntimeConfig::default(),
+            stream_id_allocator: Box::new(SequentialAllocator::new(Role::Client)),
+            event_bus: EventBus::new(),
+            quality_monitor: None,
+            labels: LabelInterner::new(),
+            clock: SharedClock::default(),
+            on_data_channel: None,
+        };
+        manager.seed_max_streams();
+        manager
+    }
+
+    /// WithClock sets the clock `drain` measures its deadline with.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// WithRole makes `open` pick the stream identifiers owned by the
+    /// local endpoint's DTLS role, in ascending order.
+    pub fn with_role(self, role: Role) -> Self {
+        self.with_stream_id_allocator(SequentialAllocator::new(role))
+    }
+
+    /// WithStreamIdAllocator sets the strategy `open` picks stream identifiers with.
+    /// Defaults to a `SequentialAllocator` for the DTLS client role.
+    ///
+    /// The allocator is limited to the outbound streams negotiated for the association.
+    pub fn with_stream_id_allocator<A>(mut self, stream_id_allocator: A) -> Self
+    where
+        A: StreamIdAllocator + 'static,
+    {
+        self.stream_id_allocator = Box::new(stream_id_allocator);
+        self.seed_max_streams();
+        self
+    }
+
+    // Hands the number of outbound streams negotiated so far to the allocator.
+    fn seed_max_streams(&mut self) {
+        match self.association.max_outbound_streams() {
+            Ok(max_outbound_streams) => self
+                .stream_id_allocator
+                .set_max_streams(max_outbound_streams),
+            Err(error) => log::warn!("Failed to get the outbound streams: {:?}", error),
+        }
+    }
+
+    /// WithQualityMonitor sets the monitor `sample_quality` feeds the association's signals to.
+    pub fn with_quality_monitor(mut self, quality_monitor: QualityMonitor) -> Self {
+        self.quality_monitor = Some(quality_monitor);
+        self
+    }
+
+    pub fn quality_monitor(&self) -> Option<&QualityMonitor> {
+        self.quality_monitor.as_ref()
+    }
+
+    /// SampleQuality records the association's current SCTP signals with the
+    /// manager's `QualityMonitor`, returning the resulting score, or `None`
+    /// if no monitor is set.
+    ///
+    /// There is no background task sampling the signals,
+    /// so this needs to be called periodically.
+    pub fn sample_quality(&mut self) -> Result<Option<u8>, DataChannelError> {
+        let quality_monitor = match &mut self.quality_monitor {
+            Some(quality_monitor) => quality_monitor,
+            None => return Ok(None),
+        };
+
+        let signals = self.association.quality_signals()?;
+        Ok(Some(quality_monitor.record(signals)))
+    }
+
+    /// Labels returns the table the labels and protocols of all managed channels are interned in.
+    pub fn labels(&self) -> &LabelInterner {
+        &self.labels
+    }
+
+    pub fn association(&self) -> &Association {
+        &self.association
+    }
+
+    /// CancellationToken returns the token handed to channels dialed or accepted
+    /// through the manager whose config doesn't carry a token of its own.
+    ///
+    /// Cancelling it stops all of these channels, as well as `drain`.
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancellation_token
+    }
+
+    /// DcepRegistry returns the handlers for experimental DCEP message types
+    /// shared with channels dialed or accepted through the manager whose
+    /// config doesn't carry a registry of its own.
+    pub fn dcep_registry(&self) -> &DcepRegistry {
+        &self.dcep_registry
+    }
+
+    fn with_shared_state(&self, mut config: Config) -> Config {
+        if config.cancellation_token.is_none() {
+            config.cancellation_token = Some(self.cancellation_token.clone());
+        }
+        if config.dcep_registry.is_none() {
+            config.dcep_registry = Some(self.dcep_registry.clone());
+        }
+        config
+    }
+
+    /// Dial opens a data channel on the given stream and starts managing it.
+    pub fn dial(
+        &mut self,
+        identifier: u16,
+        config: Config,
+    ) -> Result<&mut DataChannel, DataChannelError> {
+        let data_channel = self.dial_unmanaged(identifier, config)?;
+
+        Ok(self.insert(data_channel))
+    }
+
+    fn dial_unmanaged(
+        &self,
+        identifier: u16,
+        config: Config,
+    ) -> Result<DataChannel, DataChannelError> {
+        if self.draining {
+            return Err(DataChannelError::Draining);
+        }
+
+        let config = self.with_shared_state(config);
+        DataChannel::dial(&self.association, identifier, config)
+            .map_err(|error| self.handshake_failed(vec![identifier], error))
+    }
+
+    /// Open dials a data channel on a stream picked by the
+    /// manager's `StreamIdAllocator` and starts managing it.
+    ///
+    /// If the allocator ran out of stream identifiers, more outbound streams
+    /// are requested from the association, failing with
+    /// `DataChannelError::StreamIdentifiersExhausted` only if none can be added.
+    pub fn open(&mut self, config: Config) -> Result<&mut DataChannel, DataChannelError> {
+        let identifier = self.allocate_for_open()?;
+
+        match self.dial_unmanaged(identifier, config) {
+            Ok(data_channel) => Ok(self.insert(data_channel)),
+            Err(error) => {
+                self.stream_id_allocator.release(identifier);
+                Err(error)
+            }
+        }
+    }
+
+    /// Create is like `open`, but returns a future resolving with the stream
+    /// identifier of the channel once its DCEP handshake has completed,
+    /// rather than blocking for the DATA_CHANNEL_ACK.
+    ///
+    /// The channel is managed from the first poll on. If it fails to open,
+    /// re-sending the DATA_CHANNEL_OPEN as `dial` does within the config's
+    /// `open_timeout`, the channel is closed.
+    pub fn create(&mut self, config: Config) -> Create<'_> {
+        Create {
+            manager: self,
+            config: Some(config),
+            opening: None,
+        }
+    }
+
+    // Picks the stream identifier for `open` and `create`.
+    fn allocate_for_open(&mut self) -> Result<u16, DataChannelError> {
+        if self.draining {
+            return Err(DataChannelError::Draining);
+        }
+
+        self.reap_closed();
+        match self.allocate_stream_identifier() {
+            Some(identifier) => Ok(identifier),
+            None => {
+                self.add_outbound_streams()?;
+                self.allocate_stream_identifier()
+                    .ok_or(DataChannelError::StreamIdentifiersExhausted)
+            }
+        }
+    }
+
+    // Dials a channel for `create`, without waiting for its DATA_CHANNEL_ACK.
+    fn start_create(&mut self, mut config: Config) -> Result<Opening, DataChannelError> {
+        let identifier = self.allocate_for_open()?;
+
+        let timeout = config.open_timeout.take();
+        let retries = config.open_retries;
+        let started_at = self.clock.now();
+        match self.dial_unmanaged(identifier, config) {
+            Ok(data_channel) => {
+                self.insert(data_channel);
+            }
+            Err(error) => {
+                self.stream_id_allocator.release(identifier);
+                return Err(error);
+            }
+        }
+
+        Ok(Opening {
+            stream_identifier: identifier,
+            started_at,
+            timeout,
+            retries,
+            delay: timeout.map(|timeout| self.clock.delay(timeout)),
+        })
+    }
+
+    fn allocate_stream_identifier(&mut self) -> Option<u16> {
+        let data_channels = &self.data_channels;
+        let closing = &self.closing;
+        self.stream_id_allocator.allocate(&|stream_identifier| {
+            data_channels.contains_key(&stream_identifier)
+                || closing.contains_key(&stream_identifier)
+        })
+    }
+
+    // Requests more outbound streams, handing the new capacity to the allocator.
+    fn add_outbound_streams(&mut self) -> Result<(), DataChannelError> {
+        let max_outbound_streams = self.association.max_outbound_streams()?;
+        if max_outbound_streams == u16::MAX {
+            return Err(DataChannelError::StreamIdentifiersExhausted);
+        }
+
+        let max_outbound_streams = self.association.add_outbound_streams(ADD_STREAMS_STEP)?;
+        log::debug!(
+            "Added outbound streams, {} from here on",
+            max_outbound_streams
+        );
+        self.stream_id_allocator
+            .set_max_streams(max_outbound_streams);
+
+        Ok(())
+    }
+
+    /// OpenGroup opens a channel for each config, like `open`, and waits up
+    /// to `timeout` for all of their handshakes to complete. Returns their
+    /// stream identifiers, in the order of the configs.
+    ///
+    /// If any of the channels fails to open in time, all channels of the
+    /// group are closed and `DataChannelError::Timeout`, or the error the
+    /// channel failed with, is returned.
+    pub fn open_group(
+        &mut self,
+        configs: Vec<Config>,
+        timeout: Duration,
+    ) -> Result<Vec<u16>, DataChannelError> {
+        let deadline = self.clock.now() + timeout;
+
+        let mut stream_identifiers = Vec::with_capacity(configs.len());
+        for config in configs {
+            match self.open(config) {
+                Ok(data_channel) => stream_identifiers.push(data_channel.stream_identifier()),
+                Err(error) => return Err(self.roll_back_group(stream_identifiers, error)),
+            }
+        }
+
+        let data_channels = &mut self.data_channels;
+        let opened = blocking::poll_until(&self.clock, deadline, |cx| {
+            let mut is_open = true;
+            for stream_identifier in &stream_identifiers {
+                let data_channel = data_channels
+                    .get_mut(stream_identifier)
+                    .expect("channel of the group is managed");
+                match data_channel.poll_open(cx) {
+                    Poll::Ready(Ok(())) => {}
+                    Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+                    Poll::Pending => is_open = false,
+                }
+            }
+
+            if is_open {
+                Poll::Ready(Ok(()))
+            } else {
+                Poll::Pending
+            }
+        });
+
+        match opened {
+            Some(Ok(())) => Ok(stream_identifiers),
+            Some(Err(error)) => Err(self.roll_back_group(stream_identifiers, error)),
+            None => Err(self.roll_back_group(stream_identifiers, DataChannelError::Timeout)),
+        }
+    }
+
+    fn roll_back_group(
+        &mut self,
+        stream_identifiers: Vec<u16>,
+        error: DataChannelError,
+    ) -> DataChannelError {
+        for stream_identifier in &stream_identifiers {
+            if let Err(error) = self.close(*stream_identifier) {
+                log::warn!(
+                    "Failed to close channel on stream {}: {:?}",
+                    stream_identifier,
+                    error
+                );
+            }
+        }
+
+        self.handshake_failed(stream_identifiers, error)
+    }
+
+    /// Accept accepts the next incoming data channel and starts managing it.
+    ///
+    /// While draining, incoming streams are reset instead.
+    pub fn accept(&mut self, config: Config) -> Result<&mut DataChannel, DataChannelError> {
+        if self.draining {
+            let mut stream = self.association.accept_stream()?;
+            log::debug!(
+                "Rejecting channel on stream {} while draining",
+                stream.stream_identifier()
+            );
+            stream.close()?;
+
+            return Err(DataChannelError::Draining);
+        }
+
+        let config = self.with_shared_state(config);
+        let data_channel = DataChannel::accept(&self.association, config)
+            .map_err(|error| self.handshake_failed(vec![], error))?;
+
+        Ok(self.insert_accepted(data_channel))
+    }
+
+    /// OnDataChannel sets a handler called with every channel the remote opened,
+    /// once it's accepted through the manager, that is once its DATA_CHANNEL_OPEN
+    /// has been validated and acknowledged. The channel is managed by then.
+    pub fn on_data_channel<F>(&mut self, f: F)
+    where
+        F: Fn(&mut DataChannel) + Send + Sync + 'static,
+    {
+        self.on_data_channel = Some(Arc::new(f));
+    }
+
+    fn insert_accepted(&mut self, data_channel: DataChannel) -> &mut DataChannel {
+        let on_data_channel = self.on_data_channel.clone();
+        let data_channel = self.insert(data_channel);
+        if let Some(on_data_channel) = on_data_channel {
+            on_data_channel(data_channel);
+        }
+
+        data_channel
+    }
+
+    /// AcceptIncoming is like `accept`, but returns a future resolving with the
+    /// stream identifier of the next channel once it has been accepted, which
+    /// neither blocks for the remote to open a stream nor for its DATA_CHANNEL_OPEN.
+    ///
+    /// Fails with `DataChannelError::Draining` while draining.
+    pub fn accept_incoming(&mut self, config: Config) -> Accept<'_> {
+        Accept {
+            incoming: self.incoming(config),
+        }
+    }
+
+    /// Incoming returns the stream identifiers of the channels the remote opens,
+    /// accepting them with `config` as they come. Ends once the manager drains.
+    pub fn incoming(&mut self, config: Config) -> Incoming<'_> {
+        Incoming {
+            manager: self,
+            config,
+            accepting: None,
+        }
+    }
+
+    /// Subscribe returns a receiver for the manager's events from here on.
+    pub fn subscribe(&self) -> Receiver<AssociationEvent> {
+        self.event_bus.subscribe()
+    }
+
+    fn handshake_failed(
+        &self,
+        stream_identifiers: Vec<u16>,
+        error: DataChannelError,
+    ) -> DataChannelError {
+        self.event_bus.publish(AssociationEvent::HandshakeFailed {
+            stream_identifiers,
+            error: error.clone(),
+        });
+        error
+    }
+
+    /// RuntimeConfig returns the settings applied to all managed channels.
+    pub fn runtime_config(&self) -> &RuntimeConfig {
+        &self.runtime_config
+    }
+
+    /// UpdateConfig applies the settings set in `runtime_config` to all
+    /// managed channels, as well as to channels managed from here on,
+    /// without reopening any of them.
+    pub fn update_config(&mut self, runtime_config: RuntimeConfig) {
+        for data_channel in self.data_channels.values_mut() {
+            data_channel.apply_runtime_config(&runtime_config);
+        }
+
+        let current = &mut self.runtime_config;
+        if let Some(threshold) = runtime_config.buffered_amount_low_threshold {
+            current.buffered_amount_low_threshold = Some(threshold);
+        }
+        if let Some(idle_timeout) = runtime_config.idle_timeout {
+            current.idle_timeout = Some(idle_timeout);
+        }
+    }
+
+    /// AcceptTimeout is like `accept`, but gives up with `DataChannelError::Timeout`
+    /// if the remote hasn't opened a channel within `timeout`.
+    ///
+    /// While draining, fails with `DataChannelError::Draining` right away.
+    pub fn accept_timeout(
+        &mut self,
+        config: Config,
+        timeout: Duration,
+    ) -> Result<&mut DataChannel, DataChannelError> {
+        if self.draining {
+            return Err(DataChannelError::Draining);
+        }
+
+        let config = self.with_shared_state(config);
+        let data_channel = DataChannel::accept_timeout(&self.association, config, timeout)
+            .map_err(|error| match error {
+                DataChannelError::Timeout => error,
+                error => self.handshake_failed(vec![], error),
+            })?;
+
+        Ok(self.insert_accepted(data_channel))
+    }
+
+    /// AcceptMany accepts up to `n` incoming channels until `deadline`, as
+    /// measured by the manager's clock, and starts managing them. Returns
+    /// the stream identifiers of the channels accepted in time, which may
+    /// be fewer than `n`.
+    pub fn accept_many(
+        &mut self,
+        n: usize,
+        config: Config,
+        deadline: Instant,
+    ) -> Result<Vec<u16>, DataChannelError> {
+        let mut stream_identifiers = Vec::with_capacity(n);
+
+        while stream_identifiers.len() < n {
+            let timeout = deadline.saturating_duration_since(self.clock.now());
+            match self.accept_timeout(config.clone(), timeout) {
+                Ok(data_channel) => stream_identifiers.push(data_channel.stream_identifier()),
+                Err(DataChannelError::Timeout) => break,
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(stream_identifiers)
+    }
+
+    /// Insert starts managing a channel opened over the manager's association,
+    /// replacing any channel previously managed on the same stream.
+    ///
+    /// The manager's runtime config is applied to the channel.
+    pub fn insert(&mut self, mut data_channel: DataChannel) -> &mut DataChannel {
+        data_channel.apply_runtime_config(&self.runtime_config);
+        data_channel.intern_labels(&self.labels);
+
+        let stream_identifier = data_channel.stream_identifier();
+        self.stream_id_allocator.reserve(stream_identifier);
+        if self.data_channels.contains_key(&stream_identifier) {
+            log::warn!("Replacing channel on stream {}", stream_identifier);
+        }
+
+        self.event_bus.publish(AssociationEvent::ChannelOpened {
+            stream_identifier,
+            label: data_channel.label().clone(),
+        });
+
+        self.data_channels.insert(stream_identifier, data_channel);
+        self.data_channels
+            .get_mut(&stream_identifier)
+            .expect("channel was just inserted")
+    }
+
+    pub fn get(&self, stream_identifier: u16) -> Option<&DataChannel> {
+        self.data_channels.get(&stream_identifier)
+    }
+
+    pub fn get_mut(&mut self, stream_identifier: u16) -> Option<&mut DataChannel> {
+        self.data_channels.get_mut(&stream_identifier)
+    }
+
+    /// Remove stops managing a channel, handing it back to the caller.
+    ///
+    /// The channel's stream identifier stays allocated, as the channel may
+    /// still be open; `insert` the channel again and `close` it through the
+    /// manager for its stream identifier to be handed out again.
+    pub fn remove(&mut self, stream_identifier: u16) -> Option<DataChannel> {
+        self.data_channels.remove(&stream_identifier)
+    }
+
+    /// Close closes a managed channel and stops managing it.
+    ///
+    /// The channel's stream identifier is only handed out again once the
+    /// remote has reset its stream as well; see `reap_closed`.
+    pub fn close(&mut self, stream_identifier: u16) -> Result<(), DataChannelError> {
+        let data_channel = self
+            .data_channels
+            .get_mut(&stream_identifier)
+            .ok_or(DataChannelError::UnknownStreamIdentifier { stream_identifier })?;
+
+        let result = data_channel.close();
+        self.retire(stream_identifier);
+        let reason = match &result {
+            Ok(()) => CloseReason::Closed,
+            Err(error) => CloseReason::Failed(error.clone()),
+        };
+        self.event_bus.publish(AssociationEvent::ChannelClosed {
+            stream_identifier,
+            reason,
+        });
+
+        result
+    }
+
+    // Stops managing a closed channel, holding on to it
+    // until the remote has reset its stream, if it hasn't yet.
+    fn retire(&mut self, stream_identifier: u16) {
+        let data_channel = match self.data_channels.remove(&stream_identifier) {
+            Some(data_channel) => data_channel,
+            None => return,
+        };
+
+        if data_channel.ready_state() == ReadyState::Closing {
+            self.closing.insert(stream_identifier, data_channel);
+        } else {
+            self.stream_id_allocator.release(stream_identifier);
+        }
+    }
+
+    /// ReapClosed releases the stream identifiers of the closed channels whose
+    /// stream the remote has reset since, returning them. `open` calls this
+    /// before allocating a stream identifier.
+    ///
+    /// Messages still arriving on these channels are discarded.
+    pub fn reap_closed(&mut self) -> Vec<u16> {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut stream_identifiers = vec![];
+
+        self.closing.retain(|&stream_identifier, data_channel| {
+            match data_channel.poll_closed(&mut cx) {
+                Poll::Ready(result) => {
+                    if let Err(error) = result {
+                        log::warn!(
+                            "Channel on stream {} failed while closing: {:?}",
+                            stream_identifier,
+                            error
+                        );
+                    }
+                    stream_identifiers.push(stream_identifier);
+                    false
+                }
+                Poll::Pending => true,
+            }
+        });
+
+        for stream_identifier in &stream_identifiers {
+            self.stream_id_allocator.release(*stream_identifier);
+        }
+
+        stream_identifiers
+    }
+
+    /// IsClosing returns whether a channel closed on the given stream is still
+    /// waiting for the remote to reset its stream.
+    pub fn is_closing(&self, stream_identifier: u16) -> bool {
+        self.closing.contains_key(&stream_identifier)
+    }
+
+    /// SweepIdle takes the idle action of every managed channel which has been
+    /// idle for longer than its idle timeout, returning their stream identifiers.
+    ///
+    /// Channels closed for being idle stop being managed. Channels set to
+    /// `IdleAction::Notify` publish `AssociationEvent::ChannelIdle` instead,
+    /// once per idle period.
+    pub fn sweep_idle(&mut self) -> Vec<u16> {
+        let mut stream_identifiers = vec![];
+        let mut closed = vec![];
+
+        for (stream_identifier, data_channel) in self.data_channels.iter_mut() {
+            let stream_identifier = *stream_identifier;
+            let idle_for = data_channel.idle_for();
+            match data_channel.check_idle() {
+                Ok(None) => continue,
+                Ok(Some(IdleAction::Close)) => closed.push((stream_identifier, CloseReason::Idle)),
+                Ok(Some(IdleAction::Notify)) => {
+                    self.event_bus.publish(AssociationEvent::ChannelIdle {
+                        stream_identifier,
+                        idle_for,
+                    });
+                }
+                Err(error) => closed.push((stream_identifier, CloseReason::Failed(error))),
+            }
+            stream_identifiers.push(stream_identifier);
+        }
+
+        for (stream_identifier, reason) in closed {
+            self.retire(stream_identifier);
+            self.event_bus.publish(AssociationEvent::ChannelClosed {
+                stream_identifier,
+                reason,
+            });
+        }
+
+        stream_identifiers
+    }
+
+    /// Reopen closes a managed channel and opens a replacement with the given
+    /// config on a stream picked by the manager's `StreamIdAllocator`.
+    pub fn reopen(
+        &mut self,
+        stream_identifier: u16,
+        config: Config,
+    ) -> Result<&mut DataChannel, DataChannelError> {
+        self.close(stream_identifier)?;
+        self.open(config)
+    }
+
+    /// Channels returns a snapshot of all managed channels, ordered by stream identifier.
+    pub fn channels(&self) -> Vec<ChannelInfo> {
+        self.data_channels
+            .iter()
+            .map(|(stream_identifier, data_channel)| ChannelInfo {
+                stream_identifier: *stream_identifier,
+                label: data_channel.label().clone(),
+                protocol: data_channel.protocol().clone(),
+                ready_state: data_channel.ready_state(),
+                buffered_amount: data_channel.buffered_amount(),
+                bytes_in_flight: data_channel.bytes_in_flight(),
+                sequence_numbers: data_channel.sequence_numbers(),
+            })
+            .collect()
+    }
+
+    /// Stats returns a snapshot of the counters of all managed channels.
+    pub fn stats(&self) -> AssociationStats {
+        AssociationStats {
+            channels: self.data_channels.values().map(ChannelStats::of).collect(),
+        }
+    }
+
+    /// StatsStream yields a snapshot of the counters of all managed channels
+    /// once per `interval`, timed by the manager's clock.
+    pub fn stats_stream(&self, interval: Duration) -> StatsStream<'_, AssociationStats> {
+        StatsStream::new(move || self.stats(), self.clock.clone(), interval)
+    }
+
+    /// Drain shuts the manager down in a controlled manner: new channels are
+    /// rejected from here on, managed channels get up to `timeout` to flush
+    /// their queued messages and are then closed, as by `close`. Cancelling
+    /// the manager's token cuts the wait short.
+    ///
+    /// All channels are closed even if closing one of them fails,
+    /// in which case the first error is returned.
+    pub fn drain(&mut self, timeout: Duration) -> Result<(), DataChannelError> {
+        self.draining = true;
+        self.event_bus.publish(AssociationEvent::Draining);
+
+        let deadline = self.clock.now() + timeout;
+        let cancellation_token = &self.cancellation_token;
+        let data_channels = &mut self.data_channels;
+        blocking::poll_until(&self.clock, deadline, |cx| {
+            if cancellation_token.poll_cancelled(cx).is_ready() {
+                return Poll::Ready(());
+            }
+
+            let mut is_flushed = true;
+            for data_channel in data_channels.values_mut() {
+                if data_channel.poll_buffered_amount_below(cx, 1).is_pending() {
+                    is_flushed = false;
+                }
+            }
+
+            if is_flushed {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        });
+
+        let mut result = Ok(());
+        let stream_identifiers: Vec<u16> = self.data_channels.keys().copied().collect();
+        for stream_identifier in stream_identifiers {
+            let data_channel = self
+                .data_channels
+                .get_mut(&stream_identifier)
+                .expect("drained channel is managed");
+            if data_channel.buffered_amount() > 0 {
+                log::warn!(
+                    "Closing channel on stream {} with {} bytes still queued",
+                    stream_identifier,
+                    data_channel.buffered_amount()
+                );
+            }
+            let reason = match data_channel.close() {
+                Ok(()) => CloseReason::Drained,
+                Err(error) => {
+                    log::warn!(
+                        "Failed to close channel on stream {}: {:?}",
+                        stream_identifier,
+                        error
+                    );
+                    result = result.and(Err(error.clone()));
+                    CloseReason::Failed(error)
+                }
+            };
+            self.retire(stream_identifier);
+            self.event_bus.publish(AssociationEvent::ChannelClosed {
+                stream_identifier,
+                reason,
+            });
+        }
+
+        result
+    }
+
+    /// IsDraining returns whether `drain` has been called.
+    pub fn is_draining(&self) -> bool {
+        self.draining
+    }
+
+    pub fn len(&self) -> usize {
+        self.data_channels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data_channels.is_empty()
+    }
+
+    pub fn into_inner(self) -> (Association, Vec<DataChannel>) {
+        (self.association, self.data_channels.into_values().collect())
+    }
+}
+
+/// The future returned by `DataChannelManager::create`.
+pub struct Create<'a> {
+    manager: &'a mut DataChannelManager,
+    // The config of the channel, until it has been dialled.
+    config: Option<Config>,
+    opening: Option<Opening>,
+}
+
+// A channel dialled by `Create`, waiting for its DATA_CHANNEL_ACK.
+struct Opening {
+    stream_identifier: u16,
+    started_at: Instant,
+    timeout: Option<Duration>,
+    retries: u32,
+    // Elapses once the DATA_CHANNEL_OPEN sent last timed out.
+    delay: Option<BoxDelay>,
+}
+
+impl Future for Create<'_> {
+    type Output = Result<u16, DataChannelError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let opening = match &mut this.opening {
+            Some(opening) => opening,
+            None => {
+                let config = this.config.take().expect("Create polled after completion");
+                let opening = this.manager.start_create(config)?;
+                this.opening.insert(opening)
+            }
+        };
+        let stream_identifier = opening.stream_identifier;
+
+        loop {
+            let data_channel = this
+                .manager
+                .data_channels
+                .get_mut(&stream_identifier)
+                .expect("channel being created is managed");
+            let error = match data_channel.poll_open(cx) {
+                Poll::Ready(Ok(())) => return Poll::Ready(Ok(stream_identifier)),
+                Poll::Ready(Err(error)) => error,
+                Poll::Pending => {
+                    let delay = match &mut opening.delay {
+                        Some(delay) => delay,
+                        None => return Poll::Pending,
+                    };
+                    ready!(delay.as_mut().poll(cx));
+
+                    let timeout = opening.timeout.expect("delay is set with a timeout");
+                    if opening.retries > 0 {
+                        opening.retries -= 1;
+                        log::debug!(
+                            "Re-sending DATA_CHANNEL_OPEN on stream {}, {} retries left",
+                            stream_identifier,
+                            opening.retries
+                        );
+                        match data_channel.send_open() {
+                            Ok(()) => {
+                                opening.delay = Some(this.manager.clock.delay(timeout));
+                                continue;
+                            }
+                            Err(error) => error,
+                        }
+                    } else {
+                        let elapsed = this
+                            .manager
+                            .clock
+                            .now()
+                            .saturating_duration_since(opening.started_at);
+                        log::debug!(
+                            "Handshake on stream {} timed out after {:?}",
+                            stream_identifier,
+                            elapsed
+                        );
+                        DataChannelError::HandshakeTimedOut { elapsed }
+                    }
+                }
+            };
+
+            return Poll::Ready(Err(this
+                .manager
+                .roll_back_group(vec![stream_identifier], error)));
+        }
+    }
+}
+
+/// The future returned by `DataChannelManager::accept_incoming`.
+pub struct Accept<'a> {
+    incoming: Incoming<'a>,
+}
+
+impl Future for Accept<'_> {
+    type Output = Result<u16, DataChannelError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.get_mut().incoming.poll_next(cx)
+    }
+}
+
+/// Incoming accepts the channels the remote opens through a manager.
+///
+/// As an `Iterator` it blocks until the next channel is open; it can also
+/// be polled from async code via `poll_next`, or as a `futures_core::Stream`
+/// with the `futures` feature.
+pub struct Incoming<'a> {
+    manager: &'a mut DataChannelManager,
+    config: Config,
+    // The channel on the stream accepted last, until its DATA_CHANNEL_OPEN arrived.
+    accepting: Option<DataChannel>,
+}
+
+impl Incoming<'_> {
+    /// PollNext resolves with the stream identifier of the next channel
+    /// accepted, or `DataChannelError::Draining` once the manager drains.
+    ///
+    /// Neither waiting for the remote to open a stream nor for its
+    /// DATA_CHANNEL_OPEN blocks; the current task gets woken up instead.
+    pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Result<u16, DataChannelError>> {
+        if self.manager.draining {
+            return Poll::Ready(Err(DataChannelError::Draining));
+        }
+
+        let data_channel = match &mut self.accepting {
+            Some(data_channel) => data_channel,
+            None => {
+                let stream = ready!(self.manager.association.poll_accept_stream(cx))?;
+                let config = self.manager.with_shared_state(self.config.clone());
+                self.accepting
+                    .insert(DataChannel::accepting(stream, config))
+            }
+        };
+
+        let result = ready!(data_channel.poll_receive_open(cx, &AcceptOptions::default()));
+        let data_channel = self.accepting.take().expect("channel is being accepted");
+        match result {
+            Ok(()) => Poll::Ready(Ok(self
+                .manager
+                .insert_accepted(data_channel)
+                .stream_identifier())),
+            Err(error) => Poll::Ready(Err(self.manager.handshake_failed(vec![], error))),
+        }
+    }
+}
+
+#[cfg(feature = "futures")]
+impl futures_core::Stream for Incoming<'_> {
+    type Item = Result<u16, DataChannelError>;
+
+    /// Ends once the manager drains.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.manager.draining {
+            return Poll::Ready(None);
+        }
+
+        Incoming::poll_next(this, cx).map(Some)
+    }
+}
+
+impl Iterator for Incoming<'_> {
+    type Item = Result<u16, DataChannelError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.manager.draining {
+            return None;
+        }
+
+        Some(
+            self.manager
+                .accept(self.config.clone())
+                .map(|data_channel| data_channel.stream_identifier()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        task::Wake,
+    };
+
+    use crate::{
+        clock::{Clock, ManualClock},
+        data_channel::ReceiveBufferPolicy,
+        quality::{QualityLevel, QualitySignals},
+        testing,
+    };
+
+    use super::*;
+
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn accept_many_until_deadline() {
+        let clock = ManualClock::new();
+        let (remote, association) = testing::simulated();
+        let mut manager =
+            DataChannelManager::new(association).with_clock(SharedClock::new(clock.clone()));
+        let config = Config {
+            clock: SharedClock::new(clock.clone()),
+            ..Default::default()
+        };
+        DataChannel::dial(&remote, 1, Config::default()).unwrap();
+        DataChannel::dial(&remote, 3, Config::default()).unwrap();
+
+        let deadline = clock.now() + Duration::from_secs(1);
+        let stream_identifiers = manager.accept_many(3, config, deadline).unwrap();
+
+        assert_eq!(stream_identifiers, vec![1, 3]);
+        assert_eq!(manager.len(), 2);
+        assert!(clock.now() >= deadline);
+    }
+
+    #[test]
+    fn stats_count_open_channels() {
+        let (association, remote) = testing::simulated();
+        let mut manager = DataChannelManager::new(association);
+        manager.dial(1, Config::default()).unwrap();
+        manager.dial(3, Config::default()).unwrap();
+        let mut acceptor = DataChannel::accept(&remote, Config::default()).unwrap();
+        DataChannel::accept(&remote, Config::default()).unwrap();
+        acceptor
+            .write(&mut bytes::Bytes::from_static(b"opened"))
+            .unwrap();
+
+        manager.get_mut(1).unwrap().read_bytes().unwrap();
+        let stats = manager.stats();
+        assert_eq!(stats.open_channels(), 1);
+        assert_eq!(stats.channels[0].ready_state, ReadyState::Open);
+        assert_eq!(stats.channels[1].ready_state, ReadyState::Connecting);
+        assert_eq!((stats.reads_in_flight(), stats.writes_in_flight()), (0, 0));
+    }
+
+    #[test]
+    fn publishes_events() {
+        let (association, _remote) = testing::simulated();
+        let mut manager = DataChannelManager::new(association);
+        let events = manager.subscribe();
+        let config = Config {
+            label: "chat".to_owned(),
+            ..Default::default()
+        };
+
+        manager.dial(1, config).unwrap();
+        manager.drain(Duration::ZERO).unwrap();
+
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            vec![
+                AssociationEvent::ChannelOpened {
+                    stream_identifier: 1,
+                    label: Label::from("chat"),
+                },
+                AssociationEvent::Draining,
+                AssociationEvent::ChannelClosed {
+                    stream_identifier: 1,
+                    reason: CloseReason::Drained,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn drain_flushes_then_retires_channels() {
+        let (association, remote) = testing::simulated();
+        let mut manager = DataChannelManager::new(association);
+        manager.dial(1, Config::default()).unwrap();
+        let config = Config {
+            max_receive_buffer: Some(4),
+            receive_buffer_policy: ReceiveBufferPolicy::Block,
+            ..Default::default()
+        };
+        let mut acceptor = DataChannel::accept(&remote, config).unwrap();
+
+        let data_channel = manager.get_mut(1).unwrap();
+        data_channel
+            .write(&mut bytes::Bytes::from_static(b"abc"))
+            .unwrap();
+        data_channel
+            .write(&mut bytes::Bytes::from_static(b"de"))
+            .unwrap();
+        assert_eq!(data_channel.buffered_amount(), 2);
+        let reader = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            acceptor.read_bytes().unwrap();
+            acceptor
+        });
+
+        manager.drain(Duration::from_secs(60)).unwrap();
+        let mut acceptor = reader.join().unwrap();
+        assert!(manager.is_empty());
+        assert!(manager.is_closing(1));
+
+        assert_eq!(acceptor.read_bytes().unwrap().0, &b"de"[..]);
+        assert!(acceptor.read_bytes().is_err());
+        assert_eq!(manager.reap_closed(), vec![1]);
+    }
+
+    #[test]
+    fn open_allocates_stream_identifiers() {
+        let (association, _remote) = testing::simulated();
+        let mut manager = DataChannelManager::new(association).with_role(Role::Server);
+        manager.dial(3, Config::default()).unwrap();
+
+        let first = manager.open(Config::default()).unwrap().stream_identifier();
+        let second = manager.open(Config::default()).unwrap().stream_identifier();
+
+        assert_eq!((first, second), (1, 5));
+    }
+
+    #[test]
+    fn removed_channels_keep_their_stream_identifiers() {
+        let (association, _remote) = testing::simulated();
+        let mut manager = DataChannelManager::new(association).with_role(Role::Server);
+        let first = manager.open(Config::default()).unwrap().stream_identifier();
+
+        let removed = manager.remove(first).unwrap();
+        assert_eq!(
+            manager.open(Config::default()).unwrap().stream_identifier(),
+            3
+        );
+
+        manager.insert(removed);
+        manager.close(first).unwrap();
+        assert!(manager.is_closing(first));
+    }
+
+    #[test]
+    fn open_adds_streams_once_exhausted() {
+        let (association, _remote) = testing::simulated();
+        testing::set_max_outbound_streams(&association, 4);
+        let allocator = SequentialAllocator::new(Role::Client).with_max_streams(4);
+        let mut manager = DataChannelManager::new(association).with_stream_id_allocator(allocator);
+
+        let stream_identifiers: Vec<u16> = (0..3)
+            .map(|_| manager.open(Config::default()).unwrap().stream_identifier())
+            .collect();
+
+        assert_eq!(stream_identifiers, vec![0, 2, 4]);
+        assert_eq!(manager.association().max_outbound_streams(), Ok(20));
+    }
+
+    #[test]
+    fn open_stays_within_negotiated_streams() {
+        let (association, _remote) = testing::simulated();
+        testing::set_max_outbound_streams(&association, 4);
+        let mut manager = DataChannelManager::new(association).with_role(Role::Server);
+
+        let stream_identifiers: Vec<u16> = (0..2)
+            .map(|_| manager.open(Config::default()).unwrap().stream_identifier())
+            .collect();
+        assert_eq!(stream_identifiers, vec![1, 3]);
+        assert_eq!(manager.association().max_outbound_streams(), Ok(4));
+
+        let stream_identifier = manager.open(Config::default()).unwrap().stream_identifier();
+        assert_eq!(stream_identifier, 5);
+        assert_eq!(manager.association().max_outbound_streams(), Ok(20));
+    }
+
+    #[test]
+    fn open_reuses_stream_identifiers_once_reset() {
+        let (association, remote) = testing::simulated();
+        testing::set_max_outbound_streams(&association, 2);
+        testing::refuse_outbound_streams(&association);
+        let mut manager = DataChannelManager::new(association);
+
+        assert_eq!(
+            manager.open(Config::default()).unwrap().stream_identifier(),
+            0
+        );
+        let mut acceptor = DataChannel::accept(&remote, Config::default()).unwrap();
+        manager.close(0).unwrap();
+        assert!(manager.is_closing(0));
+        assert_eq!(
+            manager.open(Config::default()).err(),
+            Some(DataChannelError::StreamIdentifiersExhausted)
+        );
+
+        // The remote resets its stream in response.
+        acceptor.read_bytes().unwrap_err();
+        let data_channel = manager.open(Config::default()).unwrap();
+        assert_eq!(data_channel.stream_identifier(), 0);
+        assert!(!manager.is_closing(0));
+
+        let mut acceptor = DataChannel::accept(&remote, Config::default()).unwrap();
+        manager
+            .get_mut(0)
+            .unwrap()
+            .write_data_channel(&mut bytes::Bytes::from_static(b"again"), false)
+            .unwrap();
+        assert_eq!(acceptor.read_bytes().unwrap().0, &b"again"[..]);
+    }
+
+    #[test]
+    fn incoming_calls_on_data_channel() {
+        let (remote, association) = testing::simulated();
+        let mut manager = DataChannelManager::new(association);
+        let (sender, labels) = std::sync::mpsc::channel();
+        let sender = std::sync::Mutex::new(sender);
+        manager.on_data_channel(move |data_channel| {
+            assert_eq!(data_channel.ready_state(), ReadyState::Open);
+            let label = data_channel.config.label.clone();
+            sender.lock().unwrap().send(label).unwrap();
+        });
+
+        let config = |label: &str| Config {
+            label: label.to_owned(),
+            ..Default::default()
+        };
+        DataChannel::dial(&remote, 1, config("chat")).unwrap();
+        DataChannel::dial(&remote, 3, config("files")).unwrap();
+
+        let stream_identifiers: Vec<u16> = manager
+            .incoming(Config::default())
+            .take(2)
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(stream_identifiers, vec![1, 3]);
+        assert_eq!(labels.try_iter().collect::<Vec<_>>(), vec!["chat", "files"]);
+        assert_eq!(manager.get(3).unwrap().config.label, "files");
+    }
+
+    #[test]
+    fn incoming_waits_for_handshakes_without_blocking() {
+        let (remote, association) = testing::simulated();
+        let mut manager = DataChannelManager::new(association);
+        let woken = Arc::new(CountingWaker::default());
+        let waker = Waker::from(woken.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut incoming = manager.incoming(Config::default());
+
+        assert!(incoming.poll_next(&mut cx).is_pending());
+        let stream = remote
+            .open_stream(1, crate::sctp::PayloadType::WebRtcDcep)
+            .unwrap();
+        assert_eq!(woken.0.load(Ordering::Relaxed), 1);
+
+        // The stream is accepted, but the remote hasn't sent its DATA_CHANNEL_OPEN yet.
+        assert!(incoming.poll_next(&mut cx).is_pending());
+        let config = Config {
+            label: "chat".to_owned(),
+            ..Default::default()
+        };
+        DataChannel::client(stream, config).unwrap();
+        assert_eq!(woken.0.load(Ordering::Relaxed), 2);
+
+        assert_eq!(incoming.poll_next(&mut cx), Poll::Ready(Ok(1)));
+        assert_eq!(manager.get(1).unwrap().config.label, "chat");
+    }
+
+    #[test]
+    fn create_and_accept_incoming_without_blocking() {
+        let (association, remote) = testing::simulated();
+        let mut manager = DataChannelManager::new(association);
+        let mut remote_manager = DataChannelManager::new(remote);
+        let mut cx = Context::from_waker(Waker::noop());
+        let config = Config {
+            label: "chat".to_owned(),
+            ..Default::default()
+        };
+
+        let mut create = manager.create(config);
+        assert!(Pin::new(&mut create).poll(&mut cx).is_pending());
+
+        let mut accept = remote_manager.accept_incoming(Config::default());
+        let accepted = match Pin::new(&mut accept).poll(&mut cx) {
+            Poll::Ready(result) => result.unwrap(),
+            Poll::Pending => panic!("DATA_CHANNEL_OPEN was sent"),
+        };
+        assert_eq!(
+            Pin::new(&mut create).poll(&mut cx),
+            Poll::Ready(Ok(accepted))
+        );
+
+        assert_eq!(
+            manager.get(accepted).unwrap().ready_state(),
+            ReadyState::Open
+        );
+        assert_eq!(remote_manager.get(accepted).unwrap().config.label, "chat");
+    }
+
+    #[test]
+    fn create_closes_channels_timing_out() {
+        let clock = ManualClock::new();
+        let (association, _remote) = testing::simulated();
+        let mut manager =
+            DataChannelManager::new(association).with_clock(SharedClock::new(clock.clone()));
+        let events = manager.subscribe();
+        let mut cx = Context::from_waker(Waker::noop());
+        let config = Config {
+            open_timeout: Some(Duration::from_secs(1)),
+            open_retries: 1,
+            ..Default::default()
+        };
+
+        let mut create = manager.create(config);
+        assert!(Pin::new(&mut create).poll(&mut cx).is_pending());
+        clock.advance(Duration::from_secs(1));
+        // The DATA_CHANNEL_OPEN is re-sent once.
+        assert!(Pin::new(&mut create).poll(&mut cx).is_pending());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(
+            Pin::new(&mut create).poll(&mut cx),
+            Poll::Ready(Err(DataChannelError::HandshakeTimedOut {
+                elapsed: Duration::from_secs(2)
+            }))
+        );
+
+        assert!(manager.is_empty());
+        assert!(events
+            .try_iter()
+            .any(|event| matches!(event, AssociationEvent::HandshakeFailed { .. })));
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn incoming_stream_ends_once_drained() {
+        use futures_core::Stream;
+
+        let (remote, association) = testing::simulated();
+        let mut manager = DataChannelManager::new(association);
+        let mut cx = Context::from_waker(Waker::noop());
+        DataChannel::dial(&remote, 1, Config::default()).unwrap();
+
+        let mut incoming = manager.incoming(Config::default());
+        assert_eq!(
+            Pin::new(&mut incoming).poll_next(&mut cx),
+            Poll::Ready(Some(Ok(1)))
+        );
+        assert!(Pin::new(&mut incoming).poll_next(&mut cx).is_pending());
+
+        manager.drain(Duration::ZERO).unwrap();
+        let mut incoming = manager.incoming(Config::default());
+        assert_eq!(
+            Pin::new(&mut incoming).poll_next(&mut cx),
+            Poll::Ready(None)
+        );
+    }
+
+    #[test]
+    fn open_group_waits_for_all_acks() {
+        let (association, remote) = testing::simulated();
+        let mut manager = DataChannelManager::new(association);
+        let acceptor = std::thread::spawn(move || {
+            for _ in 0..2 {
+                DataChannel::accept(&remote, Config::default()).unwrap();
+            }
+        });
+
+        let stream_identifiers = manager
+            .open_group(
+                vec![Config::default(), Config::default()],
+                Duration::from_secs(10),
+            )
+            .unwrap();
+        acceptor.join().unwrap();
+
+        assert_eq!(stream_identifiers, vec![0, 2]);
+        for stream_identifier in stream_identifiers {
+            let data_channel = manager.get(stream_identifier).unwrap();
+            assert_eq!(data_channel.ready_state(), ReadyState::Open);
+        }
+    }
+
+    #[test]
+    fn open_group_rolls_back_on_timeout() {
+        let clock = ManualClock::new();
+        let (association, _remote) = testing::simulated();
+        let mut manager =
+            DataChannelManager::new(association).with_clock(SharedClock::new(clock.clone()));
+
+        let result = manager.open_group(
+            vec![Config::default(), Config::default()],
+            Duration::from_secs(1),
+        );
+
+        assert_eq!(result, Err(DataChannelError::Timeout));
+        assert!(manager.is_empty());
+    }
+
+    #[test]
+    fn update_config_applies_to_all_channels() {
+        let (association, _remote) = testing::simulated();
+        let mut manager = DataChannelManager::new(association);
+        manager.dial(1, Config::default()).unwrap();
+
+        manager.update_config(RuntimeConfig {
+            buffered_amount_low_threshold: Some(1024),
+            ..Default::default()
+        });
+        manager.dial(3, Config::default()).unwrap();
+
+        for stream_identifier in [1, 3] {
+            let data_channel = manager.get(stream_identifier).unwrap();
+            assert_eq!(data_channel.buffered_amount_low_threshold(), 1024);
+        }
+    }
+
+    #[test]
+    fn sweep_idle() {
+        let clock = ManualClock::new();
+        let (association, _remote) = testing::simulated();
+        let mut manager =
+            DataChannelManager::new(association).with_clock(SharedClock::new(clock.clone()));
+        let events = manager.subscribe();
+
+        let config = Config {
+            negotiated: true,
+            idle_timeout: Some(Duration::from_secs(10)),
+            clock: SharedClock::new(clock.clone()),
+            ..Default::default()
+        };
+        manager.dial(1, config.clone()).unwrap();
+        manager
+            .dial(
+                3,
+                Config {
+                    idle_action: IdleAction::Notify,
+                    ..config
+                },
+            )
+            .unwrap();
+        manager.dial(5, Config::default()).unwrap();
+
+        assert!(manager.sweep_idle().is_empty());
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(manager.sweep_idle(), vec![1, 3]);
+        // Already notified of this idle period.
+        assert!(manager.sweep_idle().is_empty());
+
+        assert!(manager.get(1).is_none());
+        assert!(manager.get(3).is_some());
+
+        let events: Vec<_> = events.try_iter().collect();
+        assert!(events.contains(&AssociationEvent::ChannelIdle {
+            stream_identifier: 3,
+            idle_for: Duration::from_secs(10),
+        }));
+        assert!(events.contains(&AssociationEvent::ChannelClosed {
+            stream_identifier: 1,
+            reason: CloseReason::Idle,
+        }));
+    }
+
+    #[test]
+    fn sample_quality() {
+        let (association, _remote) = testing::simulated();
+        testing::set_quality_signals(
+            &association,
+            QualitySignals {
+                rtt: Some(Duration::from_millis(300)),
+                ..Default::default()
+            },
+        );
+
+        let mut manager = DataChannelManager::new(association);
+        assert_eq!(manager.sample_quality(), Ok(None));
+
+        let mut manager = manager.with_quality_monitor(QualityMonitor::new());
+        let events = manager.quality_monitor().unwrap().subscribe();
+        assert_eq!(manager.sample_quality(), Ok(Some(70)));
+        assert_eq!(events.try_recv().unwrap().current, QualityLevel::Good);
+    }
+
+    #[test]
+    fn interns_labels_of_managed_channels() {
+        let (association, _remote) = testing::simulated();
+        let mut manager = DataChannelManager::new(association);
+
+        let config = Config {
+            label: "chat".to_owned(),
+            ..Default::default()
+        };
+        manager.dial(1, config.clone()).unwrap();
+        manager.dial(3, config).unwrap();
+
+        let channels = manager.channels();
+        assert_eq!(channels[0].label, "chat");
+        assert!(channels[0].label.ptr_eq(&channels[1].label));
+        // The label and the empty protocol.
+        assert_eq!(manager.labels().len(), 2);
+    }
+}
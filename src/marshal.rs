@@ -16,6 +16,13 @@ pub trait Marshal: MarshalSize {
         let _ = self.marshal_to(&mut buf)?;
         Ok(buf.freeze())
     }
+
+    /// MarshalToSlice writes the message to the front of `buf`, such as a
+    /// stack array, returning the number of bytes written. Fails, as
+    /// `marshal_to` does, if `buf` is shorter than `marshal_size`.
+    fn marshal_to_slice(&self, mut buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.marshal_to(&mut buf)
+    }
 }
 
 pub trait Unmarshal: Sized + MarshalSize {
@@ -25,3 +32,40 @@ pub trait Unmarshal: Sized + MarshalSize {
     where
         B: Buf;
 }
+
+/// How strictly `UnmarshalWith::unmarshal_from_with` parses its input.
+///
+/// Lenient parsing, the default, is what `Unmarshal::unmarshal_from` does,
+/// accepting messages as browsers send them. Strict parsing, meant for
+/// fuzzing and conformance testing, also rejects trailing bytes, reserved
+/// channel types and empty labels.
+///
+/// Proxies and recorders which must pass on messages of future extensions
+/// can instead preserve unknown channel types as `ChannelType::Unknown`,
+/// unless parsing strictly.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub struct UnmarshalConfig {
+    pub strict: bool,
+    pub preserve_unknown_channel_types: bool,
+}
+
+impl UnmarshalConfig {
+    pub const LENIENT: Self = Self {
+        strict: false,
+        preserve_unknown_channel_types: false,
+    };
+    pub const STRICT: Self = Self {
+        strict: true,
+        preserve_unknown_channel_types: false,
+    };
+    pub const PRESERVING: Self = Self {
+        strict: false,
+        preserve_unknown_channel_types: true,
+    };
+}
+
+pub trait UnmarshalWith: Unmarshal {
+    fn unmarshal_from_with<B>(buf: &mut B, config: &UnmarshalConfig) -> Result<Self, Self::Error>
+    where
+        B: Buf;
+}
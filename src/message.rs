@@ -1,25 +1,93 @@
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
 use bytes::{Buf, BufMut};
 
 use crate::{
     error::MessageError,
-    marshal::{Marshal, MarshalSize, Unmarshal},
+    marshal::{Marshal, MarshalSize, Unmarshal, UnmarshalConfig, UnmarshalWith},
 };
 
 mod data_channel_ack;
 mod data_channel_open;
+mod dump;
 mod message_type;
+mod raw_message;
 
 pub use data_channel_ack::DataChannelAck;
-pub use data_channel_open::DataChannelOpen;
+pub(crate) use data_channel_open::CHANNEL_OPEN_HEADER_LEN;
+pub use data_channel_open::{
+    DataChannelOpen, DataChannelOpenBuilder, DataChannelOpenRef, Violation, MAX_FIELD_LEN,
+};
+pub use dump::debug_dump;
 pub use message_type::MessageType;
+pub(crate) use message_type::MESSAGE_TYPE_LEN;
+pub use raw_message::RawMessage;
 
 // A parsed DataChannel message
 #[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
 pub enum Message {
     DataChannelAck,
     DataChannelOpen(DataChannelOpen),
 }
 
+/// Displays the message on one line; see `DataChannelOpen`'s `Display`.
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DataChannelAck => write!(f, "{}", MessageType::DataChannelAck),
+            Self::DataChannelOpen(open) => open.fmt(f),
+        }
+    }
+}
+
+/// A borrowed view of a `Message`, parsed without allocating.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum MessageRef<'a> {
+    DataChannelAck,
+    DataChannelOpen(DataChannelOpenRef<'a>),
+}
+
+impl<'a> MessageRef<'a> {
+    /// Parse reads the message from `buf`, borrowing
+    /// the variable-length parts of its body.
+    pub fn parse(buf: &'a [u8]) -> Result<Self, MessageError> {
+        let mut type_buf = buf;
+        match MessageType::unmarshal_from(&mut type_buf)? {
+            MessageType::DataChannelAck => Ok(Self::DataChannelAck),
+            MessageType::DataChannelOpen => {
+                let info = DataChannelOpenRef::parse(type_buf)?;
+                Ok(Self::DataChannelOpen(info))
+            }
+        }
+    }
+
+    #[inline]
+    pub fn message_type(&self) -> MessageType {
+        match self {
+            Self::DataChannelAck => MessageType::DataChannelAck,
+            Self::DataChannelOpen(_) => MessageType::DataChannelOpen,
+        }
+    }
+
+    /// IntoOwned copies the borrowed parts of the message into a `Message`.
+    pub fn into_owned(self) -> Message {
+        Message::from(self)
+    }
+}
+
+impl<'a> From<MessageRef<'a>> for Message {
+    fn from(message: MessageRef<'a>) -> Self {
+        match message {
+            MessageRef::DataChannelAck => Self::DataChannelAck,
+            MessageRef::DataChannelOpen(info) => Self::DataChannelOpen(info.into_owned()),
+        }
+    }
+}
+
 impl MarshalSize for Message {
     fn marshal_size(&self) -> usize {
         let type_size = self.message_type().marshal_size();
@@ -50,6 +118,26 @@ impl Unmarshal for Message {
     }
 }
 
+impl UnmarshalWith for Message {
+    fn unmarshal_from_with<B>(buf: &mut B, config: &UnmarshalConfig) -> Result<Self, Self::Error>
+    where
+        B: Buf,
+    {
+        match MessageType::unmarshal_from(buf)? {
+            MessageType::DataChannelAck if config.strict && buf.has_remaining() => {
+                Err(Self::Error::TrailingBytes {
+                    len: buf.remaining(),
+                })
+            }
+            MessageType::DataChannelAck => Ok(Self::DataChannelAck),
+            MessageType::DataChannelOpen => {
+                let info = DataChannelOpen::unmarshal_from_with(buf, config)?;
+                Ok(Self::DataChannelOpen(info))
+            }
+        }
+    }
+}
+
 impl Marshal for Message {
     type Error = MessageError;
 
@@ -68,6 +156,34 @@ impl Marshal for Message {
 }
 
 impl Message {
+    /// Parse reads a message from `bytes`, the payload of a DCEP
+    /// SCTP message, ignoring anything after it.
+    pub fn parse(bytes: &[u8]) -> Result<Self, MessageError> {
+        Self::unmarshal_from(&mut &bytes[..])
+    }
+
+    /// ToVec marshals the message into a new vector, failing only if the
+    /// label or protocol of a DATA_CHANNEL_OPEN is too long to encode.
+    pub fn to_vec(&self) -> Result<Vec<u8>, MessageError> {
+        let mut buf = Vec::with_capacity(self.marshal_size());
+        self.marshal_to(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// DebugDump returns an annotated hex dump of the marshaled
+    /// message; see `debug_dump`.
+    pub fn debug_dump(&self) -> Result<String, MessageError> {
+        Ok(debug_dump(&self.to_vec()?))
+    }
+
+    /// ToRef returns a borrowed view of the message.
+    pub fn to_ref(&self) -> MessageRef<'_> {
+        match self {
+            Self::DataChannelAck => MessageRef::DataChannelAck,
+            Self::DataChannelOpen(info) => MessageRef::DataChannelOpen(info.to_ref()),
+        }
+    }
+
     #[inline]
     pub fn message_type(&self) -> MessageType {
         match self {
@@ -81,7 +197,11 @@ impl Message {
 mod tests {
     use bytes::{Bytes, BytesMut};
 
-    use crate::{channel_type::ChannelType, error::MessageTypeError};
+    use crate::{
+        channel_type::ChannelType,
+        error::{ChannelTypeError, DataChannelOpenError, MessageTypeError},
+        wire,
+    };
 
     use super::*;
 
@@ -104,8 +224,8 @@ mod tests {
             channel_type: ChannelType::Reliable,
             priority: 3893,
             reliability_parameter: 16715573,
-            label: b"label".iter().cloned().collect(),
-            protocol: b"protocol".iter().cloned().collect(),
+            label: b"label".to_vec(),
+            protocol: b"protocol".to_vec(),
         });
 
         assert_eq!(actual, expected);
@@ -121,6 +241,36 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn parse_ref_open_success() {
+        let bytes = [
+            0x03, // message type
+            0x00, // channel type
+            0x0f, 0x35, // priority
+            0x00, 0xff, 0x0f, 0x35, // reliability parameter
+            0x00, 0x05, // label length
+            0x00, 0x08, // protocol length
+            0x6c, 0x61, 0x62, 0x65, 0x6c, // label
+            0x70, 0x72, 0x6f, 0x74, 0x6f, 0x63, 0x6f, 0x6c, // protocol
+        ];
+
+        let actual = MessageRef::parse(&bytes).unwrap();
+
+        let expected = Message::unmarshal_from(&mut &bytes[..]).unwrap();
+        assert_eq!(actual, expected.to_ref());
+        assert_eq!(actual.into_owned(), expected);
+    }
+
+    #[test]
+    fn parse_ref_invalid_message_type() {
+        let actual = MessageRef::parse(&[0x01]);
+        let expected = Err(MessageError::MessageType(
+            MessageTypeError::InvalidMessageType { invalid_type: 0x01 },
+        ));
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn unmarshal_invalid_message_type() {
         let mut bytes = Bytes::from_static(&[0x01]);
@@ -152,8 +302,8 @@ mod tests {
             channel_type: ChannelType::Reliable,
             priority: 3893,
             reliability_parameter: 16715573,
-            label: b"label".iter().cloned().collect(),
-            protocol: b"protocol".iter().cloned().collect(),
+            label: b"label".to_vec(),
+            protocol: b"protocol".to_vec(),
         });
 
         let actual = msg.marshal_to(&mut buf).unwrap();
@@ -176,4 +326,218 @@ mod tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let message = Message::DataChannelOpen(DataChannelOpen {
+            channel_type: ChannelType::PartialReliableRexmit,
+            priority: 256,
+            reliability_parameter: 3,
+            label: b"chat".to_vec(),
+            protocol: vec![0xff],
+        });
+
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "data_channel_open",
+                "channel_type": "partial_reliable_rexmit",
+                "priority": 256,
+                "reliability_parameter": 3,
+                "label": "chat",
+                "protocol": [255],
+            })
+        );
+        assert_eq!(serde_json::from_value::<Message>(json).unwrap(), message);
+
+        let ack = serde_json::to_string(&Message::DataChannelAck).unwrap();
+        assert_eq!(ack, r#"{"type":"data_channel_ack"}"#);
+        assert_eq!(
+            serde_json::from_str::<Message>(&ack).unwrap(),
+            Message::DataChannelAck
+        );
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_messages_round_trip() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let seed: Vec<u8> = (0..=255u8).cycle().take(4096).collect();
+        let mut unstructured = Unstructured::new(&seed);
+        while !unstructured.is_empty() {
+            let message = Message::arbitrary(&mut unstructured).unwrap();
+            let bytes = message.marshal().unwrap();
+
+            // Known channel types may have been generated as `ChannelType::Unknown`.
+            let unmarshaled =
+                Message::unmarshal_from_with(&mut bytes.clone(), &UnmarshalConfig::PRESERVING)
+                    .unwrap();
+            assert_eq!(unmarshaled.marshal().unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn strict_unmarshal_rejects_what_lenient_accepts() {
+        let open = [
+            0x03, // message type
+            0x00, // channel type
+            0x00, 0x00, // priority
+            0x00, 0x00, 0x00, 0x00, // reliability parameter
+            0x00, 0x00, // label length
+            0x00, 0x00, // protocol length
+        ];
+        let ack_with_trailer = [0x02, 0xff];
+
+        for bytes in [&open[..], &ack_with_trailer[..]] {
+            assert!(
+                Message::unmarshal_from_with(&mut &bytes[..], &UnmarshalConfig::LENIENT).is_ok()
+            );
+        }
+
+        assert_eq!(
+            Message::unmarshal_from_with(&mut &open[..], &UnmarshalConfig::STRICT),
+            Err(MessageError::DataChannelOpen(
+                DataChannelOpenError::EmptyLabel
+            ))
+        );
+        assert_eq!(
+            Message::unmarshal_from_with(&mut &ack_with_trailer[..], &UnmarshalConfig::STRICT),
+            Err(MessageError::TrailingBytes { len: 1 })
+        );
+
+        let mut labeled = open.to_vec();
+        labeled[9] = 0x01;
+        labeled.extend_from_slice(b"a\x00");
+        assert_eq!(
+            Message::unmarshal_from_with(&mut &labeled[..], &UnmarshalConfig::STRICT),
+            Err(MessageError::DataChannelOpen(
+                DataChannelOpenError::TrailingBytes { len: 1 }
+            ))
+        );
+    }
+
+    #[test]
+    fn preserves_unknown_channel_types() {
+        let bytes = [
+            0x03, // message type
+            0x03, // channel type
+            0x00, 0x00, // priority
+            0x00, 0x00, 0x00, 0x00, // reliability parameter
+            0x00, 0x01, // label length
+            0x00, 0x00, // protocol length
+            0x61, // label
+        ];
+
+        assert_eq!(
+            Message::unmarshal_from(&mut &bytes[..]),
+            Err(MessageError::DataChannelOpen(
+                DataChannelOpenError::ChannelType(ChannelTypeError::InvalidChannelType {
+                    invalid_type: 0x03
+                })
+            ))
+        );
+
+        let message =
+            Message::unmarshal_from_with(&mut &bytes[..], &UnmarshalConfig::PRESERVING).unwrap();
+        match &message {
+            Message::DataChannelOpen(open) => {
+                assert_eq!(open.channel_type, ChannelType::Unknown(0x03));
+                assert!(open.channel_type.is_ordered());
+            }
+            message => panic!("expected an OPEN, got {:?}", message),
+        }
+        assert_eq!(&message.marshal().unwrap()[..], &bytes[..]);
+
+        let strict = UnmarshalConfig {
+            preserve_unknown_channel_types: true,
+            ..UnmarshalConfig::STRICT
+        };
+        assert!(Message::unmarshal_from_with(&mut &bytes[..], &strict).is_err());
+    }
+
+    #[test]
+    fn marshal_to_slice() {
+        let mut buf = [0; wire::ACK_WIRE_SIZE];
+        assert_eq!(Message::DataChannelAck.marshal_to_slice(&mut buf), Ok(1));
+        assert_eq!(buf, [wire::MESSAGE_TYPE_ACK]);
+        assert_eq!(
+            Message::DataChannelAck.marshal_to_slice(&mut []),
+            Err(MessageError::MessageType(
+                MessageTypeError::UnexpectedEndOfBuffer {
+                    expected: 1,
+                    actual: 0
+                }
+            ))
+        );
+
+        let open = DataChannelOpenBuilder::new().label("chat").build().unwrap();
+        let open = Message::DataChannelOpen(open);
+        let mut buf = [0; wire::OPEN_HEADER_WIRE_SIZE + 4];
+        assert_eq!(open.marshal_to_slice(&mut buf), Ok(buf.len()));
+        assert_eq!(&buf[..], &open.marshal().unwrap()[..]);
+        assert!(open.marshal_to_slice(&mut buf[..15]).is_err());
+    }
+
+    #[test]
+    fn unmarshal_from_chained_buffers() {
+        let open = DataChannelOpenBuilder::new()
+            .priority(3893)
+            .label("label")
+            .protocol("protocol")
+            .build()
+            .unwrap();
+        let bytes = Message::DataChannelOpen(open.clone()).marshal().unwrap();
+
+        // Splitting within the header and within the label.
+        for (first, second) in [(1, 7), (4, 14), (15, 21)] {
+            let mut chained = bytes
+                .slice(..first)
+                .chain(bytes.slice(first..second))
+                .chain(bytes.slice(second..));
+            assert_eq!(
+                Message::unmarshal_from(&mut chained),
+                Ok(Message::DataChannelOpen(open.clone()))
+            );
+            assert!(!chained.has_remaining());
+        }
+    }
+
+    #[test]
+    fn parse_and_to_vec() {
+        let open = DataChannelOpenBuilder::new().label("chat").build().unwrap();
+        let message = Message::DataChannelOpen(open);
+
+        let bytes = message.to_vec().unwrap();
+        assert_eq!(&bytes[..], &message.marshal().unwrap()[..]);
+        assert_eq!(Message::parse(&bytes), Ok(message));
+
+        assert_eq!(
+            Message::parse(&[0x01]),
+            Err(MessageError::MessageType(
+                MessageTypeError::InvalidMessageType { invalid_type: 0x01 }
+            ))
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Message::DataChannelAck.to_string(), "DATA_CHANNEL_ACK");
+
+        let open = DataChannelOpenBuilder::new()
+            .channel_type(ChannelType::PartialReliableRexmit)
+            .reliability_parameter(3)
+            .label("chat")
+            .protocol(vec![0xff])
+            .build()
+            .unwrap();
+        assert_eq!(
+            Message::DataChannelOpen(open).to_string(),
+            "DATA_CHANNEL_OPEN label=\"chat\" protocol=b\"\\xff\" \
+             channel_type=partial_reliable_rexmit priority=0 reliability_parameter=3"
+        );
+        assert_eq!(ChannelType::Unknown(0x03).to_string(), "unknown(0x03)");
+    }
 }
@@ -17,6 +17,8 @@ use crate::{
 ///+-+-+-+-+-+-+-+-+
 /// ```
 #[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct DataChannelAck;
 
 impl MarshalSize for DataChannelAck {
@@ -80,6 +82,6 @@ mod tests {
         let bytes = buf.freeze();
 
         assert_eq!(bytes_written, data_channel_ack.marshal_size());
-        assert_eq!(&bytes[..], &[]);
+        assert_eq!(&bytes[..], &[] as &[u8]);
     }
 }
@@ -1,12 +1,19 @@
+use alloc::{vec, vec::Vec};
+use core::{fmt, str};
+
 use bytes::{Buf, BufMut};
 
+use super::dump::Quoted;
 use crate::{
     channel_type::ChannelType,
-    error::DataChannelOpenError,
-    marshal::{Marshal, MarshalSize, Unmarshal},
+    error::{ChannelTypeError, DataChannelOpenError},
+    marshal::{Marshal, MarshalSize, Unmarshal, UnmarshalConfig, UnmarshalWith},
 };
 
-const CHANNEL_OPEN_HEADER_LEN: usize = 11;
+pub(crate) const CHANNEL_OPEN_HEADER_LEN: usize = 11;
+
+/// The length of the longest label or protocol, as limited by its length field.
+pub const MAX_FIELD_LEN: usize = u16::MAX as usize;
 
 /// The data-part of an data-channel OPEN message without the message type.
 ///
@@ -31,15 +38,258 @@ const CHANNEL_OPEN_HEADER_LEN: usize = 11;
 /// |                                                               |
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
+///
+/// With the `serde` feature, the label and protocol are serialized
+/// as strings, unless they aren't valid UTF-8.
 #[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct DataChannelOpen {
     pub channel_type: ChannelType,
     pub priority: u16,
     pub reliability_parameter: u32,
+    #[cfg_attr(feature = "serde", serde(with = "utf8_or_bytes"))]
     pub label: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "utf8_or_bytes"))]
     pub protocol: Vec<u8>,
 }
 
+/// Displays the message on one line, such as `DATA_CHANNEL_OPEN label="chat"
+/// protocol="" channel_type=reliable priority=256 reliability_parameter=0`.
+impl fmt::Display for DataChannelOpen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "DATA_CHANNEL_OPEN label={} protocol={} channel_type={} priority={} reliability_parameter={}",
+            Quoted(&self.label),
+            Quoted(&self.protocol),
+            self.channel_type,
+            self.priority,
+            self.reliability_parameter
+        )
+    }
+}
+
+// Serializes a field as a string if it is valid UTF-8, or else as bytes.
+#[cfg(feature = "serde")]
+mod utf8_or_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Field {
+        Str(String),
+        Bytes(Vec<u8>),
+    }
+
+    pub fn serialize<S>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match std::str::from_utf8(value) {
+            Ok(value) => serializer.serialize_str(value),
+            Err(_) => serializer.serialize_bytes(value),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Field::deserialize(deserializer)? {
+            Field::Str(value) => Ok(value.into_bytes()),
+            Field::Bytes(value) => Ok(value),
+        }
+    }
+}
+
+/// A borrowed view of a `DataChannelOpen`, parsed without allocating.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub struct DataChannelOpenRef<'a> {
+    pub channel_type: ChannelType,
+    pub priority: u16,
+    pub reliability_parameter: u32,
+    pub label: &'a [u8],
+    pub protocol: &'a [u8],
+}
+
+impl<'a> DataChannelOpenRef<'a> {
+    /// Parse reads the message from `buf`, borrowing its label and protocol.
+    /// Like `DataChannelOpen::unmarshal_from`, trailing bytes are ignored.
+    pub fn parse(buf: &'a [u8]) -> Result<Self, DataChannelOpenError> {
+        let required_len = CHANNEL_OPEN_HEADER_LEN;
+        if buf.len() < required_len {
+            return Err(DataChannelOpenError::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.len(),
+            });
+        }
+
+        let mut header = &buf[..required_len];
+        let channel_type = ChannelType::unmarshal_from(&mut header)?;
+        let priority = header.get_u16();
+        let reliability_parameter = header.get_u32();
+        let label_len = header.get_u16() as usize;
+        let protocol_len = header.get_u16() as usize;
+
+        let rest = &buf[required_len..];
+        let required_len = label_len + protocol_len;
+        if rest.len() < required_len {
+            return Err(DataChannelOpenError::ExpectedAndActualLengthMismatch {
+                expected: required_len,
+                actual: rest.len(),
+            });
+        }
+
+        let (label, rest) = rest.split_at(label_len);
+        let protocol = &rest[..protocol_len];
+
+        Ok(Self {
+            channel_type,
+            priority,
+            reliability_parameter,
+            label,
+            protocol,
+        })
+    }
+
+    /// LabelStr returns the label, failing if it isn't valid UTF-8.
+    pub fn label_str(&self) -> Result<&'a str, DataChannelOpenError> {
+        utf8_field("label", self.label)
+    }
+
+    /// ProtocolStr returns the protocol, failing if it isn't valid UTF-8.
+    pub fn protocol_str(&self) -> Result<&'a str, DataChannelOpenError> {
+        utf8_field("protocol", self.protocol)
+    }
+
+    /// IntoOwned copies the borrowed label and protocol into a `DataChannelOpen`.
+    pub fn into_owned(self) -> DataChannelOpen {
+        DataChannelOpen::from(self)
+    }
+}
+
+impl<'a> From<DataChannelOpenRef<'a>> for DataChannelOpen {
+    fn from(open: DataChannelOpenRef<'a>) -> Self {
+        Self {
+            channel_type: open.channel_type,
+            priority: open.priority,
+            reliability_parameter: open.reliability_parameter,
+            label: open.label.to_vec(),
+            protocol: open.protocol.to_vec(),
+        }
+    }
+}
+
+impl DataChannelOpen {
+    /// UnmarshalStrict is like `unmarshal_from`, but also fails with
+    /// `DataChannelOpenError::NotUtf8` if the label or protocol isn't
+    /// valid UTF-8, as RFC 8832 requires them to be.
+    pub fn unmarshal_strict<B>(buf: &mut B) -> Result<Self, DataChannelOpenError>
+    where
+        B: Buf,
+    {
+        let open = Self::unmarshal_from(buf)?;
+        open.label_str()?;
+        open.protocol_str()?;
+
+        Ok(open)
+    }
+
+    /// LabelStr returns the label, failing if it isn't valid UTF-8.
+    pub fn label_str(&self) -> Result<&str, DataChannelOpenError> {
+        utf8_field("label", &self.label)
+    }
+
+    /// ProtocolStr returns the protocol, failing if it isn't valid UTF-8.
+    pub fn protocol_str(&self) -> Result<&str, DataChannelOpenError> {
+        utf8_field("protocol", &self.protocol)
+    }
+
+    /// CheckFieldLengths fails with `DataChannelOpenError::FieldTooLong`
+    /// if the label or protocol doesn't fit into its length field.
+    pub fn check_field_lengths(&self) -> Result<(), DataChannelOpenError> {
+        check_field_len("label", &self.label)?;
+        check_field_len("protocol", &self.protocol)
+    }
+
+    /// ToRef returns a borrowed view of the message.
+    pub fn to_ref(&self) -> DataChannelOpenRef<'_> {
+        DataChannelOpenRef {
+            channel_type: self.channel_type,
+            priority: self.priority,
+            reliability_parameter: self.reliability_parameter,
+            label: &self.label,
+            protocol: &self.protocol,
+        }
+    }
+}
+
+/// A way in which a `DataChannelOpen` does not conform to RFC 8832.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum Violation {
+    // The channel type is reserved, or defined by an extension unknown to this crate.
+    UnknownChannelType { channel_type: u8 },
+    // Reliable channels must have a reliability parameter of zero.
+    NonZeroReliabilityParameter { reliability_parameter: u32 },
+    // The label doesn't fit into the 16 bit length field.
+    LabelTooLong { len: usize },
+    // The protocol doesn't fit into the 16 bit length field.
+    ProtocolTooLong { len: usize },
+    // The label is not valid UTF-8.
+    LabelNotUtf8,
+    // The protocol is not valid UTF-8.
+    ProtocolNotUtf8,
+}
+
+impl DataChannelOpen {
+    /// Validate checks the message against the rules of RFC 8832,
+    /// returning every violation found, or an empty list if it conforms.
+    ///
+    /// Reserved channel types are already rejected by `unmarshal_from`,
+    /// unless preserved as `ChannelType::Unknown`.
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut violations = Vec::new();
+
+        if let ChannelType::Unknown(channel_type) = self.channel_type {
+            violations.push(Violation::UnknownChannelType { channel_type });
+        }
+
+        // https://www.rfc-editor.org/rfc/rfc8832.html#section-5.1
+        // For reliable channels this field MUST be set to 0 on the
+        // sending side and MUST be ignored on the receiving side.
+        let is_reliable = matches!(
+            self.channel_type,
+            ChannelType::Reliable | ChannelType::ReliableUnordered
+        );
+        if is_reliable && self.reliability_parameter != 0 {
+            violations.push(Violation::NonZeroReliabilityParameter {
+                reliability_parameter: self.reliability_parameter,
+            });
+        }
+
+        if self.label.len() > MAX_FIELD_LEN {
+            violations.push(Violation::LabelTooLong {
+                len: self.label.len(),
+            });
+        }
+        if self.protocol.len() > MAX_FIELD_LEN {
+            violations.push(Violation::ProtocolTooLong {
+                len: self.protocol.len(),
+            });
+        }
+
+        if str::from_utf8(&self.label).is_err() {
+            violations.push(Violation::LabelNotUtf8);
+        }
+        if str::from_utf8(&self.protocol).is_err() {
+            violations.push(Violation::ProtocolNotUtf8);
+        }
+
+        violations
+    }
+}
+
 impl MarshalSize for DataChannelOpen {
     fn marshal_size(&self) -> usize {
         let label_len = self.label.len();
@@ -53,18 +303,37 @@ impl Unmarshal for DataChannelOpen {
     type Error = DataChannelOpenError;
 
     fn unmarshal_from<B>(buf: &mut B) -> Result<Self, Self::Error>
+    where
+        B: Buf,
+    {
+        Self::unmarshal_body(buf, false)
+    }
+}
+
+impl DataChannelOpen {
+    fn unmarshal_body<B>(
+        buf: &mut B,
+        preserve_unknown_channel_types: bool,
+    ) -> Result<Self, DataChannelOpenError>
     where
         B: Buf,
     {
         let required_len = CHANNEL_OPEN_HEADER_LEN;
         if buf.remaining() < required_len {
-            return Err(Self::Error::UnexpectedEndOfBuffer {
+            return Err(DataChannelOpenError::UnexpectedEndOfBuffer {
                 expected: required_len,
                 actual: buf.remaining(),
             });
         }
 
-        let channel_type = ChannelType::unmarshal_from(buf)?;
+        let channel_type = match ChannelType::unmarshal_from(buf) {
+            Err(ChannelTypeError::InvalidChannelType { invalid_type })
+                if preserve_unknown_channel_types =>
+            {
+                ChannelType::Unknown(invalid_type)
+            }
+            result => result?,
+        };
         let priority = buf.get_u16();
         let reliability_parameter = buf.get_u32();
         let label_len = buf.get_u16() as usize;
@@ -72,17 +341,18 @@ impl Unmarshal for DataChannelOpen {
 
         let required_len = label_len + protocol_len;
         if buf.remaining() < required_len {
-            return Err(Self::Error::ExpectedAndActualLengthMismatch {
+            return Err(DataChannelOpenError::ExpectedAndActualLengthMismatch {
                 expected: required_len,
                 actual: buf.remaining(),
             });
         }
 
-        let mut label = vec![0; label_len];
-        let mut protocol = vec![0; protocol_len];
-
-        buf.copy_to_slice(&mut label[..]);
-        buf.copy_to_slice(&mut protocol[..]);
+        // Putting the fields chunk by chunk copies each exactly once, from
+        // chained buffers too, without zero-filling the Vecs first.
+        let mut label = Vec::with_capacity(label_len);
+        label.put(Buf::take(&mut *buf, label_len));
+        let mut protocol = Vec::with_capacity(protocol_len);
+        protocol.put(Buf::take(&mut *buf, protocol_len));
 
         Ok(Self {
             channel_type,
@@ -94,6 +364,31 @@ impl Unmarshal for DataChannelOpen {
     }
 }
 
+impl UnmarshalWith for DataChannelOpen {
+    fn unmarshal_from_with<B>(buf: &mut B, config: &UnmarshalConfig) -> Result<Self, Self::Error>
+    where
+        B: Buf,
+    {
+        let preserve_unknown_channel_types =
+            config.preserve_unknown_channel_types && !config.strict;
+        let open = Self::unmarshal_body(buf, preserve_unknown_channel_types)?;
+        if !config.strict {
+            return Ok(open);
+        }
+
+        if buf.has_remaining() {
+            return Err(Self::Error::TrailingBytes {
+                len: buf.remaining(),
+            });
+        }
+        if open.label.is_empty() {
+            return Err(Self::Error::EmptyLabel);
+        }
+
+        Ok(open)
+    }
+}
+
 impl Marshal for DataChannelOpen {
     type Error = DataChannelOpenError;
 
@@ -101,6 +396,8 @@ impl Marshal for DataChannelOpen {
     where
         B: BufMut,
     {
+        self.check_field_lengths()?;
+
         let required_len = self.marshal_size();
         if buf.remaining_mut() < required_len {
             return Err(Self::Error::UnexpectedEndOfBuffer {
@@ -109,14 +406,99 @@ impl Marshal for DataChannelOpen {
             });
         }
 
-        self.channel_type.marshal_to(buf)?;
-        buf.put_u16(self.priority);
-        buf.put_u32(self.reliability_parameter);
-        buf.put_u16(self.label.len() as u16);
-        buf.put_u16(self.protocol.len() as u16);
+        // Assemble the fixed-size header on the stack,
+        // so it can be written to `buf` in a single call:
+        let mut header = [0; CHANNEL_OPEN_HEADER_LEN];
+        {
+            let mut header = &mut header[..];
+            self.channel_type.marshal_to(&mut header)?;
+            header.put_u16(self.priority);
+            header.put_u32(self.reliability_parameter);
+            header.put_u16(self.label.len() as u16);
+            header.put_u16(self.protocol.len() as u16);
+        }
+
+        buf.put_slice(&header);
         buf.put_slice(self.label.as_slice());
         buf.put_slice(self.protocol.as_slice());
-        Ok(self.marshal_size())
+        Ok(required_len)
+    }
+}
+
+fn utf8_field<'a>(field: &'static str, value: &'a [u8]) -> Result<&'a str, DataChannelOpenError> {
+    str::from_utf8(value).map_err(|_| DataChannelOpenError::NotUtf8 { field })
+}
+
+fn check_field_len(field: &'static str, value: &[u8]) -> Result<(), DataChannelOpenError> {
+    if value.len() > MAX_FIELD_LEN {
+        return Err(DataChannelOpenError::FieldTooLong {
+            field,
+            len: value.len(),
+            max: MAX_FIELD_LEN,
+        });
+    }
+
+    Ok(())
+}
+
+/// DataChannelOpenBuilder assembles a `DataChannelOpen`,
+/// checking the lengths of its label and protocol on `build`.
+#[derive(Clone, Debug)]
+pub struct DataChannelOpenBuilder {
+    open: DataChannelOpen,
+}
+
+impl Default for DataChannelOpenBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataChannelOpenBuilder {
+    /// Creates a builder for a reliable channel without a label or protocol.
+    pub fn new() -> Self {
+        Self {
+            open: DataChannelOpen {
+                channel_type: ChannelType::Reliable,
+                priority: 0,
+                reliability_parameter: 0,
+                label: vec![],
+                protocol: vec![],
+            },
+        }
+    }
+
+    pub fn channel_type(mut self, channel_type: ChannelType) -> Self {
+        self.open.channel_type = channel_type;
+        self
+    }
+
+    pub fn priority(mut self, priority: u16) -> Self {
+        self.open.priority = priority;
+        self
+    }
+
+    pub fn reliability_parameter(mut self, reliability_parameter: u32) -> Self {
+        self.open.reliability_parameter = reliability_parameter;
+        self
+    }
+
+    pub fn label(mut self, label: impl Into<Vec<u8>>) -> Self {
+        self.open.label = label.into();
+        self
+    }
+
+    pub fn protocol(mut self, protocol: impl Into<Vec<u8>>) -> Self {
+        self.open.protocol = protocol.into();
+        self
+    }
+
+    /// Build returns the message, failing with `DataChannelOpenError::FieldTooLong`
+    /// if its label or protocol is longer than `MAX_FIELD_LEN` bytes.
+    pub fn build(&self) -> Result<DataChannelOpen, DataChannelOpenError> {
+        self.open.check_field_lengths()?;
+
+        Ok(self.open.clone())
     }
 }
 
@@ -207,8 +589,8 @@ mod tests {
             channel_type: ChannelType::Reliable,
             priority: 3893,
             reliability_parameter: 16715573,
-            label: b"label".iter().cloned().collect(),
-            protocol: b"protocol".iter().cloned().collect(),
+            label: b"label".to_vec(),
+            protocol: b"protocol".to_vec(),
         };
 
         let marshal_size = data_channel_open.marshal_size();
@@ -222,8 +604,8 @@ mod tests {
             channel_type: ChannelType::Reliable,
             priority: 3893,
             reliability_parameter: 16715573,
-            label: b"label".iter().cloned().collect(),
-            protocol: b"protocol".iter().cloned().collect(),
+            label: b"label".to_vec(),
+            protocol: b"protocol".to_vec(),
         };
 
         let mut buf = BytesMut::with_capacity(11 + 5 + 8);
@@ -233,4 +615,122 @@ mod tests {
         assert_eq!(bytes_written, data_channel_open.marshal_size());
         assert_eq!(&bytes[..], &MARSHALED_BYTES);
     }
+
+    #[test]
+    fn parse_ref_success() {
+        let open = DataChannelOpenRef::parse(&MARSHALED_BYTES).unwrap();
+
+        assert_eq!(open.label, b"label");
+        assert_eq!(open.protocol, b"protocol");
+
+        let mut bytes = Bytes::from_static(&MARSHALED_BYTES);
+        let expected = DataChannelOpen::unmarshal_from(&mut bytes).unwrap();
+        assert_eq!(open.into_owned(), expected);
+        assert_eq!(expected.to_ref(), open);
+    }
+
+    #[test]
+    fn parse_ref_unexpected_length_mismatch() {
+        let result = DataChannelOpenRef::parse(&MARSHALED_BYTES[..20]);
+
+        assert_eq!(
+            result,
+            Err(DataChannelOpenError::ExpectedAndActualLengthMismatch {
+                expected: 5 + 8,
+                actual: 9
+            })
+        );
+    }
+
+    #[test]
+    fn validate_success() {
+        let data_channel_open = DataChannelOpen {
+            channel_type: ChannelType::PartialReliableRexmit,
+            priority: 0,
+            reliability_parameter: 3,
+            label: b"label".to_vec(),
+            protocol: b"protocol".to_vec(),
+        };
+
+        assert_eq!(data_channel_open.validate(), vec![]);
+    }
+
+    #[test]
+    fn validate_violations() {
+        let mut bytes = Bytes::from_static(&MARSHALED_BYTES);
+        let mut data_channel_open = DataChannelOpen::unmarshal_from(&mut bytes).unwrap();
+        data_channel_open.protocol = vec![0xff, 0xfe];
+
+        assert_eq!(
+            data_channel_open.validate(),
+            vec![
+                Violation::NonZeroReliabilityParameter {
+                    reliability_parameter: 16715573
+                },
+                Violation::ProtocolNotUtf8,
+            ]
+        );
+    }
+
+    #[test]
+    fn marshal_rejects_overlong_fields() {
+        let data_channel_open = DataChannelOpen {
+            channel_type: ChannelType::Reliable,
+            priority: 0,
+            reliability_parameter: 0,
+            label: vec![b'a'; MAX_FIELD_LEN + 1],
+            protocol: vec![],
+        };
+
+        assert_eq!(
+            data_channel_open.marshal(),
+            Err(DataChannelOpenError::FieldTooLong {
+                field: "label",
+                len: MAX_FIELD_LEN + 1,
+                max: MAX_FIELD_LEN,
+            })
+        );
+    }
+
+    #[test]
+    fn builder_enforces_field_lengths() {
+        let builder = DataChannelOpenBuilder::new()
+            .channel_type(ChannelType::PartialReliableRexmit)
+            .reliability_parameter(3)
+            .label("chat");
+        let data_channel_open = builder.build().unwrap();
+        assert_eq!(data_channel_open.label, b"chat");
+        assert_eq!(data_channel_open.reliability_parameter, 3);
+
+        let result = builder.protocol(vec![0; MAX_FIELD_LEN + 1]).build();
+        assert_eq!(
+            result,
+            Err(DataChannelOpenError::FieldTooLong {
+                field: "protocol",
+                len: MAX_FIELD_LEN + 1,
+                max: MAX_FIELD_LEN,
+            })
+        );
+    }
+
+    #[test]
+    fn typed_accessors_and_strict_unmarshal() {
+        let mut bytes = Bytes::from_static(&MARSHALED_BYTES);
+        let data_channel_open = DataChannelOpen::unmarshal_strict(&mut bytes).unwrap();
+        assert_eq!(data_channel_open.label_str(), Ok("label"));
+        assert_eq!(data_channel_open.to_ref().protocol_str(), Ok("protocol"));
+
+        let mut data_channel_open = data_channel_open;
+        data_channel_open.protocol = vec![0xff, 0xfe];
+        let marshaled = data_channel_open.marshal().unwrap();
+        assert_eq!(
+            data_channel_open.protocol_str(),
+            Err(DataChannelOpenError::NotUtf8 { field: "protocol" })
+        );
+        assert!(DataChannelOpen::unmarshal_from(&mut marshaled.clone()).is_ok());
+        assert_eq!(
+            DataChannelOpen::unmarshal_strict(&mut marshaled.clone()),
+            Err(DataChannelOpenError::NotUtf8 { field: "protocol" })
+        );
+    }
 }
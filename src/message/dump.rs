@@ -0,0 +1,201 @@
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+use crate::{
+    channel_type::ChannelType,
+    marshal::Unmarshal,
+    message::MessageType,
+    wire::{MESSAGE_TYPE_ACK, MESSAGE_TYPE_OPEN},
+};
+
+// The number of bytes on each line of a dump.
+const DUMP_WIDTH: usize = 16;
+
+/// DebugDump returns an annotated hex dump of `bytes`, the payload of a
+/// DCEP SCTP message, naming the field of each run of bytes.
+///
+/// Malformed messages are dumped as far as they go, making the dump suited
+/// to finding out why a remote's message fails to parse.
+pub fn debug_dump(bytes: &[u8]) -> String {
+    let mut dump = Dump {
+        out: String::new(),
+        rest: bytes,
+    };
+    dump.message();
+
+    let rest = core::mem::take(&mut dump.rest);
+    if !rest.is_empty() {
+        dump.line(rest, format_args!("trailing bytes: {}", rest.len()));
+    }
+
+    dump.out
+}
+
+struct Dump<'a> {
+    out: String,
+    rest: &'a [u8],
+}
+
+impl<'a> Dump<'a> {
+    fn message(&mut self) -> Option<()> {
+        let message_type = self.take("message type", 1)?[0];
+        let bytes = [message_type];
+        match message_type {
+            MESSAGE_TYPE_ACK => self.line(
+                &bytes,
+                format_args!("message type: {}", MessageType::DataChannelAck),
+            ),
+            MESSAGE_TYPE_OPEN => {
+                self.line(
+                    &bytes,
+                    format_args!("message type: {}", MessageType::DataChannelOpen),
+                );
+                self.open()?;
+            }
+            _ => {
+                self.line(
+                    &bytes,
+                    format_args!("message type: unknown ({:#04x})", message_type),
+                );
+                let rest = core::mem::take(&mut self.rest);
+                self.line(rest, format_args!("payload"));
+            }
+        }
+
+        Some(())
+    }
+
+    fn open(&mut self) -> Option<()> {
+        let bytes = self.take("channel type", 1)?;
+        let channel_type =
+            ChannelType::unmarshal_from(&mut &bytes[..]).unwrap_or(ChannelType::Unknown(bytes[0]));
+        self.line(bytes, format_args!("channel type: {}", channel_type));
+
+        let bytes = self.take("priority", 2)?;
+        self.line(
+            bytes,
+            format_args!("priority: {}", u16::from_be_bytes([bytes[0], bytes[1]])),
+        );
+
+        let bytes = self.take("reliability parameter", 4)?;
+        let reliability_parameter = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        self.line(
+            bytes,
+            format_args!("reliability parameter: {}", reliability_parameter),
+        );
+
+        let bytes = self.take("label length", 2)?;
+        let label_len = u16::from_be_bytes([bytes[0], bytes[1]]);
+        self.line(bytes, format_args!("label length: {}", label_len));
+
+        let bytes = self.take("protocol length", 2)?;
+        let protocol_len = u16::from_be_bytes([bytes[0], bytes[1]]);
+        self.line(bytes, format_args!("protocol length: {}", protocol_len));
+
+        let bytes = self.take("label", label_len as usize)?;
+        self.line(bytes, format_args!("label: {}", Quoted(bytes)));
+
+        let bytes = self.take("protocol", protocol_len as usize)?;
+        self.line(bytes, format_args!("protocol: {}", Quoted(bytes)));
+
+        Some(())
+    }
+
+    // Takes the next `len` bytes, or else dumps those left of the
+    // truncated field and returns `None`.
+    fn take(&mut self, field: &str, len: usize) -> Option<&'a [u8]> {
+        if self.rest.len() < len {
+            let rest = core::mem::take(&mut self.rest);
+            self.line(
+                rest,
+                format_args!("{}: truncated, {} of {} bytes", field, rest.len(), len),
+            );
+            return None;
+        }
+
+        let (bytes, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Some(bytes)
+    }
+
+    // Writes `bytes` in hex, annotating their first line.
+    fn line(&mut self, bytes: &[u8], annotation: fmt::Arguments<'_>) {
+        let mut chunks = bytes.chunks(DUMP_WIDTH);
+        let first = chunks.next().unwrap_or_default();
+
+        let mut hex = String::new();
+        write_hex(&mut hex, first);
+        // Writing into a `String` never fails.
+        let _ = writeln!(
+            self.out,
+            "{:<width$}  {}",
+            hex,
+            annotation,
+            width = DUMP_WIDTH * 3 - 1
+        );
+        for chunk in chunks {
+            write_hex(&mut self.out, chunk);
+            self.out.push('\n');
+        }
+    }
+}
+
+fn write_hex(out: &mut String, bytes: &[u8]) {
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        let _ = write!(out, "{:02x}", byte);
+    }
+}
+
+// Displays a label or protocol as a quoted string,
+// or as escaped bytes if it isn't valid UTF-8.
+pub(super) struct Quoted<'a>(pub(super) &'a [u8]);
+
+impl fmt::Display for Quoted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match core::str::from_utf8(self.0) {
+            Ok(value) => write!(f, "{:?}", value),
+            Err(_) => write!(f, "b\"{}\"", self.0.escape_ascii()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dumps_open() {
+        let bytes = [
+            0x03, // message type
+            0x80, // channel type
+            0x01, 0x00, // priority
+            0x00, 0x00, 0x00, 0x00, // reliability parameter
+            0x00, 0x04, // label length
+            0x00, 0x00, // protocol length
+            0x63, 0x68, 0x61, 0x74, // label
+            0xff, // trailing
+        ];
+
+        let expected = [
+            "03                                               message type: DATA_CHANNEL_OPEN",
+            "80                                               channel type: reliable_unordered",
+            "01 00                                            priority: 256",
+            "00 00 00 00                                      reliability parameter: 0",
+            "00 04                                            label length: 4",
+            "00 00                                            protocol length: 0",
+            "63 68 61 74                                      label: \"chat\"",
+            "                                                 protocol: \"\"",
+            "ff                                               trailing bytes: 1",
+        ];
+        let dump = debug_dump(&bytes);
+        assert_eq!(
+            dump.lines().map(str::trim_end).collect::<Vec<_>>(),
+            expected
+        );
+
+        assert!(debug_dump(&bytes[..11]).ends_with("protocol length: truncated, 1 of 2 bytes\n"));
+    }
+}
@@ -1,23 +1,34 @@
+use core::fmt;
+
 use bytes::{Buf, BufMut};
 
 use crate::{
     error::MessageTypeError,
     marshal::{Marshal, MarshalSize, Unmarshal},
+    wire::{MESSAGE_TYPE_ACK, MESSAGE_TYPE_OPEN},
 };
 
-// The first byte in a `Message` that specifies its type:
-const MESSAGE_TYPE_ACK: u8 = 0x02;
-const MESSAGE_TYPE_OPEN: u8 = 0x03;
-
-const MESSAGE_TYPE_LEN: usize = 1;
+pub(crate) const MESSAGE_TYPE_LEN: usize = 1;
 
 // A parsed DataChannel message
 #[derive(Eq, PartialEq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum MessageType {
     DataChannelAck,
     DataChannelOpen,
 }
 
+impl fmt::Display for MessageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DataChannelAck => f.write_str("DATA_CHANNEL_ACK"),
+            Self::DataChannelOpen => f.write_str("DATA_CHANNEL_OPEN"),
+        }
+    }
+}
+
 impl MarshalSize for MessageType {
     fn marshal_size(&self) -> usize {
         MESSAGE_TYPE_LEN
@@ -56,6 +67,13 @@ impl Marshal for MessageType {
     where
         B: BufMut,
     {
+        if buf.remaining_mut() < MESSAGE_TYPE_LEN {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: MESSAGE_TYPE_LEN,
+                actual: buf.remaining_mut(),
+            });
+        }
+
         let byte = match self {
             MessageType::DataChannelAck => MESSAGE_TYPE_ACK,
             MessageType::DataChannelOpen => MESSAGE_TYPE_OPEN,
@@ -0,0 +1,126 @@
+use bytes::{Buf, BufMut, Bytes};
+
+use crate::{
+    error::MessageError,
+    marshal::{Marshal, MarshalSize, Unmarshal},
+};
+
+const RAW_MESSAGE_HEADER_LEN: usize = 1;
+
+/// A DCEP message of any type, with its body left unparsed.
+///
+/// Unlike `Message`, unmarshaling a `RawMessage` accepts unknown
+/// message types, which makes it suitable for handling experimental
+/// extensions to DCEP.
+///
+/// # Memory layout
+///
+/// ```plain
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |  Message Type |                                               |
+/// +-+-+-+-+-+-+-+-+                                               +
+/// |                            Payload                            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct RawMessage {
+    pub message_type: u8,
+    pub payload: Bytes,
+}
+
+impl MarshalSize for RawMessage {
+    fn marshal_size(&self) -> usize {
+        RAW_MESSAGE_HEADER_LEN + self.payload.len()
+    }
+}
+
+impl Unmarshal for RawMessage {
+    type Error = MessageError;
+
+    fn unmarshal_from<B>(buf: &mut B) -> Result<Self, Self::Error>
+    where
+        B: Buf,
+    {
+        let required_len = RAW_MESSAGE_HEADER_LEN;
+        if buf.remaining() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining(),
+            });
+        }
+
+        let message_type = buf.get_u8();
+        let payload = buf.copy_to_bytes(buf.remaining());
+
+        Ok(Self {
+            message_type,
+            payload,
+        })
+    }
+}
+
+impl Marshal for RawMessage {
+    type Error = MessageError;
+
+    fn marshal_to<B>(&self, buf: &mut B) -> Result<usize, Self::Error>
+    where
+        B: BufMut,
+    {
+        let required_len = self.marshal_size();
+        if buf.remaining_mut() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining_mut(),
+            });
+        }
+
+        buf.put_u8(self.message_type);
+        buf.put_slice(&self.payload[..]);
+
+        Ok(required_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unmarshal_success() {
+        let mut bytes = Bytes::from_static(&[0x42, 0x01, 0x02]);
+
+        let raw_message = RawMessage::unmarshal_from(&mut bytes).unwrap();
+
+        assert_eq!(raw_message.message_type, 0x42);
+        assert_eq!(&raw_message.payload[..], &[0x01, 0x02]);
+    }
+
+    #[test]
+    fn unmarshal_unexpected_end_of_buffer() {
+        let mut bytes = Bytes::new();
+
+        let result = RawMessage::unmarshal_from(&mut bytes);
+
+        assert_eq!(
+            result,
+            Err(MessageError::UnexpectedEndOfBuffer {
+                expected: 1,
+                actual: 0
+            })
+        );
+    }
+
+    #[test]
+    fn marshal() {
+        let raw_message = RawMessage {
+            message_type: 0x42,
+            payload: Bytes::from_static(&[0x01, 0x02]),
+        };
+
+        let bytes = raw_message.marshal().unwrap();
+
+        assert_eq!(&bytes[..], &[0x42, 0x01, 0x02]);
+    }
+}
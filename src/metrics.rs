@@ -0,0 +1,115 @@
+//! DataChannel-level metrics, recorded through the `metrics` crate facade.
+//!
+//! With the `metrics` feature enabled, channels record the metrics named
+//! below with whichever recorder the application installed, such as a
+//! Prometheus exporter. Without a recorder, recording does nothing.
+//! All metrics but `UNMARSHAL_ERRORS` carry the channel's label.
+
+use std::time::Duration;
+
+use metrics::{describe_counter, describe_gauge, describe_histogram, Unit};
+
+use crate::{data_channel::ReadyState, observer::Operation, runtime::TaskKind};
+
+/// The number of channels currently open.
+pub const CHANNELS_OPEN: &str = "data_channel.channels_open";
+/// The time from creating a channel to completing its DCEP handshake.
+pub const DCEP_HANDSHAKE_DURATION: &str = "data_channel.dcep_handshake_duration";
+/// The number of payload bytes received.
+pub const BYTES_IN: &str = "data_channel.bytes_in";
+/// The number of payload bytes sent.
+pub const BYTES_OUT: &str = "data_channel.bytes_out";
+/// The number of DCEP messages which failed to unmarshal.
+pub const UNMARSHAL_ERRORS: &str = "data_channel.unmarshal_errors";
+/// The number of reads waiting for a message, including pending polled reads.
+pub const READS_IN_FLIGHT: &str = "data_channel.reads_in_flight";
+/// The number of writes waiting for the stream, including pending polled writes.
+pub const WRITES_IN_FLIGHT: &str = "data_channel.writes_in_flight";
+/// The number of background tasks running, also labelled with their `TaskKind`.
+pub const TASKS: &str = "data_channel.tasks";
+
+/// Describe registers the units and descriptions of the crate's metrics
+/// with the installed recorder. Call it once the recorder is installed.
+pub fn describe() {
+    describe_gauge!(CHANNELS_OPEN, Unit::Count, "Number of open data channels");
+    describe_histogram!(
+        DCEP_HANDSHAKE_DURATION,
+        Unit::Seconds,
+        "Time taken by the DCEP handshake of data channels"
+    );
+    describe_counter!(
+        BYTES_IN,
+        Unit::Bytes,
+        "Number of payload bytes received over data channels"
+    );
+    describe_counter!(
+        BYTES_OUT,
+        Unit::Bytes,
+        "Number of payload bytes sent over data channels"
+    );
+    describe_counter!(
+        UNMARSHAL_ERRORS,
+        Unit::Count,
+        "Number of DCEP messages which failed to unmarshal"
+    );
+    describe_gauge!(
+        READS_IN_FLIGHT,
+        Unit::Count,
+        "Number of reads waiting for a message on data channels"
+    );
+    describe_gauge!(
+        WRITES_IN_FLIGHT,
+        Unit::Count,
+        "Number of writes waiting to be taken by the stream of data channels"
+    );
+    describe_gauge!(
+        TASKS,
+        Unit::Count,
+        "Number of background tasks running for data channels"
+    );
+}
+
+/// Records a channel's transition between ready states, given the time
+/// since its creation. `handshake` tells whether the transition to open
+/// completes a DCEP handshake.
+pub(crate) fn record_transition(
+    label: &str,
+    previous: ReadyState,
+    ready_state: ReadyState,
+    handshake: bool,
+    elapsed: Duration,
+) {
+    if ready_state == ReadyState::Open {
+        metrics::gauge!(CHANNELS_OPEN, "label" => label.to_owned()).increment(1.0);
+        if handshake {
+            metrics::histogram!(DCEP_HANDSHAKE_DURATION, "label" => label.to_owned())
+                .record(elapsed.as_secs_f64());
+        }
+    } else if previous == ReadyState::Open {
+        metrics::gauge!(CHANNELS_OPEN, "label" => label.to_owned()).decrement(1.0);
+    }
+}
+
+pub(crate) fn record_sent(label: &str, bytes_len: usize) {
+    metrics::counter!(BYTES_OUT, "label" => label.to_owned()).increment(bytes_len as u64);
+}
+
+pub(crate) fn record_received(label: &str, bytes_len: usize) {
+    metrics::counter!(BYTES_IN, "label" => label.to_owned()).increment(bytes_len as u64);
+}
+
+pub(crate) fn record_in_flight(label: &str, operation: Operation, delta: f64) {
+    let name = match operation {
+        Operation::Read => READS_IN_FLIGHT,
+        Operation::Write => WRITES_IN_FLIGHT,
+    };
+    metrics::gauge!(name, "label" => label.to_owned()).increment(delta);
+}
+
+pub(crate) fn record_task(label: &str, kind: TaskKind, delta: f64) {
+    metrics::gauge!(TASKS, "label" => label.to_owned(), "kind" => kind.as_str()).increment(delta);
+}
+
+pub(crate) fn record_unmarshal_error() {
+    metrics::counter!(UNMARSHAL_ERRORS).increment(1);
+}
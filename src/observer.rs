@@ -0,0 +1,356 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    time::SystemTime,
+};
+
+#[cfg(feature = "metrics")]
+use crate::metrics;
+use crate::{
+    data_channel::ReadyState, event_log::EventRecord, label::Label, runtime::TaskKind,
+    stats::DataChannelStats,
+};
+
+/// The part of a channel's state shared with its observers.
+#[derive(Debug)]
+pub(crate) struct Observed {
+    ready_state: Mutex<ReadyState>,
+    subscribers: Mutex<Vec<Sender<EventRecord>>>,
+    errors: AtomicUsize,
+    opened_at: Mutex<Option<SystemTime>>,
+    reads_in_flight: AtomicUsize,
+    writes_in_flight: AtomicUsize,
+    // The background tasks running for the channel, by `TaskKind`.
+    tasks: [AtomicUsize; 3],
+}
+
+impl Observed {
+    pub(crate) fn new(ready_state: ReadyState) -> Self {
+        Self {
+            ready_state: Mutex::new(ready_state),
+            subscribers: Mutex::new(Vec::new()),
+            errors: AtomicUsize::new(0),
+            opened_at: Mutex::new(None),
+            reads_in_flight: AtomicUsize::new(0),
+            writes_in_flight: AtomicUsize::new(0),
+            tasks: Default::default(),
+        }
+    }
+
+    pub(crate) fn set_ready_state(&self, ready_state: ReadyState) {
+        *self.ready_state.lock().unwrap() = ready_state;
+        if ready_state == ReadyState::Open {
+            self.opened_at
+                .lock()
+                .unwrap()
+                .get_or_insert_with(SystemTime::now);
+        }
+    }
+
+    pub(crate) fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn errors(&self) -> usize {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn opened_at(&self) -> Option<SystemTime> {
+        *self.opened_at.lock().unwrap()
+    }
+
+    pub(crate) fn in_flight(&self, operation: Operation) -> usize {
+        self.in_flight_counter(operation).load(Ordering::Relaxed)
+    }
+
+    fn in_flight_counter(&self, operation: Operation) -> &AtomicUsize {
+        match operation {
+            Operation::Read => &self.reads_in_flight,
+            Operation::Write => &self.writes_in_flight,
+        }
+    }
+
+    pub(crate) fn tasks(&self, kind: TaskKind) -> usize {
+        self.tasks_counter(kind).load(Ordering::Relaxed)
+    }
+
+    fn tasks_counter(&self, kind: TaskKind) -> &AtomicUsize {
+        match kind {
+            TaskKind::Reader => &self.tasks[0],
+            TaskKind::Writer => &self.tasks[1],
+            TaskKind::Timer => &self.tasks[2],
+        }
+    }
+
+    pub(crate) fn has_subscribers(&self) -> bool {
+        !self.subscribers.lock().unwrap().is_empty()
+    }
+
+    pub(crate) fn publish(&self, record: &EventRecord) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|subscriber| subscriber.send(record.clone()).is_ok());
+    }
+}
+
+/// What an `InFlight` counts.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub(crate) enum Operation {
+    Read,
+    Write,
+}
+
+/// Counts a read or write on a channel as in flight for as long as it exists,
+/// in the channel's stats and, with the `metrics` feature, its metrics.
+#[derive(Debug)]
+pub(crate) struct InFlight {
+    observed: Arc<Observed>,
+    operation: Operation,
+    #[cfg(feature = "metrics")]
+    label: Label,
+}
+
+impl InFlight {
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    pub(crate) fn new(observed: Arc<Observed>, operation: Operation, label: &Label) -> Self {
+        observed
+            .in_flight_counter(operation)
+            .fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        metrics::record_in_flight(label.as_str(), operation, 1.0);
+
+        Self {
+            observed,
+            operation,
+            #[cfg(feature = "metrics")]
+            label: label.clone(),
+        }
+    }
+
+    pub(crate) fn operation(&self) -> Operation {
+        self.operation
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.observed
+            .in_flight_counter(self.operation)
+            .fetch_sub(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        metrics::record_in_flight(self.label.as_str(), self.operation, -1.0);
+    }
+}
+
+/// Counts a background task of a channel as running for as long as it exists,
+/// in the channel's stats and, with the `metrics` feature, its metrics.
+#[derive(Debug)]
+pub(crate) struct RunningTask {
+    observed: Arc<Observed>,
+    kind: TaskKind,
+    #[cfg(feature = "metrics")]
+    label: Label,
+}
+
+impl RunningTask {
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    pub(crate) fn new(observed: Arc<Observed>, kind: TaskKind, label: &Label) -> Self {
+        observed.tasks_counter(kind).fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        metrics::record_task(label.as_str(), kind, 1.0);
+
+        Self {
+            observed,
+            kind,
+            #[cfg(feature = "metrics")]
+            label: label.clone(),
+        }
+    }
+}
+
+impl Drop for RunningTask {
+    fn drop(&mut self) {
+        self.observed
+            .tasks_counter(self.kind)
+            .fetch_sub(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        metrics::record_task(self.label.as_str(), self.kind, -1.0);
+    }
+}
+
+/// Observer is a read-only handle to a `DataChannel`, for monitoring it
+/// from elsewhere without being able to send or consume messages.
+///
+/// Observers stay valid after the channel has been dropped,
+/// reporting the state it was last in.
+#[derive(Clone, Debug)]
+pub struct Observer {
+    pub(crate) stream_identifier: u16,
+    pub(crate) label: Label,
+    pub(crate) messages_sent: Arc<AtomicUsize>,
+    pub(crate) messages_received: Arc<AtomicUsize>,
+    pub(crate) bytes_sent: Arc<AtomicUsize>,
+    pub(crate) bytes_received: Arc<AtomicUsize>,
+    pub(crate) observed: Arc<Observed>,
+}
+
+impl Observer {
+    pub fn stream_identifier(&self) -> u16 {
+        self.stream_identifier
+    }
+
+    pub fn label(&self) -> &Label {
+        &self.label
+    }
+
+    pub fn ready_state(&self) -> ReadyState {
+        *self.observed.ready_state.lock().unwrap()
+    }
+
+    pub fn messages_sent(&self) -> usize {
+        self.messages_sent.load(Ordering::Relaxed)
+    }
+
+    pub fn messages_received(&self) -> usize {
+        self.messages_received.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_sent(&self) -> usize {
+        self.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_received(&self) -> usize {
+        self.bytes_received.load(Ordering::Relaxed)
+    }
+
+    /// ReadsInFlight returns the number of reads waiting for a message,
+    /// including polled reads which are pending.
+    pub fn reads_in_flight(&self) -> usize {
+        self.observed.in_flight(Operation::Read)
+    }
+
+    /// WritesInFlight returns the number of writes waiting for the stream
+    /// to take their message, including polled writes which are pending.
+    pub fn writes_in_flight(&self) -> usize {
+        self.observed.in_flight(Operation::Write)
+    }
+
+    /// Tasks returns the number of background tasks of the given kind
+    /// running for the channel.
+    pub fn tasks(&self, kind: TaskKind) -> usize {
+        self.observed.tasks(kind)
+    }
+
+    /// Stats returns a snapshot of the channel's counters.
+    pub fn stats(&self) -> DataChannelStats {
+        DataChannelStats {
+            messages_sent: self.messages_sent(),
+            messages_received: self.messages_received(),
+            bytes_sent: self.bytes_sent(),
+            bytes_received: self.bytes_received(),
+            errors: self.observed.errors(),
+            opened_at: self.observed.opened_at(),
+            reads_in_flight: self.reads_in_flight(),
+            writes_in_flight: self.writes_in_flight(),
+            tasks: TaskKind::ALL.iter().map(|kind| self.tasks(*kind)).sum(),
+        }
+    }
+
+    /// Subscribe returns a receiver for the channel's lifecycle events from here on.
+    pub fn subscribe(&self) -> Receiver<EventRecord> {
+        let (sender, receiver) = mpsc::channel();
+        self.observed.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        task::{Context, Waker},
+        thread,
+    };
+
+    use bytes::{Bytes, BytesMut};
+
+    use crate::{data_channel::Config, event_log::EventKind, testing};
+
+    use super::*;
+
+    #[test]
+    fn observer_follows_channel() {
+        let (mut dialer, mut acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        let observer = acceptor.observer();
+        let events = dialer.observer().subscribe();
+
+        dialer
+            .write_data_channel(&mut Bytes::from_static(b"hello"), false)
+            .unwrap();
+        let mut buf = BytesMut::with_capacity(1024);
+        acceptor.read_data_channel(&mut buf).unwrap();
+
+        assert_eq!(observer.ready_state(), ReadyState::Open);
+        assert_eq!(observer.messages_received(), 1);
+        assert_eq!(observer.bytes_received(), 5);
+
+        dialer.close().unwrap();
+        assert_eq!(observer.ready_state(), ReadyState::Open);
+
+        assert!(acceptor.read_data_channel(&mut buf).is_err());
+        dialer.wait_closed().unwrap();
+        drop(acceptor);
+
+        assert_eq!(observer.ready_state(), ReadyState::Closed);
+        let kinds: Vec<_> = events.try_iter().map(|record| record.kind).collect();
+        // The dialer read the ACK on its way to the remote's reset.
+        assert_eq!(
+            kinds,
+            vec![
+                EventKind::CloseSent,
+                EventKind::AckReceived,
+                EventKind::Closed
+            ]
+        );
+    }
+    #[test]
+    fn observer_counts_reads_in_flight() {
+        let (mut dialer, mut acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        let observer = acceptor.observer();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        assert!(acceptor.poll_read_bytes(&mut cx).is_pending());
+        assert!(acceptor.poll_read_bytes(&mut cx).is_pending());
+        assert_eq!(observer.reads_in_flight(), 1);
+        dialer.write(&mut Bytes::from_static(b"a")).unwrap();
+        assert!(acceptor.poll_read_bytes(&mut cx).is_ready());
+        assert_eq!(observer.stats().reads_in_flight, 0);
+
+        let reader = thread::spawn(move || {
+            acceptor.read_bytes().unwrap();
+            acceptor
+        });
+        while observer.reads_in_flight() == 0 {
+            thread::yield_now();
+        }
+        dialer.write(&mut Bytes::from_static(b"b")).unwrap();
+        let mut acceptor = reader.join().unwrap();
+        assert_eq!(observer.reads_in_flight(), 0);
+
+        let mut messages = vec![];
+        dialer.write(&mut Bytes::from_static(b"c")).unwrap();
+        acceptor.read_batch(&mut messages, 2).unwrap();
+        assert_eq!(observer.reads_in_flight(), 0);
+        let mut buf = BytesMut::new();
+        let timeout = std::time::Duration::from_millis(1);
+        assert!(acceptor.read_timeout(&mut buf, timeout).is_err());
+        assert_eq!(observer.reads_in_flight(), 0);
+
+        // Dropping a channel ends its pending reads.
+        assert!(acceptor.poll_read_bytes(&mut cx).is_pending());
+        drop(acceptor);
+        assert_eq!(observer.reads_in_flight(), 0);
+        assert_eq!(observer.writes_in_flight(), 0);
+    }
+}
@@ -0,0 +1,15 @@
+//! An opt-in ordering layer for unordered data channels.
+//!
+//! An `OrderingChannel` numbers outgoing messages and, on the receiving end,
+//! puts incoming messages back into order within a bounded window. Rather
+//! than waiting for a missing message indefinitely, as an ordered channel
+//! would, it skips over it once the window is exceeded and reports the gap,
+//! giving "mostly ordered, never blocking" delivery.
+
+mod channel;
+mod reorder_buffer;
+mod sequenced_frame;
+
+pub use channel::OrderingChannel;
+pub use reorder_buffer::{Delivery, ReorderBuffer};
+pub use sequenced_frame::SequencedFrame;
@@ -0,0 +1,85 @@
+use bytes::Bytes;
+
+use crate::{
+    data_channel::{DataChannel, RECEIVE_MTU},
+    error::DataChannelError,
+    marshal::{Marshal, Unmarshal},
+    ordering::{Delivery, ReorderBuffer, SequencedFrame},
+};
+
+/// OrderingChannel sends sequenced messages over a data channel, typically
+/// an unordered one, and reorders the messages it receives within a window.
+pub struct OrderingChannel {
+    data_channel: DataChannel,
+    next_sequence: u64,
+    reorder_buffer: ReorderBuffer,
+}
+
+impl OrderingChannel {
+    /// Creates an ordering channel holding back at most `window` sequence
+    /// numbers' worth of messages behind a missing one.
+    pub fn new(data_channel: DataChannel, window: usize) -> Self {
+        Self {
+            data_channel,
+            next_sequence: 0,
+            reorder_buffer: ReorderBuffer::new(window),
+        }
+    }
+
+    /// Write sends a message, returning its sequence number.
+    pub fn write(&mut self, payload: Bytes, is_string: bool) -> Result<u64, DataChannelError> {
+        let sequence = self.next_sequence;
+        let frame = SequencedFrame {
+            sequence,
+            is_string,
+            payload,
+        };
+
+        let mut bytes = frame.marshal()?;
+        self.data_channel.write_data_channel(&mut bytes, false)?;
+
+        self.next_sequence += 1;
+
+        Ok(sequence)
+    }
+
+    /// Read blocks until the next message or gap is due for delivery.
+    pub fn read(&mut self) -> Result<Delivery, DataChannelError> {
+        loop {
+            if let Some(delivery) = self.reorder_buffer.pop() {
+                return Ok(delivery);
+            }
+
+            let frame = self.read_frame()?;
+            self.reorder_buffer
+                .push(frame.sequence, frame.is_string, frame.payload);
+        }
+    }
+
+    /// Flush makes all messages held back behind missing ones due for
+    /// delivery, e.g. once the remote is known to have stopped sending.
+    pub fn flush(&mut self) {
+        self.reorder_buffer.flush()
+    }
+
+    pub fn reorder_buffer(&self) -> &ReorderBuffer {
+        &self.reorder_buffer
+    }
+
+    pub fn into_inner(self) -> DataChannel {
+        self.data_channel
+    }
+
+    fn read_frame(&mut self) -> Result<SequencedFrame, DataChannelError> {
+        let buffer_provider = self.data_channel.config.buffer_provider.clone();
+        let mut buf = buffer_provider.acquire(RECEIVE_MTU);
+        let result = self
+            .data_channel
+            .read(&mut buf)
+            .map(|n| buf.split_to(n).freeze());
+        buffer_provider.release(buf);
+
+        let mut bytes = result?;
+        SequencedFrame::unmarshal_from(&mut bytes).map_err(From::from)
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use bytes::Bytes;
+
+/// An item delivered by a `ReorderBuffer`, in sequence order.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum Delivery {
+    Message {
+        sequence: u64,
+        is_string: bool,
+        payload: Bytes,
+    },
+    /// The messages numbered `first..=last` were skipped, as they
+    /// didn't arrive in time. Any of them arriving later is dropped.
+    Gap { first: u64, last: u64 },
+}
+
+/// ReorderBuffer puts sequenced messages back into order, holding back at
+/// most `window` sequence numbers' worth of messages behind a missing one.
+#[derive(Debug)]
+pub struct ReorderBuffer {
+    window: u64,
+    next: u64,
+    buffered: BTreeMap<u64, (Bytes, bool)>,
+    ready: VecDeque<Delivery>,
+    late: u64,
+}
+
+impl ReorderBuffer {
+    /// Creates a buffer expecting sequence numbers to start at zero.
+    ///
+    /// A `window` of zero is treated like a window of one,
+    /// which delivers messages in arrival order.
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1) as u64,
+            next: 0,
+            buffered: BTreeMap::new(),
+            ready: VecDeque::new(),
+            late: 0,
+        }
+    }
+
+    /// Push adds a message. Messages arriving after their sequence
+    /// number has been delivered or skipped are dropped.
+    pub fn push(&mut self, sequence: u64, is_string: bool, payload: Bytes) {
+        if sequence < self.next {
+            log::debug!("Dropping late message {}", sequence);
+            self.late += 1;
+            return;
+        }
+
+        if sequence - self.next >= self.window {
+            self.advance_to(sequence - self.window + 1);
+        }
+
+        self.buffered
+            .entry(sequence)
+            .or_insert((payload, is_string));
+
+        while let Some((payload, is_string)) = self.buffered.remove(&self.next) {
+            self.deliver(self.next, is_string, payload);
+        }
+    }
+
+    /// Flush delivers all buffered messages, reporting the gaps between them.
+    pub fn flush(&mut self) {
+        if let Some(last) = self.buffered.keys().next_back().copied() {
+            self.advance_to(last + 1);
+        }
+    }
+
+    /// Pop returns the next item due for delivery, if any.
+    pub fn pop(&mut self) -> Option<Delivery> {
+        self.ready.pop_front()
+    }
+
+    /// Buffered returns the number of messages held back behind a missing one.
+    pub fn buffered(&self) -> usize {
+        self.buffered.len()
+    }
+
+    /// Late returns the number of messages dropped for arriving too late.
+    pub fn late(&self) -> u64 {
+        self.late
+    }
+
+    // Delivers everything buffered below `next`, skipping what's missing.
+    fn advance_to(&mut self, next: u64) {
+        while let Some(entry) = self.buffered.first_entry() {
+            if *entry.key() >= next {
+                break;
+            }
+
+            let (sequence, (payload, is_string)) = entry.remove_entry();
+            if sequence > self.next {
+                self.skip_to(sequence);
+            }
+            self.deliver(sequence, is_string, payload);
+        }
+
+        if next > self.next {
+            self.skip_to(next);
+        }
+    }
+
+    fn skip_to(&mut self, next: u64) {
+        self.ready.push_back(Delivery::Gap {
+            first: self.next,
+            last: next - 1,
+        });
+        self.next = next;
+    }
+
+    fn deliver(&mut self, sequence: u64, is_string: bool, payload: Bytes) {
+        self.ready.push_back(Delivery::Message {
+            sequence,
+            is_string,
+            payload,
+        });
+        self.next = sequence + 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(buffer: &mut ReorderBuffer) -> Vec<String> {
+        std::iter::from_fn(|| buffer.pop())
+            .map(|delivery| match delivery {
+                Delivery::Message { sequence, .. } => sequence.to_string(),
+                Delivery::Gap { first, last } => format!("gap {}..={}", first, last),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reorders_within_window() {
+        let mut buffer = ReorderBuffer::new(4);
+
+        buffer.push(1, false, Bytes::new());
+        buffer.push(2, false, Bytes::new());
+        assert_eq!(drain(&mut buffer), Vec::<String>::new());
+        assert_eq!(buffer.buffered(), 2);
+
+        buffer.push(0, false, Bytes::new());
+        assert_eq!(drain(&mut buffer), vec!["0", "1", "2"]);
+    }
+
+    #[test]
+    fn skips_gaps_beyond_window() {
+        let mut buffer = ReorderBuffer::new(2);
+
+        buffer.push(0, false, Bytes::new());
+        buffer.push(2, false, Bytes::new());
+        buffer.push(3, false, Bytes::new());
+        assert_eq!(drain(&mut buffer), vec!["0", "gap 1..=1", "2", "3"]);
+
+        buffer.push(1, false, Bytes::new());
+        assert_eq!(buffer.late(), 1);
+
+        buffer.push(9, false, Bytes::new());
+        assert_eq!(drain(&mut buffer), vec!["gap 4..=7"]);
+    }
+
+    #[test]
+    fn flush_delivers_buffered_messages() {
+        let mut buffer = ReorderBuffer::new(8);
+
+        buffer.push(2, true, Bytes::from_static(b"two"));
+        buffer.push(4, false, Bytes::new());
+        buffer.flush();
+
+        assert_eq!(drain(&mut buffer), vec!["gap 0..=1", "2", "gap 3..=3", "4"]);
+        assert_eq!(buffer.buffered(), 0);
+    }
+}
@@ -0,0 +1,143 @@
+use bytes::{Buf, BufMut, Bytes};
+
+use crate::{
+    error::SequencedFrameError,
+    marshal::{Marshal, MarshalSize, Unmarshal},
+};
+
+const FLAG_IS_STRING: u8 = 0b0000_0001;
+
+const SEQUENCED_FRAME_HEADER_LEN: usize = 9;
+
+/// A message carrying its sequence number, with each
+/// data channel message carrying exactly one frame.
+///
+/// # Memory layout
+///
+/// ```plain
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |     Flags     |                                               |
+/// +-+-+-+-+-+-+-+-+                                               +
+/// |                        Sequence Number                        |
+/// +               +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |               |                                               |
+/// +-+-+-+-+-+-+-+-+                                               +
+/// |                            Payload                            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+///
+/// Bit 0 of the flags is set for text messages.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct SequencedFrame {
+    pub sequence: u64,
+    pub is_string: bool,
+    pub payload: Bytes,
+}
+
+impl MarshalSize for SequencedFrame {
+    fn marshal_size(&self) -> usize {
+        SEQUENCED_FRAME_HEADER_LEN + self.payload.len()
+    }
+}
+
+impl Unmarshal for SequencedFrame {
+    type Error = SequencedFrameError;
+
+    fn unmarshal_from<B>(buf: &mut B) -> Result<Self, Self::Error>
+    where
+        B: Buf,
+    {
+        let required_len = SEQUENCED_FRAME_HEADER_LEN;
+        if buf.remaining() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining(),
+            });
+        }
+
+        let flags = buf.get_u8();
+        let sequence = buf.get_u64();
+        let payload = buf.copy_to_bytes(buf.remaining());
+
+        Ok(Self {
+            sequence,
+            is_string: flags & FLAG_IS_STRING != 0,
+            payload,
+        })
+    }
+}
+
+impl Marshal for SequencedFrame {
+    type Error = SequencedFrameError;
+
+    fn marshal_to<B>(&self, buf: &mut B) -> Result<usize, Self::Error>
+    where
+        B: BufMut,
+    {
+        let required_len = self.marshal_size();
+        if buf.remaining_mut() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining_mut(),
+            });
+        }
+
+        buf.put_u8(if self.is_string { FLAG_IS_STRING } else { 0 });
+        buf.put_u64(self.sequence);
+        buf.put_slice(&self.payload[..]);
+
+        Ok(required_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static MARSHALED_BYTES: [u8; 11] = [
+        0x01, // flags
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x02, // sequence number
+        0x68, 0x69, // payload
+    ];
+
+    #[test]
+    fn unmarshal_success() {
+        let mut bytes = Bytes::from_static(&MARSHALED_BYTES);
+
+        let frame = SequencedFrame::unmarshal_from(&mut bytes).unwrap();
+
+        assert_eq!(frame.sequence, 258);
+        assert!(frame.is_string);
+        assert_eq!(&frame.payload[..], b"hi");
+    }
+
+    #[test]
+    fn unmarshal_unexpected_end_of_buffer() {
+        let mut bytes = Bytes::from_static(&MARSHALED_BYTES[..4]);
+
+        let result = SequencedFrame::unmarshal_from(&mut bytes);
+
+        assert_eq!(
+            result,
+            Err(SequencedFrameError::UnexpectedEndOfBuffer {
+                expected: 9,
+                actual: 4
+            })
+        );
+    }
+
+    #[test]
+    fn marshal() {
+        let frame = SequencedFrame {
+            sequence: 258,
+            is_string: true,
+            payload: Bytes::from_static(b"hi"),
+        };
+
+        let bytes = frame.marshal().unwrap();
+
+        assert_eq!(&bytes[..], &MARSHALED_BYTES);
+    }
+}
@@ -0,0 +1,83 @@
+use std::sync::OnceLock;
+
+use opentelemetry::{
+    global::{self, BoxedSpan},
+    metrics::Counter,
+    trace::{Span, Status, Tracer},
+    KeyValue,
+};
+
+use crate::error::DataChannelError;
+
+const INSTRUMENTATION_NAME: &str = "webrtc-data";
+
+struct Instruments {
+    messages_sent: Counter<u64>,
+    messages_received: Counter<u64>,
+    bytes_sent: Counter<u64>,
+    bytes_received: Counter<u64>,
+}
+
+fn instruments() -> &'static Instruments {
+    static INSTRUMENTS: OnceLock<Instruments> = OnceLock::new();
+
+    INSTRUMENTS.get_or_init(|| {
+        let meter = global::meter(INSTRUMENTATION_NAME);
+
+        Instruments {
+            messages_sent: meter
+                .u64_counter("data_channel.messages_sent")
+                .with_description("Number of messages sent over data channels")
+                .build(),
+            messages_received: meter
+                .u64_counter("data_channel.messages_received")
+                .with_description("Number of messages received over data channels")
+                .build(),
+            bytes_sent: meter
+                .u64_counter("data_channel.bytes_sent")
+                .with_description("Number of payload bytes sent over data channels")
+                .with_unit("By")
+                .build(),
+            bytes_received: meter
+                .u64_counter("data_channel.bytes_received")
+                .with_description("Number of payload bytes received over data channels")
+                .with_unit("By")
+                .build(),
+        }
+    })
+}
+
+/// Starts a span for a data channel operation on the given stream.
+pub(crate) fn start_span(name: &'static str, stream_identifier: u16) -> BoxedSpan {
+    let mut span = global::tracer(INSTRUMENTATION_NAME).start(name);
+    span.set_attribute(KeyValue::new(
+        "stream_identifier",
+        i64::from(stream_identifier),
+    ));
+    span
+}
+
+/// Ends a span started by `start_span`, recording the outcome of the operation.
+pub(crate) fn end_span<T>(mut span: BoxedSpan, label: &str, result: &Result<T, DataChannelError>) {
+    span.set_attribute(KeyValue::new("label", label.to_owned()));
+    if let Err(error) = result {
+        span.set_status(Status::error(error.to_string()));
+    }
+    span.end();
+}
+
+pub(crate) fn record_sent(label: &str, bytes_len: usize) {
+    let attributes = [KeyValue::new("label", label.to_owned())];
+    let instruments = instruments();
+    instruments.messages_sent.add(1, &attributes);
+    instruments.bytes_sent.add(bytes_len as u64, &attributes);
+}
+
+pub(crate) fn record_received(label: &str, bytes_len: usize) {
+    let attributes = [KeyValue::new("label", label.to_owned())];
+    let instruments = instruments();
+    instruments.messages_received.add(1, &attributes);
+    instruments
+        .bytes_received
+        .add(bytes_len as u64, &attributes);
+}
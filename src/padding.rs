@@ -0,0 +1,14 @@
+//! Padding of messages to fixed size buckets.
+//!
+//! A `PaddedChannel` pads each outgoing message with zeros up to the
+//! smallest of a set of bucket sizes fitting it, so message sizes leak
+//! less about their contents to an observer of the traffic. Each end
+//! announces it strips padding with a hello frame when it starts, and
+//! only pads its messages once it has seen the remote's: until then,
+//! messages are framed but sent unpadded.
+
+mod channel;
+mod frame;
+
+pub use channel::{PaddedChannel, PaddingBuckets};
+pub use frame::PaddingFrame;
@@ -0,0 +1,175 @@
+use bytes::Bytes;
+
+use crate::{
+    data_channel::{DataChannel, RECEIVE_MTU},
+    error::DataChannelError,
+    marshal::{Marshal, Unmarshal},
+    padding::{frame::DATA_HEADER_LEN, PaddingFrame},
+};
+
+/// The sizes messages are padded to, in bytes, framing included.
+///
+/// Messages larger than the largest bucket are padded
+/// to a multiple of the largest bucket.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct PaddingBuckets {
+    sizes: Vec<usize>,
+}
+
+impl Default for PaddingBuckets {
+    fn default() -> Self {
+        Self::new(vec![256, 1024, 4096, 16384])
+    }
+}
+
+impl PaddingBuckets {
+    /// Creates buckets of the given sizes, in any order. Sizes below
+    /// the size of the framing are ignored, as are duplicates.
+    pub fn new(mut sizes: Vec<usize>) -> Self {
+        sizes.retain(|size| *size > DATA_HEADER_LEN);
+        sizes.sort_unstable();
+        sizes.dedup();
+
+        Self { sizes }
+    }
+
+    /// PaddedLen returns the size a frame of `len` bytes is padded to.
+    pub fn padded_len(&self, len: usize) -> usize {
+        match self.sizes.iter().find(|size| **size >= len) {
+            Some(size) => *size,
+            None => match self.sizes.last() {
+                Some(largest) => len.div_ceil(*largest) * largest,
+                None => len,
+            },
+        }
+    }
+}
+
+/// PaddedChannel pads the messages it sends over a data channel
+/// to `PaddingBuckets`, and strips the padding of those it receives.
+pub struct PaddedChannel {
+    data_channel: DataChannel,
+    buckets: PaddingBuckets,
+    // Whether the remote announced it strips padding.
+    remote_enabled: bool,
+}
+
+impl PaddedChannel {
+    /// Creates a padded channel, announcing to the remote that it strips padding.
+    pub fn new(
+        mut data_channel: DataChannel,
+        buckets: PaddingBuckets,
+    ) -> Result<Self, DataChannelError> {
+        let mut hello = PaddingFrame::Hello.marshal()?;
+        data_channel.write_data_channel(&mut hello, false)?;
+
+        Ok(Self {
+            data_channel,
+            buckets,
+            remote_enabled: false,
+        })
+    }
+
+    /// IsRemoteEnabled returns whether the remote announced it strips
+    /// padding, and messages are padded accordingly.
+    pub fn is_remote_enabled(&self) -> bool {
+        self.remote_enabled
+    }
+
+    /// Write sends a message, returning the number of bytes sent including framing and padding.
+    pub fn write(&mut self, payload: Bytes, is_string: bool) -> Result<usize, DataChannelError> {
+        let len = DATA_HEADER_LEN + payload.len();
+        let padding = if self.remote_enabled {
+            self.buckets.padded_len(len) - len
+        } else {
+            0
+        };
+
+        let frame = PaddingFrame::Data {
+            is_string,
+            payload,
+            padding,
+        };
+        let mut bytes = frame.marshal()?;
+        self.data_channel.write_data_channel(&mut bytes, false)
+    }
+
+    /// Read blocks until the next message arrives, returning its
+    /// payload, stripped of padding, and whether it is text.
+    pub fn read(&mut self) -> Result<(Bytes, bool), DataChannelError> {
+        loop {
+            match self.read_frame()? {
+                PaddingFrame::Hello => {
+                    log::debug!(
+                        "Remote strips padding on stream {}",
+                        self.data_channel.stream_identifier()
+                    );
+                    self.remote_enabled = true;
+                }
+                PaddingFrame::Data {
+                    is_string, payload, ..
+                } => return Ok((payload, is_string)),
+            }
+        }
+    }
+
+    pub fn into_inner(self) -> DataChannel {
+        self.data_channel
+    }
+
+    fn read_frame(&mut self) -> Result<PaddingFrame, DataChannelError> {
+        let buffer_provider = self.data_channel.config.buffer_provider.clone();
+        let mut buf = buffer_provider.acquire(RECEIVE_MTU);
+        let result = self
+            .data_channel
+            .read(&mut buf)
+            .map(|n| buf.split_to(n).freeze());
+        buffer_provider.release(buf);
+
+        let mut bytes = result?;
+        PaddingFrame::unmarshal_from(&mut bytes).map_err(From::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{data_channel::Config, testing};
+
+    use super::*;
+
+    #[test]
+    fn padded_len() {
+        let buckets = PaddingBuckets::new(vec![1024, 256, 4, 256]);
+
+        assert_eq!(buckets.padded_len(7), 256);
+        assert_eq!(buckets.padded_len(256), 256);
+        assert_eq!(buckets.padded_len(257), 1024);
+        assert_eq!(buckets.padded_len(1025), 2048);
+        assert_eq!(PaddingBuckets::new(vec![]).padded_len(7), 7);
+    }
+
+    #[test]
+    fn pads_once_remote_enabled() {
+        let (dialer, acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        let mut dialer = PaddedChannel::new(dialer, PaddingBuckets::default()).unwrap();
+        let mut acceptor = PaddedChannel::new(acceptor, PaddingBuckets::default()).unwrap();
+
+        // The dialer hasn't seen the acceptor's hello yet.
+        let n = dialer.write(Bytes::from_static(b"hello"), true).unwrap();
+        assert_eq!(n, DATA_HEADER_LEN + 5);
+
+        assert_eq!(
+            acceptor.read().unwrap(),
+            (Bytes::from_static(b"hello"), true)
+        );
+        assert!(acceptor.is_remote_enabled());
+        let n = acceptor.write(Bytes::from_static(b"world"), false).unwrap();
+        assert_eq!(n, 256);
+
+        assert_eq!(
+            dialer.read().unwrap(),
+            (Bytes::from_static(b"world"), false)
+        );
+        assert!(dialer.is_remote_enabled());
+    }
+}
@@ -0,0 +1,225 @@
+use bytes::{Buf, BufMut, Bytes};
+
+use crate::{
+    error::PaddingError,
+    marshal::{Marshal, MarshalSize, Unmarshal},
+};
+
+// The first byte in a `PaddingFrame` that specifies its type:
+const FRAME_TYPE_HELLO: u8 = 0x01;
+const FRAME_TYPE_DATA: u8 = 0x02;
+
+const FLAG_IS_STRING: u8 = 0b0000_0001;
+
+const HELLO_LEN: usize = 1;
+pub(crate) const DATA_HEADER_LEN: usize = 6;
+
+/// A frame exchanged by `PaddedChannel`s,
+/// with each data channel message carrying exactly one frame.
+///
+/// # Memory layout
+///
+/// Announcing the sender strips padding, sent once when it starts:
+///
+/// ```plain
+///  0 1 2 3 4 5 6 7
+/// +-+-+-+-+-+-+-+-+
+/// |  Frame Type   |
+/// +-+-+-+-+-+-+-+-+
+/// ```
+///
+/// A message, followed by as many zero bytes of padding as
+/// the rest of the data channel message holds:
+///
+/// ```plain
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |  Frame Type   |     Flags     |        Length (high)          |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |         Length (low)          |                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+                               +
+/// |                            Payload                            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                            Padding                            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+///
+/// Bit 0 of the flags is set for text messages.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum PaddingFrame {
+    Hello,
+    Data {
+        is_string: bool,
+        payload: Bytes,
+        padding: usize,
+    },
+}
+
+impl MarshalSize for PaddingFrame {
+    fn marshal_size(&self) -> usize {
+        match self {
+            Self::Hello => HELLO_LEN,
+            Self::Data {
+                payload, padding, ..
+            } => DATA_HEADER_LEN + payload.len() + padding,
+        }
+    }
+}
+
+impl Unmarshal for PaddingFrame {
+    type Error = PaddingError;
+
+    fn unmarshal_from<B>(buf: &mut B) -> Result<Self, Self::Error>
+    where
+        B: Buf,
+    {
+        if !buf.has_remaining() {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: HELLO_LEN,
+                actual: 0,
+            });
+        }
+
+        match buf.chunk()[0] {
+            FRAME_TYPE_HELLO => {
+                buf.advance(HELLO_LEN);
+                Ok(Self::Hello)
+            }
+            FRAME_TYPE_DATA => {
+                if buf.remaining() < DATA_HEADER_LEN {
+                    return Err(Self::Error::UnexpectedEndOfBuffer {
+                        expected: DATA_HEADER_LEN,
+                        actual: buf.remaining(),
+                    });
+                }
+
+                buf.advance(1);
+                let flags = buf.get_u8();
+                let len = buf.get_u32() as usize;
+                if buf.remaining() < len {
+                    return Err(Self::Error::UnexpectedEndOfBuffer {
+                        expected: DATA_HEADER_LEN + len,
+                        actual: DATA_HEADER_LEN + buf.remaining(),
+                    });
+                }
+
+                let payload = buf.copy_to_bytes(len);
+                let padding = buf.remaining();
+                buf.advance(padding);
+
+                Ok(Self::Data {
+                    is_string: flags & FLAG_IS_STRING != 0,
+                    payload,
+                    padding,
+                })
+            }
+            invalid_type => Err(Self::Error::InvalidFrameType { invalid_type }),
+        }
+    }
+}
+
+impl Marshal for PaddingFrame {
+    type Error = PaddingError;
+
+    fn marshal_to<B>(&self, buf: &mut B) -> Result<usize, Self::Error>
+    where
+        B: BufMut,
+    {
+        let required_len = self.marshal_size();
+        if buf.remaining_mut() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining_mut(),
+            });
+        }
+
+        match self {
+            Self::Hello => buf.put_u8(FRAME_TYPE_HELLO),
+            Self::Data {
+                is_string,
+                payload,
+                padding,
+            } => {
+                buf.put_u8(FRAME_TYPE_DATA);
+                buf.put_u8(if *is_string { FLAG_IS_STRING } else { 0 });
+                buf.put_u32(payload.len() as u32);
+                buf.put_slice(&payload[..]);
+                buf.put_bytes(0, *padding);
+            }
+        }
+
+        Ok(required_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static MARSHALED_DATA: [u8; 11] = [
+        0x02, // frame type
+        0x01, // flags
+        0x00, 0x00, 0x00, 0x02, // length
+        0x68, 0x69, // payload
+        0x00, 0x00, 0x00, // padding
+    ];
+
+    #[test]
+    fn marshal_data() {
+        let frame = PaddingFrame::Data {
+            is_string: true,
+            payload: Bytes::from_static(b"hi"),
+            padding: 3,
+        };
+
+        assert_eq!(
+            frame.marshal().unwrap(),
+            Bytes::from_static(&MARSHALED_DATA)
+        );
+    }
+
+    #[test]
+    fn unmarshal_data() {
+        let mut bytes = Bytes::from_static(&MARSHALED_DATA);
+
+        let frame = PaddingFrame::unmarshal_from(&mut bytes).unwrap();
+
+        assert_eq!(
+            frame,
+            PaddingFrame::Data {
+                is_string: true,
+                payload: Bytes::from_static(b"hi"),
+                padding: 3,
+            }
+        );
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn unmarshal_truncated_payload() {
+        let mut bytes = Bytes::from_static(&MARSHALED_DATA[..7]);
+
+        let result = PaddingFrame::unmarshal_from(&mut bytes);
+
+        assert_eq!(
+            result,
+            Err(PaddingError::UnexpectedEndOfBuffer {
+                expected: 8,
+                actual: 7,
+            })
+        );
+    }
+
+    #[test]
+    fn unmarshal_invalid_frame_type() {
+        let mut bytes = Bytes::from_static(&[0x07]);
+
+        let result = PaddingFrame::unmarshal_from(&mut bytes);
+
+        assert_eq!(
+            result,
+            Err(PaddingError::InvalidFrameType { invalid_type: 0x07 })
+        );
+    }
+}
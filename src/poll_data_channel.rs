@@ -0,0 +1,221 @@
+//! A byte stream over a data channel, for protocols not caring about message boundaries.
+//!
+//! `PollDataChannel` implements `tokio::io::AsyncRead` and `AsyncWrite`:
+//! reads hand out the bytes of incoming messages in order, keeping the
+//! rest of a message for the next read, and writes are cut into messages
+//! of at most `MAX_MESSAGE_LEN` bytes.
+
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use bytes::{Bytes, BytesMut};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::{
+    data_channel::{DataChannel, RECEIVE_MTU},
+    error::DataChannelError,
+    sctp::StreamError,
+};
+
+/// The largest message written by `PollDataChannel`, so the remote
+/// can read every message whole.
+pub const MAX_MESSAGE_LEN: usize = RECEIVE_MTU;
+
+/// PollDataChannel wraps a `DataChannel` as an asynchronous byte stream.
+///
+/// Messages are written as binary, and text messages are read as their bytes.
+/// The remote resetting its stream reads as the end of the stream.
+pub struct PollDataChannel {
+    data_channel: DataChannel,
+    // The part of the latest message not read yet.
+    remainder: Bytes,
+}
+
+impl PollDataChannel {
+    pub fn new(data_channel: DataChannel) -> Self {
+        Self {
+            data_channel,
+            remainder: Bytes::new(),
+        }
+    }
+
+    pub fn data_channel(&self) -> &DataChannel {
+        &self.data_channel
+    }
+
+    /// IntoInner returns the wrapped channel, discarding any unread
+    /// part of the latest message.
+    pub fn into_inner(self) -> DataChannel {
+        self.data_channel
+    }
+
+    // Reads the next message into the remainder, resolving to `false` at the end of the stream.
+    fn poll_fill_remainder(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<bool>> {
+        let buffer_provider = self.data_channel.config.buffer_provider.clone();
+        let mut buf: BytesMut = buffer_provider.acquire(RECEIVE_MTU);
+        let result = match self.data_channel.poll_read_data_channel(cx, &mut buf) {
+            Poll::Ready(result) => result,
+            Poll::Pending => {
+                buffer_provider.release(buf);
+                return Poll::Pending;
+            }
+        };
+
+        let result = match result {
+            Ok((n, _)) => {
+                self.remainder = buf.split_to(n).freeze();
+                Ok(true)
+            }
+            Err(DataChannelError::Stream(StreamError::Eof)) => Ok(false),
+            Err(error) => Err(io_error(error)),
+        };
+        buffer_provider.release(buf);
+
+        Poll::Ready(result)
+    }
+}
+
+impl AsyncRead for PollDataChannel {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        // Empty messages don't end the stream, so read past them.
+        while this.remainder.is_empty() {
+            if !ready!(this.poll_fill_remainder(cx))? {
+                return Poll::Ready(Ok(()));
+            }
+        }
+
+        let n = this.remainder.len().min(buf.remaining());
+        buf.put_slice(&this.remainder.split_to(n));
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for PollDataChannel {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        let len = buf.len().min(MAX_MESSAGE_LEN);
+        let mut message = Bytes::copy_from_slice(&buf[..len]);
+        match ready!(this
+            .data_channel
+            .poll_write_data_channel(cx, &mut message, false))
+        {
+            Ok(_) => Poll::Ready(Ok(len)),
+            Err(error) => Poll::Ready(Err(io_error(error))),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // Messages are handed to SCTP as they are written.
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.get_mut().data_channel.close().map_err(io_error))
+    }
+}
+
+fn io_error(error: DataChannelError) -> io::Error {
+    let kind = match &error {
+        DataChannelError::Stream(StreamError::Eof) => io::ErrorKind::BrokenPipe,
+        DataChannelError::Stream(StreamError::SendBufferFull) => io::ErrorKind::WouldBlock,
+        DataChannelError::Cancelled => io::ErrorKind::Interrupted,
+        DataChannelError::Timeout => io::ErrorKind::TimedOut,
+        _ => io::ErrorKind::Other,
+    };
+
+    io::Error::new(kind, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::Waker;
+
+    use crate::{data_channel::Config, testing};
+
+    use super::*;
+
+    fn read(stream: &mut PollDataChannel, len: usize) -> Vec<u8> {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut storage = vec![0; len];
+        let mut buf = ReadBuf::new(&mut storage);
+        match Pin::new(stream).poll_read(&mut cx, &mut buf) {
+            Poll::Ready(Ok(())) => buf.filled().to_vec(),
+            poll => panic!("expected a read, got {:?}", poll),
+        }
+    }
+
+    fn write(stream: &mut PollDataChannel, buf: &[u8]) -> usize {
+        let mut cx = Context::from_waker(Waker::noop());
+        match Pin::new(stream).poll_write(&mut cx, buf) {
+            Poll::Ready(Ok(n)) => n,
+            poll => panic!("expected a write, got {:?}", poll),
+        }
+    }
+
+    #[test]
+    fn reads_across_message_boundaries() {
+        let (dialer, acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        let mut local = PollDataChannel::new(dialer);
+        let mut remote = PollDataChannel::new(acceptor);
+
+        assert_eq!(write(&mut remote, b"hello"), 5);
+        assert_eq!(write(&mut remote, b"world"), 5);
+
+        assert_eq!(read(&mut local, 3), b"hel");
+        assert_eq!(read(&mut local, 16), b"lo");
+        assert_eq!(read(&mut local, 16), b"world");
+    }
+
+    #[test]
+    fn splits_large_writes() {
+        let (dialer, acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        let mut local = PollDataChannel::new(dialer);
+        let mut remote = PollDataChannel::new(acceptor);
+
+        let payload = vec![7; MAX_MESSAGE_LEN + 1];
+        assert_eq!(write(&mut local, &payload), MAX_MESSAGE_LEN);
+        assert_eq!(write(&mut local, &payload[MAX_MESSAGE_LEN..]), 1);
+
+        assert_eq!(
+            read(&mut remote, 2 * MAX_MESSAGE_LEN).len(),
+            MAX_MESSAGE_LEN
+        );
+        assert_eq!(read(&mut remote, 2 * MAX_MESSAGE_LEN), [7]);
+    }
+
+    #[test]
+    fn remote_reset_ends_stream() {
+        let (dialer, acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        let mut local = PollDataChannel::new(dialer);
+        let mut remote = PollDataChannel::new(acceptor);
+
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(matches!(
+            Pin::new(&mut remote).poll_shutdown(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+
+        assert!(read(&mut local, 16).is_empty());
+
+        let error = match Pin::new(&mut local).poll_write(&mut cx, b"hello") {
+            Poll::Ready(Err(error)) => error,
+            poll => panic!("expected an error, got {:?}", poll),
+        };
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+    }
+}
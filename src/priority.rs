@@ -0,0 +1,221 @@
+//! Weighted scheduling of writes by channel priority.
+//!
+//! RFC 8831 defines four priority levels for the DCEP `priority` field,
+//! which entitle channels sharing an association to a share of its
+//! bandwidth proportional to their weight. A `PriorityScheduler` queues
+//! the messages of a manager's channels and flushes them by deficit round
+//! robin, letting each channel send its weight's worth of quanta per round,
+//! so that higher priority channels get ahead without starving the others.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use bytes::Bytes;
+
+use crate::{
+    data_channel::{DataChannel, RECEIVE_MTU},
+    error::DataChannelError,
+    manager::DataChannelManager,
+};
+
+/// The priority levels of RFC 8831, section 6.4.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Debug, Default)]
+pub enum Priority {
+    BelowNormal,
+    #[default]
+    Normal,
+    High,
+    ExtraHigh,
+}
+
+impl Priority {
+    /// Of returns the level of a DCEP priority value, rounding
+    /// values in between levels up to the next one.
+    pub fn of(value: u16) -> Self {
+        match value {
+            0..=128 => Self::BelowNormal,
+            129..=256 => Self::Normal,
+            257..=512 => Self::High,
+            _ => Self::ExtraHigh,
+        }
+    }
+
+    /// Value returns the DCEP priority value advertising the level.
+    pub fn value(&self) -> u16 {
+        match self {
+            Self::BelowNormal => 128,
+            Self::Normal => 256,
+            Self::High => 512,
+            Self::ExtraHigh => 1024,
+        }
+    }
+
+    /// Weight returns the level's share of the bandwidth,
+    /// relative to that of the other levels.
+    pub fn weight(&self) -> usize {
+        self.value() as usize / 128
+    }
+}
+
+struct Queue {
+    priority: Priority,
+    // The number of bytes the queue may still send in the current round.
+    deficit: usize,
+    messages: VecDeque<(Bytes, bool)>,
+}
+
+/// PriorityScheduler queues messages per channel and sends them in
+/// proportion to the weight of their channel's priority.
+pub struct PriorityScheduler {
+    queues: BTreeMap<u16, Queue>,
+    quantum: usize,
+}
+
+impl Default for PriorityScheduler {
+    fn default() -> Self {
+        Self::new(RECEIVE_MTU)
+    }
+}
+
+impl PriorityScheduler {
+    /// Creates a scheduler letting channels of `Priority::BelowNormal`
+    /// send `quantum` bytes per round, and the others a multiple of it.
+    pub fn new(quantum: usize) -> Self {
+        Self {
+            queues: BTreeMap::new(),
+            quantum,
+        }
+    }
+
+    /// Enqueue queues a message to be sent over the given channel,
+    /// at the priority of the channel's config.
+    pub fn enqueue(&mut self, data_channel: &DataChannel, payload: Bytes, is_string: bool) {
+        let priority = Priority::of(data_channel.config.priority);
+        let queue = self
+            .queues
+            .entry(data_channel.stream_identifier())
+            .or_insert_with(|| Queue {
+                priority,
+                deficit: 0,
+                messages: VecDeque::new(),
+            });
+        queue.priority = priority;
+        queue.messages.push_back((payload, is_string));
+    }
+
+    /// Pending returns the number of messages queued for the given stream.
+    pub fn pending(&self, stream_identifier: u16) -> usize {
+        self.queues
+            .get(&stream_identifier)
+            .map(|queue| queue.messages.len())
+            .unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queues.is_empty()
+    }
+
+    /// FlushRound runs a single scheduling round over the manager's
+    /// channels, returning the number of messages sent.
+    ///
+    /// Messages queued for channels the manager no longer has are dropped.
+    /// A message which fails to be sent stays at the head of its queue.
+    pub fn flush_round(
+        &mut self,
+        manager: &mut DataChannelManager,
+    ) -> Result<usize, DataChannelError> {
+        let mut sent = 0;
+        let quantum = self.quantum;
+        let mut result = Ok(());
+        self.queues.retain(|&stream_identifier, queue| {
+            if result.is_err() {
+                return true;
+            }
+            let data_channel = match manager.get_mut(stream_identifier) {
+                Some(data_channel) => data_channel,
+                None => return false,
+            };
+
+            queue.deficit += quantum * queue.priority.weight();
+            while let Some((payload, is_string)) = queue.messages.pop_front() {
+                if payload.len() > queue.deficit {
+                    queue.messages.push_front((payload, is_string));
+                    return true;
+                }
+
+                let len = payload.len();
+                let mut bytes = payload.clone();
+                if let Err(error) = data_channel.write_data_channel(&mut bytes, is_string) {
+                    queue.messages.push_front((payload, is_string));
+                    result = Err(error);
+                    return true;
+                }
+                queue.deficit -= len;
+                sent += 1;
+            }
+
+            // Idle queues don't bank their deficit for later rounds.
+            false
+        });
+
+        result.map(|_| sent)
+    }
+
+    /// Flush runs scheduling rounds until all queues are empty,
+    /// returning the number of messages sent.
+    pub fn flush(&mut self, manager: &mut DataChannelManager) -> Result<usize, DataChannelError> {
+        let mut sent = 0;
+        while !self.is_empty() {
+            sent += self.flush_round(manager)?;
+        }
+
+        Ok(sent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{data_channel::Config, testing};
+
+    use super::*;
+
+    #[test]
+    fn maps_values_to_levels() {
+        assert_eq!(Priority::of(0), Priority::BelowNormal);
+        assert_eq!(Priority::of(256), Priority::Normal);
+        assert_eq!(Priority::of(300), Priority::High);
+        assert_eq!(Priority::of(u16::MAX), Priority::ExtraHigh);
+        assert_eq!(Priority::of(Priority::High.value()), Priority::High);
+        assert_eq!(Priority::ExtraHigh.weight(), 8);
+    }
+
+    #[test]
+    fn flushes_in_proportion_to_weight() {
+        let (association, _remote) = testing::simulated();
+        let mut manager = DataChannelManager::new(association);
+        let config = |priority: Priority| Config {
+            priority: priority.value(),
+            ..Default::default()
+        };
+        let bulk = manager.open(config(Priority::BelowNormal)).unwrap();
+        let bulk_id = bulk.stream_identifier();
+
+        let mut scheduler = PriorityScheduler::new(100);
+        for _ in 0..10 {
+            scheduler.enqueue(bulk, Bytes::from(vec![0; 100]), false);
+        }
+        let urgent = manager.open(config(Priority::ExtraHigh)).unwrap();
+        let urgent_id = urgent.stream_identifier();
+        for _ in 0..10 {
+            scheduler.enqueue(urgent, Bytes::from(vec![0; 100]), false);
+        }
+
+        assert_eq!(scheduler.flush_round(&mut manager), Ok(9));
+        assert_eq!(scheduler.pending(bulk_id), 9);
+        assert_eq!(scheduler.pending(urgent_id), 2);
+
+        assert_eq!(scheduler.flush(&mut manager), Ok(11));
+        assert!(scheduler.is_empty());
+        assert_eq!(manager.get(bulk_id).unwrap().messages_sent(), 10);
+        assert_eq!(manager.get(urgent_id).unwrap().messages_sent(), 10);
+    }
+}
@@ -0,0 +1,14 @@
+//! Topic-based publish/subscribe over data channels.
+//!
+//! A `Peer` subscribes to topics and publishes messages over a single data
+//! channel. A `Broker` owns any number of channels, tracks their
+//! subscriptions and forwards every published message to the channels
+//! subscribed to its topic.
+
+mod broker;
+mod packet;
+mod subscriptions;
+
+pub use broker::{Broker, Peer};
+pub use packet::Packet;
+pub use subscriptions::Subscriptions;
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+
+use crate::{
+    data_channel::{DataChannel, RECEIVE_MTU},
+    error::DataChannelError,
+    marshal::{Marshal, Unmarshal},
+    pubsub::{Packet, Subscriptions},
+};
+
+fn write_packet(
+    data_channel: &mut DataChannel,
+    packet: &Packet,
+) -> Result<usize, DataChannelError> {
+    let mut bytes = packet.marshal()?;
+    data_channel.write_data_channel(&mut bytes, false)
+}
+
+fn read_packet(data_channel: &mut DataChannel) -> Result<Packet, DataChannelError> {
+    let buffer_provider = data_channel.config.buffer_provider.clone();
+    let mut buf = buffer_provider.acquire(RECEIVE_MTU);
+    let result = data_channel
+        .read(&mut buf)
+        .map(|n| buf.split_to(n).freeze());
+    buffer_provider.release(buf);
+
+    let mut bytes = result?;
+    Packet::unmarshal_from(&mut bytes).map_err(From::from)
+}
+
+/// Peer publishes and subscribes to topics over a single data channel,
+/// typically connected to a `Broker` on the remote end.
+pub struct Peer {
+    data_channel: DataChannel,
+}
+
+impl Peer {
+    pub fn new(data_channel: DataChannel) -> Self {
+        Self { data_channel }
+    }
+
+    pub fn subscribe(&mut self, topic: &str) -> Result<(), DataChannelError> {
+        let packet = Packet::Subscribe {
+            topic: topic.to_owned(),
+        };
+        write_packet(&mut self.data_channel, &packet).map(|_| ())
+    }
+
+    pub fn unsubscribe(&mut self, topic: &str) -> Result<(), DataChannelError> {
+        let packet = Packet::Unsubscribe {
+            topic: topic.to_owned(),
+        };
+        write_packet(&mut self.data_channel, &packet).map(|_| ())
+    }
+
+    pub fn publish(&mut self, topic: &str, payload: Bytes) -> Result<(), DataChannelError> {
+        let packet = Packet::Publish {
+            topic: topic.to_owned(),
+            payload,
+        };
+        write_packet(&mut self.data_channel, &packet).map(|_| ())
+    }
+
+    /// Receive blocks until a message is published to one of the subscribed
+    /// topics, returning its topic and payload.
+    pub fn receive(&mut self) -> Result<(String, Bytes), DataChannelError> {
+        loop {
+            match read_packet(&mut self.data_channel)? {
+                Packet::Publish { topic, payload } => return Ok((topic, payload)),
+                packet => log::warn!("Dropping unexpected packet: {:?}", packet),
+            }
+        }
+    }
+
+    pub fn into_inner(self) -> DataChannel {
+        self.data_channel
+    }
+}
+
+/// Broker fans published messages out to all subscribed channels,
+/// with channels identified by their stream identifiers.
+#[derive(Default)]
+pub struct Broker {
+    channels: HashMap<u16, DataChannel>,
+    subscriptions: Subscriptions,
+}
+
+impl Broker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// AddChannel adds a channel to the broker, returning its stream identifier
+    pub fn add_channel(&mut self, data_channel: DataChannel) -> u16 {
+        let stream_identifier = data_channel.stream_identifier();
+        self.channels.insert(stream_identifier, data_channel);
+        stream_identifier
+    }
+
+    /// RemoveChannel removes a channel and all of its subscriptions from the broker
+    pub fn remove_channel(&mut self, stream_identifier: u16) -> Option<DataChannel> {
+        self.subscriptions.remove_channel(stream_identifier);
+        self.channels.remove(&stream_identifier)
+    }
+
+    pub fn subscriptions(&self) -> &Subscriptions {
+        &self.subscriptions
+    }
+
+    /// Publish sends a message to all channels subscribed to its topic,
+    /// returning the number of channels it was delivered to.
+    pub fn publish(&mut self, topic: &str, payload: Bytes) -> Result<usize, DataChannelError> {
+        self.forward(None, topic, payload)
+    }
+
+    /// Receive blocks until a packet arrives on the given channel and processes it.
+    ///
+    /// Subscription changes are applied and published messages are forwarded
+    /// to all other subscribed channels before the packet is returned.
+    pub fn receive(&mut self, stream_identifier: u16) -> Result<Packet, DataChannelError> {
+        let data_channel = self
+            .channels
+            .get_mut(&stream_identifier)
+            .ok_or(DataChannelError::UnknownStreamIdentifier { stream_identifier })?;
+
+        let packet = read_packet(data_channel)?;
+
+        match &packet {
+            Packet::Subscribe { topic } => {
+                self.subscriptions.subscribe(stream_identifier, topic);
+            }
+            Packet::Unsubscribe { topic } => {
+                self.subscriptions.unsubscribe(stream_identifier, topic);
+            }
+            Packet::Publish { topic, payload } => {
+                self.forward(Some(stream_identifier), topic, payload.clone())?;
+            }
+        }
+
+        Ok(packet)
+    }
+
+    fn forward(
+        &mut self,
+        origin: Option<u16>,
+        topic: &str,
+        payload: Bytes,
+    ) -> Result<usize, DataChannelError> {
+        let packet = Packet::Publish {
+            topic: topic.to_owned(),
+            payload,
+        };
+        let bytes = packet.marshal()?;
+
+        let mut delivered = 0;
+        for stream_identifier in self.subscriptions.subscribers(topic) {
+            if Some(stream_identifier) == origin {
+                continue;
+            }
+
+            let data_channel = match self.channels.get_mut(&stream_identifier) {
+                Some(data_channel) => data_channel,
+                None => continue,
+            };
+
+            match data_channel.write_data_channel(&mut bytes.clone(), false) {
+                Ok(_) => delivered += 1,
+                Err(error) => log::warn!(
+                    "Failed to forward message on topic {:?} to stream {}: {:?}",
+                    topic,
+                    stream_identifier,
+                    error
+                ),
+            }
+        }
+
+        Ok(delivered)
+    }
+}
@@ -0,0 +1,255 @@
+use bytes::{Buf, BufMut, Bytes};
+
+use crate::{
+    error::PacketError,
+    marshal::{Marshal, MarshalSize, Unmarshal},
+};
+
+// The first byte in a `Packet` that specifies its type:
+const PACKET_TYPE_SUBSCRIBE: u8 = 0x01;
+const PACKET_TYPE_UNSUBSCRIBE: u8 = 0x02;
+const PACKET_TYPE_PUBLISH: u8 = 0x03;
+
+const PACKET_HEADER_LEN: usize = 3;
+
+/// A pub/sub control or data packet, carried in a single data channel message.
+///
+/// # Memory layout
+///
+/// ```plain
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |  Packet Type  |         Topic Length          |               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+               |
+/// |                             Topic                             |
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// |                    Payload (PUBLISH only)                     |
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum Packet {
+    Subscribe { topic: String },
+    Unsubscribe { topic: String },
+    Publish { topic: String, payload: Bytes },
+}
+
+impl Packet {
+    pub fn topic(&self) -> &str {
+        match self {
+            Self::Subscribe { topic } => topic,
+            Self::Unsubscribe { topic } => topic,
+            Self::Publish { topic, .. } => topic,
+        }
+    }
+}
+
+impl MarshalSize for Packet {
+    fn marshal_size(&self) -> usize {
+        let payload_len = match self {
+            Self::Publish { payload, .. } => payload.len(),
+            _ => 0,
+        };
+
+        PACKET_HEADER_LEN + self.topic().len() + payload_len
+    }
+}
+
+impl Unmarshal for Packet {
+    type Error = PacketError;
+
+    fn unmarshal_from<B>(buf: &mut B) -> Result<Self, Self::Error>
+    where
+        B: Buf,
+    {
+        let required_len = PACKET_HEADER_LEN;
+        if buf.remaining() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining(),
+            });
+        }
+
+        let packet_type = buf.get_u8();
+        let topic_len = buf.get_u16() as usize;
+
+        if buf.remaining() < topic_len {
+            return Err(Self::Error::ExpectedAndActualLengthMismatch {
+                expected: topic_len,
+                actual: buf.remaining(),
+            });
+        }
+
+        let mut topic = Vec::with_capacity(topic_len);
+        topic.put(Buf::take(&mut *buf, topic_len));
+        let topic = String::from_utf8(topic)?;
+
+        match packet_type {
+            PACKET_TYPE_SUBSCRIBE => Ok(Self::Subscribe { topic }),
+            PACKET_TYPE_UNSUBSCRIBE => Ok(Self::Unsubscribe { topic }),
+            PACKET_TYPE_PUBLISH => {
+                let payload = buf.copy_to_bytes(buf.remaining());
+                Ok(Self::Publish { topic, payload })
+            }
+            _ => Err(Self::Error::InvalidPacketType {
+                invalid_type: packet_type,
+            }),
+        }
+    }
+}
+
+impl Marshal for Packet {
+    type Error = PacketError;
+
+    fn marshal_to<B>(&self, buf: &mut B) -> Result<usize, Self::Error>
+    where
+        B: BufMut,
+    {
+        let topic = self.topic();
+        if topic.len() > u16::MAX as usize {
+            return Err(Self::Error::TopicTooLong { len: topic.len() });
+        }
+
+        let required_len = self.marshal_size();
+        if buf.remaining_mut() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining_mut(),
+            });
+        }
+
+        let packet_type = match self {
+            Self::Subscribe { .. } => PACKET_TYPE_SUBSCRIBE,
+            Self::Unsubscribe { .. } => PACKET_TYPE_UNSUBSCRIBE,
+            Self::Publish { .. } => PACKET_TYPE_PUBLISH,
+        };
+
+        buf.put_u8(packet_type);
+        buf.put_u16(topic.len() as u16);
+        buf.put_slice(topic.as_bytes());
+        if let Self::Publish { payload, .. } = self {
+            buf.put_slice(&payload[..]);
+        }
+
+        Ok(required_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use super::*;
+
+    static MARSHALED_PUBLISH: [u8; 11] = [
+        0x03, // packet type
+        0x00, 0x04, // topic length
+        0x63, 0x68, 0x61, 0x74, // topic
+        0x68, 0x65, 0x79, 0x21, // payload
+    ];
+
+    #[test]
+    fn unmarshal_publish() {
+        let mut bytes = Bytes::from_static(&MARSHALED_PUBLISH);
+
+        let packet = Packet::unmarshal_from(&mut bytes).unwrap();
+
+        assert_eq!(
+            packet,
+            Packet::Publish {
+                topic: "chat".to_owned(),
+                payload: Bytes::from_static(b"hey!"),
+            }
+        );
+    }
+
+    #[test]
+    fn unmarshal_subscribe() {
+        let mut bytes = Bytes::from_static(&[0x01, 0x00, 0x01, 0x78]);
+
+        let packet = Packet::unmarshal_from(&mut bytes).unwrap();
+
+        assert_eq!(
+            packet,
+            Packet::Subscribe {
+                topic: "x".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn unmarshal_invalid_packet_type() {
+        let mut bytes = Bytes::from_static(&[0x09, 0x00, 0x00]);
+
+        let result = Packet::unmarshal_from(&mut bytes);
+
+        assert_eq!(
+            result,
+            Err(PacketError::InvalidPacketType { invalid_type: 0x09 })
+        );
+    }
+
+    #[test]
+    fn unmarshal_unexpected_length_mismatch() {
+        let mut bytes = Bytes::from_static(&[0x02, 0x00, 0x05, 0x61]);
+
+        let result = Packet::unmarshal_from(&mut bytes);
+
+        assert_eq!(
+            result,
+            Err(PacketError::ExpectedAndActualLengthMismatch {
+                expected: 5,
+                actual: 1
+            })
+        );
+    }
+
+    #[test]
+    fn unmarshal_unexpected_end_of_buffer() {
+        let mut bytes = Bytes::from_static(&[0x01]);
+
+        let result = Packet::unmarshal_from(&mut bytes);
+
+        assert_eq!(
+            result,
+            Err(PacketError::UnexpectedEndOfBuffer {
+                expected: 3,
+                actual: 1
+            })
+        );
+    }
+
+    #[test]
+    fn marshal() {
+        let packet = Packet::Publish {
+            topic: "chat".to_owned(),
+            payload: Bytes::from_static(b"hey!"),
+        };
+
+        let mut buf = BytesMut::with_capacity(packet.marshal_size());
+        let bytes_written = packet.marshal_to(&mut buf).unwrap();
+        let bytes = buf.freeze();
+
+        assert_eq!(bytes_written, packet.marshal_size());
+        assert_eq!(&bytes[..], &MARSHALED_PUBLISH);
+    }
+
+    #[test]
+    fn marshal_topic_too_long() {
+        let packet = Packet::Subscribe {
+            topic: "x".repeat(u16::MAX as usize + 1),
+        };
+
+        let result = packet.marshal();
+
+        assert_eq!(
+            result,
+            Err(PacketError::TopicTooLong {
+                len: u16::MAX as usize + 1
+            })
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::{BTreeSet, HashMap};
+
+/// Subscriptions maps topics to the stream identifiers of the channels subscribed to them.
+#[derive(Eq, PartialEq, Default, Clone, Debug)]
+pub struct Subscriptions {
+    topics: HashMap<String, BTreeSet<u16>>,
+}
+
+impl Subscriptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe adds a channel to a topic, returning `false` if it already was subscribed.
+    pub fn subscribe(&mut self, stream_identifier: u16, topic: &str) -> bool {
+        self.topics
+            .entry(topic.to_owned())
+            .or_default()
+            .insert(stream_identifier)
+    }
+
+    /// Unsubscribe removes a channel from a topic, returning `false` if it wasn't subscribed.
+    pub fn unsubscribe(&mut self, stream_identifier: u16, topic: &str) -> bool {
+        let subscribers = match self.topics.get_mut(topic) {
+            Some(subscribers) => subscribers,
+            None => return false,
+        };
+
+        let removed = subscribers.remove(&stream_identifier);
+        if subscribers.is_empty() {
+            self.topics.remove(topic);
+        }
+        removed
+    }
+
+    /// RemoveChannel removes a channel from all topics it is subscribed to.
+    pub fn remove_channel(&mut self, stream_identifier: u16) {
+        self.topics.retain(|_, subscribers| {
+            subscribers.remove(&stream_identifier);
+            !subscribers.is_empty()
+        });
+    }
+
+    pub fn is_subscribed(&self, stream_identifier: u16, topic: &str) -> bool {
+        self.topics
+            .get(topic)
+            .map(|subscribers| subscribers.contains(&stream_identifier))
+            .unwrap_or(false)
+    }
+
+    /// Subscribers returns the channels subscribed to a topic, in ascending order.
+    pub fn subscribers<'a>(&'a self, topic: &str) -> impl Iterator<Item = u16> + 'a {
+        self.topics
+            .get(topic)
+            .into_iter()
+            .flat_map(|subscribers| subscribers.iter().copied())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.topics.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscribe() {
+        let mut subscriptions = Subscriptions::new();
+
+        assert!(subscriptions.subscribe(3, "chat"));
+        assert!(subscriptions.subscribe(1, "chat"));
+        assert!(!subscriptions.subscribe(1, "chat"));
+        assert!(subscriptions.subscribe(1, "presence"));
+
+        assert_eq!(
+            subscriptions.subscribers("chat").collect::<Vec<_>>(),
+            [1, 3]
+        );
+        assert_eq!(
+            subscriptions.subscribers("presence").collect::<Vec<_>>(),
+            [1]
+        );
+        assert_eq!(subscriptions.subscribers("unknown").count(), 0);
+    }
+
+    #[test]
+    fn unsubscribe() {
+        let mut subscriptions = Subscriptions::new();
+        subscriptions.subscribe(1, "chat");
+
+        assert!(!subscriptions.unsubscribe(2, "chat"));
+        assert!(!subscriptions.unsubscribe(1, "presence"));
+        assert!(subscriptions.unsubscribe(1, "chat"));
+
+        assert!(!subscriptions.is_subscribed(1, "chat"));
+        assert!(subscriptions.is_empty());
+    }
+
+    #[test]
+    fn remove_channel() {
+        let mut subscriptions = Subscriptions::new();
+        subscriptions.subscribe(1, "chat");
+        subscriptions.subscribe(1, "presence");
+        subscriptions.subscribe(2, "chat");
+
+        subscriptions.remove_channel(1);
+
+        assert_eq!(subscriptions.subscribers("chat").collect::<Vec<_>>(), [2]);
+        assert_eq!(subscriptions.subscribers("presence").count(), 0);
+    }
+}
@@ -0,0 +1,219 @@
+//! Connection-quality monitoring.
+//!
+//! A `QualityMonitor` turns periodic samples of an association's SCTP
+//! signals into a quality score between 0 and 100, and notifies its
+//! subscribers whenever the score crosses into a different
+//! `QualityLevel`, so applications can adapt their codecs, rates or
+//! reliability modes as conditions change.
+
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+    time::Duration,
+};
+
+// Penalties subtracted from a perfect score, per unit of each signal, and their caps.
+const RTT_PENALTY_PER: Duration = Duration::from_millis(10);
+const MAX_RTT_PENALTY: u64 = 40;
+const LOSS_PENALTY: u64 = 5;
+const MAX_LOSS_PENALTY: u64 = 40;
+const STALL_PENALTY: u64 = 10;
+const MAX_STALL_PENALTY: u64 = 20;
+
+/// A snapshot of an association's SCTP signals.
+///
+/// All counters are cumulative since the association was established.
+#[derive(Eq, PartialEq, Default, Copy, Clone, Debug)]
+pub struct QualitySignals {
+    /// The smoothed round-trip time, once measured.
+    pub rtt: Option<Duration>,
+    /// The number of retransmitted chunks.
+    pub retransmits: u64,
+    /// The number of messages abandoned by partial reliability.
+    pub abandoned: u64,
+    /// The number of times sending stalled on a zero receiver window.
+    pub rwnd_stalls: u64,
+}
+
+/// A coarse classification of a quality score.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Debug)]
+pub enum QualityLevel {
+    Poor,
+    Fair,
+    Good,
+    Excellent,
+}
+
+impl QualityLevel {
+    pub fn of(score: u8) -> Self {
+        match score {
+            80..=u8::MAX => Self::Excellent,
+            60..=79 => Self::Good,
+            40..=59 => Self::Fair,
+            _ => Self::Poor,
+        }
+    }
+}
+
+/// Published whenever the quality score crosses into a different level.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub struct QualityEvent {
+    pub previous: QualityLevel,
+    pub current: QualityLevel,
+    pub score: u8,
+}
+
+/// QualityMonitor aggregates samples of `QualitySignals` into a quality score.
+///
+/// Each sample is scored by its round-trip time, and by the retransmits,
+/// abandoned messages and receiver window stalls which happened since the
+/// previous sample; so sampling at a steady interval keeps scores comparable.
+#[derive(Debug)]
+pub struct QualityMonitor {
+    previous: Option<QualitySignals>,
+    score: u8,
+    subscribers: Mutex<Vec<Sender<QualityEvent>>>,
+}
+
+impl Default for QualityMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QualityMonitor {
+    pub fn new() -> Self {
+        Self {
+            previous: None,
+            score: 100,
+            subscribers: Mutex::new(vec![]),
+        }
+    }
+
+    /// Score returns the score of the latest sample, or 100 before the first one.
+    pub fn score(&self) -> u8 {
+        self.score
+    }
+
+    pub fn level(&self) -> QualityLevel {
+        QualityLevel::of(self.score)
+    }
+
+    /// Subscribe returns a receiver for all level changes from here on.
+    pub fn subscribe(&self) -> Receiver<QualityEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    /// Record scores a new sample, notifying subscribers if the level changed.
+    /// Returns the new score.
+    pub fn record(&mut self, signals: QualitySignals) -> u8 {
+        let previous = self.previous.replace(signals).unwrap_or_default();
+
+        let rtt_penalty = signals
+            .rtt
+            .map(|rtt| (rtt.as_nanos() / RTT_PENALTY_PER.as_nanos()) as u64)
+            .unwrap_or_default()
+            .min(MAX_RTT_PENALTY);
+        let losses = signals.retransmits.saturating_sub(previous.retransmits)
+            + signals.abandoned.saturating_sub(previous.abandoned);
+        let loss_penalty = losses.saturating_mul(LOSS_PENALTY).min(MAX_LOSS_PENALTY);
+        let stalls = signals.rwnd_stalls.saturating_sub(previous.rwnd_stalls);
+        let stall_penalty = stalls.saturating_mul(STALL_PENALTY).min(MAX_STALL_PENALTY);
+
+        let score = 100u64.saturating_sub(rtt_penalty + loss_penalty + stall_penalty) as u8;
+
+        let previous_level = self.level();
+        self.score = score;
+        let current_level = self.level();
+        if current_level != previous_level {
+            self.publish(QualityEvent {
+                previous: previous_level,
+                current: current_level,
+                score,
+            });
+        }
+
+        score
+    }
+
+    fn publish(&self, event: QualityEvent) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(event).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn perfect_conditions() {
+        let mut monitor = QualityMonitor::new();
+
+        let score = monitor.record(QualitySignals {
+            rtt: Some(Duration::from_millis(5)),
+            ..Default::default()
+        });
+
+        assert_eq!(score, 100);
+        assert_eq!(monitor.level(), QualityLevel::Excellent);
+    }
+
+    #[test]
+    fn penalizes_changes_since_previous_sample() {
+        let mut monitor = QualityMonitor::new();
+        let events = monitor.subscribe();
+
+        let signals = QualitySignals {
+            rtt: Some(Duration::from_millis(100)),
+            retransmits: 4,
+            abandoned: 2,
+            rwnd_stalls: 1,
+        };
+        // 100 - 10 (rtt) - 30 (losses) - 10 (stalls)
+        assert_eq!(monitor.record(signals), 50);
+        assert_eq!(
+            events.try_recv(),
+            Ok(QualityEvent {
+                previous: QualityLevel::Excellent,
+                current: QualityLevel::Fair,
+                score: 50,
+            })
+        );
+
+        // Nothing was lost since.
+        assert_eq!(monitor.record(signals), 90);
+        assert_eq!(
+            events.try_recv(),
+            Ok(QualityEvent {
+                previous: QualityLevel::Fair,
+                current: QualityLevel::Excellent,
+                score: 90,
+            })
+        );
+
+        assert_eq!(monitor.record(signals), 90);
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn penalties_are_capped() {
+        let mut monitor = QualityMonitor::new();
+
+        let score = monitor.record(QualitySignals {
+            rtt: Some(Duration::from_secs(10)),
+            retransmits: 1000,
+            abandoned: 0,
+            rwnd_stalls: 1000,
+        });
+
+        assert_eq!(score, 0);
+        assert_eq!(monitor.level(), QualityLevel::Poor);
+    }
+}
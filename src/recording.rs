@@ -0,0 +1,15 @@
+//! Record and replay of data channel traffic.
+//!
+//! A `TrafficRecorder` attached to a channel's `Config` captures every
+//! inbound and outbound message, timestamped relative to the start of the
+//! recording. A `Replayer` reads such a recording back and feeds its inbound
+//! messages into a channel under test, at the original or an accelerated
+//! pace, to reproduce a reported session deterministically.
+
+mod recorded_message;
+mod recorder;
+mod replayer;
+
+pub use recorded_message::{Direction, RecordedMessage};
+pub use recorder::{Recorder, SharedRecorder, TrafficRecorder};
+pub use replayer::{Pace, Replayer};
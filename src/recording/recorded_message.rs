@@ -0,0 +1,223 @@
+use std::time::Duration;
+
+use bytes::{Buf, BufMut, Bytes};
+
+use crate::{
+    error::RecordedMessageError,
+    marshal::{Marshal, MarshalSize, Unmarshal},
+};
+
+// The first byte in a `RecordedMessage` that specifies its direction:
+const DIRECTION_INBOUND: u8 = 0x00;
+const DIRECTION_OUTBOUND: u8 = 0x01;
+
+const FLAG_IS_STRING: u8 = 0b0000_0001;
+
+pub(crate) const RECORDED_MESSAGE_HEADER_LEN: usize = 16;
+
+/// Whether a message was received from or sent to the remote.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+/// A single message captured by a `TrafficRecorder`.
+///
+/// Recordings are a plain concatenation of marshaled messages.
+///
+/// # Memory layout
+///
+/// ```plain
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |   Direction   |     Flags     |       Stream Identifier       |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// +                     Elapsed (microseconds)                    +
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                        Payload Length                         |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// |                            Payload                            |
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+///
+/// Bit 0 of the flags is set for text messages.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct RecordedMessage {
+    pub direction: Direction,
+    pub stream_identifier: u16,
+    /// Time elapsed since the recording started.
+    pub elapsed: Duration,
+    pub is_string: bool,
+    pub payload: Bytes,
+}
+
+impl MarshalSize for RecordedMessage {
+    fn marshal_size(&self) -> usize {
+        RECORDED_MESSAGE_HEADER_LEN + self.payload.len()
+    }
+}
+
+impl Unmarshal for RecordedMessage {
+    type Error = RecordedMessageError;
+
+    fn unmarshal_from<B>(buf: &mut B) -> Result<Self, Self::Error>
+    where
+        B: Buf,
+    {
+        let required_len = RECORDED_MESSAGE_HEADER_LEN;
+        if buf.remaining() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining(),
+            });
+        }
+
+        let direction = match buf.get_u8() {
+            DIRECTION_INBOUND => Direction::Inbound,
+            DIRECTION_OUTBOUND => Direction::Outbound,
+            invalid_direction => return Err(Self::Error::InvalidDirection { invalid_direction }),
+        };
+        let flags = buf.get_u8();
+        let stream_identifier = buf.get_u16();
+        let elapsed = Duration::from_micros(buf.get_u64());
+        let payload_len = buf.get_u32() as usize;
+
+        if buf.remaining() < payload_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len + payload_len,
+                actual: required_len + buf.remaining(),
+            });
+        }
+
+        let payload = buf.copy_to_bytes(payload_len);
+
+        Ok(Self {
+            direction,
+            stream_identifier,
+            elapsed,
+            is_string: flags & FLAG_IS_STRING != 0,
+            payload,
+        })
+    }
+}
+
+impl Marshal for RecordedMessage {
+    type Error = RecordedMessageError;
+
+    fn marshal_to<B>(&self, buf: &mut B) -> Result<usize, Self::Error>
+    where
+        B: BufMut,
+    {
+        let payload_len = self.payload.len();
+        if payload_len > u32::MAX as usize {
+            return Err(Self::Error::PayloadTooLong { len: payload_len });
+        }
+
+        let required_len = self.marshal_size();
+        if buf.remaining_mut() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining_mut(),
+            });
+        }
+
+        let direction = match self.direction {
+            Direction::Inbound => DIRECTION_INBOUND,
+            Direction::Outbound => DIRECTION_OUTBOUND,
+        };
+        let flags = if self.is_string { FLAG_IS_STRING } else { 0 };
+
+        buf.put_u8(direction);
+        buf.put_u8(flags);
+        buf.put_u16(self.stream_identifier);
+        buf.put_u64(self.elapsed.as_micros() as u64);
+        buf.put_u32(payload_len as u32);
+        buf.put_slice(&self.payload[..]);
+
+        Ok(required_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use super::*;
+
+    static MARSHALED_BYTES: [u8; 18] = [
+        0x01, // direction
+        0x01, // flags
+        0x00, 0x03, // stream identifier
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0xdc, // elapsed
+        0x00, 0x00, 0x00, 0x02, // payload length
+        0x68, 0x69, // payload
+    ];
+
+    #[test]
+    fn unmarshal_success() {
+        let mut bytes = Bytes::from_static(&MARSHALED_BYTES);
+
+        let message = RecordedMessage::unmarshal_from(&mut bytes).unwrap();
+
+        assert_eq!(message.direction, Direction::Outbound);
+        assert_eq!(message.stream_identifier, 3);
+        assert_eq!(message.elapsed, Duration::from_micros(1500));
+        assert!(message.is_string);
+        assert_eq!(&message.payload[..], b"hi");
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn unmarshal_invalid_direction() {
+        let mut bytes = BytesMut::from(&MARSHALED_BYTES[..]);
+        bytes[0] = 0x7f;
+
+        let result = RecordedMessage::unmarshal_from(&mut bytes);
+
+        assert_eq!(
+            result,
+            Err(RecordedMessageError::InvalidDirection {
+                invalid_direction: 0x7f
+            })
+        );
+    }
+
+    #[test]
+    fn unmarshal_truncated_payload() {
+        let mut bytes = Bytes::from_static(&MARSHALED_BYTES[..17]);
+
+        let result = RecordedMessage::unmarshal_from(&mut bytes);
+
+        assert_eq!(
+            result,
+            Err(RecordedMessageError::UnexpectedEndOfBuffer {
+                expected: 18,
+                actual: 17
+            })
+        );
+    }
+
+    #[test]
+    fn marshal() {
+        let message = RecordedMessage {
+            direction: Direction::Outbound,
+            stream_identifier: 3,
+            elapsed: Duration::from_micros(1500),
+            is_string: true,
+            payload: Bytes::from_static(b"hi"),
+        };
+
+        let mut buf = BytesMut::with_capacity(message.marshal_size());
+        let bytes_written = message.marshal_to(&mut buf).unwrap();
+        let bytes = buf.freeze();
+
+        assert_eq!(bytes_written, message.marshal_size());
+        assert_eq!(&bytes[..], &MARSHALED_BYTES);
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use bytes::Bytes;
+
+use crate::{
+    clock::SharedClock,
+    marshal::Marshal,
+    recording::{Direction, RecordedMessage},
+};
+
+/// A receiver of the messages passing through a data channel.
+pub trait Recorder: Send + Sync {
+    fn record(&self, stream_identifier: u16, direction: Direction, is_string: bool, payload: &[u8]);
+}
+
+/// A `Recorder` writing marshaled `RecordedMessage`s to the wrapped writer,
+/// timestamped relative to the recorder's creation.
+#[derive(Debug)]
+pub struct TrafficRecorder<W> {
+    writer: Mutex<W>,
+    clock: SharedClock,
+    started_at: Instant,
+}
+
+impl<W> TrafficRecorder<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_clock(writer, SharedClock::default())
+    }
+
+    pub fn with_clock(writer: W, clock: SharedClock) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            started_at: clock.now(),
+            clock,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<W> Recorder for TrafficRecorder<W>
+where
+    W: Write + Send,
+{
+    fn record(
+        &self,
+        stream_identifier: u16,
+        direction: Direction,
+        is_string: bool,
+        payload: &[u8],
+    ) {
+        let message = RecordedMessage {
+            direction,
+            stream_identifier,
+            elapsed: self.clock.now().saturating_duration_since(self.started_at),
+            is_string,
+            payload: Bytes::copy_from_slice(payload),
+        };
+        let bytes = match message.marshal() {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                log::warn!("Failed to marshal recorded message: {:?}", error);
+                return;
+            }
+        };
+
+        let mut writer = self
+            .writer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Err(error) = writer.write_all(&bytes[..]) {
+            log::warn!("Failed to write recorded message: {:?}", error);
+        }
+    }
+}
+
+/// A cloneable handle to a `Recorder`, suitable for storing in a `Config`.
+///
+/// Two handles compare equal if they point to the same recorder.
+#[derive(Clone)]
+pub struct SharedRecorder(Arc<dyn Recorder>);
+
+impl SharedRecorder {
+    pub fn new<R>(recorder: R) -> Self
+    where
+        R: Recorder + 'static,
+    {
+        Self(Arc::new(recorder))
+    }
+
+    pub fn record(
+        &self,
+        stream_identifier: u16,
+        direction: Direction,
+        is_string: bool,
+        payload: &[u8],
+    ) {
+        self.0
+            .record(stream_identifier, direction, is_string, payload)
+    }
+}
+
+impl From<Arc<dyn Recorder>> for SharedRecorder {
+    fn from(recorder: Arc<dyn Recorder>) -> Self {
+        Self(recorder)
+    }
+}
+
+impl PartialEq for SharedRecorder {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedRecorder {}
+
+impl std::fmt::Debug for SharedRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SharedRecorder").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::marshal::Unmarshal;
+
+    use super::*;
+
+    #[test]
+    fn records_marshaled_messages() {
+        let recorder = TrafficRecorder::new(Vec::new());
+
+        recorder.record(1, Direction::Inbound, false, b"ping");
+        recorder.record(1, Direction::Outbound, true, b"pong");
+
+        let mut bytes = Bytes::from(recorder.into_inner());
+        let inbound = RecordedMessage::unmarshal_from(&mut bytes).unwrap();
+        let outbound = RecordedMessage::unmarshal_from(&mut bytes).unwrap();
+
+        assert!(bytes.is_empty());
+        assert_eq!(inbound.direction, Direction::Inbound);
+        assert!(!inbound.is_string);
+        assert_eq!(&inbound.payload[..], b"ping");
+        assert_eq!(outbound.direction, Direction::Outbound);
+        assert!(outbound.is_string);
+        assert_eq!(&outbound.payload[..], b"pong");
+        assert!(inbound.elapsed <= outbound.elapsed);
+    }
+}
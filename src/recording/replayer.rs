@@ -0,0 +1,273 @@
+use std::{
+    io::{ErrorKind, Read},
+    time::{Duration, Instant},
+};
+
+use bytes::BytesMut;
+
+use crate::{
+    clock::SharedClock,
+    data_channel::DataChannel,
+    error::ReplayError,
+    marshal::Unmarshal,
+    recording::{recorded_message::RECORDED_MESSAGE_HEADER_LEN, Direction, RecordedMessage},
+};
+
+// Offset of the payload length within the header of a `RecordedMessage`.
+const PAYLOAD_LEN_OFFSET: usize = 12;
+
+/// The pace at which a `Replayer` feeds messages into a channel.
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
+pub enum Pace {
+    /// Preserves the original gaps between messages.
+    #[default]
+    Original,
+    /// Divides the original gaps between messages by the given factor.
+    ///
+    /// Factors that are not positive are treated like `Pace::Immediate`.
+    Accelerated(f64),
+    /// Feeds messages back to back, without any delay.
+    Immediate,
+}
+
+impl Pace {
+    /// Delay returns how long after the first replayed message a message
+    /// recorded `elapsed` after it is due.
+    pub fn delay(&self, elapsed: Duration) -> Duration {
+        match *self {
+            Self::Original => elapsed,
+            Self::Accelerated(factor) if factor > 0.0 => elapsed.div_f64(factor),
+            Self::Accelerated(_) | Self::Immediate => Duration::ZERO,
+        }
+    }
+}
+
+/// Replayer reads a recording made by a `TrafficRecorder` and feeds its
+/// inbound messages into a channel under test.
+pub struct Replayer<R> {
+    reader: R,
+    pace: Pace,
+    clock: SharedClock,
+}
+
+impl<R> Replayer<R>
+where
+    R: Read,
+{
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            pace: Pace::default(),
+            clock: SharedClock::default(),
+        }
+    }
+
+    pub fn with_pace(mut self, pace: Pace) -> Self {
+        self.pace = pace;
+        self
+    }
+
+    /// WithClock sets the clock messages are paced with.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn pace(&self) -> Pace {
+        self.pace
+    }
+
+    /// ReadMessage reads the next recorded message, in either direction,
+    /// returning `None` once the recording is exhausted.
+    pub fn read_message(&mut self) -> Result<Option<RecordedMessage>, ReplayError> {
+        let mut header = [0u8; RECORDED_MESSAGE_HEADER_LEN];
+
+        // A recording may only end in between messages.
+        let n = loop {
+            match self.reader.read(&mut header) {
+                Ok(n) => break n,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error.into()),
+            }
+        };
+        if n == 0 {
+            return Ok(None);
+        }
+        self.reader.read_exact(&mut header[n..])?;
+
+        let mut payload_len = [0u8; 4];
+        payload_len.copy_from_slice(&header[PAYLOAD_LEN_OFFSET..]);
+        let payload_len = u32::from_be_bytes(payload_len) as usize;
+
+        let mut buf = BytesMut::zeroed(RECORDED_MESSAGE_HEADER_LEN + payload_len);
+        buf[..RECORDED_MESSAGE_HEADER_LEN].copy_from_slice(&header);
+        self.reader
+            .read_exact(&mut buf[RECORDED_MESSAGE_HEADER_LEN..])?;
+
+        let message = RecordedMessage::unmarshal_from(&mut buf)?;
+
+        Ok(Some(message))
+    }
+
+    /// ReplayWith passes each recorded inbound message to `f` once it is due
+    /// according to the replayer's pace, returning the number of messages replayed.
+    ///
+    /// Timing is relative to the first inbound message, which is replayed immediately.
+    pub fn replay_with<F>(&mut self, mut f: F) -> Result<usize, ReplayError>
+    where
+        F: FnMut(&RecordedMessage) -> Result<(), ReplayError>,
+    {
+        let mut origin: Option<(Instant, Duration)> = None;
+        let mut replayed = 0;
+
+        while let Some(message) = self.read_message()? {
+            if message.direction != Direction::Inbound {
+                continue;
+            }
+
+            let (started_at, first_elapsed) =
+                *origin.get_or_insert_with(|| (self.clock.now(), message.elapsed));
+            let due = self
+                .pace
+                .delay(message.elapsed.saturating_sub(first_elapsed));
+            let replaying_for = self.clock.now().saturating_duration_since(started_at);
+            if let Some(remaining) = due.checked_sub(replaying_for) {
+                self.clock.sleep(remaining);
+            }
+
+            f(&message)?;
+            replayed += 1;
+        }
+
+        Ok(replayed)
+    }
+
+    /// Replay writes each recorded inbound message to `data_channel`, which
+    /// is expected to be the remote end of the channel under test.
+    pub fn replay(&mut self, data_channel: &mut DataChannel) -> Result<usize, ReplayError> {
+        self.replay_with(|message| {
+            let mut payload = message.payload.clone();
+            data_channel.write_data_channel(&mut payload, message.is_string)?;
+            Ok(())
+        })
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::{
+        clock::{Clock, ManualClock},
+        error::RecordedMessageError,
+        marshal::Marshal,
+    };
+
+    use super::*;
+
+    fn recording(messages: &[RecordedMessage]) -> Vec<u8> {
+        messages
+            .iter()
+            .flat_map(|message| message.marshal().unwrap().to_vec())
+            .collect()
+    }
+
+    fn message(direction: Direction, elapsed_ms: u64, payload: &'static [u8]) -> RecordedMessage {
+        RecordedMessage {
+            direction,
+            stream_identifier: 1,
+            elapsed: Duration::from_millis(elapsed_ms),
+            is_string: false,
+            payload: Bytes::from_static(payload),
+        }
+    }
+
+    #[test]
+    fn pace_delay() {
+        let elapsed = Duration::from_millis(100);
+
+        assert_eq!(Pace::Original.delay(elapsed), elapsed);
+        assert_eq!(
+            Pace::Accelerated(4.0).delay(elapsed),
+            Duration::from_millis(25)
+        );
+        assert_eq!(Pace::Accelerated(0.0).delay(elapsed), Duration::ZERO);
+        assert_eq!(Pace::Immediate.delay(elapsed), Duration::ZERO);
+    }
+
+    #[test]
+    fn replays_inbound_messages_only() {
+        let bytes = recording(&[
+            message(Direction::Inbound, 10, b"one"),
+            message(Direction::Outbound, 20, b"reply"),
+            message(Direction::Inbound, 30, b"two"),
+        ]);
+        let mut replayer = Replayer::new(&bytes[..]).with_pace(Pace::Immediate);
+
+        let mut payloads = Vec::new();
+        let replayed = replayer
+            .replay_with(|message| {
+                payloads.push(message.payload.clone());
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(replayed, 2);
+        assert_eq!(payloads, vec![&b"one"[..], &b"two"[..]]);
+    }
+
+    #[test]
+    fn replay_preserves_relative_timing() {
+        let bytes = recording(&[
+            message(Direction::Inbound, 1_000, b"one"),
+            message(Direction::Inbound, 1_040, b"two"),
+        ]);
+        let clock = ManualClock::new();
+        let mut replayer = Replayer::new(&bytes[..])
+            .with_pace(Pace::Accelerated(2.0))
+            .with_clock(SharedClock::new(clock.clone()));
+
+        let started_at = clock.now();
+        let mut offsets = Vec::new();
+        replayer
+            .replay_with(|_| {
+                offsets.push(clock.now() - started_at);
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(offsets, vec![Duration::ZERO, Duration::from_millis(20)]);
+    }
+
+    #[test]
+    fn read_message_truncated_recording() {
+        let bytes = recording(&[message(Direction::Inbound, 0, b"one")]);
+        let mut replayer = Replayer::new(&bytes[..bytes.len() - 1]);
+
+        let result = replayer.read_message();
+
+        assert_eq!(
+            result,
+            Err(ReplayError::Io {
+                kind: ErrorKind::UnexpectedEof
+            })
+        );
+
+        let mut bytes = bytes;
+        bytes[0] = 0x7f;
+        let mut replayer = Replayer::new(&bytes[..]);
+
+        assert_eq!(
+            replayer.read_message(),
+            Err(ReplayError::RecordedMessage(
+                RecordedMessageError::InvalidDirection {
+                    invalid_direction: 0x7f
+                }
+            ))
+        );
+    }
+}
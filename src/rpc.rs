@@ -0,0 +1,21 @@
+//! A minimal request/response protocol multiplexed over a single data channel.
+//!
+//! Every data channel message carries exactly one `Frame`. Requests sent by an
+//! `RpcClient` are answered by an `RpcServer` on the other end of the channel
+//! with a response or error frame carrying the same correlation ID.
+
+#[cfg(feature = "rpc")]
+mod client;
+mod frame;
+#[cfg(feature = "rpc")]
+mod server;
+#[cfg(feature = "tower")]
+mod service;
+
+#[cfg(feature = "rpc")]
+pub use client::{ResponseFuture, RpcClient};
+pub use frame::{Frame, FrameKind};
+#[cfg(feature = "rpc")]
+pub use server::RpcServer;
+#[cfg(feature = "tower")]
+pub use service::DataChannelService;
@@ -0,0 +1,319 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll, Waker},
+    time::Duration,
+};
+
+use bytes::Bytes;
+
+use crate::{
+    buffer_provider::SharedBufferProvider,
+    clock::{BoxDelay, SharedClock},
+    data_channel::{DataChannel, RECEIVE_MTU},
+    error::{DataChannelError, RpcError},
+    marshal::{Marshal, Unmarshal},
+    rpc::{Frame, FrameKind},
+};
+
+#[derive(Default)]
+struct Call {
+    waker: Option<Waker>,
+    response: Option<Result<Bytes, RpcError>>,
+}
+
+#[derive(Default)]
+struct State {
+    next_correlation_id: u32,
+    // The call currently polling the data channel for incoming frames.
+    reader: Option<u32>,
+    calls: HashMap<u32, Call>,
+    // Marshaled cancel frames of abandoned calls, waiting to be written.
+    cancellations: VecDeque<Bytes>,
+}
+
+impl State {
+    fn wake_all(&mut self) {
+        for call in self.calls.values_mut() {
+            if let Some(waker) = call.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+struct Shared {
+    data_channel: Mutex<DataChannel>,
+    buffer_provider: SharedBufferProvider,
+    clock: SharedClock,
+    state: Mutex<State>,
+}
+
+/// RpcClient sends requests over a data channel and resolves each call once
+/// the response frame carrying the same correlation ID arrives.
+///
+/// Any number of calls may be in flight at once; whichever call is being
+/// polled reads incoming frames on behalf of all others. Dropping a call
+/// before it resolves, or letting it time out, cancels it on the remote.
+#[derive(Clone)]
+pub struct RpcClient {
+    shared: Arc<Shared>,
+}
+
+impl RpcClient {
+    pub fn new(data_channel: DataChannel) -> Self {
+        let shared = Arc::new(Shared {
+            buffer_provider: data_channel.config.buffer_provider.clone(),
+            clock: data_channel.config.clock.clone(),
+            data_channel: Mutex::new(data_channel),
+            state: Mutex::new(State::default()),
+        });
+
+        Self { shared }
+    }
+
+    /// Call sends a request and waits for its response
+    pub fn call(&self, payload: Bytes) -> ResponseFuture {
+        self.call_with(payload, None)
+    }
+
+    /// CallWithTimeout sends a request and waits for its response for at most `timeout`
+    pub fn call_with_timeout(&self, payload: Bytes, timeout: Duration) -> ResponseFuture {
+        self.call_with(payload, Some(timeout))
+    }
+
+    fn call_with(&self, payload: Bytes, timeout: Option<Duration>) -> ResponseFuture {
+        let correlation_id = {
+            let mut state = self.shared.state.lock().unwrap();
+            let mut correlation_id = state.next_correlation_id;
+            while state.calls.contains_key(&correlation_id) {
+                correlation_id = correlation_id.wrapping_add(1);
+            }
+            state.next_correlation_id = correlation_id.wrapping_add(1);
+            state.calls.insert(correlation_id, Call::default());
+            correlation_id
+        };
+
+        let request = Frame::new(FrameKind::Request, correlation_id, payload)
+            .marshal()
+            .map_err(From::from);
+
+        ResponseFuture {
+            shared: self.shared.clone(),
+            correlation_id,
+            request: Some(request),
+            timeout: timeout.map(|timeout| self.shared.clock.delay(timeout)),
+            finished: false,
+        }
+    }
+}
+
+/// The future returned by `RpcClient::call`.
+pub struct ResponseFuture {
+    shared: Arc<Shared>,
+    correlation_id: u32,
+    // The marshaled request frame, until it has been written.
+    request: Option<Result<Bytes, DataChannelError>>,
+    timeout: Option<BoxDelay>,
+    finished: bool,
+}
+
+impl ResponseFuture {
+    fn poll_write_cancellations(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), DataChannelError>> {
+        loop {
+            let cancellation = self.shared.state.lock().unwrap().cancellations.pop_front();
+            let mut cancellation = match cancellation {
+                Some(cancellation) => cancellation,
+                None => return Poll::Ready(Ok(())),
+            };
+
+            let mut data_channel = self.shared.data_channel.lock().unwrap();
+            match data_channel.poll_write_data_channel(cx, &mut cancellation, false) {
+                Poll::Ready(result) => {
+                    result?;
+                }
+                Poll::Pending => {
+                    let mut state = self.shared.state.lock().unwrap();
+                    state.cancellations.push_front(cancellation);
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+
+    fn poll_write_request(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), DataChannelError>> {
+        if self.request.is_none() {
+            return Poll::Ready(Ok(()));
+        }
+
+        ready!(self.poll_write_cancellations(cx))?;
+
+        let request = match &mut self.request {
+            Some(Ok(request)) => request,
+            Some(Err(error)) => return Poll::Ready(Err(error.clone())),
+            None => return Poll::Ready(Ok(())),
+        };
+
+        let mut data_channel = self.shared.data_channel.lock().unwrap();
+        ready!(data_channel.poll_write_data_channel(cx, request, false))?;
+
+        self.request = None;
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_timeout(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        match &mut self.timeout {
+            Some(timeout) => timeout.as_mut().poll(cx),
+            None => Poll::Pending,
+        }
+    }
+
+    fn dispatch(&self, frame: Frame) -> Option<Result<Bytes, RpcError>> {
+        let response = match frame.kind {
+            FrameKind::Response => Ok(frame.payload),
+            FrameKind::Error => Err(RpcError::Remote {
+                message: String::from_utf8_lossy(&frame.payload[..]).into_owned(),
+            }),
+            kind => {
+                log::warn!("Dropping unexpected {:?} frame", kind);
+                return None;
+            }
+        };
+
+        if frame.correlation_id == self.correlation_id {
+            return Some(response);
+        }
+
+        let mut state = self.shared.state.lock().unwrap();
+        match state.calls.get_mut(&frame.correlation_id) {
+            Some(call) => {
+                call.response = Some(response);
+                if let Some(waker) = call.waker.take() {
+                    waker.wake();
+                }
+            }
+            None => log::debug!(
+                "Dropping response with unknown correlation ID: {}",
+                frame.correlation_id
+            ),
+        }
+
+        None
+    }
+
+    fn finish(&mut self, result: Result<Bytes, RpcError>) -> Poll<Result<Bytes, RpcError>> {
+        self.release();
+
+        Poll::Ready(result)
+    }
+
+    fn release(&mut self) {
+        self.finished = true;
+
+        let mut state = self.shared.state.lock().unwrap();
+        state.calls.remove(&self.correlation_id);
+        if state.reader == Some(self.correlation_id) {
+            state.reader = None;
+            state.wake_all();
+        }
+    }
+
+    fn cancel(&mut self) {
+        // A request which was never written needs no cancellation.
+        if self.request.is_some() {
+            return;
+        }
+
+        let cancellation = Frame::new(FrameKind::Cancel, self.correlation_id, Bytes::new());
+        match cancellation.marshal() {
+            Ok(cancellation) => {
+                let mut state = self.shared.state.lock().unwrap();
+                state.cancellations.push_back(cancellation);
+            }
+            Err(error) => log::warn!("Failed to marshal cancel frame: {:?}", error),
+        }
+    }
+}
+
+impl Future for ResponseFuture {
+    type Output = Result<Bytes, RpcError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if this.poll_timeout(cx).is_ready() {
+            this.cancel();
+            return this.finish(Err(RpcError::Timeout));
+        }
+
+        if let Err(error) = ready!(this.poll_write_request(cx)) {
+            return this.finish(Err(error.into()));
+        }
+
+        loop {
+            {
+                let mut state = this.shared.state.lock().unwrap();
+                let call = state.calls.entry(this.correlation_id).or_default();
+
+                if let Some(response) = call.response.take() {
+                    drop(state);
+                    return this.finish(response);
+                }
+
+                match state.reader {
+                    Some(reader) if reader != this.correlation_id => {
+                        let call = state.calls.entry(this.correlation_id).or_default();
+                        call.waker = Some(cx.waker().clone());
+                        return Poll::Pending;
+                    }
+                    _ => state.reader = Some(this.correlation_id),
+                }
+            }
+
+            let buffer_provider = &this.shared.buffer_provider;
+            let mut buf = buffer_provider.acquire(RECEIVE_MTU);
+            let result = {
+                let mut data_channel = this.shared.data_channel.lock().unwrap();
+                data_channel.poll_read_data_channel(cx, &mut buf)
+            };
+
+            let n = match result {
+                Poll::Ready(Ok((n, _))) => n,
+                Poll::Ready(Err(error)) => {
+                    buffer_provider.release(buf);
+                    return this.finish(Err(error.into()));
+                }
+                Poll::Pending => {
+                    buffer_provider.release(buf);
+                    return Poll::Pending;
+                }
+            };
+
+            let mut bytes = buf.split_to(n).freeze();
+            buffer_provider.release(buf);
+            match Frame::unmarshal_from(&mut bytes) {
+                Ok(frame) => {
+                    if let Some(response) = this.dispatch(frame) {
+                        return this.finish(response);
+                    }
+                }
+                Err(error) => log::warn!("Dropping malformed frame: {:?}", error),
+            }
+        }
+    }
+}
+
+impl Drop for ResponseFuture {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.cancel();
+            self.release();
+        }
+    }
+}
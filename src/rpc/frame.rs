@@ -0,0 +1,214 @@
+use bytes::{Buf, BufMut, Bytes};
+
+use crate::{
+    error::FrameError,
+    marshal::{Marshal, MarshalSize, Unmarshal},
+};
+
+// The first byte in a `Frame` that specifies its kind:
+const FRAME_KIND_REQUEST: u8 = 0x00;
+const FRAME_KIND_RESPONSE: u8 = 0x01;
+const FRAME_KIND_ERROR: u8 = 0x02;
+const FRAME_KIND_CANCEL: u8 = 0x03;
+
+const FRAME_HEADER_LEN: usize = 5;
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum FrameKind {
+    Request,
+    Response,
+    // The handler failed; the payload carries a human-readable description.
+    Error,
+    // The caller is no longer interested in the response; the payload is empty.
+    Cancel,
+}
+
+/// A request or response multiplexed over a data channel,
+/// with each data channel message carrying exactly one frame.
+///
+/// # Memory layout
+///
+/// ```plain
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |     Kind      |                Correlation ID                 |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |               |                                               |
+/// +-+-+-+-+-+-+-+-+                                               |
+/// |                            Payload                            |
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Frame {
+    pub kind: FrameKind,
+    pub correlation_id: u32,
+    pub payload: Bytes,
+}
+
+impl Frame {
+    pub fn new(kind: FrameKind, correlation_id: u32, payload: Bytes) -> Self {
+        Self {
+            kind,
+            correlation_id,
+            payload,
+        }
+    }
+}
+
+impl MarshalSize for Frame {
+    fn marshal_size(&self) -> usize {
+        FRAME_HEADER_LEN + self.payload.len()
+    }
+}
+
+impl Unmarshal for Frame {
+    type Error = FrameError;
+
+    fn unmarshal_from<B>(buf: &mut B) -> Result<Self, Self::Error>
+    where
+        B: Buf,
+    {
+        let required_len = FRAME_HEADER_LEN;
+        if buf.remaining() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining(),
+            });
+        }
+
+        let kind = match buf.get_u8() {
+            FRAME_KIND_REQUEST => FrameKind::Request,
+            FRAME_KIND_RESPONSE => FrameKind::Response,
+            FRAME_KIND_ERROR => FrameKind::Error,
+            FRAME_KIND_CANCEL => FrameKind::Cancel,
+            invalid_kind => return Err(Self::Error::InvalidFrameKind { invalid_kind }),
+        };
+        let correlation_id = buf.get_u32();
+        let payload = buf.copy_to_bytes(buf.remaining());
+
+        Ok(Self {
+            kind,
+            correlation_id,
+            payload,
+        })
+    }
+}
+
+impl Marshal for Frame {
+    type Error = FrameError;
+
+    fn marshal_to<B>(&self, buf: &mut B) -> Result<usize, Self::Error>
+    where
+        B: BufMut,
+    {
+        let required_len = self.marshal_size();
+        if buf.remaining_mut() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining_mut(),
+            });
+        }
+
+        let kind = match self.kind {
+            FrameKind::Request => FRAME_KIND_REQUEST,
+            FrameKind::Response => FRAME_KIND_RESPONSE,
+            FrameKind::Error => FRAME_KIND_ERROR,
+            FrameKind::Cancel => FRAME_KIND_CANCEL,
+        };
+
+        buf.put_u8(kind);
+        buf.put_u32(self.correlation_id);
+        buf.put_slice(&self.payload[..]);
+
+        Ok(required_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use super::*;
+
+    static MARSHALED_BYTES: [u8; 9] = [
+        0x01, // kind
+        0x00, 0x00, 0x01, 0x02, // correlation id
+        0x70, 0x6f, 0x6e, 0x67, // payload
+    ];
+
+    #[test]
+    fn unmarshal_success() {
+        let mut bytes = Bytes::from_static(&MARSHALED_BYTES);
+
+        let frame = Frame::unmarshal_from(&mut bytes).unwrap();
+
+        assert_eq!(frame.kind, FrameKind::Response);
+        assert_eq!(frame.correlation_id, 258);
+        assert_eq!(&frame.payload[..], b"pong");
+    }
+
+    #[test]
+    fn unmarshal_empty_payload() {
+        let mut bytes = Bytes::from_static(&[0x00, 0x00, 0x00, 0x00, 0x07]);
+
+        let frame = Frame::unmarshal_from(&mut bytes).unwrap();
+
+        assert_eq!(frame.kind, FrameKind::Request);
+        assert_eq!(frame.correlation_id, 7);
+        assert!(frame.payload.is_empty());
+    }
+
+    #[test]
+    fn unmarshal_cancel() {
+        let mut bytes = Bytes::from_static(&[0x03, 0x00, 0x00, 0x00, 0x2a]);
+
+        let frame = Frame::unmarshal_from(&mut bytes).unwrap();
+
+        assert_eq!(frame, Frame::new(FrameKind::Cancel, 42, Bytes::new()));
+    }
+
+    #[test]
+    fn unmarshal_invalid_frame_kind() {
+        let mut bytes = Bytes::from_static(&[0x7f, 0x00, 0x00, 0x00, 0x00]);
+
+        let result = Frame::unmarshal_from(&mut bytes);
+
+        assert_eq!(
+            result,
+            Err(FrameError::InvalidFrameKind { invalid_kind: 0x7f })
+        );
+    }
+
+    #[test]
+    fn unmarshal_unexpected_end_of_buffer() {
+        let mut bytes = Bytes::from_static(&[0x00, 0x00]);
+
+        let result = Frame::unmarshal_from(&mut bytes);
+
+        assert_eq!(
+            result,
+            Err(FrameError::UnexpectedEndOfBuffer {
+                expected: 5,
+                actual: 2
+            })
+        );
+    }
+
+    #[test]
+    fn marshal() {
+        let frame = Frame {
+            kind: FrameKind::Response,
+            correlation_id: 258,
+            payload: Bytes::from_static(b"pong"),
+        };
+
+        let mut buf = BytesMut::with_capacity(frame.marshal_size());
+        let bytes_written = frame.marshal_to(&mut buf).unwrap();
+        let bytes = buf.freeze();
+
+        assert_eq!(bytes_written, frame.marshal_size());
+        assert_eq!(&bytes[..], &MARSHALED_BYTES);
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+
+use crate::{
+    data_channel::{DataChannel, RECEIVE_MTU},
+    error::DataChannelError,
+    marshal::{Marshal, Unmarshal},
+    rpc::{Frame, FrameKind},
+    sctp::StreamError,
+};
+
+type HandlerFuture = Pin<Box<dyn Future<Output = Result<Bytes, String>> + Send>>;
+
+struct InFlight {
+    correlation_id: u32,
+    future: HandlerFuture,
+}
+
+/// RpcServer answers requests sent by an `RpcClient` on the other end of
+/// the data channel, invoking the registered handler for each of them.
+///
+/// RpcServer is a future which drives all in-flight handlers concurrently
+/// and resolves once the channel has been closed by the remote. Cancelled
+/// requests have their handler futures dropped.
+pub struct RpcServer<H> {
+    data_channel: DataChannel,
+    handler: H,
+    in_flight: Vec<InFlight>,
+    // Marshaled response and error frames, waiting to be written.
+    outgoing: VecDeque<Bytes>,
+}
+
+impl<H, F> RpcServer<H>
+where
+    H: FnMut(Bytes) -> F,
+    F: Future<Output = Result<Bytes, String>> + Send + 'static,
+{
+    pub fn new(data_channel: DataChannel, handler: H) -> Self {
+        Self {
+            data_channel,
+            handler,
+            in_flight: Vec::new(),
+            outgoing: VecDeque::new(),
+        }
+    }
+
+    /// InFlight returns the number of requests currently being handled.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    pub fn into_inner(self) -> DataChannel {
+        self.data_channel
+    }
+
+    fn handle_frame(&mut self, frame: Frame) {
+        match frame.kind {
+            FrameKind::Request => {
+                let future = Box::pin((self.handler)(frame.payload));
+                self.in_flight.push(InFlight {
+                    correlation_id: frame.correlation_id,
+                    future,
+                });
+            }
+            FrameKind::Cancel => {
+                log::debug!("Cancelling request {}", frame.correlation_id);
+                self.in_flight
+                    .retain(|in_flight| in_flight.correlation_id != frame.correlation_id);
+            }
+            kind => log::warn!("Dropping unexpected {:?} frame", kind),
+        }
+    }
+
+    fn poll_in_flight(&mut self, cx: &mut Context<'_>) -> bool {
+        let mut progress = false;
+        let mut index = 0;
+        while index < self.in_flight.len() {
+            let in_flight = &mut self.in_flight[index];
+            let result = match in_flight.future.as_mut().poll(cx) {
+                Poll::Ready(result) => result,
+                Poll::Pending => {
+                    index += 1;
+                    continue;
+                }
+            };
+
+            let correlation_id = in_flight.correlation_id;
+            self.in_flight.swap_remove(index);
+            progress = true;
+
+            let frame = match result {
+                Ok(payload) => Frame::new(FrameKind::Response, correlation_id, payload),
+                Err(message) => Frame::new(FrameKind::Error, correlation_id, message.into()),
+            };
+            match frame.marshal() {
+                Ok(bytes) => self.outgoing.push_back(bytes),
+                Err(error) => log::warn!("Failed to marshal {:?} frame: {:?}", frame.kind, error),
+            }
+        }
+        progress
+    }
+}
+
+impl<H, F> Future for RpcServer<H>
+where
+    H: FnMut(Bytes) -> F + Unpin,
+    F: Future<Output = Result<Bytes, String>> + Send + 'static,
+{
+    type Output = Result<(), DataChannelError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        loop {
+            let mut progress = false;
+
+            let buffer_provider = this.data_channel.config.buffer_provider.clone();
+            let mut buf = buffer_provider.acquire(RECEIVE_MTU);
+            let result = this
+                .data_channel
+                .poll_read_data_channel(cx, &mut buf)
+                .map_ok(|(n, _)| buf.split_to(n).freeze());
+            buffer_provider.release(buf);
+
+            match result {
+                Poll::Ready(Ok(mut bytes)) => {
+                    progress = true;
+
+                    match Frame::unmarshal_from(&mut bytes) {
+                        Ok(frame) => this.handle_frame(frame),
+                        Err(error) => log::warn!("Dropping malformed frame: {:?}", error),
+                    }
+                }
+                Poll::Ready(Err(DataChannelError::Stream(StreamError::Eof))) => {
+                    return Poll::Ready(Ok(()));
+                }
+                Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+                Poll::Pending => {}
+            }
+
+            progress |= this.poll_in_flight(cx);
+
+            while let Some(frame) = this.outgoing.front_mut() {
+                match this.data_channel.poll_write_data_channel(cx, frame, false) {
+                    Poll::Ready(Ok(_)) => {
+                        this.outgoing.pop_front();
+                        progress = true;
+                    }
+                    Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+                    Poll::Pending => break,
+                }
+            }
+
+            if !progress {
+                return Poll::Pending;
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use tower_service::Service;
+
+use crate::{
+    data_channel::DataChannel,
+    error::RpcError,
+    rpc::{ResponseFuture, RpcClient},
+};
+
+/// DataChannelService exposes an `RpcClient` as a `tower::Service`,
+/// so middleware stacks can sit directly on top of a data channel.
+#[derive(Clone)]
+pub struct DataChannelService {
+    client: RpcClient,
+}
+
+impl DataChannelService {
+    pub fn new(data_channel: DataChannel) -> Self {
+        Self::from(RpcClient::new(data_channel))
+    }
+}
+
+impl From<RpcClient> for DataChannelService {
+    fn from(client: RpcClient) -> Self {
+        Self { client }
+    }
+}
+
+impl Service<Bytes> for DataChannelService {
+    type Response = Bytes;
+    type Error = RpcError;
+    type Future = ResponseFuture;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Bytes) -> Self::Future {
+        self.client.call(request)
+    }
+}
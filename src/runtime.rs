@@ -0,0 +1,258 @@
+//! Executors to run a channel's background work on.
+//!
+//! Channels themselves don't spawn anything, but work which is handed off
+//! to the background does so through a `Runtime`, which spawns tasks and
+//! tells time. The `tokio` and `smol` features provide implementations for
+//! those executors; embedders on any other executor can supply their own,
+//! so that the crate pulls in neither.
+//!
+//! Every task is named after its `TaskKind` and the channel it works for,
+//! and counted in the channel's stats until it completes. With the
+//! `tracing` feature, tasks run within a span carrying their name, and on
+//! tokio built with `--cfg tokio_unstable` they show up under their name
+//! in tokio-console.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{clock::BoxDelay, data_channel::DataChannel, observer::RunningTask};
+
+/// A task to be run to completion in the background.
+pub type BoxTask = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// The background work a task spawned for a channel does.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum TaskKind {
+    /// Hands the messages received on a channel to a callback.
+    Reader,
+    /// Writes the messages queued in an `Outbox`.
+    Writer,
+    /// Checks whether a channel is idle, periodically.
+    Timer,
+}
+
+impl TaskKind {
+    pub(crate) const ALL: [TaskKind; 3] = [TaskKind::Reader, TaskKind::Writer, TaskKind::Timer];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Reader => "reader",
+            Self::Writer => "writer",
+            Self::Timer => "timer",
+        }
+    }
+}
+
+/// An executor to spawn background tasks on, along with its timers.
+pub trait Runtime: Send + Sync {
+    /// Runs `task` in the background, detached from the caller, naming it
+    /// `name` if the executor supports task names.
+    fn spawn(&self, name: &str, task: BoxTask);
+
+    /// Returns a future resolving once `duration` has elapsed.
+    fn sleep(&self, duration: Duration) -> BoxDelay;
+
+    fn now(&self) -> Instant;
+}
+
+/// A `Runtime` spawning onto a tokio runtime.
+///
+/// Time is told by tokio, so that it stands still while paused with
+/// `tokio::time::pause`.
+#[cfg(feature = "tokio")]
+#[derive(Clone, Debug)]
+pub struct TokioRuntime(tokio::runtime::Handle);
+
+#[cfg(feature = "tokio")]
+impl TokioRuntime {
+    /// Current returns the tokio runtime the caller runs on.
+    ///
+    /// Panics if called outside of a tokio runtime.
+    pub fn current() -> Self {
+        Self(tokio::runtime::Handle::current())
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl From<tokio::runtime::Handle> for TokioRuntime {
+    fn from(handle: tokio::runtime::Handle) -> Self {
+        Self(handle)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Runtime for TokioRuntime {
+    fn spawn(&self, name: &str, task: BoxTask) {
+        #[cfg(all(tokio_unstable, feature = "tracing"))]
+        {
+            let spawned = tokio::task::Builder::new()
+                .name(name)
+                .spawn_on(task, &self.0);
+            if let Err(error) = spawned {
+                log::warn!("Failed to spawn task {}: {}", name, error);
+            }
+        }
+        #[cfg(not(all(tokio_unstable, feature = "tracing")))]
+        {
+            let _ = name;
+            drop(self.0.spawn(task));
+        }
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxDelay {
+        // Timers register with the runtime they are created on.
+        let _guard = self.0.enter();
+        Box::pin(tokio::time::sleep(duration))
+    }
+
+    fn now(&self) -> Instant {
+        let _guard = self.0.enter();
+        tokio::time::Instant::now().into_std()
+    }
+}
+
+/// A `Runtime` spawning onto smol's global executor.
+#[cfg(feature = "smol")]
+#[derive(Eq, PartialEq, Default, Copy, Clone, Debug)]
+pub struct SmolRuntime;
+
+#[cfg(feature = "smol")]
+impl Runtime for SmolRuntime {
+    fn spawn(&self, _name: &str, task: BoxTask) {
+        smol::spawn(task).detach();
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxDelay {
+        Box::pin(async move {
+            smol::Timer::after(duration).await;
+        })
+    }
+
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A cloneable handle to a `Runtime`.
+///
+/// Two handles compare equal if they point to the same runtime.
+#[derive(Clone)]
+pub struct SharedRuntime(Arc<dyn Runtime>);
+
+impl SharedRuntime {
+    pub fn new<R>(runtime: R) -> Self
+    where
+        R: Runtime + 'static,
+    {
+        Self(Arc::new(runtime))
+    }
+
+    pub fn spawn<F>(&self, name: &str, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        #[cfg(feature = "tracing")]
+        let task = tracing::Instrument::instrument(task, crate::trace::task_span(name));
+
+        self.0.spawn(name, Box::pin(task))
+    }
+
+    pub fn sleep(&self, duration: Duration) -> BoxDelay {
+        self.0.sleep(duration)
+    }
+
+    pub fn now(&self) -> Instant {
+        self.0.now()
+    }
+}
+
+impl DataChannel {
+    /// Spawns `task` on `runtime` to do the channel's background work of the
+    /// given kind, named after the channel and counted in its stats.
+    pub(crate) fn spawn_task<F>(&self, runtime: &SharedRuntime, kind: TaskKind, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let name = format!(
+            "data_channel.{} {:?} on stream {}",
+            kind.as_str(),
+            self.label().as_str(),
+            self.stream_identifier()
+        );
+        let running = RunningTask::new(self.observer().observed, kind, self.label());
+
+        runtime.spawn(&name, async move {
+            task.await;
+            drop(running);
+        });
+    }
+}
+
+impl From<Arc<dyn Runtime>> for SharedRuntime {
+    fn from(runtime: Arc<dyn Runtime>) -> Self {
+        Self(runtime)
+    }
+}
+
+impl PartialEq for SharedRuntime {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedRuntime {}
+
+impl std::fmt::Debug for SharedRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SharedRuntime").finish()
+    }
+}
+
+#[cfg(all(test, any(feature = "tokio", feature = "smol")))]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    // Spawns a task which reports how long it slept for.
+    fn spawn_sleeper(runtime: &SharedRuntime) -> mpsc::Receiver<Duration> {
+        let (sender, slept) = mpsc::channel();
+        let task_runtime = runtime.clone();
+        runtime.spawn("sleeper", async move {
+            let start = task_runtime.now();
+            task_runtime.sleep(Duration::from_millis(10)).await;
+            sender.send(task_runtime.now() - start).unwrap();
+        });
+        slept
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn tokio_runtime_spawns_and_sleeps() {
+        let tokio = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let runtime = SharedRuntime::new(TokioRuntime::from(tokio.handle().clone()));
+
+        let slept = spawn_sleeper(&runtime);
+        tokio.block_on(runtime.sleep(Duration::from_millis(50)));
+
+        assert!(slept.try_recv().unwrap() >= Duration::from_millis(10));
+    }
+
+    #[cfg(feature = "smol")]
+    #[test]
+    fn smol_runtime_spawns_and_sleeps() {
+        let runtime = SharedRuntime::new(SmolRuntime);
+
+        let slept = spawn_sleeper(&runtime);
+
+        let slept = slept.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(slept >= Duration::from_millis(10));
+    }
+}
@@ -1,33 +1,188 @@
+use bytes::{Buf, BufMut};
 use thiserror::Error;
 
+use crate::{
+    error::PayloadTypeError,
+    marshal::{Marshal, MarshalSize, Unmarshal},
+};
+
+pub(crate) mod simulated;
 mod stream;
 
-pub use stream::{Error as StreamError, Stream};
+pub use stream::{BufferedAmountLowFn, Error as StreamError, Stream};
 
 pub mod association {
     use super::*;
 
-    use crate::sctp::{PayloadType, Stream};
+    use std::task::{Context, Poll};
+
+    use crate::{
+        quality::QualitySignals,
+        sctp::{simulated, PayloadType, Stream},
+    };
 
     #[derive(Error, Eq, PartialEq, Clone, Debug)]
     pub enum Error {}
 
     #[derive(Debug)]
-    pub struct Association;
+    pub struct Association(Backend);
+
+    #[derive(Debug)]
+    enum Backend {
+        // The real SCTP stack, once 'webrtc-sctp' is integrated.
+        #[allow(dead_code)]
+        Sctp,
+        Simulated(simulated::Association),
+    }
+
+    impl From<simulated::Association> for Association {
+        fn from(association: simulated::Association) -> Self {
+            Self(Backend::Simulated(association))
+        }
+    }
 
     impl Association {
-        pub fn open_stream(&self, _id: u16, _payload_type: PayloadType) -> Result<Stream, Error> {
-            todo!()
+        pub fn open_stream(&self, id: u16, _payload_type: PayloadType) -> Result<Stream, Error> {
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => Ok(association.open_stream(id).into()),
+            }
         }
 
         pub fn accept_stream(&self) -> Result<Stream, Error> {
-            todo!()
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => Ok(association.accept_stream().into()),
+            }
+        }
+
+        /// PollAcceptStream resolves with the next stream opened by the remote,
+        /// registering the current task to be woken up once there is one.
+        pub fn poll_accept_stream(&self, cx: &mut Context<'_>) -> Poll<Result<Stream, Error>> {
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => association
+                    .poll_accept_stream(cx)
+                    .map(|stream| Ok(stream.into())),
+            }
+        }
+
+        pub fn try_accept_stream(&self) -> Result<Option<Stream>, Error> {
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => {
+                    Ok(association.try_accept_stream().map(Stream::from))
+                }
+            }
+        }
+
+        pub fn restore_stream(&self, id: u16) -> Result<Stream, Error> {
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => Ok(association.restore_stream(id).into()),
+            }
+        }
+
+        /// MaxOutboundStreams returns the number of outbound streams negotiated
+        /// for the association, which bounds the stream identifiers usable locally.
+        pub fn max_outbound_streams(&self) -> Result<u16, Error> {
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => Ok(association.max_outbound_streams()),
+            }
+        }
+
+        /// AddOutboundStreams requests `additional` outbound streams from the
+        /// remote, with an SCTP RE-CONFIG Add Outgoing Streams request (RFC 6525),
+        /// returning the number of outbound streams from here on.
+        pub fn add_outbound_streams(&self, additional: u16) -> Result<u16, Error> {
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => Ok(association.add_outbound_streams(additional)),
+            }
+        }
+
+        pub(crate) fn refuse_outbound_streams(&self) {
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => association.refuse_outbound_streams(),
+            }
+        }
+
+        pub(crate) fn set_max_outbound_streams(&self, max_outbound_streams: u16) {
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => {
+                    association.set_max_outbound_streams(max_outbound_streams)
+                }
+            }
+        }
+
+        pub fn quality_signals(&self) -> Result<QualitySignals, Error> {
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => Ok(association.quality_signals()),
+            }
+        }
+
+        pub(crate) fn set_quality_signals(&self, signals: QualitySignals) {
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => association.set_quality_signals(signals),
+            }
+        }
+
+        pub(crate) fn set_congestion_window(&self, congestion_window: u64) {
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => {
+                    association.set_congestion_window(congestion_window)
+                }
+            }
+        }
+
+        pub(crate) fn fail_writes(&self, id: u16, count: usize) {
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => association.fail_writes(id, count),
+            }
+        }
+
+        pub(crate) fn fail_reliability_params(&self, id: u16) {
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => association.fail_reliability_params(id),
+            }
+        }
+
+        pub(crate) fn set_max_message_size(&self, max_message_size: usize) {
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => {
+                    association.set_max_message_size(max_message_size)
+                }
+            }
+        }
+
+        pub(crate) fn hold_writes(&self, id: u16) {
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => association.hold_writes(id),
+            }
+        }
+
+        pub(crate) fn release_writes(&self, id: u16) {
+            match &self.0 {
+                Backend::Sctp => todo!(),
+                Backend::Simulated(association) => association.release_writes(id),
+            }
         }
     }
 }
 
 pub use association::{Association, Error as AssociationError};
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Eq, PartialEq, Copy, Clone, Debug)]
 pub enum PayloadType {
     WebRtcBinary,
@@ -69,9 +224,126 @@ impl PayloadType {
     }
 }
 
+// https://www.iana.org/assignments/sctp-parameters, as used by RFC 8831.
+const PAYLOAD_TYPE_WEBRTC_DCEP: u32 = 50;
+const PAYLOAD_TYPE_WEBRTC_STRING: u32 = 51;
+const PAYLOAD_TYPE_WEBRTC_BINARY: u32 = 53;
+const PAYLOAD_TYPE_WEBRTC_STRING_EMPTY: u32 = 56;
+const PAYLOAD_TYPE_WEBRTC_BINARY_EMPTY: u32 = 57;
+
+const PAYLOAD_TYPE_LEN: usize = 4;
+
+impl MarshalSize for PayloadType {
+    fn marshal_size(&self) -> usize {
+        PAYLOAD_TYPE_LEN
+    }
+}
+
+impl Unmarshal for PayloadType {
+    type Error = PayloadTypeError;
+
+    fn unmarshal_from<B>(buf: &mut B) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+        B: Buf,
+    {
+        let required_len = PAYLOAD_TYPE_LEN;
+        if buf.remaining() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining(),
+            });
+        }
+
+        let identifier = buf.get_u32();
+
+        match identifier {
+            PAYLOAD_TYPE_WEBRTC_DCEP => Ok(Self::WebRtcDcep),
+            PAYLOAD_TYPE_WEBRTC_STRING => Ok(Self::WebRtcString),
+            PAYLOAD_TYPE_WEBRTC_BINARY => Ok(Self::WebRtcBinary),
+            PAYLOAD_TYPE_WEBRTC_STRING_EMPTY => Ok(Self::WebRtcStringEmpty),
+            PAYLOAD_TYPE_WEBRTC_BINARY_EMPTY => Ok(Self::WebRtcBinaryEmpty),
+            _ => Err(Self::Error::InvalidPayloadType {
+                invalid_type: identifier,
+            }),
+        }
+    }
+}
+
+impl Marshal for PayloadType {
+    type Error = PayloadTypeError;
+
+    fn marshal_to<B>(&self, buf: &mut B) -> Result<usize, Self::Error>
+    where
+        B: BufMut,
+    {
+        let required_len = self.marshal_size();
+        if buf.remaining_mut() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining_mut(),
+            });
+        }
+
+        let identifier = match self {
+            Self::WebRtcDcep => PAYLOAD_TYPE_WEBRTC_DCEP,
+            Self::WebRtcString => PAYLOAD_TYPE_WEBRTC_STRING,
+            Self::WebRtcBinary => PAYLOAD_TYPE_WEBRTC_BINARY,
+            Self::WebRtcStringEmpty => PAYLOAD_TYPE_WEBRTC_STRING_EMPTY,
+            Self::WebRtcBinaryEmpty => PAYLOAD_TYPE_WEBRTC_BINARY_EMPTY,
+        };
+
+        buf.put_u32(identifier);
+
+        Ok(required_len)
+    }
+}
+
 #[derive(Eq, PartialEq, Copy, Clone, Debug)]
 pub enum ReliabilityType {
     Reliable,
     Rexmit,
     Timed,
 }
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+
+    #[test]
+    fn payload_type_round_trip() {
+        for payload_type in [
+            PayloadType::WebRtcDcep,
+            PayloadType::WebRtcString,
+            PayloadType::WebRtcBinary,
+            PayloadType::WebRtcStringEmpty,
+            PayloadType::WebRtcBinaryEmpty,
+        ] {
+            let mut bytes = payload_type.marshal().unwrap();
+            assert_eq!(PayloadType::unmarshal_from(&mut bytes), Ok(payload_type));
+        }
+
+        let bytes = PayloadType::WebRtcStringEmpty.marshal().unwrap();
+        assert_eq!(&bytes[..], &[0x00, 0x00, 0x00, 0x38]);
+    }
+
+    #[test]
+    fn unmarshal_invalid_payload_type() {
+        let mut bytes = Bytes::from_static(&[0x00, 0x00, 0x00, 0x34]);
+        assert_eq!(
+            PayloadType::unmarshal_from(&mut bytes),
+            Err(PayloadTypeError::InvalidPayloadType { invalid_type: 52 })
+        );
+
+        let mut bytes = Bytes::from_static(&[0x00, 0x00]);
+        assert_eq!(
+            PayloadType::unmarshal_from(&mut bytes),
+            Err(PayloadTypeError::UnexpectedEndOfBuffer {
+                expected: 4,
+                actual: 2
+            })
+        );
+    }
+}
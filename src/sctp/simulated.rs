@@ -0,0 +1,707 @@
+//! An in-memory stand-in for an SCTP association, connecting two endpoints
+//! within the same process. Messages are delivered reliably, in order and
+//! instantly, regardless of the requested reliability parameters, unless
+//! writes are held back to simulate a full send buffer.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use bytes::{Buf, Bytes, BytesMut};
+
+use crate::{
+    data_channel::ReceiveBufferPolicy,
+    quality::QualitySignals,
+    sctp::{BufferedAmountLowFn, PayloadType, ReliabilityType, StreamError},
+};
+
+// The limit implied by an SDP offer without an a=max-message-size attribute (RFC 8841).
+const DEFAULT_MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+// The initial congestion window of SCTP for a 1200 byte MTU (RFC 4960, section 7.2.1).
+const DEFAULT_CONGESTION_WINDOW: u64 = 4380;
+
+#[derive(Default)]
+struct Direction {
+    messages: VecDeque<(Bytes, PayloadType)>,
+    // Set once the sending end has reset the direction.
+    closed: bool,
+    // The task waiting for the receiving end to have something to read.
+    waker: Option<Waker>,
+    // The task waiting for the sending end's buffered amount to drop.
+    drain_waker: Option<Waker>,
+    next_ssn: u16,
+    last_delivered_ssn: Option<u16>,
+    // The number of upcoming writes to fail with `StreamError::SendBufferFull`.
+    failing_writes: usize,
+    // Set to fail the next change of reliability parameters with `StreamError::Eof`.
+    failing_reliability_params: bool,
+    // Set while written messages are held back instead of being delivered.
+    holding: bool,
+    held: VecDeque<(Bytes, PayloadType)>,
+    held_bytes: u64,
+    buffered_amount_low_threshold: u64,
+    on_buffered_amount_low: Option<BufferedAmountLowFn>,
+    // Left by `unblock` once it lowered the buffered amount to the threshold,
+    // to be called once the lock has been dropped.
+    buffered_amount_low: Option<BufferedAmountLowFn>,
+    // The most bytes the receiving end queues, and what happens beyond that.
+    receive_limit: Option<(usize, ReceiveBufferPolicy)>,
+    queued_bytes: usize,
+    // Messages waiting for room in the receiving end's queue, under
+    // `ReceiveBufferPolicy::Block`, as if its receiver window was closed.
+    blocked: VecDeque<(Bytes, PayloadType)>,
+    blocked_bytes: u64,
+    dropped: u64,
+    // Set once a message overflowed the queue under `ReceiveBufferPolicy::CloseChannel`.
+    overflowed: bool,
+}
+
+impl Direction {
+    // Queues a message for the receiving end, within its receive limit.
+    fn deliver(&mut self, message: Bytes, payload_type: PayloadType) {
+        if let Some((_, policy)) = self.receive_limit {
+            if !self.blocked.is_empty() || !self.has_room(message.len()) {
+                match policy {
+                    ReceiveBufferPolicy::Block => {
+                        self.blocked_bytes += message.len() as u64;
+                        self.blocked.push_back((message, payload_type));
+                    }
+                    ReceiveBufferPolicy::DropNewest => self.dropped += 1,
+                    ReceiveBufferPolicy::CloseChannel => {
+                        self.dropped += 1;
+                        self.overflowed = true;
+                    }
+                }
+                return;
+            }
+        }
+
+        self.queued_bytes += message.len();
+        self.messages.push_back((message, payload_type));
+    }
+
+    // Whether a message fits into the receiving end's queue; one always does into an empty queue.
+    fn has_room(&self, len: usize) -> bool {
+        match self.receive_limit {
+            Some((limit, _)) => self.queued_bytes == 0 || self.queued_bytes + len <= limit,
+            None => true,
+        }
+    }
+
+    fn unblock(&mut self) {
+        let blocked_bytes = self.blocked_bytes;
+        let was_above_threshold =
+            self.held_bytes + blocked_bytes > self.buffered_amount_low_threshold;
+        while let Some((message, _)) = self.blocked.front() {
+            if !self.has_room(message.len()) {
+                break;
+            }
+            let (message, payload_type) = self.blocked.pop_front().expect("front exists");
+            self.blocked_bytes -= message.len() as u64;
+            self.queued_bytes += message.len();
+            self.messages.push_back((message, payload_type));
+        }
+
+        if self.blocked_bytes < blocked_bytes {
+            if let Some(waker) = self.drain_waker.take() {
+                waker.wake();
+            }
+        }
+
+        let is_above_threshold =
+            self.held_bytes + self.blocked_bytes > self.buffered_amount_low_threshold;
+        if was_above_threshold && !is_above_threshold {
+            self.buffered_amount_low = self.on_buffered_amount_low.clone();
+        }
+    }
+}
+
+// A bidirectional stream; `directions[side]` carries messages towards `side`.
+#[derive(Default)]
+struct Pipe {
+    directions: Mutex<[Direction; 2]>,
+    readable: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    pipes: HashMap<u16, Arc<Pipe>>,
+    // Streams opened by the other end, waiting to be accepted by `side`.
+    incoming: [VecDeque<u16>; 2],
+    // The tasks waiting for the other end to open a stream towards `side`.
+    acceptors: [Vec<Waker>; 2],
+    quality_signals: QualitySignals,
+    // Notified whenever the round-trip time changes.
+    rtt_subscribers: Vec<Sender<Duration>>,
+    // The largest message either end accepts, if not the default.
+    max_message_size: Option<usize>,
+    // The number of outbound streams negotiated by `side`, if limited.
+    outbound_streams: [Option<u16>; 2],
+    // Set once requests for more outbound streams are refused.
+    refusing_outbound_streams: bool,
+    // The congestion window of both ends, if not the default.
+    congestion_window: Option<u64>,
+}
+
+#[derive(Default)]
+struct Network {
+    state: Mutex<State>,
+    incoming: Condvar,
+}
+
+/// One end of a simulated association.
+pub(crate) struct Association {
+    side: usize,
+    network: Arc<Network>,
+}
+
+impl Association {
+    pub(crate) fn pair() -> (Self, Self) {
+        let network = Arc::new(Network::default());
+
+        (
+            Self {
+                side: 0,
+                network: network.clone(),
+            },
+            Self { side: 1, network },
+        )
+    }
+
+    pub(crate) fn open_stream(&self, id: u16) -> Stream {
+        let mut state = self.network.state.lock().unwrap();
+        // Once both directions have been reset, the stream starts afresh.
+        if let Some(pipe) = state.pipes.get(&id) {
+            let directions = pipe.directions.lock().unwrap();
+            let is_reset = directions.iter().all(|direction| direction.closed);
+            drop(directions);
+            if is_reset {
+                state.pipes.remove(&id);
+            }
+        }
+        let pipe = state.pipes.entry(id).or_default().clone();
+        state.incoming[1 - self.side].push_back(id);
+        let acceptors = std::mem::take(&mut state.acceptors[1 - self.side]);
+        drop(state);
+
+        self.network.incoming.notify_all();
+        for waker in acceptors {
+            waker.wake();
+        }
+
+        self.stream(id, pipe)
+    }
+
+    pub(crate) fn accept_stream(&self) -> Stream {
+        let mut state = self.network.state.lock().unwrap();
+        let id = loop {
+            match state.incoming[self.side].pop_front() {
+                Some(id) => break id,
+                None => state = self.network.incoming.wait(state).unwrap(),
+            }
+        };
+        let pipe = state.pipes[&id].clone();
+
+        self.stream(id, pipe)
+    }
+
+    pub(crate) fn poll_accept_stream(&self, cx: &mut Context<'_>) -> Poll<Stream> {
+        let mut state = self.network.state.lock().unwrap();
+        let id = match state.incoming[self.side].pop_front() {
+            Some(id) => id,
+            None => {
+                let acceptors = &mut state.acceptors[self.side];
+                if !acceptors.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    acceptors.push(cx.waker().clone());
+                }
+                return Poll::Pending;
+            }
+        };
+        let pipe = state.pipes[&id].clone();
+
+        Poll::Ready(self.stream(id, pipe))
+    }
+
+    pub(crate) fn try_accept_stream(&self) -> Option<Stream> {
+        let mut state = self.network.state.lock().unwrap();
+        let id = state.incoming[self.side].pop_front()?;
+        let pipe = state.pipes[&id].clone();
+
+        Some(self.stream(id, pipe))
+    }
+
+    pub(crate) fn restore_stream(&self, id: u16) -> Stream {
+        let mut state = self.network.state.lock().unwrap();
+        let pipe = state.pipes.entry(id).or_default().clone();
+
+        self.stream(id, pipe)
+    }
+
+    /// Makes the next `count` writes from this end of the stream fail transiently.
+    pub(crate) fn fail_writes(&self, id: u16, count: usize) {
+        let mut state = self.network.state.lock().unwrap();
+        let pipe = state.pipes.entry(id).or_default();
+        pipe.directions.lock().unwrap()[1 - self.side].failing_writes = count;
+    }
+
+    /// Makes the next change of reliability parameters on this end of the
+    /// stream fail, as if the stream had been reset meanwhile.
+    pub(crate) fn fail_reliability_params(&self, id: u16) {
+        let mut state = self.network.state.lock().unwrap();
+        let pipe = state.pipes.entry(id).or_default();
+        pipe.directions.lock().unwrap()[1 - self.side].failing_reliability_params = true;
+    }
+
+    /// Holds back all writes from this end of the stream, as if they were
+    /// stuck in the send buffer, until `release_writes`.
+    pub(crate) fn hold_writes(&self, id: u16) {
+        let mut state = self.network.state.lock().unwrap();
+        let pipe = state.pipes.entry(id).or_default();
+        pipe.directions.lock().unwrap()[1 - self.side].holding = true;
+    }
+
+    /// Delivers the writes held back from this end of the stream, calling its
+    /// buffered amount low callback if the buffered amount dropped to its threshold.
+    pub(crate) fn release_writes(&self, id: u16) {
+        let pipe = match self.network.state.lock().unwrap().pipes.get(&id) {
+            Some(pipe) => pipe.clone(),
+            None => return,
+        };
+
+        let mut directions = pipe.directions.lock().unwrap();
+        let direction = &mut directions[1 - self.side];
+        let was_above_threshold = direction.held_bytes > direction.buffered_amount_low_threshold;
+        direction.holding = false;
+        let held = std::mem::take(&mut direction.held);
+        for (message, payload_type) in held {
+            direction.deliver(message, payload_type);
+        }
+        direction.held_bytes = 0;
+        let waker = direction.waker.take();
+        let drain_waker = direction.drain_waker.take();
+        let on_buffered_amount_low = direction
+            .on_buffered_amount_low
+            .clone()
+            .filter(|_| was_above_threshold);
+        drop(directions);
+
+        pipe.readable.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        if let Some(waker) = drain_waker {
+            waker.wake();
+        }
+        if let Some(on_buffered_amount_low) = on_buffered_amount_low {
+            on_buffered_amount_low();
+        }
+    }
+
+    /// Makes both ends of the association advertise the given maximum message size.
+    pub(crate) fn set_max_message_size(&self, max_message_size: usize) {
+        self.network.state.lock().unwrap().max_message_size = Some(max_message_size);
+    }
+
+    pub(crate) fn max_outbound_streams(&self) -> u16 {
+        self.network.state.lock().unwrap().outbound_streams[self.side].unwrap_or(u16::MAX)
+    }
+
+    /// Adds outbound streams to this end, as if by an SCTP RE-CONFIG
+    /// Add Outgoing Streams request, which the other end grants
+    /// unless `refuse_outbound_streams` was called.
+    pub(crate) fn add_outbound_streams(&self, additional: u16) -> u16 {
+        let mut state = self.network.state.lock().unwrap();
+        let additional = if state.refusing_outbound_streams {
+            0
+        } else {
+            additional
+        };
+        let outbound_streams = &mut state.outbound_streams[self.side];
+        let total = outbound_streams
+            .unwrap_or(u16::MAX)
+            .saturating_add(additional);
+        *outbound_streams = Some(total);
+
+        total
+    }
+
+    /// Limits both ends of the association to the given number of outbound streams.
+    pub(crate) fn set_max_outbound_streams(&self, max_outbound_streams: u16) {
+        self.network.state.lock().unwrap().outbound_streams = [Some(max_outbound_streams); 2];
+    }
+
+    /// Makes both ends of the association refuse requests for more outbound streams.
+    pub(crate) fn refuse_outbound_streams(&self) {
+        self.network.state.lock().unwrap().refusing_outbound_streams = true;
+    }
+
+    /// Makes both ends of the association report the given congestion window.
+    pub(crate) fn set_congestion_window(&self, congestion_window: u64) {
+        self.network.state.lock().unwrap().congestion_window = Some(congestion_window);
+    }
+
+    pub(crate) fn quality_signals(&self) -> QualitySignals {
+        self.network.state.lock().unwrap().quality_signals
+    }
+
+    /// Reports the given signals to both ends of the association from here on.
+    pub(crate) fn set_quality_signals(&self, signals: QualitySignals) {
+        let mut state = self.network.state.lock().unwrap();
+        let previous = std::mem::replace(&mut state.quality_signals, signals);
+        if let Some(rtt) = signals.rtt.filter(|rtt| previous.rtt != Some(*rtt)) {
+            state
+                .rtt_subscribers
+                .retain(|subscriber| subscriber.send(rtt).is_ok());
+        }
+    }
+
+    fn stream(&self, id: u16, pipe: Arc<Pipe>) -> Stream {
+        Stream {
+            id,
+            side: self.side,
+            pipe,
+            network: self.network.clone(),
+            reliability_params: (false, ReliabilityType::Reliable, 0),
+        }
+    }
+}
+
+impl std::fmt::Debug for Association {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Association")
+            .field("side", &self.side)
+            .finish()
+    }
+}
+
+/// One end of a simulated stream.
+pub(crate) struct Stream {
+    id: u16,
+    side: usize,
+    pipe: Arc<Pipe>,
+    network: Arc<Network>,
+    // As last set, though messages are delivered reliably and in order anyway.
+    reliability_params: (bool, ReliabilityType, u32),
+}
+
+impl Stream {
+    pub(crate) fn stream_identifier(&self) -> u16 {
+        self.id
+    }
+
+    pub(crate) fn read_sctp(
+        &mut self,
+        bytes: &mut BytesMut,
+    ) -> Result<(usize, PayloadType), StreamError> {
+        let mut directions = self.pipe.directions.lock().unwrap();
+        loop {
+            if let Some(result) = Self::receive(&mut directions[self.side], bytes) {
+                self.notify_buffered_amount_low(directions);
+                return result;
+            }
+            directions = self.pipe.readable.wait(directions).unwrap();
+        }
+    }
+
+    pub(crate) fn poll_read_sctp(
+        &mut self,
+        cx: &mut Context<'_>,
+        bytes: &mut BytesMut,
+    ) -> Poll<Result<(usize, PayloadType), StreamError>> {
+        let mut directions = self.pipe.directions.lock().unwrap();
+        match Self::receive(&mut directions[self.side], bytes) {
+            Some(result) => {
+                self.notify_buffered_amount_low(directions);
+                Poll::Ready(result)
+            }
+            None => {
+                directions[self.side].waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    pub(crate) fn read_sctp_bytes(&mut self) -> Result<(Bytes, PayloadType), StreamError> {
+        let mut directions = self.pipe.directions.lock().unwrap();
+        loop {
+            if let Some(result) = Self::receive_bytes(&mut directions[self.side]) {
+                self.notify_buffered_amount_low(directions);
+                return result;
+            }
+            directions = self.pipe.readable.wait(directions).unwrap();
+        }
+    }
+
+    pub(crate) fn poll_read_sctp_bytes(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(Bytes, PayloadType), StreamError>> {
+        let mut directions = self.pipe.directions.lock().unwrap();
+        match Self::receive_bytes(&mut directions[self.side]) {
+            Some(result) => {
+                self.notify_buffered_amount_low(directions);
+                Poll::Ready(result)
+            }
+            None => {
+                directions[self.side].waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    // Calls the writer's `on_buffered_amount_low` callback if reading let
+    // blocked messages in, after unlocking `directions`, as release_writes does.
+    fn notify_buffered_amount_low(&self, mut directions: MutexGuard<'_, [Direction; 2]>) {
+        let buffered_amount_low = directions[self.side].buffered_amount_low.take();
+        drop(directions);
+
+        if let Some(buffered_amount_low) = buffered_amount_low {
+            buffered_amount_low();
+        }
+    }
+
+    fn receive(
+        direction: &mut Direction,
+        bytes: &mut BytesMut,
+    ) -> Option<Result<(usize, PayloadType), StreamError>> {
+        let result = Self::receive_bytes(direction)?.map(|(message, payload_type)| {
+            bytes.clear();
+            bytes.extend_from_slice(&message[..]);
+            (message.len(), payload_type)
+        });
+
+        Some(result)
+    }
+
+    fn receive_bytes(
+        direction: &mut Direction,
+    ) -> Option<Result<(Bytes, PayloadType), StreamError>> {
+        if direction.overflowed {
+            direction.overflowed = false;
+            return Some(Err(StreamError::ReceiveBufferExceeded));
+        }
+
+        match direction.messages.pop_front() {
+            Some((message, payload_type)) => {
+                direction.queued_bytes -= message.len();
+                direction.unblock();
+                direction.last_delivered_ssn = Some(
+                    direction
+                        .last_delivered_ssn
+                        .map_or(0, |ssn| ssn.wrapping_add(1)),
+                );
+                Some(Ok((message, payload_type)))
+            }
+            None if direction.closed => Some(Err(StreamError::Eof)),
+            None => None,
+        }
+    }
+
+    pub(crate) fn write_sctp<B>(
+        &mut self,
+        bytes: &mut B,
+        payload_type: PayloadType,
+    ) -> Result<usize, StreamError>
+    where
+        B: Buf,
+    {
+        let mut directions = self.pipe.directions.lock().unwrap();
+        let direction = &mut directions[1 - self.side];
+        if direction.closed {
+            return Err(StreamError::Eof);
+        }
+        if direction.failing_writes > 0 {
+            direction.failing_writes -= 1;
+            return Err(StreamError::SendBufferFull);
+        }
+
+        let message = bytes.copy_to_bytes(bytes.remaining());
+        let len = message.len();
+        if direction.holding {
+            direction.held_bytes += len as u64;
+            direction.held.push_back((message, payload_type));
+            direction.next_ssn = direction.next_ssn.wrapping_add(1);
+            return Ok(len);
+        }
+        direction.deliver(message, payload_type);
+        direction.next_ssn = direction.next_ssn.wrapping_add(1);
+        let waker = direction.waker.take();
+        drop(directions);
+
+        self.pipe.readable.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+
+        Ok(len)
+    }
+
+    /// Writes all messages at once, or none of them.
+    pub(crate) fn write_sctp_batch(
+        &mut self,
+        messages: Vec<(Bytes, PayloadType)>,
+    ) -> Result<usize, StreamError> {
+        let mut directions = self.pipe.directions.lock().unwrap();
+        let direction = &mut directions[1 - self.side];
+        if direction.closed {
+            return Err(StreamError::Eof);
+        }
+        if direction.failing_writes > 0 {
+            direction.failing_writes -= 1;
+            return Err(StreamError::SendBufferFull);
+        }
+
+        let len = messages.iter().map(|(message, _)| message.len()).sum();
+        direction.next_ssn = direction.next_ssn.wrapping_add(messages.len() as u16);
+        if direction.holding {
+            direction.held_bytes += len as u64;
+            direction.held.extend(messages);
+            return Ok(len);
+        }
+        for (message, payload_type) in messages {
+            direction.deliver(message, payload_type);
+        }
+        let waker = direction.waker.take();
+        drop(directions);
+
+        self.pipe.readable.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+
+        Ok(len)
+    }
+
+    pub(crate) fn close(&mut self) {
+        let mut directions = self.pipe.directions.lock().unwrap();
+        let direction = &mut directions[1 - self.side];
+        direction.closed = true;
+        let waker = direction.waker.take();
+        drop(directions);
+
+        self.pipe.readable.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    pub(crate) fn next_outbound_ssn(&self) -> u16 {
+        self.pipe.directions.lock().unwrap()[1 - self.side].next_ssn
+    }
+
+    pub(crate) fn last_delivered_ssn(&self) -> Option<u16> {
+        self.pipe.directions.lock().unwrap()[self.side].last_delivered_ssn
+    }
+
+    pub(crate) fn reliability_params(&self) -> (bool, ReliabilityType, u32) {
+        self.reliability_params
+    }
+
+    pub(crate) fn set_reliability_params(
+        &mut self,
+        unordered: bool,
+        reliability_type: ReliabilityType,
+        reliability_parameter: u32,
+    ) -> Result<(), StreamError> {
+        let direction = &mut self.pipe.directions.lock().unwrap()[1 - self.side];
+        if std::mem::take(&mut direction.failing_reliability_params) {
+            return Err(StreamError::Eof);
+        }
+
+        self.reliability_params = (unordered, reliability_type, reliability_parameter);
+        Ok(())
+    }
+
+    pub(crate) fn max_message_size(&self) -> usize {
+        self.network
+            .state
+            .lock()
+            .unwrap()
+            .max_message_size
+            .unwrap_or(DEFAULT_MAX_MESSAGE_SIZE)
+    }
+
+    pub(crate) fn rtt(&self) -> Option<Duration> {
+        self.network.state.lock().unwrap().quality_signals.rtt
+    }
+
+    pub(crate) fn congestion_window(&self) -> u64 {
+        self.network
+            .state
+            .lock()
+            .unwrap()
+            .congestion_window
+            .unwrap_or(DEFAULT_CONGESTION_WINDOW)
+    }
+
+    pub(crate) fn rtt_updates(&self) -> Receiver<Duration> {
+        let (sender, receiver) = mpsc::channel();
+        self.network
+            .state
+            .lock()
+            .unwrap()
+            .rtt_subscribers
+            .push(sender);
+        receiver
+    }
+
+    pub(crate) fn buffered_amount(&self) -> u64 {
+        let direction = &self.pipe.directions.lock().unwrap()[1 - self.side];
+        direction.held_bytes + direction.blocked_bytes
+    }
+
+    pub(crate) fn poll_buffered_amount_below(
+        &mut self,
+        cx: &mut Context<'_>,
+        threshold: u64,
+    ) -> Poll<()> {
+        let direction = &mut self.pipe.directions.lock().unwrap()[1 - self.side];
+        if direction.held_bytes + direction.blocked_bytes < threshold {
+            return Poll::Ready(());
+        }
+
+        direction.drain_waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+
+    pub(crate) fn set_receive_buffer_limit(&mut self, limit: usize, policy: ReceiveBufferPolicy) {
+        let mut directions = self.pipe.directions.lock().unwrap();
+        let direction = &mut directions[self.side];
+        direction.receive_limit = Some((limit, policy));
+        direction.unblock();
+        self.notify_buffered_amount_low(directions);
+    }
+
+    pub(crate) fn dropped_messages(&self) -> u64 {
+        self.pipe.directions.lock().unwrap()[self.side].dropped
+    }
+
+    pub(crate) fn buffered_amount_low_threshold(&self) -> u64 {
+        self.pipe.directions.lock().unwrap()[1 - self.side].buffered_amount_low_threshold
+    }
+
+    pub(crate) fn set_buffered_amount_low_threshold(&mut self, threshold: u64) {
+        self.pipe.directions.lock().unwrap()[1 - self.side].buffered_amount_low_threshold =
+            threshold;
+    }
+
+    pub(crate) fn on_buffered_amount_low(&mut self, f: BufferedAmountLowFn) {
+        self.pipe.directions.lock().unwrap()[1 - self.side].on_buffered_amount_low = Some(f);
+    }
+}
+
+impl std::fmt::Debug for Stream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Stream")
+            .field("id", &self.id)
+            .field("side", &self.side)
+            .finish()
+    }
+}
@@ -1,74 +1,308 @@
-use bytes::{Buf, BytesMut};
+use std::{
+    sync::{mpsc::Receiver, Arc},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::{Buf, Bytes, BytesMut};
 use thiserror::Error;
 
-use crate::sctp::{PayloadType, ReliabilityType};
+use crate::{
+    data_channel::ReceiveBufferPolicy,
+    sctp::{simulated, PayloadType, ReliabilityType},
+};
 
 #[derive(Error, Eq, PartialEq, Clone, Debug)]
 pub enum Error {
     Eof,
+    SendBufferFull,
+    // A message overflowed the receive buffer limit under `ReceiveBufferPolicy::CloseChannel`.
+    ReceiveBufferExceeded,
+}
+
+impl Error {
+    /// Whether the operation may succeed if tried again.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::Eof | Error::ReceiveBufferExceeded => false,
+            Error::SendBufferFull => true,
+        }
+    }
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::Eof => writeln!(f, "Unexpected end of file"),
+            Error::SendBufferFull => writeln!(f, "Send buffer is full"),
+            Error::ReceiveBufferExceeded => writeln!(f, "Receive buffer limit exceeded"),
         }
     }
 }
 
+/// Called once the bytes buffered on a stream drop to its buffered amount low threshold.
+pub type BufferedAmountLowFn = Arc<dyn Fn() + Send + Sync>;
+
 #[derive(Debug)]
-pub struct Stream;
+pub struct Stream(Backend);
+
+#[derive(Debug)]
+enum Backend {
+    // The real SCTP stack, once 'webrtc-sctp' is integrated.
+    #[allow(dead_code)]
+    Sctp,
+    Simulated(simulated::Stream),
+}
+
+impl From<simulated::Stream> for Stream {
+    fn from(stream: simulated::Stream) -> Self {
+        Self(Backend::Simulated(stream))
+    }
+}
 
 impl Stream {
     pub fn stream_identifier(&self) -> u16 {
-        todo!()
+        match &self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.stream_identifier(),
+        }
+    }
+
+    pub fn read_sctp(&mut self, bytes: &mut BytesMut) -> Result<(usize, PayloadType), Error> {
+        match &mut self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.read_sctp(bytes),
+        }
     }
 
-    pub fn read_sctp(&mut self, _bytes: &mut BytesMut) -> Result<(usize, PayloadType), Error> {
-        todo!()
+    /// ReadSctpBytes reads the next message without copying it.
+    pub fn read_sctp_bytes(&mut self) -> Result<(Bytes, PayloadType), Error> {
+        match &mut self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.read_sctp_bytes(),
+        }
+    }
+
+    pub fn poll_read_sctp_bytes(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(Bytes, PayloadType), Error>> {
+        match &mut self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.poll_read_sctp_bytes(cx),
+        }
     }
 
     pub fn write_sctp<B>(
         &mut self,
-        _bytes: &mut B,
-        _payload_type: PayloadType,
+        bytes: &mut B,
+        payload_type: PayloadType,
     ) -> Result<usize, Error>
     where
         B: Buf,
     {
-        todo!()
+        match &mut self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.write_sctp(bytes, payload_type),
+        }
+    }
+
+    /// WriteSctpBatch writes several messages in one go, returning the
+    /// number of bytes written. Either all of them are written, or none.
+    pub fn write_sctp_batch(
+        &mut self,
+        messages: Vec<(Bytes, PayloadType)>,
+    ) -> Result<usize, Error> {
+        match &mut self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.write_sctp_batch(messages),
+        }
+    }
+
+    pub fn poll_read_sctp(
+        &mut self,
+        cx: &mut Context<'_>,
+        bytes: &mut BytesMut,
+    ) -> Poll<Result<(usize, PayloadType), Error>> {
+        match &mut self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.poll_read_sctp(cx, bytes),
+        }
+    }
+
+    pub fn poll_write_sctp<B>(
+        &mut self,
+        _cx: &mut Context<'_>,
+        bytes: &mut B,
+        payload_type: PayloadType,
+    ) -> Poll<Result<usize, Error>>
+    where
+        B: Buf,
+    {
+        match &mut self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => Poll::Ready(stream.write_sctp(bytes, payload_type)),
+        }
     }
 
     pub fn close(&mut self) -> Result<(), Error> {
-        todo!()
+        match &mut self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => {
+                stream.close();
+                Ok(())
+            }
+        }
     }
 
     pub fn buffered_amount(&self) -> u64 {
-        todo!()
+        match &self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.buffered_amount(),
+        }
+    }
+
+    /// PollBufferedAmountBelow resolves once fewer than `threshold` bytes are
+    /// buffered on the stream, registering the current task to be woken up
+    /// once the buffered amount drops otherwise.
+    pub fn poll_buffered_amount_below(&mut self, cx: &mut Context<'_>, threshold: u64) -> Poll<()> {
+        match &mut self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.poll_buffered_amount_below(cx, threshold),
+        }
+    }
+
+    /// SetReceiveBufferLimit limits the bytes of received messages queued on
+    /// the stream waiting to be read, applying `policy` to messages beyond it.
+    pub fn set_receive_buffer_limit(&mut self, limit: usize, policy: ReceiveBufferPolicy) {
+        match &mut self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.set_receive_buffer_limit(limit, policy),
+        }
+    }
+
+    /// DroppedMessages returns the number of received messages dropped for
+    /// exceeding the receive buffer limit.
+    pub fn dropped_messages(&self) -> u64 {
+        match &self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.dropped_messages(),
+        }
+    }
+
+    pub fn bytes_in_flight(&self) -> u64 {
+        match &self.0 {
+            Backend::Sctp => todo!(),
+            // Messages are delivered, and thereby acknowledged, as soon as they are written.
+            Backend::Simulated(_) => 0,
+        }
+    }
+
+    /// MaxMessageSize returns the size of the largest message the remote
+    /// advertised to accept, or 0 if it accepts messages of any size.
+    pub fn max_message_size(&self) -> usize {
+        match &self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.max_message_size(),
+        }
+    }
+
+    /// Rtt returns the association's smoothed round-trip time, once measured.
+    pub fn rtt(&self) -> Option<Duration> {
+        match &self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.rtt(),
+        }
+    }
+
+    /// CongestionWindow returns the association's congestion window, in bytes.
+    pub fn congestion_window(&self) -> u64 {
+        match &self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.congestion_window(),
+        }
+    }
+
+    /// RttUpdates returns a receiver for every new smoothed round-trip time
+    /// of the association from here on.
+    pub fn rtt_updates(&self) -> Receiver<Duration> {
+        match &self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.rtt_updates(),
+        }
+    }
+
+    pub fn held_back_amount(&self) -> u64 {
+        match &self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(_) => 0,
+        }
+    }
+
+    pub fn next_outbound_ssn(&self) -> u16 {
+        match &self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.next_outbound_ssn(),
+        }
+    }
+
+    pub fn last_delivered_ssn(&self) -> Option<u16> {
+        match &self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.last_delivered_ssn(),
+        }
     }
 
     pub fn buffered_amount_low_threshold(&self) -> u64 {
-        todo!()
+        match &self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.buffered_amount_low_threshold(),
+        }
     }
 
-    pub fn set_buffered_amount_low_threshold(&mut self, _threshold: u64) {
-        todo!()
+    pub fn set_buffered_amount_low_threshold(&mut self, threshold: u64) {
+        match &mut self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.set_buffered_amount_low_threshold(threshold),
+        }
     }
 
-    pub fn on_buffered_amount_low<F>(&mut self, _f: F) {
-        todo!()
+    pub fn on_buffered_amount_low(&mut self, f: BufferedAmountLowFn) {
+        match &mut self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.on_buffered_amount_low(f),
+        }
     }
 
     pub fn set_reliability_params(
         &mut self,
-        _unordered: bool,
-        _reliability_type: ReliabilityType,
-        _reliability_parameter: u32,
+        unordered: bool,
+        reliability_type: ReliabilityType,
+        reliability_parameter: u32,
     ) -> Result<(), Error> {
-        todo!()
+        match &mut self.0 {
+            Backend::Sctp => todo!(),
+            // Simulated streams are always reliable and ordered, whatever is set.
+            Backend::Simulated(stream) => {
+                stream.set_reliability_params(unordered, reliability_type, reliability_parameter)
+            }
+        }
+    }
+
+    /// ReliabilityParams returns whether messages are sent unordered, along
+    /// with the reliability type and parameter they are sent with.
+    pub fn reliability_params(&self) -> (bool, ReliabilityType, u32) {
+        match &self.0 {
+            Backend::Sctp => todo!(),
+            Backend::Simulated(stream) => stream.reliability_params(),
+        }
     }
 
     pub fn set_default_payload_type(&mut self, _payload_type: PayloadType) {
-        todo!()
+        match &self.0 {
+            Backend::Sctp => todo!(),
+            // Every message written carries its payload type explicitly.
+            Backend::Simulated(_) => {}
+        }
     }
 }
@@ -0,0 +1,87 @@
+//! Recovery from transient send failures.
+//!
+//! By default, a message which can't be handed to SCTP fails the write.
+//! A `SendFailureHandler` attached to a channel's `Config` is consulted
+//! whenever this happens for a transient reason, such as a full send
+//! buffer, and decides per message whether to retry, drop it, or close
+//! the channel.
+
+use std::sync::Arc;
+
+use crate::sctp::StreamError;
+
+/// What to do about a message which failed to be handed to SCTP.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum SendFailureAction {
+    /// Tries handing the message to SCTP again, right away.
+    Retry,
+    /// Discards the message; the write reports 0 bytes written.
+    Drop,
+    /// Closes the channel and fails the write with the original error.
+    Close,
+}
+
+/// The details of a failed attempt at handing a message to SCTP.
+#[derive(Debug)]
+pub struct SendFailure<'a> {
+    pub stream_identifier: u16,
+    /// The number of attempts made at sending the message so far, starting at 1.
+    pub attempt: u32,
+    pub error: &'a StreamError,
+    /// The length of the message, in bytes.
+    pub len: usize,
+    pub is_string: bool,
+}
+
+/// A decider of what to do about messages which failed to be sent.
+pub trait SendFailureHandler: Send + Sync {
+    fn on_send_failure(&self, failure: &SendFailure<'_>) -> SendFailureAction;
+}
+
+impl<F> SendFailureHandler for F
+where
+    F: Fn(&SendFailure<'_>) -> SendFailureAction + Send + Sync,
+{
+    fn on_send_failure(&self, failure: &SendFailure<'_>) -> SendFailureAction {
+        self(failure)
+    }
+}
+
+/// A cloneable handle to a `SendFailureHandler`, suitable for storing in a `Config`.
+///
+/// Two handles compare equal if they point to the same handler.
+#[derive(Clone)]
+pub struct SharedSendFailureHandler(Arc<dyn SendFailureHandler>);
+
+impl SharedSendFailureHandler {
+    pub fn new<H>(handler: H) -> Self
+    where
+        H: SendFailureHandler + 'static,
+    {
+        Self(Arc::new(handler))
+    }
+
+    pub fn on_send_failure(&self, failure: &SendFailure<'_>) -> SendFailureAction {
+        self.0.on_send_failure(failure)
+    }
+}
+
+impl From<Arc<dyn SendFailureHandler>> for SharedSendFailureHandler {
+    fn from(handler: Arc<dyn SendFailureHandler>) -> Self {
+        Self(handler)
+    }
+}
+
+impl PartialEq for SharedSendFailureHandler {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedSendFailureHandler {}
+
+impl std::fmt::Debug for SharedSendFailureHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SharedSendFailureHandler").finish()
+    }
+}
@@ -0,0 +1,77 @@
+use std::ops::{Deref, DerefMut};
+
+use bytes::{Bytes, BytesMut};
+
+use crate::{data_channel::DataChannel, error::DataChannelError};
+
+/// A region of the send path reserved with `DataChannel::send_reserve`.
+///
+/// Encoders can serialize straight into the slot through its `BufMut`
+/// implementation, obtained by dereferencing it to a `BytesMut`. Committing
+/// the slot sends its contents as a single message without copying them;
+/// dropping it discards them.
+pub struct SendSlot<'a> {
+    data_channel: &'a mut DataChannel,
+    // Only taken on commit.
+    buf: Option<BytesMut>,
+}
+
+impl<'a> SendSlot<'a> {
+    pub(crate) fn new(data_channel: &'a mut DataChannel, buf: BytesMut) -> Self {
+        Self {
+            data_channel,
+            buf: Some(buf),
+        }
+    }
+
+    /// Commit sends the slot's contents as binary or text data
+    /// according to the channel's default payload format.
+    pub fn commit(mut self) -> Result<usize, DataChannelError> {
+        let mut bytes = self.take();
+        self.data_channel.write(&mut bytes)
+    }
+
+    /// CommitDataChannel sends the slot's contents.
+    pub fn commit_data_channel(mut self, is_string: bool) -> Result<usize, DataChannelError> {
+        let mut bytes = self.take();
+        self.data_channel.write_data_channel(&mut bytes, is_string)
+    }
+
+    fn take(&mut self) -> Bytes {
+        self.buf
+            .take()
+            .expect("slot is only committed once")
+            .freeze()
+    }
+}
+
+impl Deref for SendSlot<'_> {
+    type Target = BytesMut;
+
+    fn deref(&self) -> &Self::Target {
+        self.buf.as_ref().expect("slot is not committed")
+    }
+}
+
+impl DerefMut for SendSlot<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.buf.as_mut().expect("slot is not committed")
+    }
+}
+
+impl Drop for SendSlot<'_> {
+    fn drop(&mut self) {
+        if let Some(buf) = self.buf.take() {
+            self.data_channel.config.buffer_provider.release(buf);
+        }
+    }
+}
+
+impl std::fmt::Debug for SendSlot<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SendSlot")
+            .field("stream_identifier", &self.data_channel.stream_identifier())
+            .field("len", &self.len())
+            .finish()
+    }
+}
@@ -0,0 +1,534 @@
+//! Shared handles to a data channel, for reading and writing from different
+//! threads or tasks.
+//!
+//! `DataChannel::split` hands out a `DataChannelReader` and a
+//! `DataChannelWriter` sharing the channel, and `DataChannel::into_handle`
+//! a `DataChannelHandle` which clones cheaply and hands out both on demand.
+//! Any number of writers may share a channel, their writes being
+//! serialized, but only one reader at a time. Each operation locks the
+//! channel only for as long as it takes to hand a message to or from the
+//! stream; reads wait for messages without holding the lock, so writes
+//! aren't held up by a pending read.
+//!
+//! Reads are cancellation safe: a message is either returned whole or left
+//! with the channel, so dropping a pending `poll_recv` future loses nothing.
+//!
+//! Either half can also be handed to a task on a `Runtime`: a reader task
+//! passes the messages received to a callback, a writer task writes the
+//! messages queued in an `Outbox`, and an idle timer checks the channel
+//! for being idle.
+
+use std::{
+    collections::VecDeque,
+    future::poll_fn,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use bytes::Bytes;
+
+use crate::{
+    blocking,
+    data_channel::{DataChannel, DataChannelMessage, ReadyState},
+    error::DataChannelError,
+    exact_size_buf::ExactSizeBuf,
+    runtime::{SharedRuntime, TaskKind},
+    sctp::StreamError,
+};
+
+impl DataChannel {
+    /// Split divides the channel into a reading and a writing half.
+    pub fn split(self) -> (DataChannelReader, DataChannelWriter) {
+        let handle = self.into_handle();
+        let reader = handle.reader().expect("a new handle has no reader");
+
+        (reader, handle.writer())
+    }
+
+    /// IntoHandle turns the channel into a handle which may be cloned
+    /// to share the channel.
+    pub fn into_handle(self) -> DataChannelHandle {
+        DataChannelHandle {
+            shared: Arc::new(Shared {
+                channel: Mutex::new(self),
+                has_reader: AtomicBool::new(false),
+            }),
+        }
+    }
+}
+
+struct Shared {
+    channel: Mutex<DataChannel>,
+    has_reader: AtomicBool,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, DataChannel> {
+        // A panic while holding the lock doesn't leave the channel in an
+        // inconsistent state, as its operations don't unwind half-way.
+        self.channel
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A cheaply cloned handle to a shared data channel.
+#[derive(Clone)]
+pub struct DataChannelHandle {
+    shared: Arc<Shared>,
+}
+
+impl DataChannelHandle {
+    /// Reader returns the channel's reading half, unless another handle
+    /// holds it. The reading half is given back once dropped.
+    pub fn reader(&self) -> Option<DataChannelReader> {
+        self.shared
+            .has_reader
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .ok()
+            .map(|_| DataChannelReader {
+                shared: self.shared.clone(),
+            })
+    }
+
+    /// Writer returns a writing half of the channel.
+    pub fn writer(&self) -> DataChannelWriter {
+        DataChannelWriter {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// WithChannel runs `f` with the shared channel locked, such as to
+    /// query its state.
+    pub fn with_channel<T>(&self, f: impl FnOnce(&mut DataChannel) -> T) -> T {
+        f(&mut self.shared.lock())
+    }
+
+    /// SpawnIdleTimer checks every `interval` whether the channel has been idle
+    /// for longer than its idle timeout, from a task on `runtime`, taking its
+    /// idle action as `DataChannel::check_idle` does. The task ends once the
+    /// channel is closing, or once all handles and halves have been dropped.
+    pub fn spawn_idle_timer(&self, runtime: &SharedRuntime, interval: Duration) {
+        let shared = Arc::downgrade(&self.shared);
+        let timer = runtime.clone();
+
+        self.shared
+            .lock()
+            .spawn_task(runtime, TaskKind::Timer, async move {
+                loop {
+                    timer.sleep(interval).await;
+
+                    let shared = match shared.upgrade() {
+                        Some(shared) => shared,
+                        None => return,
+                    };
+                    let mut data_channel = shared.lock();
+                    if let Err(error) = data_channel.check_idle() {
+                        log::warn!(
+                            "Failed to check channel on stream {} for being idle: {:?}",
+                            data_channel.stream_identifier(),
+                            error
+                        );
+                    }
+                    if let ReadyState::Closing | ReadyState::Closed = data_channel.ready_state() {
+                        return;
+                    }
+                }
+            });
+    }
+}
+
+/// The reading half of a data channel, as returned by `DataChannel::split`
+/// or `DataChannelHandle::reader`.
+pub struct DataChannelReader {
+    shared: Arc<Shared>,
+}
+
+impl Drop for DataChannelReader {
+    fn drop(&mut self) {
+        self.shared.has_reader.store(false, Ordering::Release);
+    }
+}
+
+impl DataChannelReader {
+    /// Recv blocks until the next message has been read.
+    pub fn recv(&mut self) -> Result<DataChannelMessage, DataChannelError> {
+        blocking::block_on(|cx| self.poll_recv(cx))
+    }
+
+    /// PollRecv is the non-blocking variant of Recv
+    pub fn poll_recv(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<DataChannelMessage, DataChannelError>> {
+        self.shared.lock().poll_recv(cx)
+    }
+
+    /// WithChannel runs `f` with the shared channel locked, such as to
+    /// query its state.
+    pub fn with_channel<T>(&self, f: impl FnOnce(&mut DataChannel) -> T) -> T {
+        f(&mut self.shared.lock())
+    }
+
+    /// Spawn hands every message received on the channel to `on_message`
+    /// from a task on `runtime`. The task ends once the remote has reset its
+    /// stream, or once a read failed, handing over its error last.
+    pub fn spawn<F>(mut self, runtime: &SharedRuntime, mut on_message: F)
+    where
+        F: FnMut(Result<DataChannelMessage, DataChannelError>) + Send + 'static,
+    {
+        let shared = self.shared.clone();
+
+        shared
+            .lock()
+            .spawn_task(runtime, TaskKind::Reader, async move {
+                loop {
+                    match poll_fn(|cx| self.poll_recv(cx)).await {
+                        Ok(message) => on_message(Ok(message)),
+                        Err(DataChannelError::Stream(StreamError::Eof)) => return,
+                        Err(error) => return on_message(Err(error)),
+                    }
+                }
+            });
+    }
+}
+
+/// A writing half of a data channel, as returned by `DataChannel::split`
+/// or `DataChannelHandle::writer`.
+///
+/// Cloned writers share the channel, their writes being serialized.
+#[derive(Clone)]
+pub struct DataChannelWriter {
+    shared: Arc<Shared>,
+}
+
+impl DataChannelWriter {
+    /// Write writes a message in the channel's default payload format.
+    pub fn write<B>(&mut self, bytes: &mut B) -> Result<usize, DataChannelError>
+    where
+        B: bytes::Buf + ExactSizeBuf,
+    {
+        self.shared.lock().write(bytes)
+    }
+
+    /// WriteDataChannel writes a message as text or binary data.
+    pub fn write_data_channel<B>(
+        &mut self,
+        bytes: &mut B,
+        is_string: bool,
+    ) -> Result<usize, DataChannelError>
+    where
+        B: bytes::Buf + ExactSizeBuf,
+    {
+        self.shared.lock().write_data_channel(bytes, is_string)
+    }
+
+    /// WriteBatch writes several messages at once; see `DataChannel::write_batch`.
+    pub fn write_batch(&mut self, messages: &[Bytes]) -> Result<usize, DataChannelError> {
+        self.shared.lock().write_batch(messages)
+    }
+
+    /// Close closes the channel, ending the reading half's reads
+    /// once the remote has reset its stream as well.
+    pub fn close(&mut self) -> Result<(), DataChannelError> {
+        self.shared.lock().close()
+    }
+
+    /// WithChannel runs `f` with the shared channel locked, such as to
+    /// query its state.
+    pub fn with_channel<T>(&self, f: impl FnOnce(&mut DataChannel) -> T) -> T {
+        f(&mut self.shared.lock())
+    }
+
+    /// Spawn writes the messages sent to the returned `Outbox` from a task on
+    /// `runtime`, in order and in the channel's default payload format.
+    ///
+    /// The task ends once the outbox has been dropped and all of its messages
+    /// have been written, or once a write failed, whose error is logged.
+    pub fn spawn(self, runtime: &SharedRuntime) -> Outbox {
+        let queue = Arc::new(Mutex::new(OutboxQueue::default()));
+        let outbox = Outbox {
+            queue: queue.clone(),
+        };
+
+        let shared = self.shared.clone();
+        let data_channel = shared.lock();
+        let is_string = data_channel.config.default_payload_format.is_string();
+        data_channel.spawn_task(runtime, TaskKind::Writer, async move {
+            while let Some(mut message) = poll_fn(|cx| OutboxQueue::poll_next(&queue, cx)).await {
+                let written = poll_fn(|cx| {
+                    self.shared
+                        .lock()
+                        .poll_write_data_channel(cx, &mut message, is_string)
+                })
+                .await;
+                if let Err(error) = written {
+                    log::warn!("Writer task failed to write a message: {:?}", error);
+                    queue.lock().unwrap().failed = true;
+                    return;
+                }
+            }
+        });
+
+        outbox
+    }
+}
+
+/// A queue of messages for a writer task spawned with `DataChannelWriter::spawn`.
+///
+/// Dropping the outbox ends the task once the messages queued have been written.
+pub struct Outbox {
+    queue: Arc<Mutex<OutboxQueue>>,
+}
+
+#[derive(Default)]
+struct OutboxQueue {
+    messages: VecDeque<Bytes>,
+    // The writer task waiting for the next message.
+    waker: Option<Waker>,
+    // Set once the outbox has been dropped.
+    closed: bool,
+    // Set once the writer task ended after a failed write.
+    failed: bool,
+}
+
+impl OutboxQueue {
+    fn poll_next(queue: &Mutex<OutboxQueue>, cx: &mut Context<'_>) -> Poll<Option<Bytes>> {
+        let mut queue = queue.lock().unwrap();
+        if let Some(message) = queue.messages.pop_front() {
+            return Poll::Ready(Some(message));
+        }
+        if queue.closed {
+            return Poll::Ready(None);
+        }
+
+        queue.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+
+    fn wake(mut queue: MutexGuard<'_, OutboxQueue>) {
+        let waker = queue.waker.take();
+        drop(queue);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl Outbox {
+    /// Send queues `message` to be written, handing it back if the
+    /// writer task ended after a failed write.
+    pub fn send(&self, message: Bytes) -> Result<(), Bytes> {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.failed {
+            return Err(message);
+        }
+
+        queue.messages.push_back(message);
+        OutboxQueue::wake(queue);
+        Ok(())
+    }
+
+    /// Len returns the number of messages queued, not written yet.
+    pub fn len(&self) -> usize {
+        self.queue.lock().unwrap().messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for Outbox {
+    fn drop(&mut self) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.closed = true;
+        OutboxQueue::wake(queue);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::mpsc,
+        task::Waker,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::{
+        data_channel::{Config, IdleAction},
+        sctp::StreamError,
+        testing::{self, ThreadRuntime},
+    };
+
+    use super::*;
+
+    fn thread_runtime() -> SharedRuntime {
+        SharedRuntime::new(ThreadRuntime)
+    }
+
+    // Waits a bounded time for the tasks of `kind` to end.
+    fn wait_for_tasks(handle: &DataChannelHandle, kind: TaskKind) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while handle.with_channel(|dc| dc.observer().tasks(kind)) != 0 {
+            assert!(Instant::now() < deadline, "{:?} task kept running", kind);
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn halves_work_from_different_threads() {
+        let (dialer, acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        let (mut reader, _writer) = acceptor.split();
+        let (mut remote_reader, mut remote_writer) = dialer.split();
+
+        let receiver = thread::spawn(move || {
+            let mut received = vec![];
+            loop {
+                match reader.recv() {
+                    Ok(message) => received.push(message.data),
+                    Err(DataChannelError::Stream(StreamError::Eof)) => return received,
+                    Err(error) => panic!("read failed: {:?}", error),
+                }
+            }
+        });
+
+        remote_writer.write(&mut Bytes::from_static(b"a")).unwrap();
+        remote_writer.write(&mut Bytes::from_static(b"b")).unwrap();
+        remote_writer.close().unwrap();
+
+        assert_eq!(receiver.join().unwrap(), vec![&b"a"[..], &b"b"[..]]);
+        assert_eq!(
+            remote_reader.recv().unwrap_err(),
+            DataChannelError::Stream(StreamError::Eof)
+        );
+    }
+
+    #[test]
+    fn dropped_reads_lose_nothing() {
+        let (dialer, acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        let (mut reader, _writer) = acceptor.split();
+        let (_remote_reader, mut remote_writer) = dialer.split();
+
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(reader.poll_recv(&mut cx).is_pending());
+
+        remote_writer
+            .write(&mut Bytes::from_static(b"kept"))
+            .unwrap();
+        assert_eq!(reader.recv().unwrap().data, &b"kept"[..]);
+    }
+
+    #[test]
+    fn handles_share_writers_and_one_reader() {
+        let (dialer, acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        let handle = dialer.into_handle();
+        let (mut remote_reader, _remote_writer) = acceptor.split();
+
+        let writers: Vec<_> = (0..4)
+            .map(|_| {
+                let handle = handle.clone();
+                thread::spawn(move || {
+                    handle
+                        .writer()
+                        .write(&mut Bytes::from_static(b"hello"))
+                        .unwrap()
+                })
+            })
+            .collect();
+        for writer in writers {
+            assert_eq!(writer.join().unwrap(), 5);
+        }
+        for _ in 0..4 {
+            assert_eq!(remote_reader.recv().unwrap().data, &b"hello"[..]);
+        }
+        assert_eq!(handle.with_channel(|dc| dc.messages_sent()), 4);
+
+        let reader = handle.reader().unwrap();
+        assert!(handle.clone().reader().is_none());
+        drop(reader);
+        assert!(handle.reader().is_some());
+    }
+
+    #[test]
+    fn reader_task_delivers_messages() {
+        let config = Config {
+            label: "data".to_owned(),
+            ..Default::default()
+        };
+        let (dialer, acceptor) = testing::channel_pair(1, config).unwrap();
+        let handle = acceptor.into_handle();
+        let (_remote_reader, mut remote_writer) = dialer.split();
+
+        let (sender, received) = mpsc::channel();
+        handle
+            .reader()
+            .unwrap()
+            .spawn(&thread_runtime(), move |message| {
+                let name = thread::current().name().map(str::to_owned);
+                sender.send((name, message.map(|m| m.data))).unwrap();
+            });
+
+        remote_writer.write(&mut Bytes::from_static(b"a")).unwrap();
+        remote_writer.write(&mut Bytes::from_static(b"b")).unwrap();
+        remote_writer.close().unwrap();
+
+        let (name, message) = received.recv().unwrap();
+        assert_eq!(
+            name.as_deref(),
+            Some("data_channel.reader \"data\" on stream 1")
+        );
+        assert_eq!(message.unwrap(), &b"a"[..]);
+        assert_eq!(received.recv().unwrap().1.unwrap(), &b"b"[..]);
+        assert!(received.recv().is_err());
+        wait_for_tasks(&handle, TaskKind::Reader);
+    }
+
+    #[test]
+    fn writer_task_writes_outbox_in_order() {
+        let (dialer, acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        let handle = dialer.into_handle();
+        let (mut remote_reader, _remote_writer) = acceptor.split();
+
+        let outbox = handle.writer().spawn(&thread_runtime());
+        assert_eq!(
+            handle.with_channel(|dc| dc.observer().tasks(TaskKind::Writer)),
+            1
+        );
+        for message in [&b"one"[..], b"two", b"three"] {
+            outbox.send(Bytes::from_static(message)).unwrap();
+        }
+        for message in [&b"one"[..], b"two", b"three"] {
+            assert_eq!(remote_reader.recv().unwrap().data, message);
+        }
+
+        drop(outbox);
+        wait_for_tasks(&handle, TaskKind::Writer);
+        assert_eq!(handle.with_channel(|dc| dc.stats().tasks), 0);
+    }
+
+    #[test]
+    fn idle_timer_closes_idle_channel() {
+        let config = Config {
+            idle_timeout: Some(Duration::from_millis(10)),
+            idle_action: IdleAction::Close,
+            ..Default::default()
+        };
+        let (_dialer, acceptor) = testing::channel_pair(1, config).unwrap();
+        let handle = acceptor.into_handle();
+
+        handle.spawn_idle_timer(&thread_runtime(), Duration::from_millis(5));
+
+        wait_for_tasks(&handle, TaskKind::Timer);
+        assert!(matches!(
+            handle.with_channel(|dc| dc.ready_state()),
+            ReadyState::Closing | ReadyState::Closed
+        ));
+    }
+}
@@ -0,0 +1,316 @@
+use bytes::{Buf, BufMut};
+
+use crate::{
+    data_channel::{Config, PayloadFormat},
+    error::StateError,
+    marshal::{Marshal, MarshalSize, Unmarshal},
+    ChannelType,
+};
+
+const STATE_VERSION: u8 = 0x01;
+
+const FLAG_NEGOTIATED: u8 = 0b0000_0001;
+const FLAG_TEXT: u8 = 0b0000_0010;
+const FLAG_REMOTE_INITIATED: u8 = 0b0000_0100;
+
+const STATE_HEADER_LEN: usize = 48;
+
+/// The transferable state of a data channel, exported by
+/// `DataChannel::export_state` and restored by `DataChannel::restore`.
+///
+/// This allows a supervising process to hand ownership of a channel over
+/// to another process, provided the underlying SCTP association is handed
+/// over as well. Handles configured on the channel, such as its buffer
+/// provider or event emitter, are not part of the state.
+///
+/// # Memory layout
+///
+/// ```plain
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |    Version    |     Flags     |       Stream Identifier       |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |  Channel Type |   (Reserved)  |            Priority           |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                    Reliability Parameter                      |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |         Label Length          |       Protocol Length         |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// +                         Messages Sent                         +
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// +                       Messages Received                       +
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// +                          Bytes Sent                           +
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// +                        Bytes Received                         +
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// |                             Label                             |
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// |                            Protocol                           |
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+///
+/// Bit 0 of the flags is set for negotiated channels, bit 1 if the
+/// channel's default payload format is text and bit 2 if the channel
+/// was opened by the remote.
+#[derive(Eq, PartialEq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DataChannelState {
+    pub stream_identifier: u16,
+    pub channel_type: ChannelType,
+    pub negotiated: bool,
+    /// Whether the remote opened the channel by sending a DATA_CHANNEL_OPEN.
+    pub remote_initiated: bool,
+    pub priority: u16,
+    pub reliability_parameter: u32,
+    pub label: String,
+    pub protocol: String,
+    pub default_payload_format: PayloadFormat,
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+impl DataChannelState {
+    /// ApplyTo overwrites the channel parameters of `config` with those of the state.
+    pub fn apply_to(&self, config: &mut Config) {
+        config.channel_type = self.channel_type;
+        config.negotiated = self.negotiated;
+        config.priority = self.priority;
+        config.reliability_parameter = self.reliability_parameter;
+        config.label = self.label.clone();
+        config.protocol = self.protocol.clone();
+        config.default_payload_format = self.default_payload_format;
+    }
+}
+
+impl MarshalSize for DataChannelState {
+    fn marshal_size(&self) -> usize {
+        STATE_HEADER_LEN + self.label.len() + self.protocol.len()
+    }
+}
+
+impl Unmarshal for DataChannelState {
+    type Error = StateError;
+
+    fn unmarshal_from<B>(buf: &mut B) -> Result<Self, Self::Error>
+    where
+        B: Buf,
+    {
+        let required_len = STATE_HEADER_LEN;
+        if buf.remaining() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining(),
+            });
+        }
+
+        let version = buf.get_u8();
+        if version != STATE_VERSION {
+            return Err(Self::Error::UnsupportedVersion { version });
+        }
+
+        let flags = buf.get_u8();
+        let stream_identifier = buf.get_u16();
+        let channel_type = ChannelType::unmarshal_from(buf)?;
+        let _reserved = buf.get_u8();
+        let priority = buf.get_u16();
+        let reliability_parameter = buf.get_u32();
+        let label_len = buf.get_u16() as usize;
+        let protocol_len = buf.get_u16() as usize;
+        let messages_sent = buf.get_u64();
+        let messages_received = buf.get_u64();
+        let bytes_sent = buf.get_u64();
+        let bytes_received = buf.get_u64();
+
+        let required_len = label_len + protocol_len;
+        if buf.remaining() < required_len {
+            return Err(Self::Error::ExpectedAndActualLengthMismatch {
+                expected: required_len,
+                actual: buf.remaining(),
+            });
+        }
+
+        let mut label = Vec::with_capacity(label_len);
+        label.put(Buf::take(&mut *buf, label_len));
+        let mut protocol = Vec::with_capacity(protocol_len);
+        protocol.put(Buf::take(&mut *buf, protocol_len));
+
+        let default_payload_format = if flags & FLAG_TEXT != 0 {
+            PayloadFormat::Text
+        } else {
+            PayloadFormat::Binary
+        };
+
+        Ok(Self {
+            stream_identifier,
+            channel_type,
+            negotiated: flags & FLAG_NEGOTIATED != 0,
+            remote_initiated: flags & FLAG_REMOTE_INITIATED != 0,
+            priority,
+            reliability_parameter,
+            label: String::from_utf8(label)?,
+            protocol: String::from_utf8(protocol)?,
+            default_payload_format,
+            messages_sent,
+            messages_received,
+            bytes_sent,
+            bytes_received,
+        })
+    }
+}
+
+impl Marshal for DataChannelState {
+    type Error = StateError;
+
+    fn marshal_to<B>(&self, buf: &mut B) -> Result<usize, Self::Error>
+    where
+        B: BufMut,
+    {
+        for field in [&self.label, &self.protocol] {
+            if field.len() > u16::MAX as usize {
+                return Err(Self::Error::StringTooLong { len: field.len() });
+            }
+        }
+
+        let required_len = self.marshal_size();
+        if buf.remaining_mut() < required_len {
+            return Err(Self::Error::UnexpectedEndOfBuffer {
+                expected: required_len,
+                actual: buf.remaining_mut(),
+            });
+        }
+
+        let mut flags = 0;
+        if self.negotiated {
+            flags |= FLAG_NEGOTIATED;
+        }
+        if self.default_payload_format == PayloadFormat::Text {
+            flags |= FLAG_TEXT;
+        }
+        if self.remote_initiated {
+            flags |= FLAG_REMOTE_INITIATED;
+        }
+
+        buf.put_u8(STATE_VERSION);
+        buf.put_u8(flags);
+        buf.put_u16(self.stream_identifier);
+        self.channel_type.marshal_to(buf)?;
+        buf.put_u8(0);
+        buf.put_u16(self.priority);
+        buf.put_u32(self.reliability_parameter);
+        buf.put_u16(self.label.len() as u16);
+        buf.put_u16(self.protocol.len() as u16);
+        buf.put_u64(self.messages_sent);
+        buf.put_u64(self.messages_received);
+        buf.put_u64(self.bytes_sent);
+        buf.put_u64(self.bytes_received);
+        buf.put_slice(self.label.as_bytes());
+        buf.put_slice(self.protocol.as_bytes());
+
+        Ok(required_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+
+    use crate::data_channel::ConfigBuilder;
+
+    use super::*;
+
+    fn state() -> DataChannelState {
+        DataChannelState {
+            stream_identifier: 3,
+            channel_type: ChannelType::PartialReliableRexmit,
+            negotiated: true,
+            remote_initiated: false,
+            priority: 256,
+            reliability_parameter: 5,
+            label: "chat".to_owned(),
+            protocol: "json".to_owned(),
+            default_payload_format: PayloadFormat::Text,
+            messages_sent: 10,
+            messages_received: 20,
+            bytes_sent: 300,
+            bytes_received: 400,
+        }
+    }
+
+    #[test]
+    fn marshal_unmarshal_roundtrip() {
+        let state = state();
+
+        let mut buf = BytesMut::with_capacity(state.marshal_size());
+        let bytes_written = state.marshal_to(&mut buf).unwrap();
+        assert_eq!(bytes_written, STATE_HEADER_LEN + 8);
+        assert_eq!(&buf[..8], &[0x01, 0x03, 0x00, 0x03, 0x01, 0x00, 0x01, 0x00]);
+
+        let mut bytes = buf.freeze();
+        let actual = DataChannelState::unmarshal_from(&mut bytes).unwrap();
+
+        assert_eq!(actual, state);
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn unmarshal_unsupported_version() {
+        let mut bytes = state().marshal().unwrap().to_vec();
+        bytes[0] = 0x7f;
+
+        let result = DataChannelState::unmarshal_from(&mut Bytes::from(bytes));
+
+        assert_eq!(
+            result,
+            Err(StateError::UnsupportedVersion { version: 0x7f })
+        );
+    }
+
+    #[test]
+    fn unmarshal_truncated() {
+        let bytes = state().marshal().unwrap();
+
+        let result = DataChannelState::unmarshal_from(&mut bytes.slice(..bytes.len() - 1));
+
+        assert_eq!(
+            result,
+            Err(StateError::ExpectedAndActualLengthMismatch {
+                expected: 8,
+                actual: 7
+            })
+        );
+    }
+
+    #[test]
+    fn apply_to() {
+        let mut config = ConfigBuilder::default()
+            .label("stale".to_owned())
+            .build()
+            .unwrap();
+
+        state().apply_to(&mut config);
+
+        assert_eq!(config.channel_type, ChannelType::PartialReliableRexmit);
+        assert!(config.negotiated);
+        assert_eq!(config.priority, 256);
+        assert_eq!(config.reliability_parameter, 5);
+        assert_eq!(config.label, "chat");
+        assert_eq!(config.protocol, "json");
+        assert_eq!(config.default_payload_format, PayloadFormat::Text);
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    sync::atomic::Ordering,
+    task::{Context, Poll},
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{
+    clock::{BoxDelay, SharedClock},
+    data_channel::{DataChannel, ReadyState},
+};
+
+/// A snapshot of a single channel's counters.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct ChannelStats {
+    pub stream_identifier: u16,
+    pub ready_state: ReadyState,
+    pub messages_sent: usize,
+    pub messages_received: usize,
+    pub bytes_sent: usize,
+    pub bytes_received: usize,
+    /// The number of bytes queued to be sent.
+    pub buffered_amount: u64,
+    /// The number of bytes sent but not acknowledged yet.
+    pub bytes_in_flight: u64,
+    /// The number of received messages dropped for exceeding the receive buffer limit.
+    pub messages_dropped: u64,
+    /// The number of reads waiting for a message, including pending polled reads.
+    pub reads_in_flight: usize,
+    /// The number of writes waiting for the stream, including pending polled writes.
+    pub writes_in_flight: usize,
+    /// The number of background tasks running for the channel.
+    pub tasks: usize,
+}
+
+impl ChannelStats {
+    pub fn of(data_channel: &DataChannel) -> Self {
+        let observer = data_channel.observer();
+
+        Self {
+            stream_identifier: data_channel.stream_identifier(),
+            ready_state: data_channel.ready_state(),
+            messages_sent: data_channel.messages_sent.load(Ordering::Relaxed),
+            messages_received: data_channel.messages_received.load(Ordering::Relaxed),
+            bytes_sent: data_channel.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: data_channel.bytes_received.load(Ordering::Relaxed),
+            buffered_amount: data_channel.buffered_amount(),
+            bytes_in_flight: data_channel.bytes_in_flight(),
+            messages_dropped: data_channel.messages_dropped(),
+            reads_in_flight: observer.reads_in_flight(),
+            writes_in_flight: observer.writes_in_flight(),
+            tasks: observer.stats().tasks,
+        }
+    }
+}
+
+/// A snapshot of a channel's lifetime counters, as returned by `DataChannel::stats`.
+///
+/// The counters are shared with the channel's `Observer`s, so they can be
+/// sampled from elsewhere while the channel is in use.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct DataChannelStats {
+    pub messages_sent: usize,
+    pub messages_received: usize,
+    pub bytes_sent: usize,
+    pub bytes_received: usize,
+    /// The number of failed reads, writes and lifecycle steps.
+    pub errors: usize,
+    /// Wall-clock time at which the channel first became open.
+    pub opened_at: Option<SystemTime>,
+    /// The number of reads waiting for a message, including pending polled reads.
+    pub reads_in_flight: usize,
+    /// The number of writes waiting for the stream, including pending polled writes.
+    pub writes_in_flight: usize,
+    /// The number of background tasks running for the channel.
+    pub tasks: usize,
+}
+
+/// A snapshot of the counters of all channels over an association,
+/// ordered by stream identifier.
+#[derive(Eq, PartialEq, Default, Clone, Debug)]
+pub struct AssociationStats {
+    pub channels: Vec<ChannelStats>,
+}
+
+impl AssociationStats {
+    /// OpenChannels returns the number of channels in the `Open` state.
+    pub fn open_channels(&self) -> usize {
+        self.channels
+            .iter()
+            .filter(|stats| stats.ready_state == ReadyState::Open)
+            .count()
+    }
+
+    pub fn messages_sent(&self) -> usize {
+        self.channels.iter().map(|stats| stats.messages_sent).sum()
+    }
+
+    pub fn messages_received(&self) -> usize {
+        self.channels
+            .iter()
+            .map(|stats| stats.messages_received)
+            .sum()
+    }
+
+    pub fn bytes_sent(&self) -> usize {
+        self.channels.iter().map(|stats| stats.bytes_sent).sum()
+    }
+
+    pub fn bytes_received(&self) -> usize {
+        self.channels.iter().map(|stats| stats.bytes_received).sum()
+    }
+
+    pub fn buffered_amount(&self) -> u64 {
+        self.channels
+            .iter()
+            .map(|stats| stats.buffered_amount)
+            .sum()
+    }
+
+    pub fn bytes_in_flight(&self) -> u64 {
+        self.channels
+            .iter()
+            .map(|stats| stats.bytes_in_flight)
+            .sum()
+    }
+
+    pub fn reads_in_flight(&self) -> usize {
+        self.channels
+            .iter()
+            .map(|stats| stats.reads_in_flight)
+            .sum()
+    }
+
+    pub fn writes_in_flight(&self) -> usize {
+        self.channels
+            .iter()
+            .map(|stats| stats.writes_in_flight)
+            .sum()
+    }
+
+    pub fn tasks(&self) -> usize {
+        self.channels.iter().map(|stats| stats.tasks).sum()
+    }
+}
+
+/// A stats snapshot, along with the time it was taken at.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Sample<S> {
+    pub taken_at: Instant,
+    pub stats: S,
+}
+
+/// StatsStream yields a stats snapshot once per interval, starting right away.
+///
+/// As an `Iterator` it blocks between snapshots; it can also be polled
+/// from async code via `poll_next`.
+/// Intervals are measured from the previous deadline, so a slow
+/// consumer receives the snapshots it missed with no delay in between.
+pub struct StatsStream<'a, S> {
+    snapshot: Box<dyn FnMut() -> S + 'a>,
+    clock: SharedClock,
+    interval: Duration,
+    deadline: Instant,
+    delay: Option<BoxDelay>,
+}
+
+impl<'a, S> StatsStream<'a, S> {
+    pub(crate) fn new<F>(snapshot: F, clock: SharedClock, interval: Duration) -> Self
+    where
+        F: FnMut() -> S + 'a,
+    {
+        let deadline = clock.now();
+
+        Self {
+            snapshot: Box::new(snapshot),
+            clock,
+            interval,
+            deadline,
+            delay: None,
+        }
+    }
+
+    /// PollNext resolves with the next snapshot once its time has come.
+    pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Sample<S>> {
+        let remaining = self.deadline.saturating_duration_since(self.clock.now());
+        if !remaining.is_zero() {
+            let clock = &self.clock;
+            let delay = self.delay.get_or_insert_with(|| clock.delay(remaining));
+            if delay.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+
+        self.delay = None;
+        Poll::Ready(self.sample())
+    }
+
+    fn sample(&mut self) -> Sample<S> {
+        self.deadline += self.interval;
+
+        Sample {
+            taken_at: self.clock.now(),
+            stats: (self.snapshot)(),
+        }
+    }
+}
+
+impl<'a, S> Iterator for StatsStream<'a, S> {
+    type Item = Sample<S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = self.deadline.saturating_duration_since(self.clock.now());
+        if !remaining.is_zero() {
+            self.clock.sleep(remaining);
+        }
+
+        Some(self.sample())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::{Clock, ManualClock};
+
+    use super::*;
+
+    #[test]
+    fn stats_stream_yields_per_interval() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        let mut count = 0;
+
+        let samples: Vec<_> = StatsStream::new(
+            || {
+                count += 1;
+                count
+            },
+            SharedClock::new(clock.clone()),
+            Duration::from_secs(1),
+        )
+        .take(3)
+        .collect();
+
+        assert_eq!(
+            samples,
+            vec![
+                Sample {
+                    taken_at: start,
+                    stats: 1
+                },
+                Sample {
+                    taken_at: start + Duration::from_secs(1),
+                    stats: 2
+                },
+                Sample {
+                    taken_at: start + Duration::from_secs(2),
+                    stats: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn stats_stream_poll_next() {
+        let clock = ManualClock::new();
+        let mut stream = StatsStream::new(
+            || (),
+            SharedClock::new(clock.clone()),
+            Duration::from_secs(1),
+        );
+        let mut cx = Context::from_waker(std::task::Waker::noop());
+
+        assert!(stream.poll_next(&mut cx).is_ready());
+        assert!(stream.poll_next(&mut cx).is_pending());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(stream.poll_next(&mut cx).is_ready());
+    }
+}
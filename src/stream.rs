@@ -0,0 +1,180 @@
+//! `futures` integration for the messages of a data channel.
+//!
+//! `DataChannelStream` is both a `Stream` of the messages received on a
+//! channel and a `Sink` for binary messages to send on it, so channels can
+//! be driven with combinators, or `split` into a receiving and a sending half.
+
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use bytes::{Bytes, BytesMut};
+use futures_core::Stream;
+use futures_sink::Sink;
+
+use crate::{
+    data_channel::{DataChannel, DataChannelMessage, RECEIVE_MTU},
+    error::DataChannelError,
+    sctp::StreamError,
+};
+
+/// DataChannelStream wraps a `DataChannel` as a `Stream` and `Sink` of messages.
+///
+/// The stream ends once the remote has reset its stream. Messages sent are
+/// held until the sink is flushed, or until the next one is sent.
+pub struct DataChannelStream {
+    data_channel: DataChannel,
+    // The message accepted by `start_send`, not written yet.
+    pending: Option<Bytes>,
+    terminated: bool,
+}
+
+impl DataChannelStream {
+    pub fn new(data_channel: DataChannel) -> Self {
+        Self {
+            data_channel,
+            pending: None,
+            terminated: false,
+        }
+    }
+
+    pub fn data_channel(&self) -> &DataChannel {
+        &self.data_channel
+    }
+
+    /// IntoInner returns the wrapped channel, discarding a message not flushed yet.
+    pub fn into_inner(self) -> DataChannel {
+        self.data_channel
+    }
+}
+
+impl Stream for DataChannelStream {
+    type Item = Result<DataChannelMessage, DataChannelError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.terminated {
+            return Poll::Ready(None);
+        }
+
+        let buffer_provider = this.data_channel.config.buffer_provider.clone();
+        let mut buf: BytesMut = buffer_provider.acquire(RECEIVE_MTU);
+        let result = match this.data_channel.poll_read_message_into(cx, &mut buf) {
+            Poll::Ready(result) => result,
+            Poll::Pending => {
+                buffer_provider.release(buf);
+                return Poll::Pending;
+            }
+        };
+
+        let item = match result {
+            Ok((n, ppi)) => Some(Ok(this.data_channel.message(buf.split_to(n).freeze(), ppi))),
+            Err(DataChannelError::Stream(StreamError::Eof)) => {
+                this.terminated = true;
+                None
+            }
+            Err(error) => Some(Err(error)),
+        };
+        buffer_provider.release(buf);
+
+        Poll::Ready(item)
+    }
+}
+
+impl Sink<Bytes> for DataChannelStream {
+    type Error = DataChannelError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.poll_flush(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Bytes) -> Result<(), Self::Error> {
+        self.get_mut().pending = Some(item);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        if let Some(message) = &mut this.pending {
+            ready!(this
+                .data_channel
+                .poll_write_data_channel(cx, message, false))?;
+            this.pending = None;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+
+        Poll::Ready(self.get_mut().data_channel.close())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::Waker;
+
+    use crate::{data_channel::Config, sctp::PayloadType, testing};
+
+    use super::*;
+
+    fn next(
+        stream: &mut DataChannelStream,
+    ) -> Option<Result<DataChannelMessage, DataChannelError>> {
+        let mut cx = Context::from_waker(Waker::noop());
+        match Pin::new(stream).poll_next(&mut cx) {
+            Poll::Ready(item) => item,
+            Poll::Pending => panic!("expected an item"),
+        }
+    }
+
+    fn send(sink: &mut DataChannelStream, message: &'static [u8]) {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut sink = Pin::new(sink);
+        assert_eq!(sink.as_mut().poll_ready(&mut cx), Poll::Ready(Ok(())));
+        sink.as_mut()
+            .start_send(Bytes::from_static(message))
+            .unwrap();
+        assert_eq!(sink.as_mut().poll_flush(&mut cx), Poll::Ready(Ok(())));
+    }
+
+    #[test]
+    fn streams_and_sinks_messages() {
+        let (dialer, acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        let mut local = DataChannelStream::new(dialer);
+        let mut remote = DataChannelStream::new(acceptor);
+
+        send(&mut remote, b"hello");
+        send(&mut remote, b"world");
+
+        assert_eq!(
+            next(&mut local),
+            Some(Ok(DataChannelMessage {
+                is_string: false,
+                data: Bytes::from_static(b"hello"),
+                ppid: PayloadType::WebRtcBinary,
+                stream_identifier: 1,
+            }))
+        );
+        assert_eq!(next(&mut local).unwrap().unwrap().data, &b"world"[..]);
+    }
+
+    #[test]
+    fn remote_close_ends_stream() {
+        let (dialer, acceptor) = testing::channel_pair(1, Config::default()).unwrap();
+        let mut local = DataChannelStream::new(dialer);
+        let mut remote = DataChannelStream::new(acceptor);
+
+        let mut cx = Context::from_waker(Waker::noop());
+        assert_eq!(
+            Pin::new(&mut remote).poll_close(&mut cx),
+            Poll::Ready(Ok(()))
+        );
+
+        assert_eq!(next(&mut local), None);
+        assert_eq!(next(&mut local), None);
+    }
+}
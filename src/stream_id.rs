@@ -0,0 +1,201 @@
+//! Allocation of the stream identifiers new channels are opened on.
+
+use std::collections::BTreeSet;
+
+/// The DTLS role of the local endpoint, which determines the parity of the
+/// stream identifiers it may use for channels it opens.
+///
+/// See RFC 8832, section 6.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum Role {
+    // The DTLS client uses even stream identifiers.
+    Client,
+    // The DTLS server uses odd stream identifiers.
+    Server,
+}
+
+impl Role {
+    /// Owns returns whether channels opened with the role may use the stream identifier.
+    pub fn owns(&self, stream_identifier: u16) -> bool {
+        match self {
+            Self::Client => stream_identifier.is_multiple_of(2),
+            Self::Server => !stream_identifier.is_multiple_of(2),
+        }
+    }
+}
+
+/// The largest usable stream identifier; 65535 is reserved by RFC 8832.
+pub const MAX_STREAM_IDENTIFIER: u16 = 65534;
+
+/// A strategy for picking the stream identifiers of new channels.
+pub trait StreamIdAllocator: Send {
+    /// Allocate returns a stream identifier for which `is_in_use` returns
+    /// false, or `None` if no such identifier is left.
+    fn allocate(&mut self, is_in_use: &dyn Fn(u16) -> bool) -> Option<u16>;
+
+    /// Reserve marks a stream identifier as in use, such as one the
+    /// remote opened a channel on, so that it isn't handed out.
+    ///
+    /// This method has a default implementation which does nothing.
+    fn reserve(&mut self, _stream_identifier: u16) {}
+
+    /// Release hands back a stream identifier which is no longer in use.
+    ///
+    /// This method has a default implementation which does nothing.
+    fn release(&mut self, _stream_identifier: u16) {}
+
+    /// SetMaxStreams updates the number of outbound streams negotiated for
+    /// the association, such as once more were added, bounding the stream
+    /// identifiers to allocate from.
+    ///
+    /// This method has a default implementation which does nothing.
+    fn set_max_streams(&mut self, _max_streams: u16) {}
+}
+
+/// The default `StreamIdAllocator`, handing out the identifiers
+/// owned by its role in ascending order and wrapping around after
+/// the last one, skipping identifiers still in use.
+///
+/// Besides those `is_in_use` reports, the identifiers the allocator
+/// handed out or had reserved are in use until released.
+#[derive(Debug)]
+pub struct SequentialAllocator {
+    role: Role,
+    next: u16,
+    max_streams: u16,
+    in_use: BTreeSet<u16>,
+}
+
+impl SequentialAllocator {
+    pub fn new(role: Role) -> Self {
+        Self {
+            role,
+            next: Self::first(role),
+            max_streams: u16::MAX,
+            in_use: BTreeSet::new(),
+        }
+    }
+
+    /// WithMaxStreams limits the allocator to the identifiers below
+    /// `max_streams`, the number of outbound streams negotiated for the
+    /// association. Defaults to all identifiers up to `MAX_STREAM_IDENTIFIER`.
+    pub fn with_max_streams(mut self, max_streams: u16) -> Self {
+        self.max_streams = max_streams;
+        self
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn max_streams(&self) -> u16 {
+        self.max_streams
+    }
+
+    /// IsInUse returns whether the allocator considers the identifier in use.
+    pub fn is_in_use(&self, stream_identifier: u16) -> bool {
+        self.in_use.contains(&stream_identifier)
+    }
+
+    // The largest identifier the allocator may hand out, if any.
+    fn last(&self) -> Option<u16> {
+        let last = self.max_streams.checked_sub(1)?.min(MAX_STREAM_IDENTIFIER);
+        let last = if self.role.owns(last) {
+            last
+        } else {
+            last.checked_sub(1)?
+        };
+        Some(last)
+    }
+
+    fn first(role: Role) -> u16 {
+        match role {
+            Role::Client => 0,
+            Role::Server => 1,
+        }
+    }
+}
+
+impl StreamIdAllocator for SequentialAllocator {
+    fn allocate(&mut self, is_in_use: &dyn Fn(u16) -> bool) -> Option<u16> {
+        let first = Self::first(self.role);
+        let last = self.last()?;
+        let candidates = (last - first) / 2 + 1;
+        if self.next > last {
+            self.next = first;
+        }
+
+        for _ in 0..candidates {
+            let candidate = self.next;
+            self.next = match candidate.checked_add(2) {
+                Some(next) if next <= last => next,
+                _ => first,
+            };
+
+            if !is_in_use(candidate) && self.in_use.insert(candidate) {
+                return Some(candidate);
+            }
+        }
+
+        None
+    }
+
+    fn reserve(&mut self, stream_identifier: u16) {
+        self.in_use.insert(stream_identifier);
+    }
+
+    fn release(&mut self, stream_identifier: u16) {
+        self.in_use.remove(&stream_identifier);
+    }
+
+    fn set_max_streams(&mut self, max_streams: u16) {
+        self.max_streams = max_streams;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequential_allocator_respects_parity() {
+        let mut client = SequentialAllocator::new(Role::Client);
+        let mut server = SequentialAllocator::new(Role::Server);
+        let is_in_use = |stream_identifier| stream_identifier == 2;
+
+        assert_eq!(client.allocate(&is_in_use), Some(0));
+        assert_eq!(client.allocate(&is_in_use), Some(4));
+        assert_eq!(server.allocate(&is_in_use), Some(1));
+        assert_eq!(server.allocate(&is_in_use), Some(3));
+    }
+
+    #[test]
+    fn sequential_allocator_exhausted() {
+        let mut allocator = SequentialAllocator::new(Role::Server);
+
+        assert_eq!(
+            allocator.allocate(&|stream_identifier| stream_identifier != 65533),
+            Some(65533)
+        );
+        assert_eq!(allocator.allocate(&|_| false), Some(1));
+        assert_eq!(allocator.allocate(&|_| true), None);
+    }
+
+    #[test]
+    fn sequential_allocator_tracks_usage() {
+        let mut allocator = SequentialAllocator::new(Role::Client).with_max_streams(6);
+        allocator.reserve(2);
+
+        assert_eq!(allocator.allocate(&|_| false), Some(0));
+        assert_eq!(allocator.allocate(&|_| false), Some(4));
+        assert!(allocator.is_in_use(4));
+        assert_eq!(allocator.allocate(&|_| false), None);
+
+        allocator.release(2);
+        assert!(!allocator.is_in_use(2));
+        assert_eq!(allocator.allocate(&|_| false), Some(2));
+
+        let mut allocator = SequentialAllocator::new(Role::Server).with_max_streams(1);
+        assert_eq!(allocator.allocate(&|_| false), None);
+    }
+}
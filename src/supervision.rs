@@ -0,0 +1,108 @@
+//! Supervision of the work a channel does on the side of reads and writes.
+//!
+//! Handling DCEP messages, including those dispatched to a `DcepHandler`,
+//! and feeding a `Recorder` happen within a channel's reads and writes.
+//! If any of them fails or panics, the channel's `SupervisionPolicy`
+//! decides whether to carry on without the affected message, or to close
+//! the channel and fail all further operations with the cause, instead
+//! of either unwinding through the caller or going unnoticed.
+
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+};
+
+use crate::error::DataChannelError;
+
+/// What to do once a supervised task failed.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub enum SupervisionPolicy {
+    /// Logs the failure and carries on with the next message.
+    #[default]
+    Restart,
+    /// Closes the channel, emitting an `EventKind::Failed` event, and fails
+    /// all further reads and writes with `DataChannelError::TaskFailed`.
+    Fail,
+}
+
+/// The work done by a channel on the side of reads and writes.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum SupervisedTask {
+    DcepHandling,
+    Recording,
+}
+
+impl SupervisedTask {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DcepHandling => "dcep_handling",
+            Self::Recording => "recording",
+        }
+    }
+}
+
+/// The cause of a supervised task's failure.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct TaskFailure {
+    pub task: SupervisedTask,
+    pub cause: String,
+}
+
+impl From<TaskFailure> for DataChannelError {
+    fn from(failure: TaskFailure) -> Self {
+        DataChannelError::TaskFailed {
+            task: failure.task,
+            cause: failure.cause,
+        }
+    }
+}
+
+/// Runs `f`, catching both the errors it returns and its panics.
+pub(crate) fn run<T, F>(task: SupervisedTask, f: F) -> Result<T, TaskFailure>
+where
+    F: FnOnce() -> Result<T, DataChannelError>,
+{
+    let cause = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => return Ok(value),
+        Ok(Err(error)) => error.to_string().trim_end().to_owned(),
+        Err(payload) => format!("panicked: {}", panic_message(payload.as_ref())),
+    };
+
+    Err(TaskFailure { task, cause })
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown cause"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_catches_errors_and_panics() {
+        assert_eq!(run(SupervisedTask::Recording, || Ok(1)), Ok(1));
+
+        let result = run(SupervisedTask::DcepHandling, || -> Result<(), _> {
+            Err(DataChannelError::Timeout)
+        });
+        assert_eq!(
+            result,
+            Err(TaskFailure {
+                task: SupervisedTask::DcepHandling,
+                cause: "Operation timed out".to_owned(),
+            })
+        );
+
+        let result = run(SupervisedTask::Recording, || -> Result<(), _> {
+            panic!("disk full")
+        });
+        assert_eq!(result.unwrap_err().cause, "panicked: disk full");
+    }
+}
@@ -0,0 +1,175 @@
+//! In-process test doubles.
+//!
+//! `simulated` connects two associations over in-memory queues instead
+//! of SCTP, so application logic can be exercised at high speed and on
+//! targets the SCTP stack doesn't support. Messages are delivered
+//! reliably, in order and instantly, whatever the channel type.
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    blocking,
+    clock::BoxDelay,
+    data_channel::{Config, DataChannel},
+    error::DataChannelError,
+    quality::QualitySignals,
+    runtime::{BoxTask, Runtime},
+    sctp::{simulated, Association},
+};
+
+/// Simulated returns both ends of an in-memory association.
+pub fn simulated() -> (Association, Association) {
+    let (local, remote) = simulated::Association::pair();
+
+    (local.into(), remote.into())
+}
+
+/// ChannelPair opens a channel with the given configuration on a fresh
+/// simulated association, returning the dialing and the accepting end.
+///
+/// The accepting end is open right away, while the dialing end
+/// transitions to open once it has read the remote's ACK.
+pub fn channel_pair(
+    identifier: u16,
+    config: Config,
+) -> Result<(DataChannel, DataChannel), DataChannelError> {
+    let (local, remote) = simulated();
+
+    let dialer = DataChannel::dial(&local, identifier, config.clone())?;
+    let acceptor = DataChannel::accept(&remote, config)?;
+
+    Ok((dialer, acceptor))
+}
+
+/// FailWrites makes the next `count` writes on the given stream, from the
+/// given end of a simulated association, fail with a transient error.
+pub fn fail_writes(association: &Association, stream_identifier: u16, count: usize) {
+    association.fail_writes(stream_identifier, count);
+}
+
+/// FailReliabilityParams makes the next change of reliability parameters on
+/// the given stream, from the given end of a simulated association, fail
+/// as if the stream had been reset.
+pub fn fail_reliability_params(association: &Association, stream_identifier: u16) {
+    association.fail_reliability_params(stream_identifier);
+}
+
+/// HoldWrites holds back all writes on the given stream, from the given end
+/// of a simulated association, counting them towards the stream's buffered
+/// amount until `release_writes` delivers them.
+pub fn hold_writes(association: &Association, stream_identifier: u16) {
+    association.hold_writes(stream_identifier);
+}
+
+/// ReleaseWrites delivers the writes held back by `hold_writes`, calling the
+/// stream's buffered amount low callback if its threshold was crossed.
+pub fn release_writes(association: &Association, stream_identifier: u16) {
+    association.release_writes(stream_identifier);
+}
+
+/// SetMaxMessageSize makes both ends of a simulated association advertise
+/// the given maximum message size, instead of the default of 64 KiB.
+pub fn set_max_message_size(association: &Association, max_message_size: usize) {
+    association.set_max_message_size(max_message_size);
+}
+
+/// SetMaxOutboundStreams limits both ends of a simulated association to the
+/// given number of outbound streams, until more are added.
+pub fn set_max_outbound_streams(association: &Association, max_outbound_streams: u16) {
+    association.set_max_outbound_streams(max_outbound_streams);
+}
+
+/// RefuseOutboundStreams makes both ends of a simulated association refuse
+/// requests for more outbound streams, keeping the streams they have.
+pub fn refuse_outbound_streams(association: &Association) {
+    association.refuse_outbound_streams();
+}
+
+/// SetCongestionWindow makes both ends of a simulated association report
+/// the given congestion window, instead of the initial one of SCTP.
+pub fn set_congestion_window(association: &Association, congestion_window: u64) {
+    association.set_congestion_window(congestion_window);
+}
+
+/// SetQualitySignals makes both ends of a simulated association report the
+/// given SCTP signals, for exercising quality monitoring.
+pub fn set_quality_signals(association: &Association, signals: QualitySignals) {
+    association.set_quality_signals(signals);
+}
+
+/// ThreadRuntime is a `Runtime` running every task on a thread of its own,
+/// named after the task, for exercising background tasks without an executor.
+#[derive(Eq, PartialEq, Default, Copy, Clone, Debug)]
+pub struct ThreadRuntime;
+
+impl Runtime for ThreadRuntime {
+    fn spawn(&self, name: &str, mut task: BoxTask) {
+        let spawned = thread::Builder::new()
+            .name(name.to_owned())
+            .spawn(move || blocking::block_on(|cx| task.as_mut().poll(cx)));
+        if let Err(error) = spawned {
+            log::warn!("Failed to spawn task {}: {}", name, error);
+        }
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxDelay {
+        Box::pin(futures_timer::Delay::new(duration))
+    }
+
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+
+    use crate::{data_channel::ReadyState, sctp::StreamError};
+
+    use super::*;
+
+    #[test]
+    fn channel_pair_exchanges_messages() {
+        let config = Config {
+            label: "chat".to_owned(),
+            ..Default::default()
+        };
+        let (mut dialer, mut acceptor) = channel_pair(1, config).unwrap();
+
+        assert_eq!(acceptor.ready_state(), ReadyState::Open);
+        assert_eq!(acceptor.config.label, "chat");
+
+        dialer
+            .write_data_channel(&mut Bytes::from_static(b"hello"), true)
+            .unwrap();
+        let mut buf = BytesMut::with_capacity(1024);
+        let (n, is_string) = acceptor.read_data_channel(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"hello");
+        assert!(is_string);
+
+        acceptor
+            .write_data_channel(&mut Bytes::from_static(b"world"), false)
+            .unwrap();
+        let mut buf = BytesMut::with_capacity(1024);
+        let (n, is_string) = dialer.read_data_channel(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"world");
+        assert!(!is_string);
+        assert_eq!(dialer.ready_state(), ReadyState::Open);
+    }
+
+    #[test]
+    fn close_ends_remote_reads() {
+        let (mut dialer, mut acceptor) = channel_pair(1, Config::default()).unwrap();
+
+        dialer.close().unwrap();
+
+        let mut buf = BytesMut::with_capacity(1024);
+        let result = acceptor.read_data_channel(&mut buf);
+        assert_eq!(result, Err(DataChannelError::Stream(StreamError::Eof)));
+        assert_eq!(acceptor.ready_state(), ReadyState::Closed);
+    }
+}
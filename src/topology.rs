@@ -0,0 +1,165 @@
+//! Declarative channel topologies.
+//!
+//! A `Topology` lists the channels a deployment opens, and can be
+//! deserialized from any format supported by serde, such as TOML or JSON:
+//!
+//! ```toml
+//! [[channels]]
+//! stream_identifier = 0
+//! label = "chat"
+//!
+//! [[channels]]
+//! stream_identifier = 2
+//! label = "telemetry"
+//! channel_type = "partial_reliable_rexmit_unordered"
+//! reliability_parameter = 0
+//! negotiated = true
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    channel_type::ChannelType,
+    data_channel::{Config, PayloadFormat},
+    error::DataChannelError,
+    manager::DataChannelManager,
+};
+
+/// The definition of a single channel within a `Topology`.
+///
+/// All fields but the stream identifier and label are optional
+/// and default to the corresponding `Config` default.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChannelDefinition {
+    pub stream_identifier: u16,
+    pub label: String,
+    #[serde(default)]
+    pub protocol: String,
+    #[serde(default)]
+    pub channel_type: ChannelType,
+    #[serde(default)]
+    pub reliability_parameter: u32,
+    #[serde(default)]
+    pub priority: u16,
+    /// Whether the channel has been negotiated out-of-band,
+    /// skipping the DCEP handshake.
+    #[serde(default)]
+    pub negotiated: bool,
+    #[serde(default)]
+    pub default_payload_format: PayloadFormat,
+    /// The buffered amount considered "low", if different from the default.
+    #[serde(default)]
+    pub buffered_amount_low_threshold: Option<u64>,
+}
+
+impl ChannelDefinition {
+    /// Config returns the configuration to open the channel with.
+    pub fn config(&self) -> Config {
+        Config {
+            channel_type: self.channel_type,
+            negotiated: self.negotiated,
+            priority: self.priority,
+            reliability_parameter: self.reliability_parameter,
+            label: self.label.clone(),
+            protocol: self.protocol.clone(),
+            default_payload_format: self.default_payload_format,
+            ..Default::default()
+        }
+    }
+}
+
+/// A set of channels to open on a `DataChannelManager`.
+#[derive(Eq, PartialEq, Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Topology {
+    #[serde(default)]
+    pub channels: Vec<ChannelDefinition>,
+}
+
+impl Topology {
+    /// OpenAll dials every channel of the topology on the manager, in order,
+    /// returning their stream identifiers.
+    ///
+    /// Stops at the first channel which fails to open; channels opened
+    /// before it remain managed.
+    pub fn open_all(&self, manager: &mut DataChannelManager) -> Result<Vec<u16>, DataChannelError> {
+        let mut stream_identifiers = Vec::with_capacity(self.channels.len());
+
+        for definition in &self.channels {
+            let data_channel = manager.dial(definition.stream_identifier, definition.config())?;
+            if let Some(threshold) = definition.buffered_amount_low_threshold {
+                data_channel.set_buffered_amount_low_threshold(threshold);
+            }
+
+            stream_identifiers.push(definition.stream_identifier);
+        }
+
+        Ok(stream_identifiers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_defaults() {
+        let topology: Topology = serde_json::from_str(
+            r#"{ "channels": [{ "stream_identifier": 1, "label": "chat" }] }"#,
+        )
+        .unwrap();
+
+        let definition = &topology.channels[0];
+        assert_eq!(definition.stream_identifier, 1);
+        assert_eq!(definition.buffered_amount_low_threshold, None);
+        let config = definition.config();
+        assert_eq!(config.label, "chat");
+        assert_eq!(config.channel_type, ChannelType::Reliable);
+        assert!(!config.negotiated);
+        assert_eq!(config.default_payload_format, PayloadFormat::Binary);
+    }
+
+    #[test]
+    fn deserialize_full() {
+        let topology: Topology = serde_json::from_str(
+            r#"{
+                "channels": [{
+                    "stream_identifier": 2,
+                    "label": "telemetry",
+                    "protocol": "json",
+                    "channel_type": "partial_reliable_timed_unordered",
+                    "reliability_parameter": 500,
+                    "priority": 128,
+                    "negotiated": true,
+                    "default_payload_format": "text",
+                    "buffered_amount_low_threshold": 4096
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        let definition = &topology.channels[0];
+        assert_eq!(definition.buffered_amount_low_threshold, Some(4096));
+        let config = definition.config();
+        assert_eq!(
+            config.channel_type,
+            ChannelType::PartialReliableTimedUnordered
+        );
+        assert!(config.negotiated);
+        assert_eq!(config.priority, 128);
+        assert_eq!(config.reliability_parameter, 500);
+        assert_eq!(config.label, "telemetry");
+        assert_eq!(config.protocol, "json");
+        assert_eq!(config.default_payload_format, PayloadFormat::Text);
+    }
+
+    #[test]
+    fn deserialize_unknown_field() {
+        let result = serde_json::from_str::<Topology>(
+            r#"{ "channels": [{ "stream_identifier": 1, "label": "chat", "ordered": true }] }"#,
+        );
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{error::DataChannelError, event_log::EventKind};
+
+const TARGET: &str = "webrtc_data::data_channel";
+
+/// Emits an event for a step of a channel's lifecycle, at the warning
+/// level for failures and at the debug level otherwise.
+pub(crate) fn lifecycle(
+    kind: EventKind,
+    stream_identifier: u16,
+    label: &str,
+    error: Option<&DataChannelError>,
+) {
+    match error {
+        Some(error) => tracing::warn!(
+            target: TARGET,
+            stream_identifier,
+            label,
+            event = kind.as_str(),
+            error = %error.to_string().trim_end(),
+        ),
+        None => tracing::debug!(
+            target: TARGET,
+            stream_identifier,
+            label,
+            event = kind.as_str(),
+        ),
+    }
+}
+
+/// Returns the span a background task named `name` runs within.
+pub(crate) fn task_span(name: &str) -> tracing::Span {
+    tracing::debug_span!(target: TARGET, "task", name)
+}
+
+/// Emits an event for the first message written to a channel.
+pub(crate) fn first_write(stream_identifier: u16, label: &str, bytes_len: usize) {
+    tracing::debug!(
+        target: TARGET,
+        stream_identifier,
+        label,
+        bytes_len,
+        event = "first_write",
+    );
+}
+
+/// Emits an event for the first message read from a channel.
+pub(crate) fn first_read(stream_identifier: u16, label: &str, bytes_len: usize) {
+    tracing::debug!(
+        target: TARGET,
+        stream_identifier,
+        label,
+        bytes_len,
+        event = "first_read",
+    );
+}
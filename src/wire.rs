@@ -0,0 +1,120 @@
+//! Constants of the data channel wire format, for integrators and tooling
+//! which inspect SCTP traffic directly.
+//!
+//! See RFC 8831, section 8 and RFC 8832, section 8.2.
+
+use crate::message::{CHANNEL_OPEN_HEADER_LEN, MESSAGE_TYPE_LEN};
+
+/// The SCTP payload protocol identifier of DCEP messages.
+pub const PPID_DCEP: u32 = 50;
+/// The SCTP payload protocol identifier of non-empty text messages.
+pub const PPID_STRING: u32 = 51;
+/// The deprecated identifier of partial binary messages.
+pub const PPID_BINARY_PARTIAL: u32 = 52;
+/// The SCTP payload protocol identifier of non-empty binary messages.
+pub const PPID_BINARY: u32 = 53;
+/// The deprecated identifier of partial text messages.
+pub const PPID_STRING_PARTIAL: u32 = 54;
+/// The SCTP payload protocol identifier of empty text messages.
+pub const PPID_STRING_EMPTY: u32 = 56;
+/// The SCTP payload protocol identifier of empty binary messages.
+pub const PPID_BINARY_EMPTY: u32 = 57;
+
+/// The first byte of a DCEP DATA_CHANNEL_ACK message.
+pub const MESSAGE_TYPE_ACK: u8 = 0x02;
+/// The first byte of a DCEP DATA_CHANNEL_OPEN message.
+pub const MESSAGE_TYPE_OPEN: u8 = 0x03;
+
+/// The size of a DCEP DATA_CHANNEL_ACK message, which is its type alone.
+pub const ACK_WIRE_SIZE: usize = MESSAGE_TYPE_LEN;
+/// The size of a DCEP DATA_CHANNEL_OPEN message up to its label and protocol:
+/// the message type, channel type, priority, reliability parameter and the
+/// lengths of the label and protocol.
+pub const OPEN_HEADER_WIRE_SIZE: usize = MESSAGE_TYPE_LEN + CHANNEL_OPEN_HEADER_LEN;
+
+/// What an SCTP payload protocol identifier designates a message as.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum PpidKind {
+    Dcep,
+    String,
+    Binary,
+    StringEmpty,
+    BinaryEmpty,
+    // Deprecated by RFC 8831, but still sent by some legacy implementations.
+    StringPartial,
+    BinaryPartial,
+}
+
+impl PpidKind {
+    /// Classify returns the kind of a payload protocol identifier,
+    /// or `None` if it isn't used by data channels.
+    pub fn classify(ppid: u32) -> Option<Self> {
+        match ppid {
+            PPID_DCEP => Some(Self::Dcep),
+            PPID_STRING => Some(Self::String),
+            PPID_BINARY_PARTIAL => Some(Self::BinaryPartial),
+            PPID_BINARY => Some(Self::Binary),
+            PPID_STRING_PARTIAL => Some(Self::StringPartial),
+            PPID_STRING_EMPTY => Some(Self::StringEmpty),
+            PPID_BINARY_EMPTY => Some(Self::BinaryEmpty),
+            _ => None,
+        }
+    }
+
+    pub fn ppid(&self) -> u32 {
+        match self {
+            Self::Dcep => PPID_DCEP,
+            Self::String => PPID_STRING,
+            Self::Binary => PPID_BINARY,
+            Self::StringEmpty => PPID_STRING_EMPTY,
+            Self::BinaryEmpty => PPID_BINARY_EMPTY,
+            Self::StringPartial => PPID_STRING_PARTIAL,
+            Self::BinaryPartial => PPID_BINARY_PARTIAL,
+        }
+    }
+
+    /// IsString returns whether the identifier designates a text message.
+    pub fn is_string(&self) -> bool {
+        matches!(self, Self::String | Self::StringEmpty | Self::StringPartial)
+    }
+
+    /// IsBinary returns whether the identifier designates a binary message.
+    pub fn is_binary(&self) -> bool {
+        matches!(self, Self::Binary | Self::BinaryEmpty | Self::BinaryPartial)
+    }
+
+    /// IsEmpty returns whether the identifier designates an empty message,
+    /// whose payload is a single ignored byte on the wire.
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Self::StringEmpty | Self::BinaryEmpty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_roundtrip() {
+        for ppid in 0..64 {
+            if let Some(kind) = PpidKind::classify(ppid) {
+                assert_eq!(kind.ppid(), ppid);
+            }
+        }
+
+        assert_eq!(PpidKind::classify(55), None);
+    }
+
+    #[test]
+    fn classify() {
+        let string_empty = PpidKind::classify(PPID_STRING_EMPTY).unwrap();
+        assert!(string_empty.is_string());
+        assert!(string_empty.is_empty());
+        assert!(!string_empty.is_binary());
+
+        let dcep = PpidKind::classify(PPID_DCEP).unwrap();
+        assert!(!dcep.is_string());
+        assert!(!dcep.is_binary());
+        assert!(!dcep.is_empty());
+    }
+}